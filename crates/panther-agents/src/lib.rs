@@ -1,21 +1,57 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 use once_cell::sync::OnceCell;
-use std::collections::HashMap;
-use std::sync::Mutex;
+use panther_core::budget::{BudgetConfig, BudgetCtl, BudgetManager, SpendContext};
+use panther_domain::entities::AnchorReceipt;
+use panther_domain::ports::{AnchorBackend, KeyValueStore, LlmProvider, LlmProviderAsync};
+use panther_validation::anchor::{AnchorConfig, BackendKind};
+use panther_validation::secrets::{scrub_secrets, ProviderConfig};
+
+pub mod runs;
+pub mod sink;
+pub mod steps;
+pub mod subscribe;
+
+pub use runs::{agent_approve, agent_cancel, agent_gc, agent_poll, agent_result, agent_resume, agent_runs, agent_start, agent_status, configure_runner, set_run_store, RunInfo, RunStatus, RunnerConfig};
+pub use sink::{add_event_sink, AgentEventSink, ChannelSink, Fanout, MemorySink, RegistrySink, StoreSink, TelemetryEventSink};
+pub use subscribe::{agent_on_event, agent_subscribe, agent_unsubscribe, agent_wait, agent_wait_async};
+use sink::{Emitter, Sequence};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum AgentPlan {
     ValidateSealAnchor {
         guidelines_json: Option<String>,
-        anchor: Option<AnchorCfg>,
+        anchor: Option<Box<AnchorCfg>>,
         timeouts_ms: Option<Timeouts>,
         retries: Option<Retries>,
+        /// Spending caps enforced per provider call; hard limits reject the call.
+        #[serde(default)]
+        budgets: Option<BudgetConfig>,
     },
+    /// Steps composed as a DAG; see [`steps`].
+    Steps(steps::StepPlan),
+}
+
+impl AgentPlan {
+    // Every anchor section, with the id of the step it belongs to (`None` for the anchor stage)
+    pub(crate) fn anchors_mut(&mut self) -> Vec<(Option<&str>, &mut AnchorCfg)> {
+        match self {
+            AgentPlan::ValidateSealAnchor { anchor, .. } => anchor.iter_mut().map(|a| (None, a.as_mut())).collect(),
+            AgentPlan::Steps(plan) => plan
+                .steps
+                .iter_mut()
+                .filter_map(|s| match &mut s.action {
+                    steps::StepAction::Anchor { anchor, .. } => Some((Some(s.id.as_str()), anchor)),
+                    _ => None,
+                })
+                .collect(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -33,51 +69,127 @@ pub struct Retries {
     pub status: Option<u32>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AnchorCfg {
-    pub rpc_url: String,
-    pub contract_addr: String,
-    pub priv_key: String,
-}
+/// Guidelines used when a plan does not set its own.
+const DEFAULT_GUIDELINES_JSON: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/../panther-validation/guidelines/anvisa.json"));
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ProviderCfg {
-    #[serde(rename = "type")]
-    pub ty: String,
-    pub base_url: Option<String>,
-    pub model: Option<String>,
-    pub api_key: Option<String>,
-}
+/// Picks the backend (`eth`, `file`, `devchain`); plans without `backend` anchor on Ethereum.
+pub type AnchorCfg = AnchorConfig;
+pub type ProviderCfg = ProviderConfig;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentInput {
     pub prompt: String,
     pub providers: Vec<ProviderCfg>,
     pub salt: Option<String>,
+    /// Tenant charged for this run (tenant-scoped budgets).
+    #[serde(default)]
+    pub tenant: Option<String>,
+    /// Whole-run deadline, overriding `RunnerConfig::run_deadline_ms`.
+    #[serde(default)]
+    pub deadline_ms: Option<u64>,
+}
+
+/// What emitted an event: the run itself, a pipeline stage or a step kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentStage {
+    Run,
+    Steps,
+    Generate,
+    Validate,
+    Score,
+    Branch,
+    Rewrite,
+    Approval,
+    Seal,
+    Anchor,
+    Status,
+    Webhook,
+    Store,
+}
+
+impl AgentStage {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AgentStage::Run => "run",
+            AgentStage::Steps => "steps",
+            AgentStage::Generate => "generate",
+            AgentStage::Validate => "validate",
+            AgentStage::Score => "score",
+            AgentStage::Branch => "branch",
+            AgentStage::Rewrite => "rewrite",
+            AgentStage::Approval => "approval",
+            AgentStage::Seal => "seal",
+            AgentStage::Anchor => "anchor",
+            AgentStage::Status => "status",
+            AgentStage::Webhook => "webhook",
+            AgentStage::Store => "store",
+        }
+    }
+}
+
+impl std::fmt::Display for AgentStage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { f.write_str(self.as_str()) }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentEvent {
+    /// Position in the run's events, from 0 and without gaps; also the `agent_poll` cursor
+    /// that returns the event.
+    #[serde(default)]
+    pub seq: u64,
     pub ts: i64,
-    pub stage: String,
+    pub stage: AgentStage,
     pub message: String,
     pub data: Option<Value>,
+    /// Id of the plan step that emitted the event (step-based plans only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct AgentOutcome {
     pub results: Option<Vec<panther_validation::ValidationResult>>,
     pub proof: Option<panther_validation::proof::Proof>,
     pub tx_hash: Option<String>,
     pub anchored: Option<bool>,
+    #[serde(default)]
+    pub receipt: Option<AnchorReceipt>,
+    /// What each step of a step-based plan did, in execution order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<steps::StepRecord>,
+}
+
+/// What a run has done so far, saved after every completed stage or step so a resumed run
+/// starts after it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct Checkpoint {
+    pub outcome: AgentOutcome,
+    /// Validation results of step-based plans, by validate step, for seal steps still to run.
+    #[serde(default)]
+    pub validated: HashMap<String, steps::Validated>,
+    #[serde(default)]
+    pub last_validated: Option<String>,
+    /// Hash handed to the anchor backend by the stage or step in progress, saved before it is
+    /// sent; a resumed run asks the backend about it instead of sending it again.
+    #[serde(default)]
+    pub anchoring: Option<String>,
 }
 
+/// Called with the run's progress each time a checkpoint is reached.
+pub(crate) type SaveCheckpoint<'a> = &'a (dyn Fn(&Checkpoint) + Sync);
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentRunResult {
     pub outcome: AgentOutcome,
     pub events: Vec<AgentEvent>,
 }
 
+// Event data can end up in logs and stores, so it never carries credentials
+fn event(stage: AgentStage, message: impl Into<String>, data: Option<Value>) -> AgentEvent {
+    AgentEvent { seq: 0, ts: now_ms(), stage, message: message.into(), data: data.map(|d| scrub_secrets(&d)), step: None }
+}
+
 fn now_ms() -> i64 {
     use std::time::{SystemTime, UNIX_EPOCH};
     SystemTime::now()
@@ -86,63 +198,94 @@ fn now_ms() -> i64 {
         .as_millis() as i64
 }
 
-async fn do_validate(
-    prompt: &str,
-    providers: &[ProviderCfg],
-    guidelines_json: &str,
-) -> Result<Vec<panther_validation::ValidationResult>> {
-    // Prefer async providers if enabled; otherwise fallback to sync
-    #[cfg(feature = "validation-async")]
-    {
-        use panther_domain::ports::LlmProviderAsync as _;
-        let mut list: Vec<(String, Arc<dyn panther_domain::ports::LlmProviderAsync>)> = Vec::new();
-        for c in providers {
-            match c.ty.as_str() {
-                #[cfg(feature = "validation-openai-async")]
-                "openai" => {
-                    if let (Some(api_key), Some(model)) = (c.api_key.clone(), c.model.clone()) {
-                        let base = c
-                            .base_url
-                            .clone()
-                            .unwrap_or_else(|| "https://api.openai.com".to_string());
-                        let p = panther_providers::openai_async::OpenAiProviderAsync {
-                            api_key,
-                            model: model.clone(),
-                            base_url: base,
-                            timeout_secs: 30,
-                            retries: 2,
-                        };
-                        list.push((format!("openai:{}", model), Arc::new(p)));
-                    }
+static BUDGET_STORE: OnceCell<Arc<dyn KeyValueStore>> = OnceCell::new();
+
+/// Sets the store used to persist budget counters across runs.
+/// Must be called before the first run; defaults to an in-memory store.
+pub fn set_budget_store(store: Arc<dyn KeyValueStore>) -> bool {
+    BUDGET_STORE.set(store).is_ok()
+}
+
+fn budget_store() -> Arc<dyn KeyValueStore> {
+    BUDGET_STORE.get_or_init(|| Arc::new(panther_storage::InMemoryStore::default())).clone()
+}
+
+static AGENT_STORE: OnceCell<Arc<dyn KeyValueStore>> = OnceCell::new();
+
+/// Sets the store `store` steps write to. Must be called before the first run; defaults to an in-memory store.
+pub fn set_agent_store(store: Arc<dyn KeyValueStore>) -> bool {
+    AGENT_STORE.set(store).is_ok()
+}
+
+fn agent_store() -> Arc<dyn KeyValueStore> {
+    AGENT_STORE.get_or_init(|| Arc::new(panther_storage::InMemoryStore::default())).clone()
+}
+
+fn budget_for_run(cfg: Option<BudgetConfig>, input: &AgentInput, run_id: &str) -> Option<BudgetCtl> {
+    let cfg = cfg?;
+    let ctx = SpendContext { tenant: input.tenant.clone(), run: Some(run_id.to_string()) };
+    Some(BudgetCtl::new(Arc::new(BudgetManager::new(budget_store(), cfg)), ctx))
+}
+
+// A run whose every provider call hit a hard limit fails instead of sealing empty results.
+fn ensure_within_budget(results: &[panther_validation::ValidationResult], budget: &Option<BudgetCtl>) -> Result<()> {
+    let rejected = |r: &panther_validation::ValidationResult| r.error == Some(panther_validation::ErrorCategory::BudgetExceeded);
+    if budget.is_some() && !results.is_empty() && results.iter().all(rejected) {
+        return Err(panther_domain::errors::PantherError::BudgetExceeded("all providers over budget".into()).into());
+    }
+    Ok(())
+}
+
+// Async clients for the configured providers; empty when no async provider feature is on.
+#[cfg(feature = "validation-async")]
+fn async_providers(providers: &[ProviderCfg]) -> Vec<(String, Arc<dyn LlmProviderAsync>)> {
+    #[allow(unused_mut)]
+    let mut list: Vec<(String, Arc<dyn LlmProviderAsync>)> = Vec::new();
+    for c in providers {
+        match c.ty.as_str() {
+            #[cfg(feature = "validation-openai-async")]
+            "openai" => {
+                if let (Some(api_key), Some(model)) = (c.api_key(), c.model.clone()) {
+                    let base = c
+                        .base_url
+                        .clone()
+                        .unwrap_or_else(|| "https://api.openai.com".to_string());
+                    let p = panther_providers::openai_async::OpenAiProviderAsync {
+                        api_key,
+                        model: model.clone(),
+                        base_url: base,
+                        timeout_secs: 30,
+                        retries: 2,
+                    };
+                    list.push((format!("openai:{}", model), Arc::new(p)));
                 }
-                #[cfg(feature = "validation-ollama-async")]
-                "ollama" => {
-                    if let (Some(base), Some(model)) = (c.base_url.clone(), c.model.clone()) {
-                        let p = panther_providers::ollama_async::OllamaProviderAsync {
-                            base_url: base,
-                            model: model.clone(),
-                            timeout_secs: 30,
-                            retries: 2,
-                        };
-                        list.push((format!("ollama:{}", model), Arc::new(p)));
-                    }
+            }
+            #[cfg(feature = "validation-ollama-async")]
+            "ollama" => {
+                if let (Some(base), Some(model)) = (c.base_url.clone(), c.model.clone()) {
+                    let p = panther_providers::ollama_async::OllamaProviderAsync {
+                        base_url: base,
+                        model: model.clone(),
+                        timeout_secs: 30,
+                        retries: 2,
+                    };
+                    list.push((format!("ollama:{}", model), Arc::new(p)));
                 }
-                _ => {}
             }
-        }
-        if !list.is_empty() {
-            let validator = panther_validation::LLMValidatorAsync::from_json_str(guidelines_json, list)?;
-            return validator.validate(prompt).await;
+            _ => {}
         }
     }
+    list
+}
 
-    // Sync fallback
-    let list: Vec<(String, Arc<dyn panther_domain::ports::LlmProvider>)> = Vec::new();
+fn sync_providers(providers: &[ProviderCfg]) -> Vec<(String, Arc<dyn LlmProvider>)> {
+    #[allow(unused_mut)]
+    let mut list: Vec<(String, Arc<dyn LlmProvider>)> = Vec::new();
     for c in providers {
         match c.ty.as_str() {
             #[cfg(feature = "validation-openai")]
             "openai" => {
-                if let (Some(api_key), Some(model)) = (c.api_key.clone(), c.model.clone()) {
+                if let (Some(api_key), Some(model)) = (c.api_key(), c.model.clone()) {
                     let base = c
                         .base_url
                         .clone()
@@ -162,331 +305,244 @@ async fn do_validate(
                     list.push((format!("ollama:{}", model), Arc::new(p)));
                 }
             }
+            #[cfg(test)]
+            "stub" => list.push((format!("stub:{}", c.model.as_deref().unwrap_or("echo")), Arc::new(StubProvider))),
             _ => {}
         }
     }
-    if list.is_empty() {
-        anyhow::bail!("no providers configured")
-    }
-    let validator = panther_validation::LLMValidator::from_json_str(guidelines_json, list)?;
-    validator.validate(prompt).await
+    list
 }
 
-pub async fn run_plan_async(plan: AgentPlan, input: AgentInput) -> Result<AgentRunResult> {
-    let mut events: Vec<AgentEvent> = Vec::new();
-    let mut outcome = AgentOutcome { results: None, proof: None, tx_hash: None, anchored: None };
-
-    match plan {
-        AgentPlan::ValidateSealAnchor { guidelines_json, anchor, timeouts_ms, retries } => {
-            // Resolve guidelines JSON string (default to built-in ANVISA if not provided)
-            let guidelines_json = match guidelines_json {
-                Some(s) => s,
-                None => include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/../panther-validation/guidelines/anvisa.json")).to_string(),
-            };
-
-            // Defaults
-            let t_validate = timeouts_ms.as_ref().and_then(|t| t.validate_ms).unwrap_or(30_000);
-            let _t_anchor = timeouts_ms.as_ref().and_then(|t| t.anchor_ms).unwrap_or(30_000);
-            let _t_status = timeouts_ms.as_ref().and_then(|t| t.status_ms).unwrap_or(5_000);
-            let r_validate = retries.as_ref().and_then(|r| r.validate).unwrap_or(0);
-            let _r_anchor = retries.as_ref().and_then(|r| r.anchor).unwrap_or(0);
-            let _r_status = retries.as_ref().and_then(|r| r.status).unwrap_or(0);
+/// Answers with the prompt it was given; configured as `{"type":"stub"}` in tests.
+#[cfg(test)]
+struct StubProvider;
 
-            // ---- Validate (with retries/timeout) ----
-            events.push(AgentEvent { ts: now_ms(), stage: "validate".into(), message: format!("starting validation (retries={})", r_validate).into(), data: None });
-            let mut _last_err: Option<anyhow::Error> = None;
-            let mut attempt = 0u32;
-            let results = loop {
-                events.push(AgentEvent { ts: now_ms(), stage: "validate".into(), message: format!("attempt {}", attempt + 1), data: None });
-                let fut = do_validate(&input.prompt, &input.providers, &guidelines_json);
-                match tokio::time::timeout(Duration::from_millis(t_validate), fut).await {
-                    Ok(Ok(v)) => break v,
-                    Ok(Err(e)) => { _last_err = Some(e); }
-                    Err(_) => { _last_err = Some(anyhow::anyhow!("timeout: validate exceeded {} ms", t_validate)); }
-                }
-                if attempt >= r_validate { break Err(_last_err.unwrap_or_else(|| anyhow::anyhow!("validate failed")))?; }
-                // backoff with jitter
-                let wait = (200u64.saturating_mul(1 << attempt.min(4))) + ((attempt as u64 * 37) % 120);
-                tokio::time::sleep(Duration::from_millis(wait.min(2_000))).await;
-                attempt += 1;
-            };
-            events.push(AgentEvent { ts: now_ms(), stage: "validate".into(), message: "validation complete".into(), data: Some(serde_json::to_value(&results).unwrap_or(Value::Null)) });
-            outcome.results = Some(results.clone());
-
-            events.push(AgentEvent { ts: now_ms(), stage: "seal".into(), message: "computing proof".into(), data: None });
-            let providers_json = serde_json::to_string(&input.providers).unwrap_or_else(|_| "[]".to_string());
-            let results_json = serde_json::to_string(&results).unwrap_or_else(|_| "[]".to_string());
-            let ctx = panther_validation::proof::ProofContext { sdk_version: env!("CARGO_PKG_VERSION").to_string(), salt: input.salt.clone() };
-            let proof = panther_validation::proof::compute_proof(&input.prompt, &providers_json, &guidelines_json, &results_json, &ctx)?;
-            events.push(AgentEvent { ts: now_ms(), stage: "seal".into(), message: "proof computed".into(), data: Some(serde_json::to_value(&proof).unwrap_or(Value::Null)) });
-            outcome.proof = Some(proof.clone());
-
-            if let Some(a) = anchor {
-                #[cfg(feature = "blockchain-eth")]
-                {
-                    let t_anchor = _t_anchor; let r_anchor = _r_anchor; let t_status = _t_status; let r_status = _r_status;
-                    events.push(AgentEvent { ts: now_ms(), stage: "anchor".into(), message: format!("anchoring on-chain (retries={})", r_anchor), data: None });
-                    let mut last_err: Option<anyhow::Error> = None;
-                    let mut attempt = 0u32;
-                    let res = loop {
-                        events.push(AgentEvent { ts: now_ms(), stage: "anchor".into(), message: format!("attempt {}", attempt + 1), data: None });
-                        let fut = panther_validation::anchor_eth::anchor_proof(&proof.combined_hash, &a.rpc_url, &a.contract_addr, &a.priv_key);
-                        match tokio::time::timeout(Duration::from_millis(t_anchor), fut).await {
-                            Ok(Ok(v)) => break v,
-                            Ok(Err(e)) => { last_err = Some(e); }
-                            Err(_) => { last_err = Some(anyhow::anyhow!("timeout: anchor exceeded {} ms", t_anchor)); }
-                        }
-                        if attempt >= r_anchor { break Err(last_err.unwrap_or_else(|| anyhow::anyhow!("anchor failed")))?; }
-                        let wait = (300u64.saturating_mul(1 << attempt.min(4))) + ((attempt as u64 * 41) % 150);
-                        tokio::time::sleep(Duration::from_millis(wait.min(3_000))).await;
-                        attempt += 1;
-                    };
-                    events.push(AgentEvent { ts: now_ms(), stage: "anchor".into(), message: "anchor tx submitted".into(), data: Some(serde_json::to_value(&res).ok()) });
-                    outcome.tx_hash = Some(res.tx_hash.clone());
-                    // Optional status check with retries/timeout
-                    let mut last_err: Option<anyhow::Error> = None;
-                    let mut attempt = 0u32;
-                    let anchored = loop {
-                        events.push(AgentEvent { ts: now_ms(), stage: "status".into(), message: format!("checking status (attempt {} of {})", attempt + 1, r_status + 1), data: None });
-                        let fut = panther_validation::anchor_eth::is_anchored(&proof.combined_hash, &a.rpc_url, &a.contract_addr);
-                        match tokio::time::timeout(Duration::from_millis(t_status), fut).await {
-                            Ok(Ok(v)) => break v,
-                            Ok(Err(e)) => { last_err = Some(e); }
-                            Err(_) => { last_err = Some(anyhow::anyhow!("timeout: status exceeded {} ms", t_status)); }
-                        }
-                        if attempt >= r_status { break false; }
-                        let wait = (500u64.saturating_mul(1 << attempt.min(3))) + ((attempt as u64 * 47) % 200);
-                        tokio::time::sleep(Duration::from_millis(wait.min(4_000))).await;
-                        attempt += 1;
-                    };
-                    outcome.anchored = Some(anchored);
-                    events.push(AgentEvent { ts: now_ms(), stage: "status".into(), message: "status checked".into(), data: Some(serde_json::json!({"anchored": anchored})) });
-                }
-                #[cfg(not(feature = "blockchain-eth"))]
-                {
-                    let _ = &a; // silence unused when feature is off
-                    events.push(AgentEvent { ts: now_ms(), stage: "anchor".into(), message: "blockchain feature disabled".into(), data: None });
-                }
-            }
-        }
+#[cfg(test)]
+impl LlmProvider for StubProvider {
+    fn generate(&self, prompt: &panther_domain::entities::Prompt) -> Result<panther_domain::entities::Completion> {
+        Ok(panther_domain::entities::Completion { text: prompt.text.clone(), model: Some("stub".into()) })
     }
-
-    Ok(AgentRunResult { outcome, events })
-}
-
-pub fn run_plan(plan_json: &str, input_json: &str) -> Result<AgentRunResult> {
-    let plan: AgentPlan = serde_json::from_str(plan_json)?;
-    let input: AgentInput = serde_json::from_str(input_json)?;
-    let rt = tokio::runtime::Builder::new_multi_thread().enable_all().build()?;
-    rt.block_on(run_plan_async(plan, input))
 }
 
-// ---- Incremental manager (start/poll/status/result) ----
-
-#[derive(Default)]
-struct RunState {
-    status: String,
-    events: Vec<AgentEvent>,
-    outcome: Option<AgentOutcome>,
-    started_ts: i64,
-    finished_ts: Option<i64>,
-}
-
-static RUNS: OnceCell<Mutex<HashMap<String, RunState>>> = OnceCell::new();
-
-fn runs() -> &'static Mutex<HashMap<String, RunState>> {
-    RUNS.get_or_init(|| Mutex::new(HashMap::new()))
-}
-
-fn push_event(run_id: &str, ev: AgentEvent) {
-    if let Ok(mut map) = runs().lock() {
-        if let Some(st) = map.get_mut(run_id) {
-            st.events.push(ev);
-        }
+/// The provider labelled `label` (`type:model`), or the first configured one.
+fn text_provider(providers: &[ProviderCfg], label: Option<&str>) -> Result<(String, Arc<dyn LlmProviderAsync>)> {
+    #[allow(unused_mut)]
+    let mut list: Vec<(String, Arc<dyn LlmProviderAsync>)> = Vec::new();
+    #[cfg(feature = "validation-async")]
+    list.extend(async_providers(providers));
+    if list.is_empty() {
+        list = sync_providers(providers).into_iter().map(|(l, p)| (l, panther_validation::blocking_provider(p))).collect();
     }
+    let found = match label {
+        Some(label) => list.into_iter().find(|(l, _)| l == label),
+        None => list.into_iter().next(),
+    };
+    found.ok_or_else(|| match label {
+        Some(label) => anyhow::anyhow!("provider '{}' is not configured", label),
+        None => anyhow::anyhow!("no providers configured"),
+    })
 }
 
-fn finish_run(run_id: &str, outcome: AgentOutcome) {
-    if let Ok(mut map) = runs().lock() {
-        if let Some(st) = map.get_mut(run_id) {
-            st.status = "done".to_string();
-            st.finished_ts = Some(now_ms());
-            st.outcome = Some(outcome);
+async fn do_validate(
+    prompt: &str,
+    providers: &[ProviderCfg],
+    guidelines_json: &str,
+    budget: Option<BudgetCtl>,
+) -> Result<Vec<panther_validation::ValidationResult>> {
+    // Prefer async providers if enabled; otherwise fallback to sync
+    #[cfg(feature = "validation-async")]
+    {
+        let list = async_providers(providers);
+        if !list.is_empty() {
+            let mut validator = panther_validation::LLMValidatorAsync::from_json_str(guidelines_json, list)?;
+            if let Some(b) = budget {
+                validator = validator.with_budget(b.manager, b.ctx);
+            }
+            return validator.validate(prompt).await;
         }
     }
-}
 
-fn set_status(run_id: &str, status: &str) {
-    if let Ok(mut map) = runs().lock() {
-        if let Some(st) = map.get_mut(run_id) {
-            st.status = status.to_string();
-        }
+    // Sync fallback
+    let list = sync_providers(providers);
+    if list.is_empty() {
+        anyhow::bail!("no providers configured")
     }
-}
-
-fn new_run_state() -> RunState {
-    RunState { status: "running".to_string(), events: Vec::new(), outcome: None, started_ts: now_ms(), finished_ts: None }
-}
-
-pub fn agent_start(plan_json: &str, input_json: &str) -> Result<String> {
-    let plan: AgentPlan = serde_json::from_str(plan_json)?;
-    let input: AgentInput = serde_json::from_str(input_json)?;
-    // generate run id
-    let run_id = format!("r{}", now_ms());
-    {
-        let mut map = runs().lock().unwrap();
-        map.insert(run_id.clone(), new_run_state());
+    let mut validator = panther_validation::LLMValidator::from_json_str(guidelines_json, list)?;
+    if let Some(b) = budget {
+        validator = validator.with_budget(b.manager, b.ctx);
     }
-    // spawn background thread with a runtime
-    let run_id_clone = run_id.clone();
-    std::thread::spawn(move || {
-        if let Ok(rt) = tokio::runtime::Builder::new_multi_thread().enable_all().build() {
-            let res = rt.block_on(run_plan_async_with_id(plan, input, &run_id_clone));
-            if let Ok(r) = res {
-                finish_run(&run_id_clone, r.outcome);
-            } else {
-                set_status(&run_id_clone, "error: run failed");
-            }
-        } else {
-            set_status(&run_id_clone, "error: runtime init failed");
-        }
-    });
-    Ok(run_id)
+    validator.validate(prompt).await
 }
 
-pub fn agent_poll(run_id: &str, cursor: usize) -> Result<(Vec<AgentEvent>, bool, usize, String)> {
-    let (events, done, status);
-    {
-        let map = runs().lock().unwrap();
-        let st = map.get(run_id).ok_or_else(|| anyhow::anyhow!("run not found"))?;
-        let total = st.events.len();
-        let from = cursor.min(total);
-        events = st.events[from..].to_vec();
-        status = st.status.clone();
-        done = status == "done";
+type StageLimits = (u64, u32);
+
+// Anchors with the plan's backend, then checks the hash landed; each step has its own timeout and retries.
+async fn anchor_stage(
+    backend: &dyn AnchorBackend,
+    hash: &str,
+    ((t_anchor, r_anchor), (t_status, r_status)): (StageLimits, StageLimits),
+    emit: &Emitter<'_>,
+    maybe_sent: bool,
+) -> Result<(Option<AnchorReceipt>, bool)> {
+    // The transaction may have gone out just before a crash; only send it if the hash is unknown.
+    // Some backends only keep receipts in memory, so an anchored hash can come back without one.
+    if maybe_sent {
+        let receipt = backend.receipt(hash).await?;
+        if receipt.is_some() || backend.status(hash).await?.anchored {
+            emit.emit(event(AgentStage::Anchor, "anchor already submitted", serde_json::to_value(&receipt).ok()));
+            return Ok((receipt, true));
+        }
     }
-    let new_cursor = cursor + events.len();
-    Ok((events, done, new_cursor, status))
+    emit.emit(event(AgentStage::Anchor, format!("anchoring via {} (retries={})", backend.name(), r_anchor), None));
+    let mut attempt = 0u32;
+    let receipt = loop {
+        emit.emit(event(AgentStage::Anchor, format!("attempt {}", attempt + 1), None));
+        let err = match tokio::time::timeout(Duration::from_millis(t_anchor), backend.anchor(hash)).await {
+            Ok(Ok(v)) => break v,
+            Ok(Err(e)) => e,
+            Err(_) => anyhow::anyhow!("timeout: anchor exceeded {} ms", t_anchor),
+        };
+        if attempt >= r_anchor { return Err(err); }
+        let wait = (300u64.saturating_mul(1 << attempt.min(4))) + ((attempt as u64 * 41) % 150);
+        tokio::time::sleep(Duration::from_millis(wait.min(3_000))).await;
+        attempt += 1;
+    };
+    emit.emit(event(AgentStage::Anchor, "anchor tx submitted", serde_json::to_value(&receipt).ok()));
+    let mut attempt = 0u32;
+    let status = loop {
+        emit.emit(event(AgentStage::Status, format!("checking status (attempt {} of {})", attempt + 1, r_status + 1), None));
+        if let Ok(Ok(s)) = tokio::time::timeout(Duration::from_millis(t_status), backend.status(hash)).await {
+            break s;
+        }
+        if attempt >= r_status { break Default::default(); }
+        let wait = (500u64.saturating_mul(1 << attempt.min(3))) + ((attempt as u64 * 47) % 200);
+        tokio::time::sleep(Duration::from_millis(wait.min(4_000))).await;
+        attempt += 1;
+    };
+    emit.emit(event(AgentStage::Status, "status checked", serde_json::to_value(status).ok()));
+    Ok((Some(receipt), status.anchored))
 }
 
-pub fn agent_status(run_id: &str) -> Result<(String, bool)> {
-    let map = runs().lock().unwrap();
-    let st = map.get(run_id).ok_or_else(|| anyhow::anyhow!("run not found"))?;
-    // Touch started_ts to avoid dead_code warning and allow future elapsed reporting
-    let _elapsed_ms = now_ms().saturating_sub(st.started_ts);
-    Ok((st.status.clone(), st.status == "done"))
+pub async fn run_plan_async(plan: AgentPlan, input: AgentInput) -> Result<AgentRunResult> {
+    let sink = MemorySink::default();
+    let outcome = run_plan_with_sink(plan, input, &runs::new_run_id(), &sink).await?;
+    Ok(AgentRunResult { outcome, events: sink.take() })
 }
 
-pub fn agent_result(run_id: &str) -> Result<Option<AgentOutcome>> {
-    let map = runs().lock().unwrap();
-    let st = map.get(run_id).ok_or_else(|| anyhow::anyhow!("run not found"))?;
-    Ok(st.outcome.clone())
+/// Runs `plan` as `run_id`, emitting every event through `sink` and the sinks added with
+/// [`add_event_sink`]. The blocking and incremental APIs both run plans through here.
+pub async fn run_plan_with_sink(plan: AgentPlan, input: AgentInput, run_id: &str, sink: &dyn AgentEventSink) -> Result<AgentOutcome> {
+    execute(plan, input, Emitter::new(run_id, sink, &Sequence::default()), Checkpoint::default(), None).await
 }
 
-async fn run_plan_async_with_id(plan: AgentPlan, input: AgentInput, run_id: &str) -> Result<AgentRunResult> {
-    let mut events: Vec<AgentEvent> = Vec::new();
-    let mut outcome = AgentOutcome { results: None, proof: None, tx_hash: None, anchored: None };
+// Picks up after `from`: validation results and a proof already in it are reused, never recomputed.
+pub(crate) async fn execute(plan: AgentPlan, input: AgentInput, emit: Emitter<'_>, from: Checkpoint, save: Option<SaveCheckpoint<'_>>) -> Result<AgentOutcome> {
+    let run_id = emit.run_id;
+    let checkpoint = |outcome: &AgentOutcome| {
+        if let Some(save) = save {
+            save(&Checkpoint { outcome: outcome.clone(), ..Default::default() });
+        }
+    };
 
     match plan {
-        AgentPlan::ValidateSealAnchor { guidelines_json, anchor, timeouts_ms, retries } => {
+        AgentPlan::ValidateSealAnchor { guidelines_json, anchor, timeouts_ms, retries, budgets } => {
+            let (mut outcome, anchoring) = (from.outcome, from.anchoring);
+            // Resolve guidelines JSON string (default to built-in ANVISA if not provided)
             let guidelines_json = match guidelines_json {
                 Some(s) => s,
-                None => include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/../panther-validation/guidelines/anvisa.json")).to_string(),
+                None => DEFAULT_GUIDELINES_JSON.to_string(),
             };
 
+            // Defaults
             let t_validate = timeouts_ms.as_ref().and_then(|t| t.validate_ms).unwrap_or(30_000);
-            let _t_anchor = timeouts_ms.as_ref().and_then(|t| t.anchor_ms).unwrap_or(30_000);
-            let _t_status = timeouts_ms.as_ref().and_then(|t| t.status_ms).unwrap_or(5_000);
+            let t_anchor = timeouts_ms.as_ref().and_then(|t| t.anchor_ms).unwrap_or(30_000);
+            let t_status = timeouts_ms.as_ref().and_then(|t| t.status_ms).unwrap_or(5_000);
             let r_validate = retries.as_ref().and_then(|r| r.validate).unwrap_or(0);
-            let _r_anchor = retries.as_ref().and_then(|r| r.anchor).unwrap_or(0);
-            let _r_status = retries.as_ref().and_then(|r| r.status).unwrap_or(0);
-
-            let ev = AgentEvent { ts: now_ms(), stage: "validate".into(), message: format!("starting validation (retries={})", r_validate), data: None };
-            push_event(run_id, ev.clone()); events.push(ev);
-            let mut _last_err: Option<anyhow::Error> = None;
-            let mut attempt = 0u32;
-            let results = loop {
-                let ev = AgentEvent { ts: now_ms(), stage: "validate".into(), message: format!("attempt {}", attempt + 1), data: None };
-                push_event(run_id, ev.clone()); events.push(ev);
-                let fut = do_validate(&input.prompt, &input.providers, &guidelines_json);
-                match tokio::time::timeout(Duration::from_millis(t_validate), fut).await {
-                    Ok(Ok(v)) => break v,
-                    Ok(Err(e)) => { _last_err = Some(e); }
-                    Err(_) => { _last_err = Some(anyhow::anyhow!("timeout: validate exceeded {} ms", t_validate)); }
+            let r_anchor = retries.as_ref().and_then(|r| r.anchor).unwrap_or(0);
+            let r_status = retries.as_ref().and_then(|r| r.status).unwrap_or(0);
+            let budget = budget_for_run(budgets, &input, run_id);
+
+            // ---- Validate (with retries/timeout) ----
+            let results = match outcome.results.clone() {
+                Some(results) => {
+                    emit.emit(event(AgentStage::Validate, "validation restored from checkpoint", None));
+                    results
                 }
-                if attempt >= r_validate { break Err(_last_err.unwrap_or_else(|| anyhow::anyhow!("validate failed")))?; }
-                let wait = (200u64.saturating_mul(1 << attempt.min(4))) + ((attempt as u64 * 37) % 120);
-                tokio::time::sleep(Duration::from_millis(wait.min(2_000))).await;
-                attempt += 1;
-            };
-            let ev = AgentEvent { ts: now_ms(), stage: "validate".into(), message: "validation complete".into(), data: Some(serde_json::to_value(&results).unwrap_or(Value::Null)) };
-            push_event(run_id, ev.clone()); events.push(ev);
-            outcome.results = Some(results.clone());
-
-            let ev = AgentEvent { ts: now_ms(), stage: "seal".into(), message: "computing proof".into(), data: None };
-            push_event(run_id, ev.clone()); events.push(ev);
-            let providers_json = serde_json::to_string(&input.providers).unwrap_or_else(|_| "[]".to_string());
-            let results_json = serde_json::to_string(&results).unwrap_or_else(|_| "[]".to_string());
-            let ctx = panther_validation::proof::ProofContext { sdk_version: env!("CARGO_PKG_VERSION").to_string(), salt: input.salt.clone() };
-            let proof = panther_validation::proof::compute_proof(&input.prompt, &providers_json, &guidelines_json, &results_json, &ctx)?;
-            let ev = AgentEvent { ts: now_ms(), stage: "seal".into(), message: "proof computed".into(), data: Some(serde_json::to_value(&proof).unwrap_or(Value::Null)) };
-            push_event(run_id, ev.clone()); events.push(ev);
-            outcome.proof = Some(proof.clone());
-
-            if let Some(a) = anchor {
-                #[cfg(feature = "blockchain-eth")]
-                {
-                    let t_anchor = _t_anchor; let r_anchor = _r_anchor; let t_status = _t_status; let r_status = _r_status;
-                    let ev = AgentEvent { ts: now_ms(), stage: "anchor".into(), message: format!("anchoring on-chain (retries={})", r_anchor), data: None };
-                    push_event(run_id, ev.clone()); events.push(ev);
-                    let mut last_err: Option<anyhow::Error> = None;
+                None => {
+                    emit.emit(event(AgentStage::Validate, format!("starting validation (retries={})", r_validate), None));
+                    let mut _last_err: Option<anyhow::Error> = None;
                     let mut attempt = 0u32;
-                    let res = loop {
-                        let ev = AgentEvent { ts: now_ms(), stage: "anchor".into(), message: format!("attempt {}", attempt + 1), data: None };
-                        push_event(run_id, ev.clone()); events.push(ev);
-                        let fut = panther_validation::anchor_eth::anchor_proof(&proof.combined_hash, &a.rpc_url, &a.contract_addr, &a.priv_key);
-                        match tokio::time::timeout(Duration::from_millis(t_anchor), fut).await {
+                    let results = loop {
+                        emit.emit(event(AgentStage::Validate, format!("attempt {}", attempt + 1), None));
+                        let fut = do_validate(&input.prompt, &input.providers, &guidelines_json, budget.clone());
+                        match tokio::time::timeout(Duration::from_millis(t_validate), fut).await {
                             Ok(Ok(v)) => break v,
-                            Ok(Err(e)) => { last_err = Some(e); }
-                            Err(_) => { last_err = Some(anyhow::anyhow!("timeout: anchor exceeded {} ms", t_anchor)); }
+                            Ok(Err(e)) => { _last_err = Some(e); }
+                            Err(_) => { _last_err = Some(anyhow::anyhow!("timeout: validate exceeded {} ms", t_validate)); }
                         }
-                        if attempt >= r_anchor { break Err(last_err.unwrap_or_else(|| anyhow::anyhow!("anchor failed")))?; }
-                        let wait = (300u64.saturating_mul(1 << attempt.min(4))) + ((attempt as u64 * 41) % 150);
-                        tokio::time::sleep(Duration::from_millis(wait.min(3_000))).await;
+                        if attempt >= r_validate { break Err(_last_err.unwrap_or_else(|| anyhow::anyhow!("validate failed")))?; }
+                        // backoff with jitter
+                        let wait = (200u64.saturating_mul(1 << attempt.min(4))) + ((attempt as u64 * 37) % 120);
+                        tokio::time::sleep(Duration::from_millis(wait.min(2_000))).await;
                         attempt += 1;
                     };
-                    let ev = AgentEvent { ts: now_ms(), stage: "anchor".into(), message: "anchor tx submitted".into(), data: Some(serde_json::to_value(&res).ok().unwrap_or(Value::Null)) };
-                    push_event(run_id, ev.clone()); events.push(ev);
-                    outcome.tx_hash = Some(res.tx_hash.clone());
-                    let mut last_err: Option<anyhow::Error> = None;
-                    let mut attempt = 0u32;
-                    let anchored = loop {
-                        let ev = AgentEvent { ts: now_ms(), stage: "status".into(), message: format!("checking status (attempt {} of {})", attempt + 1, r_status + 1), data: None };
-                        push_event(run_id, ev.clone()); events.push(ev);
-                        let fut = panther_validation::anchor_eth::is_anchored(&proof.combined_hash, &a.rpc_url, &a.contract_addr);
-                        match tokio::time::timeout(Duration::from_millis(t_status), fut).await {
-                            Ok(Ok(v)) => break v,
-                            Ok(Err(e)) => { last_err = Some(e); }
-                            Err(_) => { last_err = Some(anyhow::anyhow!("timeout: status exceeded {} ms", t_status)); }
-                        }
-                        if attempt >= r_status { break false; }
-                        let wait = (500u64.saturating_mul(1 << attempt.min(3))) + ((attempt as u64 * 47) % 200);
-                        tokio::time::sleep(Duration::from_millis(wait.min(4_000))).await;
-                        attempt += 1;
-                    };
-                    outcome.anchored = Some(anchored);
-                    let ev = AgentEvent { ts: now_ms(), stage: "status".into(), message: "status checked".into(), data: Some(serde_json::json!({"anchored": anchored})) };
-                    push_event(run_id, ev.clone()); events.push(ev);
+                    emit.emit(event(AgentStage::Validate, "validation complete", Some(serde_json::to_value(&results).unwrap_or(Value::Null))));
+                    ensure_within_budget(&results, &budget)?;
+                    outcome.results = Some(results.clone());
+                    checkpoint(&outcome);
+                    results
+                }
+            };
+
+            let proof = match outcome.proof.clone() {
+                Some(proof) => {
+                    emit.emit(event(AgentStage::Seal, "proof restored from checkpoint", Some(serde_json::json!({ "combined_hash": proof.combined_hash }))));
+                    proof
                 }
-                #[cfg(not(feature = "blockchain-eth"))]
-                {
-                    let _ = &a;
-                    let ev = AgentEvent { ts: now_ms(), stage: "anchor".into(), message: "blockchain feature disabled".into(), data: None };
-                    push_event(run_id, ev.clone()); events.push(ev);
+                None => {
+                    emit.emit(event(AgentStage::Seal, "computing proof", None));
+                    let providers_json = serde_json::to_string(&input.providers).unwrap_or_else(|_| "[]".to_string());
+                    let results_json = serde_json::to_string(&results).unwrap_or_else(|_| "[]".to_string());
+                    let ctx = panther_validation::proof::ProofContext { sdk_version: env!("CARGO_PKG_VERSION").to_string(), salt: input.salt.clone() };
+                    let proof = panther_validation::proof::compute_proof(&input.prompt, &providers_json, &guidelines_json, &results_json, &ctx)?;
+                    emit.emit(event(AgentStage::Seal, "proof computed", Some(serde_json::to_value(&proof).unwrap_or(Value::Null))));
+                    outcome.proof = Some(proof.clone());
+                    checkpoint(&outcome);
+                    proof
+                }
+            };
+
+            if let Some(a) = anchor.filter(|_| outcome.anchored.is_none()) {
+                match a.backend() {
+                    Ok(backend) => {
+                        let limits = ((t_anchor, r_anchor), (t_status, r_status));
+                        let hash = &proof.combined_hash;
+                        if let Some(save) = save {
+                            save(&Checkpoint { outcome: outcome.clone(), anchoring: Some(hash.clone()), ..Default::default() });
+                        }
+                        let maybe_sent = anchoring.as_ref() == Some(hash);
+                        let (receipt, anchored) = anchor_stage(backend.as_ref(), hash, limits, &emit, maybe_sent).await?;
+                        outcome.tx_hash = receipt.as_ref().map(|r| r.tx_id.clone());
+                        outcome.anchored = Some(anchored);
+                        outcome.receipt = receipt;
+                        checkpoint(&outcome);
+                    }
+                    // Without `blockchain-eth` an Ethereum plan still validates and seals
+                    Err(e) if a.backend == BackendKind::Eth && !cfg!(feature = "blockchain-eth") => emit.emit(event(AgentStage::Anchor, e.to_string(), None)),
+                    Err(e) => return Err(e),
                 }
             }
+            Ok(outcome)
         }
+        AgentPlan::Steps(plan) => steps::run(plan, &input, emit, from, save).await,
     }
+}
 
-    Ok(AgentRunResult { outcome, events })
+pub fn run_plan(plan_json: &str, input_json: &str) -> Result<AgentRunResult> {
+    let plan: AgentPlan = serde_json::from_str(plan_json)?;
+    let input: AgentInput = serde_json::from_str(input_json)?;
+    let deadline = runs::deadline(&input);
+    runs::runtime()?.block_on(async {
+        tokio::time::timeout(deadline, run_plan_async(plan, input)).await.map_err(|_| anyhow::anyhow!("deadline of {} ms exceeded", deadline.as_millis()))?
+    })
 }
@@ -0,0 +1,874 @@
+//! Runs started with [`agent_start`], tracked in a registry read by `agent_poll`.
+//!
+//! Every run executes on one shared runtime with `worker_threads` threads, and at most
+//! `max_concurrent_runs` plans execute at once; later ones wait as `queued`. A run fails once
+//! its deadline passes, can be cancelled with [`agent_cancel`] while queued or running, and is
+//! dropped from the registry `finished_ttl_ms` after it ends, by a collection that runs on the
+//! shared runtime every minute (or every `finished_ttl_ms`, if shorter).
+//!
+//! With a store set by [`set_run_store`], each run's state, events and a checkpoint after every
+//! completed stage or step are persisted too. After a restart a run the previous process did not
+//! finish reads as `interrupted`, and [`agent_resume`] continues it from its last checkpoint.
+
+use super::sink::{emit_global, Sequence};
+use super::{event, execute, now_ms, steps, AgentEvent, AgentInput, AgentOutcome, AgentPlan, AgentStage, Checkpoint, Emitter, RegistrySink, StoreSink};
+use anyhow::{anyhow, bail, Result};
+use once_cell::sync::OnceCell;
+use panther_domain::ports::KeyValueStore;
+use panther_validation::anchor::BackendKind;
+use panther_validation::secrets::Secret;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{Notify, Semaphore};
+use tokio_util::sync::CancellationToken;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunnerConfig {
+    /// Threads of the runtime shared by every run.
+    pub worker_threads: usize,
+    /// Plans executing at once; further runs wait as `queued`.
+    pub max_concurrent_runs: usize,
+    /// Default whole-run deadline; `AgentInput.deadline_ms` overrides it per run.
+    pub run_deadline_ms: u64,
+    /// How long a finished run stays in the registry.
+    pub finished_ttl_ms: u64,
+}
+
+impl Default for RunnerConfig {
+    fn default() -> Self { Self { worker_threads: 2, max_concurrent_runs: 4, run_deadline_ms: 2 * 3_600_000, finished_ttl_ms: 3_600_000 } }
+}
+
+static CONFIG: OnceCell<RunnerConfig> = OnceCell::new();
+
+/// Sets the runner limits. Must be called before the first run; `false` once they are fixed.
+pub fn configure_runner(config: RunnerConfig) -> bool { CONFIG.set(config).is_ok() }
+
+fn config() -> &'static RunnerConfig { CONFIG.get_or_init(RunnerConfig::default) }
+
+struct Runner {
+    rt: tokio::runtime::Runtime,
+    slots: Arc<Semaphore>,
+}
+
+static RUNNER: OnceCell<Runner> = OnceCell::new();
+
+/// Longest wait between two collections of expired runs.
+const GC_INTERVAL_MS: u64 = 60_000;
+
+fn runner() -> Result<&'static Runner> {
+    RUNNER.get_or_try_init(|| {
+        let cfg = config();
+        let rt = tokio::runtime::Builder::new_multi_thread().worker_threads(cfg.worker_threads.max(1)).thread_name("panther-agent").enable_all().build()?;
+        let every = Duration::from_millis(cfg.finished_ttl_ms.clamp(1_000, GC_INTERVAL_MS));
+        rt.spawn(async move {
+            let mut tick = tokio::time::interval(every);
+            tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                tick.tick().await;
+                // The run store may block
+                let _ = tokio::task::spawn_blocking(agent_gc).await;
+            }
+        });
+        Ok(Runner { rt, slots: Arc::new(Semaphore::new(cfg.max_concurrent_runs.max(1))) })
+    })
+}
+
+/// The shared runtime, also used by the blocking [`crate::run_plan`].
+pub(crate) fn runtime() -> Result<&'static tokio::runtime::Runtime> { Ok(&runner()?.rt) }
+
+static NEXT_RUN: AtomicU64 = AtomicU64::new(0);
+
+/// A fresh run id; the pid and a counter keep ids apart within one millisecond and across
+/// processes sharing a run store.
+pub(crate) fn new_run_id() -> String { format!("r{}-{}-{}", now_ms(), std::process::id(), NEXT_RUN.fetch_add(1, Ordering::Relaxed)) }
+
+/// Whole-run deadline for `input`.
+pub(crate) fn deadline(input: &AgentInput) -> Duration { Duration::from_millis(input.deadline_ms.unwrap_or(config().run_deadline_ms)) }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunStatus {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+    /// Left unfinished by a process that stopped; see [`agent_resume`].
+    Interrupted,
+}
+
+impl RunStatus {
+    pub fn is_finished(self) -> bool { !matches!(self, RunStatus::Queued | RunStatus::Running) }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RunStatus::Queued => "queued",
+            RunStatus::Running => "running",
+            RunStatus::Succeeded => "succeeded",
+            RunStatus::Failed => "failed",
+            RunStatus::Cancelled => "cancelled",
+            RunStatus::Interrupted => "interrupted",
+        }
+    }
+}
+
+/// What `agent_status` reports about a run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunInfo {
+    pub run_id: String,
+    pub status: RunStatus,
+    /// Why a failed run failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub started_ms: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_ms: Option<i64>,
+    pub events: usize,
+}
+
+struct RunState {
+    status: RunStatus,
+    error: Option<String>,
+    events: Vec<AgentEvent>,
+    outcome: Option<AgentOutcome>,
+    started_ts: i64,
+    finished_ts: Option<i64>,
+    cancel: CancellationToken,
+    seq: Arc<Sequence>,
+    /// Woken on every new event, for `agent_wait` and subscriptions.
+    changed: Arc<Notify>,
+}
+
+impl RunState {
+    fn new(status: RunStatus, events: Vec<AgentEvent>, started_ts: i64) -> Self {
+        let seq = Arc::new(Sequence::starting_at(events.len() as u64));
+        Self { status, error: None, events, outcome: None, started_ts, finished_ts: None, cancel: CancellationToken::new(), seq, changed: Arc::new(Notify::new()) }
+    }
+
+    fn push(&mut self, run_id: &str, ev: AgentEvent) {
+        if let Some(store) = RUN_STORE.get() {
+            store.append(run_id, &ev);
+        }
+        self.events.push(ev);
+        self.changed.notify_waiters();
+    }
+
+    fn info(&self, run_id: &str) -> RunInfo {
+        RunInfo { run_id: run_id.to_string(), status: self.status, error: self.error.clone(), started_ms: self.started_ts, finished_ms: self.finished_ts, events: self.events.len() }
+    }
+}
+
+// ---- Persistence ----
+
+struct RunStore {
+    store: Arc<dyn KeyValueStore>,
+    events: Arc<StoreSink>,
+    writer: mpsc::Sender<Append>,
+    /// Serializes read-modify-writes of the index; they do not run under the registry lock.
+    index_lock: Mutex<()>,
+}
+
+static RUN_STORE: OnceCell<RunStore> = OnceCell::new();
+
+/// Persists runs in `store` so they survive a restart and can be resumed.
+/// Must be called before the first run; without it runs only live in memory.
+pub fn set_run_store(store: Arc<dyn KeyValueStore>) -> bool {
+    let mut created = false;
+    RUN_STORE.get_or_init(|| {
+        created = true;
+        let events = Arc::new(StoreSink::new(store.clone()));
+        let (writer, queue) = mpsc::channel();
+        let sink = events.clone();
+        std::thread::Builder::new().name("panther-agent-events".into()).spawn(move || write_events(&sink, queue)).expect("spawn event writer");
+        RunStore { store, events, writer, index_lock: Mutex::new(()) }
+    });
+    created
+}
+
+enum Append {
+    Event(String, AgentEvent),
+    /// Answered once every event queued before it is written.
+    Synced(mpsc::Sender<()>),
+}
+
+// Events reach the store on this thread, so the registry lock never waits on disk; whatever was
+// queued while a batch was written goes out together in the next one, in emission order.
+fn write_events(sink: &StoreSink, queue: mpsc::Receiver<Append>) {
+    while let Ok(first) = queue.recv() {
+        let mut batch: Vec<(String, Vec<AgentEvent>)> = Vec::new();
+        let mut synced = Vec::new();
+        for append in std::iter::once(first).chain(queue.try_iter()) {
+            match append {
+                Append::Event(run_id, ev) => match batch.iter_mut().find(|(id, _)| *id == run_id) {
+                    Some((_, events)) => events.push(ev),
+                    None => batch.push((run_id, vec![ev])),
+                },
+                Append::Synced(done) => synced.push(done),
+            }
+        }
+        // A store failure must not fail the run; the registry still has the events
+        for (run_id, events) in &batch {
+            let _ = sink.append(run_id, events);
+        }
+        for done in synced {
+            let _ = done.send(());
+        }
+    }
+}
+
+const INDEX_KEY: &str = "agent_runs";
+
+fn state_key(run_id: &str) -> String { format!("agent_run:{}:state", run_id) }
+
+fn plan_key(run_id: &str) -> String { format!("agent_run:{}:plan", run_id) }
+
+fn checkpoint_key(run_id: &str) -> String { format!("agent_run:{}:checkpoint", run_id) }
+
+#[derive(Serialize, Deserialize)]
+struct StoredRun {
+    status: RunStatus,
+    error: Option<String>,
+    started_ms: i64,
+    finished_ms: Option<i64>,
+    outcome: Option<AgentOutcome>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredPlan {
+    plan: AgentPlan,
+    input: AgentInput,
+}
+
+impl RunStore {
+    fn append(&self, run_id: &str, ev: &AgentEvent) { let _ = self.writer.send(Append::Event(run_id.to_string(), ev.clone())); }
+
+    /// Waits until every event appended so far is in the store.
+    fn sync(&self) {
+        let (done, wait) = mpsc::channel();
+        if self.writer.send(Append::Synced(done)).is_ok() {
+            let _ = wait.recv();
+        }
+    }
+
+    fn get<T: serde::de::DeserializeOwned>(&self, key: &str) -> Result<Option<T>> { Ok(self.store.get(key)?.map(|s| serde_json::from_str(&s)).transpose()?) }
+
+    fn put<T: Serialize>(&self, key: &str, value: &T) -> Result<()> { self.store.set(key, serde_json::to_string(value)?) }
+
+    fn index(&self) -> Result<Vec<String>> { Ok(self.get(INDEX_KEY)?.unwrap_or_default()) }
+
+    fn create(&self, run_id: &str, plan: &AgentPlan, input: &AgentInput, st: &RunState) -> Result<()> {
+        // Credentials are never persisted; `agent_resume` takes them again
+        let (mut plan, mut input) = (plan.clone(), input.clone());
+        for p in &mut input.providers {
+            p.api_key = None;
+        }
+        for (_, anchor) in plan.anchors_mut() {
+            anchor.priv_key = None;
+        }
+        self.put(&plan_key(run_id), &StoredPlan { plan, input })?;
+        self.save(run_id, st)?;
+        let _guard = self.index_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut index = self.index()?;
+        index.push(run_id.to_string());
+        self.put(INDEX_KEY, &index)
+    }
+
+    fn unindex(&self, removed: &HashSet<String>) -> Result<()> {
+        let _guard = self.index_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let index = self.index()?;
+        if index.iter().any(|id| removed.contains(id)) {
+            self.put(INDEX_KEY, &index.into_iter().filter(|id| !removed.contains(id)).collect::<Vec<_>>())?;
+        }
+        Ok(())
+    }
+
+    fn save(&self, run_id: &str, st: &RunState) -> Result<()> {
+        let stored = StoredRun { status: st.status, error: st.error.clone(), started_ms: st.started_ts, finished_ms: st.finished_ts, outcome: st.outcome.clone() };
+        self.put(&state_key(run_id), &stored)
+    }
+
+    fn load(&self, run_id: &str) -> Result<Option<RunState>> {
+        let Some(stored) = self.get::<StoredRun>(&state_key(run_id))? else { return Ok(None) };
+        // Nothing in this process runs it, so an unfinished run was cut short by a restart
+        let status = if stored.status.is_finished() { stored.status } else { RunStatus::Interrupted };
+        let st = RunState { error: stored.error, outcome: stored.outcome, finished_ts: stored.finished_ms, ..RunState::new(status, self.events.events(run_id)?, stored.started_ms) };
+        Ok(Some(st))
+    }
+
+    fn remove(&self, run_id: &str) -> Result<()> {
+        // An append still queued would bring the events back
+        self.sync();
+        for key in [state_key(run_id), plan_key(run_id), checkpoint_key(run_id)] {
+            self.store.delete(&key)?;
+        }
+        self.events.clear(run_id)
+    }
+}
+
+// A store failure must not fail the run; the registry still has its state
+fn persist(run_id: &str, st: &RunState) {
+    if let Some(store) = RUN_STORE.get() {
+        let _ = store.save(run_id, st);
+    }
+}
+
+fn save_checkpoint(run_id: &str, checkpoint: &Checkpoint) {
+    if let Some(store) = RUN_STORE.get() {
+        let _ = store.put(&checkpoint_key(run_id), checkpoint);
+    }
+}
+
+// ---- Registry ----
+
+static RUNS: OnceCell<Mutex<HashMap<String, RunState>>> = OnceCell::new();
+
+fn runs() -> std::sync::MutexGuard<'static, HashMap<String, RunState>> {
+    RUNS.get_or_init(|| Mutex::new(HashMap::new())).lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn not_found(run_id: &str) -> anyhow::Error { anyhow!("run {} not found", run_id) }
+
+// Runs of an earlier process are loaded from the run store on first use
+fn with_run<T>(run_id: &str, f: impl FnOnce(&mut RunState) -> T) -> Result<T> {
+    let mut map = runs();
+    let st = match map.entry(run_id.to_string()) {
+        Entry::Occupied(e) => e.into_mut(),
+        Entry::Vacant(e) => {
+            let loaded = match RUN_STORE.get() {
+                Some(store) => store.load(run_id)?,
+                None => None,
+            };
+            e.insert(loaded.ok_or_else(|| not_found(run_id))?)
+        }
+    };
+    Ok(f(st))
+}
+
+pub(crate) fn changes(run_id: &str) -> Result<Arc<Notify>> { with_run(run_id, |st| st.changed.clone()) }
+
+// The run's final event is its last; a task still winding down after a cancel adds nothing
+pub(crate) fn push_event(run_id: &str, ev: AgentEvent) {
+    if let Some(st) = runs().get_mut(run_id).filter(|st| !st.status.is_finished()) {
+        st.push(run_id, ev);
+    }
+}
+
+fn set_running(run_id: &str) -> bool {
+    match runs().get_mut(run_id) {
+        Some(st) if st.status == RunStatus::Queued => {
+            st.status = RunStatus::Running;
+            persist(run_id, st);
+            true
+        }
+        _ => false,
+    }
+}
+
+// Only the first terminal state sticks, so a cancel racing the run's own end is reported once.
+// The final event is pushed together with the status, so whoever sees the run finished has all
+// of its events. `false` when the run had already finished.
+fn finish(run_id: &str, status: RunStatus, outcome: Option<AgentOutcome>, error: Option<String>) -> bool {
+    let Some(seq) = runs().get(run_id).map(|st| st.seq.clone()) else { return false };
+    let message = match &error {
+        Some(e) => format!("run {}: {}", status.as_str(), e),
+        None => format!("run {}", status.as_str()),
+    };
+    let mut ev = event(AgentStage::Run, message, Some(serde_json::json!({ "status": status, "error": error })));
+    let mut counter = seq.lock();
+    {
+        let mut map = runs();
+        let Some(st) = map.get_mut(run_id) else { return false };
+        if st.status.is_finished() && st.status != RunStatus::Interrupted {
+            return false;
+        }
+        let Some(n) = counter.take() else { return false };
+        st.status = status;
+        st.finished_ts = Some(now_ms());
+        st.outcome = outcome;
+        st.error = error;
+        persist(run_id, st);
+        ev.seq = n;
+        counter.close();
+        st.push(run_id, ev.clone());
+    }
+    emit_global(run_id, &ev);
+    drop(counter);
+    steps::forget_approvals(run_id);
+    true
+}
+
+pub fn agent_start(plan_json: &str, input_json: &str) -> Result<String> {
+    let plan: AgentPlan = serde_json::from_str(plan_json)?;
+    let input: AgentInput = serde_json::from_str(input_json)?;
+    // Reject a malformed DAG now rather than as a failed run
+    if let AgentPlan::Steps(p) = &plan {
+        p.order()?;
+    }
+    agent_gc();
+    start(runner()?, plan, input)
+}
+
+fn start(runner: &'static Runner, plan: AgentPlan, input: AgentInput) -> Result<String> {
+    let run_id = new_run_id();
+    let state = RunState::new(RunStatus::Queued, Vec::new(), now_ms());
+    let (cancel, seq) = (state.cancel.clone(), state.seq.clone());
+    // Nobody knows the id yet, so the store can be written before the registry lock is taken
+    if let Some(store) = RUN_STORE.get() {
+        store.create(&run_id, &plan, &input, &state)?;
+    }
+    runs().insert(run_id.clone(), state);
+    spawn(runner, run_id.clone(), plan, input, None, cancel, seq);
+    Ok(run_id)
+}
+
+/// Continues a run from its last checkpoint: recorded stages and steps are not run again and a
+/// sealed proof is reused. Any run that did not succeed can be resumed, typically an
+/// `interrupted` one after a restart. Credentials are never persisted, so pass the providers
+/// again (`providers_json`) when they need an API key, and `anchor_key` when the run has yet to
+/// anchor on Ethereum; it is used for every `eth` anchor of the plan.
+pub fn agent_resume(run_id: &str, providers_json: Option<&str>, anchor_key: Option<&str>) -> Result<()> {
+    let store = RUN_STORE.get().ok_or_else(|| anyhow!("no run store set; runs cannot be resumed"))?;
+    let StoredPlan { mut plan, mut input } = store.get(&plan_key(run_id))?.ok_or_else(|| not_found(run_id))?;
+    if let Some(providers) = providers_json {
+        input.providers = serde_json::from_str(providers)?;
+    }
+    let from: Checkpoint = store.get(&checkpoint_key(run_id))?.unwrap_or_default();
+    for (step, anchor) in plan.anchors_mut() {
+        if anchor.backend != BackendKind::Eth {
+            continue;
+        }
+        if let Some(key) = anchor_key {
+            anchor.priv_key = Some(Secret::new(key));
+        }
+        let done = match step {
+            Some(id) => from.outcome.steps.iter().any(|r| r.id == id),
+            None => from.outcome.anchored.is_some(),
+        };
+        if anchor.priv_key.is_none() && !done {
+            let at = step.map_or_else(|| "its anchor stage".to_string(), |id| format!("step '{}'", id));
+            bail!("run {} has yet to anchor on eth ({}) and its key is not persisted; pass it again to resume", run_id, at);
+        }
+    }
+    let runner = runner()?;
+    let cancel = CancellationToken::new();
+    let seq = with_run(run_id, |st| {
+        if matches!(st.status, RunStatus::Queued | RunStatus::Running | RunStatus::Succeeded) {
+            bail!("run {} is {}", run_id, st.status.as_str());
+        }
+        st.status = RunStatus::Queued;
+        st.error = None;
+        st.outcome = None;
+        st.finished_ts = None;
+        st.cancel = cancel.clone();
+        // Numbering goes on from the events the run has, whatever its old task still tried to emit
+        st.seq = Arc::new(Sequence::starting_at(st.events.len() as u64));
+        persist(run_id, st);
+        Ok(st.seq.clone())
+    })??;
+    spawn(runner, run_id.to_string(), plan, input, Some(from), cancel, seq);
+    Ok(())
+}
+
+fn spawn(runner: &'static Runner, id: String, plan: AgentPlan, input: AgentInput, from: Option<Checkpoint>, cancel: CancellationToken, seq: Arc<Sequence>) {
+    let deadline = deadline(&input);
+    let slots = runner.slots.clone();
+    runner.rt.spawn(async move {
+        let _slot = tokio::select! {
+            slot = slots.acquire_owned() => slot,
+            _ = cancel.cancelled() => return,
+        };
+        if !set_running(&id) {
+            return;
+        }
+        let message = if from.is_some() { "run resumed" } else { "run started" };
+        Emitter::new(&id, &RegistrySink, &seq).emit(event(AgentStage::Run, message, None));
+        let run_id = id.clone();
+        let mut task = tokio::spawn(async move {
+            let save = |checkpoint: &Checkpoint| save_checkpoint(&run_id, checkpoint);
+            execute(plan, input, Emitter::new(&run_id, &RegistrySink, &seq), from.unwrap_or_default(), Some(&save)).await
+        });
+        tokio::select! {
+            res = tokio::time::timeout(deadline, &mut task) => {
+                let (status, outcome, error) = match res {
+                    Ok(Ok(Ok(outcome))) => (RunStatus::Succeeded, Some(outcome), None),
+                    Ok(Ok(Err(e))) => (RunStatus::Failed, None, Some(format!("{:#}", e))),
+                    Ok(Err(e)) => (RunStatus::Failed, None, Some(format!("run aborted: {}", e))),
+                    Err(_) => {
+                        task.abort();
+                        (RunStatus::Failed, None, Some(format!("deadline of {} ms exceeded", deadline.as_millis())))
+                    }
+                };
+                finish(&id, status, outcome, error);
+            }
+            _ = cancel.cancelled() => task.abort(),
+        }
+    });
+}
+
+/// Cancels a queued, running or interrupted run; `false` when it had already finished.
+pub fn agent_cancel(run_id: &str) -> Result<bool> {
+    let cancel = with_run(run_id, |st| (!st.status.is_finished() || st.status == RunStatus::Interrupted).then(|| st.cancel.clone()))?;
+    let Some(cancel) = cancel else { return Ok(false) };
+    let cancelled = finish(run_id, RunStatus::Cancelled, None, None);
+    cancel.cancel();
+    Ok(cancelled)
+}
+
+pub fn agent_poll(run_id: &str, cursor: usize) -> Result<(Vec<AgentEvent>, bool, usize, String)> {
+    with_run(run_id, |st| {
+        let from = cursor.min(st.events.len());
+        let events = st.events[from..].to_vec();
+        let new_cursor = from + events.len();
+        (events, st.status.is_finished(), new_cursor, st.status.as_str().to_string())
+    })
+}
+
+pub fn agent_status(run_id: &str) -> Result<RunInfo> { with_run(run_id, |st| st.info(run_id)) }
+
+pub fn agent_result(run_id: &str) -> Result<Option<AgentOutcome>> { with_run(run_id, |st| st.outcome.clone()) }
+
+/// Every run in the registry or the run store, oldest first; after a restart this lists the
+/// `interrupted` runs to resume.
+pub fn agent_runs() -> Result<Vec<RunInfo>> {
+    let mut ids: BTreeSet<String> = runs().keys().cloned().collect();
+    if let Some(store) = RUN_STORE.get() {
+        ids.extend(store.index()?);
+    }
+    let mut out = ids.iter().map(|id| agent_status(id)).collect::<Result<Vec<_>>>()?;
+    out.sort_by_key(|r| r.started_ms);
+    Ok(out)
+}
+
+/// Decides the approval step `step_id` of an unfinished plan; a decision may arrive before the step starts waiting.
+pub fn agent_approve(run_id: &str, step_id: &str, approved: bool, note: Option<String>) -> Result<()> {
+    let status = agent_status(run_id)?.status;
+    if status.is_finished() {
+        bail!("run {} is {}", run_id, status.as_str());
+    }
+    steps::record_approval(run_id, step_id, approved, note);
+    Ok(())
+}
+
+/// Drops runs that finished more than `finished_ttl_ms` ago, from the registry and the run
+/// store; returns how many were removed.
+pub fn agent_gc() -> usize {
+    let ttl = config().finished_ttl_ms as i64;
+    let now = now_ms();
+    let expired = |finished: Option<i64>| finished.is_some_and(|t| now.saturating_sub(t) >= ttl);
+    // The registry lock only covers the map; the store work below syncs and writes to disk
+    let (mut removed, live) = {
+        let mut map = runs();
+        let removed: HashSet<String> = map.iter().filter(|(_, st)| expired(st.finished_ts)).map(|(id, _)| id.clone()).collect();
+        map.retain(|id, _| !removed.contains(id));
+        (removed, map.keys().cloned().collect::<HashSet<_>>())
+    };
+    if let Some(store) = RUN_STORE.get() {
+        let index = store.index().unwrap_or_default();
+        // Runs of earlier processes this one never loaded
+        for id in index.iter().filter(|id| !live.contains(*id)) {
+            if let Ok(Some(stored)) = store.get::<StoredRun>(&state_key(id)) {
+                if expired(stored.finished_ms) {
+                    removed.insert(id.clone());
+                }
+            }
+        }
+        for id in &removed {
+            let _ = store.remove(id);
+        }
+        let _ = store.unindex(&removed);
+    }
+    removed.len()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // Its own runtime and slots, so the limits don't depend on other tests' runs
+    fn test_runner(slots: usize) -> &'static Runner {
+        let rt = tokio::runtime::Builder::new_multi_thread().worker_threads(2).enable_all().build().unwrap();
+        Box::leak(Box::new(Runner { rt, slots: Arc::new(Semaphore::new(slots)) }))
+    }
+
+    fn run_input(deadline_ms: Option<u64>) -> AgentInput {
+        serde_json::from_value(json!({ "prompt": "p", "providers": [{ "type": "stub" }], "salt": null, "deadline_ms": deadline_ms })).unwrap()
+    }
+
+    // Waits at the approval gate until approved
+    fn gated() -> AgentPlan { serde_json::from_value(json!({ "type": "Steps", "steps": [{ "id": "gate", "type": "approval", "timeout_ms": 60_000 }] })).unwrap() }
+
+    fn quick() -> AgentPlan { serde_json::from_value(json!({ "type": "Steps", "steps": [{ "id": "note", "type": "store", "key": "runs-test", "value": "${input.prompt}" }] })).unwrap() }
+
+    async fn wait_for(run_id: &str, status: RunStatus) -> RunInfo {
+        for _ in 0..1_000 {
+            let info = agent_status(run_id).unwrap();
+            if info.status == status {
+                return info;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("run {} never became {}", run_id, status.as_str());
+    }
+
+    fn messages(run_id: &str) -> Vec<String> { agent_poll(run_id, 0).unwrap().0.into_iter().map(|e| e.message).collect() }
+
+    fn seqs(events: &[AgentEvent]) -> Vec<u64> { events.iter().map(|e| e.seq).collect() }
+
+    #[tokio::test]
+    async fn cancels_queued_and_running_runs() {
+        let runner = test_runner(1);
+        let running = start(runner, gated(), run_input(None)).unwrap();
+        wait_for(&running, RunStatus::Running).await;
+        let queued = start(runner, quick(), run_input(None)).unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(agent_status(&queued).unwrap().status, RunStatus::Queued);
+
+        assert!(agent_cancel(&queued).unwrap());
+        assert_eq!(agent_status(&queued).unwrap().status, RunStatus::Cancelled);
+        assert_eq!(messages(&queued), ["run cancelled"]);
+
+        assert!(agent_cancel(&running).unwrap());
+        assert!(!agent_cancel(&running).unwrap());
+        let events = messages(&running);
+        assert_eq!((events.first().map(String::as_str), events.last().map(String::as_str)), (Some("run started"), Some("run cancelled")));
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(messages(&running), events);
+        assert!(agent_approve(&running, "gate", true, None).is_err());
+
+        // Both slots were given back
+        let next = start(runner, quick(), run_input(None)).unwrap();
+        wait_for(&next, RunStatus::Succeeded).await;
+    }
+
+    #[tokio::test]
+    async fn runs_beyond_max_concurrent_wait_as_queued() {
+        let runner = test_runner(1);
+        let first = start(runner, gated(), run_input(None)).unwrap();
+        wait_for(&first, RunStatus::Running).await;
+        let second = start(runner, quick(), run_input(None)).unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(agent_status(&second).unwrap().status, RunStatus::Queued);
+        assert!(messages(&second).is_empty());
+
+        agent_approve(&first, "gate", true, None).unwrap();
+        let first = wait_for(&first, RunStatus::Succeeded).await;
+        wait_for(&second, RunStatus::Succeeded).await;
+        let started = agent_poll(&second, 0).unwrap().0.into_iter().find(|e| e.message == "run started").unwrap();
+        assert!(started.ts >= first.finished_ms.unwrap());
+    }
+
+    #[tokio::test]
+    async fn deadline_fails_the_run() {
+        let run_id = start(test_runner(1), gated(), run_input(Some(100))).unwrap();
+        let info = wait_for(&run_id, RunStatus::Failed).await;
+        assert_eq!(info.error.as_deref(), Some("deadline of 100 ms exceeded"));
+        assert_eq!(messages(&run_id).last().unwrap(), "run failed: deadline of 100 ms exceeded");
+    }
+
+    #[tokio::test]
+    async fn a_cancel_racing_the_finish_is_reported_once() {
+        let run_id = start(test_runner(1), gated(), run_input(None)).unwrap();
+        wait_for(&run_id, RunStatus::Running).await;
+        let wins = std::thread::scope(|s| {
+            let handles: Vec<_> = (0..8)
+                .map(|i| {
+                    let run_id = run_id.as_str();
+                    s.spawn(move || if i % 2 == 0 { agent_cancel(run_id).unwrap() } else { finish(run_id, RunStatus::Succeeded, Some(AgentOutcome::default()), None) })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).filter(|won| *won).count()
+        });
+        assert_eq!(wins, 1);
+        let finals = messages(&run_id).into_iter().filter(|m| m != "run started" && m.starts_with("run ")).count();
+        assert_eq!(finals, 1);
+        assert!(agent_status(&run_id).unwrap().status.is_finished());
+    }
+
+    #[tokio::test]
+    async fn gc_drops_only_expired_runs() {
+        let runner = test_runner(1);
+        let (old, recent) = (start(runner, quick(), run_input(None)).unwrap(), start(runner, quick(), run_input(None)).unwrap());
+        wait_for(&old, RunStatus::Succeeded).await;
+        wait_for(&recent, RunStatus::Succeeded).await;
+        let ttl = config().finished_ttl_ms as i64;
+        with_run(&old, |st| st.finished_ts = Some(now_ms() - ttl - 1)).unwrap();
+
+        assert!(agent_gc() >= 1);
+        assert!(agent_status(&old).is_err());
+        assert_eq!(agent_status(&recent).unwrap().status, RunStatus::Succeeded);
+    }
+
+    #[test]
+    fn run_ids_do_not_collide() {
+        let ids: Vec<String> = std::thread::scope(|s| {
+            let handles: Vec<_> = (0..4).map(|_| s.spawn(|| (0..2_500).map(|_| new_run_id()).collect::<Vec<_>>())).collect();
+            handles.into_iter().flat_map(|h| h.join().unwrap()).collect()
+        });
+        assert_eq!(ids.iter().collect::<HashSet<_>>().len(), ids.len());
+    }
+
+    fn run_store() -> &'static RunStore {
+        set_run_store(Arc::new(panther_storage::InMemoryStore::default()));
+        RUN_STORE.get().unwrap()
+    }
+
+    // What a process exit leaves behind: the task stops and the registry forgets the run
+    fn interrupt(run_id: &str) {
+        let st = runs().remove(run_id).unwrap();
+        st.cancel.cancel();
+        run_store().sync();
+    }
+
+    async fn wait_event(run_id: &str, message: &str) {
+        for _ in 0..1_000 {
+            if messages(run_id).iter().any(|m| m == message) {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("run {} never emitted '{}'", run_id, message);
+    }
+
+    fn steps_plan(steps: serde_json::Value) -> AgentPlan { serde_json::from_value(json!({ "type": "Steps", "steps": steps })).unwrap() }
+
+    #[tokio::test]
+    async fn a_resumed_run_keeps_recorded_steps_and_its_sealed_proof() {
+        run_store();
+        let plan = steps_plan(json!([
+            { "id": "draft", "type": "generate", "prompt": "Gestante: ${input.prompt}" },
+            { "id": "check", "type": "validate", "depends_on": ["draft"] },
+            { "id": "seal", "type": "seal", "depends_on": ["check"] },
+            { "id": "gate", "type": "approval", "depends_on": ["seal"], "timeout_ms": 60_000 },
+            { "id": "done", "type": "store", "depends_on": ["gate"], "key": "resume-test", "value": "${seal.combined_hash}" },
+        ]));
+        let run_id = start(test_runner(1), plan, run_input(None)).unwrap();
+        wait_event(&run_id, "awaiting approval").await;
+        let before = agent_poll(&run_id, 0).unwrap().0;
+        interrupt(&run_id);
+
+        let info = agent_status(&run_id).unwrap();
+        assert_eq!((info.status, info.events), (RunStatus::Interrupted, before.len()));
+        assert!(agent_runs().unwrap().iter().any(|r| r.run_id == run_id && r.status == RunStatus::Interrupted));
+
+        agent_resume(&run_id, None, None).unwrap();
+        agent_approve(&run_id, "gate", true, None).unwrap();
+        wait_for(&run_id, RunStatus::Succeeded).await;
+        let events = agent_poll(&run_id, 0).unwrap().0;
+        assert_eq!(seqs(&events), (0..events.len() as u64).collect::<Vec<_>>());
+        let resumed = &events[before.len()..];
+        assert_eq!(resumed[0].message, "run resumed");
+        assert_eq!(resumed[1].message, "resuming after 3 recorded steps");
+        let started: Vec<&str> = resumed.iter().filter(|e| e.message == "step started").filter_map(|e| e.step.as_deref()).collect();
+        assert_eq!(started, ["gate", "done"]);
+        assert_eq!(events.iter().filter(|e| e.message == "computing proof").count(), 1);
+
+        let sealed = before.iter().find(|e| e.message == "proof computed").and_then(|e| e.data.clone()).unwrap();
+        let outcome = agent_result(&run_id).unwrap().unwrap();
+        assert_eq!(outcome.proof.map(|p| p.combined_hash), sealed["combined_hash"].as_str().map(str::to_string));
+        assert_eq!(outcome.steps.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), ["draft", "check", "seal", "gate", "done"]);
+    }
+
+    #[tokio::test]
+    async fn a_resumed_pipeline_reuses_its_proof() {
+        let store = run_store();
+        // The anchor fails while the ledger's directory is a file, after validate and seal are checkpointed
+        let dir = std::env::temp_dir().join(format!("panther-resume-{}", new_run_id()));
+        std::fs::write(&dir, "").unwrap();
+        let plan: AgentPlan = serde_json::from_value(json!({ "type": "ValidateSealAnchor", "guidelines_json": null, "timeouts_ms": null, "retries": null,
+            "anchor": { "backend": "file", "ledger_path": dir.join("ledger.jsonl") } }))
+        .unwrap();
+        let run_id = start(test_runner(1), plan, run_input(None)).unwrap();
+        wait_for(&run_id, RunStatus::Failed).await;
+        let checkpoint: Checkpoint = store.get(&checkpoint_key(&run_id)).unwrap().unwrap();
+        let sealed = checkpoint.outcome.proof.unwrap().combined_hash;
+
+        std::fs::remove_file(&dir).unwrap();
+        agent_resume(&run_id, None, None).unwrap();
+        wait_for(&run_id, RunStatus::Succeeded).await;
+        let messages = messages(&run_id);
+        assert!(messages.iter().any(|m| m == "validation restored from checkpoint"));
+        assert!(messages.iter().any(|m| m == "proof restored from checkpoint"));
+        assert_eq!(messages.iter().filter(|m| *m == "computing proof").count(), 1);
+        let outcome = agent_result(&run_id).unwrap().unwrap();
+        assert_eq!((outcome.proof.unwrap().combined_hash, outcome.anchored), (sealed, Some(true)));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn a_resumed_anchor_is_not_sent_again() {
+        let store = run_store();
+        let dir = std::env::temp_dir().join(format!("panther-resend-{}", new_run_id()));
+        std::fs::write(&dir, "").unwrap();
+        let ledger = dir.join("ledger.jsonl").display().to_string();
+        let hash = "cd".repeat(64);
+        let plan = steps_plan(json!([{ "id": "chain", "type": "anchor", "hash": hash, "anchor": { "backend": "file", "ledger_path": ledger } }]));
+        let run_id = start(test_runner(1), plan, run_input(None)).unwrap();
+        wait_for(&run_id, RunStatus::Failed).await;
+        // Recorded before the anchor was sent
+        let checkpoint: Checkpoint = store.get(&checkpoint_key(&run_id)).unwrap().unwrap();
+        assert_eq!(checkpoint.anchoring.as_deref(), Some(hash.as_str()));
+
+        // The send went through after all, and only the process died before recording it
+        std::fs::remove_file(&dir).unwrap();
+        let backend = panther_validation::anchor::AnchorConfig::file(ledger.clone()).backend().unwrap();
+        let sent = backend.anchor(&hash).await.unwrap();
+        let before = messages(&run_id).len();
+        agent_resume(&run_id, None, None).unwrap();
+        wait_for(&run_id, RunStatus::Succeeded).await;
+        let resumed = messages(&run_id).split_off(before);
+        assert!(resumed.iter().any(|m| m == "anchor already submitted"), "{:?}", resumed);
+        assert!(!resumed.iter().any(|m| m.starts_with("anchoring via")), "{:?}", resumed);
+        assert_eq!(agent_result(&run_id).unwrap().unwrap().receipt, Some(sent));
+        assert_eq!(std::fs::read_to_string(&ledger).unwrap().lines().count(), 1);
+        let checkpoint: Checkpoint = store.get(&checkpoint_key(&run_id)).unwrap().unwrap();
+        assert_eq!(checkpoint.anchoring, None);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn a_cancelled_run_numbers_no_stray_events_and_resumes_in_sequence() {
+        run_store();
+        let run_id = start(test_runner(1), gated(), run_input(None)).unwrap();
+        wait_event(&run_id, "awaiting approval").await;
+        let seq = runs().get(&run_id).unwrap().seq.clone();
+        assert!(agent_cancel(&run_id).unwrap());
+        // What the aborted task may still emit
+        Emitter::new(&run_id, &RegistrySink, &seq).emit(event(AgentStage::Approval, "late", None));
+        assert_eq!(seq.lock().take(), None);
+        assert_eq!(messages(&run_id).last().unwrap(), "run cancelled");
+
+        agent_resume(&run_id, None, None).unwrap();
+        agent_approve(&run_id, "gate", true, None).unwrap();
+        wait_for(&run_id, RunStatus::Succeeded).await;
+        let events = agent_poll(&run_id, 0).unwrap().0;
+        assert_eq!(seqs(&events), (0..events.len() as u64).collect::<Vec<_>>());
+        assert!(events.iter().all(|e| e.message != "late"));
+    }
+
+    #[tokio::test]
+    async fn anchor_keys_are_not_stored_and_resume_asks_for_them() {
+        let store = run_store();
+        let plan = steps_plan(json!([
+            { "id": "gate", "type": "approval", "timeout_ms": 60_000 },
+            { "id": "chain", "type": "anchor", "depends_on": ["gate"], "hash": "ab", "anchor": { "backend": "eth", "rpc_url": "http://127.0.0.1:1", "contract_addr": "0x0", "priv_key": "0xfeed" } },
+        ]));
+        let run_id = start(test_runner(1), plan, run_input(None)).unwrap();
+        let stored = store.store.get(&plan_key(&run_id)).unwrap().unwrap();
+        assert!(!stored.contains("priv_key") && !stored.contains("0xfeed"), "{}", stored);
+        wait_event(&run_id, "awaiting approval").await;
+        interrupt(&run_id);
+
+        let err = agent_resume(&run_id, None, None).unwrap_err().to_string();
+        assert!(err.contains("has yet to anchor on eth (step 'chain')"), "{}", err);
+        assert_eq!(agent_status(&run_id).unwrap().status, RunStatus::Interrupted);
+        agent_resume(&run_id, None, Some("0xfeed")).unwrap();
+        assert!(agent_cancel(&run_id).unwrap());
+    }
+}
@@ -0,0 +1,309 @@
+//! Where agent events go.
+//!
+//! A run emits every event through one [`AgentEventSink`]: the blocking API collects them in a
+//! [`MemorySink`], `agent_start` appends them to the run registry read by `agent_poll`, and
+//! [`Fanout`] sends the same events to several sinks (a channel, telemetry, a store). Sinks
+//! added with [`add_event_sink`] receive the events of every run. Events are numbered per run
+//! (`seq`) and every sink receives them in that order.
+
+use super::AgentEvent;
+use panther_domain::entities::TraceEvent;
+use panther_domain::ports::{KeyValueStore, TelemetrySink};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+
+pub trait AgentEventSink: Send + Sync {
+    fn emit(&self, run_id: &str, event: &AgentEvent);
+}
+
+/// Keeps the events in memory, in order.
+#[derive(Default)]
+pub struct MemorySink {
+    events: Mutex<Vec<AgentEvent>>,
+}
+
+impl MemorySink {
+    pub fn events(&self) -> Vec<AgentEvent> { self.events.lock().unwrap_or_else(|e| e.into_inner()).clone() }
+
+    pub fn take(&self) -> Vec<AgentEvent> { std::mem::take(&mut *self.events.lock().unwrap_or_else(|e| e.into_inner())) }
+}
+
+impl AgentEventSink for MemorySink {
+    fn emit(&self, _run_id: &str, event: &AgentEvent) { self.events.lock().unwrap_or_else(|e| e.into_inner()).push(event.clone()); }
+}
+
+/// Appends to the global run registry behind `agent_poll`; runs not started there are ignored.
+pub struct RegistrySink;
+
+impl AgentEventSink for RegistrySink {
+    fn emit(&self, run_id: &str, event: &AgentEvent) { super::runs::push_event(run_id, event.clone()); }
+}
+
+/// Sends each event down an unbounded channel; a closed receiver drops them.
+pub struct ChannelSink(tokio::sync::mpsc::UnboundedSender<AgentEvent>);
+
+impl ChannelSink {
+    pub fn new() -> (Self, tokio::sync::mpsc::UnboundedReceiver<AgentEvent>) {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        (Self(tx), rx)
+    }
+}
+
+impl AgentEventSink for ChannelSink {
+    fn emit(&self, _run_id: &str, event: &AgentEvent) { let _ = self.0.send(event.clone()); }
+}
+
+/// Forwards events to a [`TelemetrySink`] as `agent.<stage>` trace events.
+pub struct TelemetryEventSink(pub Arc<dyn TelemetrySink>);
+
+impl AgentEventSink for TelemetryEventSink {
+    fn emit(&self, run_id: &str, event: &AgentEvent) {
+        self.0.record(TraceEvent {
+            name: format!("agent.{}", event.stage),
+            message: event.message.clone(),
+            timestamp_ms: event.ts,
+            attributes: serde_json::json!({ "run_id": run_id, "step": event.step, "data": event.data }),
+        });
+    }
+}
+
+/// Persists events in a [`KeyValueStore`], one key per event, so they outlive the process.
+pub struct StoreSink {
+    store: Arc<dyn KeyValueStore>,
+    // Serializes the count's read-modify-write within this process
+    lock: Mutex<()>,
+}
+
+fn count_key(run_id: &str) -> String { format!("agent_run:{}:events", run_id) }
+
+fn event_key(run_id: &str, n: usize) -> String { format!("agent_run:{}:event:{:06}", run_id, n) }
+
+impl StoreSink {
+    pub fn new(store: Arc<dyn KeyValueStore>) -> Self { Self { store, lock: Mutex::new(()) } }
+
+    fn count(&self, run_id: &str) -> anyhow::Result<usize> { Ok(self.store.get(&count_key(run_id))?.and_then(|c| c.parse().ok()).unwrap_or(0)) }
+
+    /// Appends `events` to those stored for `run_id` in one store write.
+    pub(crate) fn append(&self, run_id: &str, events: &[AgentEvent]) -> anyhow::Result<()> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let n = self.count(run_id)?;
+        let mut entries = events.iter().enumerate().map(|(i, ev)| Ok((event_key(run_id, n + i), serde_json::to_string(ev)?))).collect::<anyhow::Result<Vec<_>>>()?;
+        entries.push((count_key(run_id), (n + events.len()).to_string()));
+        self.store.set_many(entries)
+    }
+
+    /// The events stored for `run_id`, in order.
+    pub fn events(&self, run_id: &str) -> anyhow::Result<Vec<AgentEvent>> {
+        let mut out = Vec::new();
+        for n in 0..self.count(run_id)? {
+            if let Some(s) = self.store.get(&event_key(run_id, n))? {
+                out.push(serde_json::from_str(&s)?);
+            }
+        }
+        Ok(out)
+    }
+
+    /// Deletes the events stored for `run_id`.
+    pub fn clear(&self, run_id: &str) -> anyhow::Result<()> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        for n in 0..self.count(run_id)? {
+            self.store.delete(&event_key(run_id, n))?;
+        }
+        self.store.delete(&count_key(run_id))
+    }
+}
+
+impl AgentEventSink for StoreSink {
+    // A store failure must not fail the run; the event still reaches the other sinks
+    fn emit(&self, run_id: &str, event: &AgentEvent) { let _ = self.append(run_id, std::slice::from_ref(event)); }
+}
+
+/// Emits to every sink, in order.
+#[derive(Default, Clone)]
+pub struct Fanout(pub Vec<Arc<dyn AgentEventSink>>);
+
+impl Fanout {
+    pub fn with(mut self, sink: Arc<dyn AgentEventSink>) -> Self {
+        self.0.push(sink);
+        self
+    }
+}
+
+impl AgentEventSink for Fanout {
+    fn emit(&self, run_id: &str, event: &AgentEvent) {
+        for s in &self.0 {
+            s.emit(run_id, event);
+        }
+    }
+}
+
+static GLOBAL_SINKS: RwLock<Vec<Arc<dyn AgentEventSink>>> = RwLock::new(Vec::new());
+
+/// Adds a sink that receives the events of every run, e.g. telemetry or a persistent store.
+pub fn add_event_sink(sink: Arc<dyn AgentEventSink>) { GLOBAL_SINKS.write().unwrap_or_else(|e| e.into_inner()).push(sink); }
+
+pub(crate) fn emit_global(run_id: &str, event: &AgentEvent) {
+    for s in GLOBAL_SINKS.read().unwrap_or_else(|e| e.into_inner()).iter() {
+        s.emit(run_id, event);
+    }
+}
+
+/// Numbers the events of one run. Emitting while holding it keeps every sink in `seq` order.
+#[derive(Default)]
+pub(crate) struct Sequence(Mutex<Counter>);
+
+/// Next `seq` of a run; closed once the run has its final event, so a task still winding down
+/// after a cancel neither emits nor skips a number.
+#[derive(Default)]
+pub(crate) struct Counter {
+    next: u64,
+    closed: bool,
+}
+
+impl Counter {
+    /// Takes the next number; `None` once closed.
+    pub fn take(&mut self) -> Option<u64> {
+        if self.closed {
+            return None;
+        }
+        self.next += 1;
+        Some(self.next - 1)
+    }
+
+    pub fn close(&mut self) { self.closed = true; }
+}
+
+impl Sequence {
+    pub fn starting_at(next: u64) -> Self { Self(Mutex::new(Counter { next, closed: false })) }
+
+    pub fn lock(&self) -> MutexGuard<'_, Counter> { self.0.lock().unwrap_or_else(|e| e.into_inner()) }
+}
+
+/// Emits the events of one run, tagging them with the step that produced them.
+#[derive(Clone, Copy)]
+pub(crate) struct Emitter<'a> {
+    pub run_id: &'a str,
+    step: Option<&'a str>,
+    sink: &'a dyn AgentEventSink,
+    seq: &'a Sequence,
+}
+
+impl<'a> Emitter<'a> {
+    pub fn new(run_id: &'a str, sink: &'a dyn AgentEventSink, seq: &'a Sequence) -> Self { Self { run_id, step: None, sink, seq } }
+
+    pub fn for_step(self, step: &'a str) -> Self { Self { step: Some(step), ..self } }
+
+    pub fn emit(&self, mut event: AgentEvent) {
+        if event.step.is_none() {
+            event.step = self.step.map(str::to_string);
+        }
+        let mut counter = self.seq.lock();
+        let Some(seq) = counter.take() else { return };
+        event.seq = seq;
+        self.sink.emit(self.run_id, &event);
+        emit_global(self.run_id, &event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{agent_poll, agent_start, event, run_plan, AgentStage};
+    use panther_storage::InMemoryStore;
+    use serde_json::json;
+
+    fn seqs(events: &[AgentEvent]) -> Vec<u64> { events.iter().map(|e| e.seq).collect() }
+
+    #[test]
+    fn every_sink_sees_consecutive_seqs_in_order() {
+        let memory = Arc::new(MemorySink::default());
+        let (channel, mut rx) = ChannelSink::new();
+        let store = Arc::new(StoreSink::new(Arc::new(InMemoryStore::default())));
+        let fanout = Fanout::default().with(memory.clone()).with(Arc::new(channel)).with(store.clone());
+        let seq = Sequence::default();
+        let emit = Emitter::new("r1", &fanout, &seq);
+        std::thread::scope(|s| {
+            for t in 0..4 {
+                s.spawn(move || (0..25).for_each(|i| emit.for_step("s").emit(event(AgentStage::Steps, format!("{}-{}", t, i), None))));
+            }
+        });
+
+        let expected: Vec<u64> = (0..100).collect();
+        let received = std::iter::from_fn(|| rx.try_recv().ok()).collect::<Vec<_>>();
+        for events in [memory.events(), received, store.events("r1").unwrap()] {
+            assert_eq!(seqs(&events), expected);
+            assert!(events.iter().all(|e| e.step.as_deref() == Some("s")));
+        }
+        assert_eq!(seq.lock().take(), Some(100));
+    }
+
+    #[test]
+    fn fanout_delivers_each_event_to_every_sink() {
+        let sinks: Vec<Arc<MemorySink>> = (0..3).map(|_| Arc::new(MemorySink::default())).collect();
+        let fanout = Fanout(sinks.iter().map(|s| s.clone() as Arc<dyn AgentEventSink>).collect());
+        let seq = Sequence::starting_at(7);
+        Emitter::new("r1", &fanout, &seq).emit(event(AgentStage::Run, "one", None));
+        Emitter::new("r1", &fanout, &seq).emit(event(AgentStage::Run, "two", None));
+        for s in &sinks {
+            let got: Vec<(u64, String)> = s.events().into_iter().map(|e| (e.seq, e.message)).collect();
+            assert_eq!(got, [(7, "one".to_string()), (8, "two".to_string())]);
+        }
+        assert!(Fanout::default().0.is_empty());
+    }
+
+    #[test]
+    fn store_sink_round_trips_and_clears_one_run() {
+        let kv: Arc<dyn KeyValueStore> = Arc::new(InMemoryStore::default());
+        let sink = StoreSink::new(kv.clone());
+        for (run, n) in [("a", 3), ("b", 2)] {
+            for i in 0..n {
+                let mut ev = event(AgentStage::Validate, format!("{}{}", run, i), Some(json!({ "i": i })));
+                ev.seq = i;
+                sink.emit(run, &ev);
+            }
+        }
+        let a = sink.events("a").unwrap();
+        assert_eq!(a.iter().map(|e| e.message.as_str()).collect::<Vec<_>>(), ["a0", "a1", "a2"]);
+        assert_eq!((a[2].seq, a[2].stage, a[2].data.clone()), (2, AgentStage::Validate, Some(json!({ "i": 2 }))));
+
+        sink.clear("a").unwrap();
+        assert!(sink.events("a").unwrap().is_empty());
+        assert_eq!(kv.get("agent_run:a:event:000000").unwrap(), None);
+        assert_eq!(sink.events("b").unwrap().len(), 2);
+        // A cleared run starts over at the first key
+        sink.emit("a", &event(AgentStage::Run, "again", None));
+        assert_eq!(sink.events("a").unwrap().iter().map(|e| e.message.as_str()).collect::<Vec<_>>(), ["again"]);
+    }
+
+    // What an event says, without when it was emitted
+    fn shape(e: &AgentEvent) -> (u64, AgentStage, String, Option<String>, Option<serde_json::Value>) { (e.seq, e.stage, e.message.clone(), e.step.clone(), e.data.clone()) }
+
+    #[test]
+    fn blocking_and_started_runs_emit_the_same_events() {
+        let plan = json!({ "type": "Steps", "steps": [
+            { "id": "draft", "type": "generate", "prompt": "About ${input.prompt}" },
+            { "id": "long", "type": "branch", "depends_on": ["draft"], "condition": { "ref": "draft.text", "op": "contains", "value": "sinks" }, "then": ["keep"] },
+            { "id": "keep", "type": "store", "depends_on": ["long"], "key": "sink-test", "value": "${draft.text}" },
+        ] })
+        .to_string();
+        let input = json!({ "prompt": "event sinks", "providers": [{ "type": "stub" }], "salt": "s" }).to_string();
+
+        let blocking = run_plan(&plan, &input).unwrap().events;
+        let run_id = agent_start(&plan, &input).unwrap();
+        let started = loop {
+            let (events, done, _, status) = agent_poll(&run_id, 0).unwrap();
+            if done {
+                assert_eq!(status, "succeeded");
+                break events;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        };
+
+        // `agent_start` wraps the same events in "run started" and the final status event
+        assert_eq!(started.len(), blocking.len() + 2);
+        assert_eq!(seqs(&started), (0..started.len() as u64).collect::<Vec<_>>());
+        assert_eq!((started[0].stage, started[0].message.as_str()), (AgentStage::Run, "run started"));
+        assert_eq!(started.last().unwrap().message, "run succeeded");
+        let shifted: Vec<_> = blocking.iter().map(|e| AgentEvent { seq: e.seq + 1, ..e.clone() }).collect();
+        assert_eq!(started[1..=blocking.len()].iter().map(shape).collect::<Vec<_>>(), shifted.iter().map(shape).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,862 @@
+//! Step-based agent plans.
+//!
+//! A [`StepPlan`] lists steps wired into a DAG by `depends_on`. Steps run one at a time in
+//! dependency order (declaration order breaks ties), each with its own timeout and retries.
+//! String fields may reference earlier outputs with `${step_id.path}` templates, where the path
+//! walks object keys and array indices (`${validate.results.0.adherence_score}`), `input.*`
+//! is the run input and `|` separates fallbacks (`${rewrite.text|draft.text}`). A template that
+//! is exactly one reference keeps the referenced JSON value; anything else is interpolated.
+//!
+//! A step is skipped when a branch did not take it, when its `when` condition is false, or when
+//! every step it depends on was skipped, so whole sub-graphs drop out behind a branch.
+//!
+//! A resumed run keeps the steps its checkpoint recorded, outputs included, and starts at the
+//! first step without a record; a sealed proof is never computed twice.
+
+use super::{
+    agent_store, anchor_stage, budget_for_run, do_validate, ensure_within_budget, event, now_ms, text_provider, AgentEvent, AgentInput, AgentOutcome, AgentStage, Emitter,
+    AnchorCfg, Checkpoint, SaveCheckpoint, DEFAULT_GUIDELINES_JSON,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use once_cell::sync::OnceCell;
+use panther_core::budget::{estimate_tokens, BudgetConfig, BudgetCtl};
+use panther_domain::entities::Prompt;
+use panther_validation::pack::GuidelinePack;
+use panther_validation::scoring::{score_guidelines, Scored, ScoringConfig};
+use panther_validation::ValidationResult;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::Duration;
+
+pub const DEFAULT_STEP_TIMEOUT_MS: u64 = 30_000;
+/// How long an approval gate waits before applying its `on_timeout` decision.
+pub const DEFAULT_APPROVAL_TIMEOUT_MS: u64 = 3_600_000;
+const STATUS_TIMEOUT_MS: u64 = 5_000;
+const APPROVAL_POLL_MS: u64 = 250;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepPlan {
+    pub steps: Vec<Step>,
+    /// Guidelines (pack or legacy array, JSON or YAML) for steps that don't set their own;
+    /// defaults to the built-in ANVISA set.
+    #[serde(default)]
+    pub guidelines_json: Option<String>,
+    /// Spending caps for every provider call the plan makes.
+    #[serde(default)]
+    pub budgets: Option<BudgetConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Step {
+    pub id: String,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// Per attempt; defaults to [`DEFAULT_STEP_TIMEOUT_MS`] ([`DEFAULT_APPROVAL_TIMEOUT_MS`] for approvals).
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    /// Extra attempts after the first failure (approvals are never retried).
+    #[serde(default)]
+    pub retries: Option<u32>,
+    /// Runs the step only when this holds.
+    #[serde(default)]
+    pub when: Option<Condition>,
+    #[serde(flatten)]
+    pub action: StepAction,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StepAction {
+    /// Completes `prompt` with `provider` (`type:model`, default the first configured one).
+    Generate {
+        prompt: String,
+        #[serde(default)]
+        provider: Option<String>,
+    },
+    /// With `text`, scores it against the guidelines; otherwise asks every provider `prompt`
+    /// (default the run's prompt) and scores the answers, like `ValidateSealAnchor`.
+    Validate {
+        #[serde(default)]
+        prompt: Option<String>,
+        #[serde(default)]
+        text: Option<String>,
+        #[serde(default)]
+        guidelines_json: Option<String>,
+    },
+    /// Content metrics are 0..1; `adherence` is the 0..100 guideline score.
+    Score {
+        metric: Metric,
+        text: String,
+        #[serde(default)]
+        reference: Option<String>,
+        #[serde(default)]
+        facts: Vec<String>,
+        #[serde(default)]
+        guidelines_json: Option<String>,
+    },
+    /// Runs `then` when the condition holds and `else` otherwise; both list steps that depend on this one.
+    Branch {
+        condition: Condition,
+        #[serde(default)]
+        then: Vec<String>,
+        #[serde(default, rename = "else")]
+        otherwise: Vec<String>,
+    },
+    /// Asks a provider to rewrite `text` following `instructions`.
+    Rewrite {
+        text: String,
+        instructions: String,
+        #[serde(default)]
+        provider: Option<String>,
+    },
+    /// Waits for [`crate::agent_approve`]; a rejection fails the run.
+    Approval {
+        #[serde(default)]
+        message: Option<String>,
+        #[serde(default)]
+        on_timeout: Decision,
+    },
+    /// Computes the proof over a prompt-mode validate step (`from`, default the latest one).
+    Seal {
+        #[serde(default)]
+        from: Option<String>,
+    },
+    /// Anchors `hash` (default the sealed proof's combined hash).
+    Anchor {
+        #[serde(default)]
+        hash: Option<String>,
+        anchor: AnchorCfg,
+    },
+    /// POSTs `body` as JSON (default the run id and every step output so far).
+    Webhook {
+        url: String,
+        #[serde(default)]
+        headers: BTreeMap<String, String>,
+        #[serde(default)]
+        body: Option<Value>,
+    },
+    /// Writes `value` as JSON under `agent:<key>` in the store set with [`crate::set_agent_store`].
+    Store { key: String, value: Value },
+}
+
+impl StepAction {
+    pub fn kind(&self) -> &'static str { self.stage().as_str() }
+
+    pub fn stage(&self) -> AgentStage {
+        match self {
+            StepAction::Generate { .. } => AgentStage::Generate,
+            StepAction::Validate { .. } => AgentStage::Validate,
+            StepAction::Score { .. } => AgentStage::Score,
+            StepAction::Branch { .. } => AgentStage::Branch,
+            StepAction::Rewrite { .. } => AgentStage::Rewrite,
+            StepAction::Approval { .. } => AgentStage::Approval,
+            StepAction::Seal { .. } => AgentStage::Seal,
+            StepAction::Anchor { .. } => AgentStage::Anchor,
+            StepAction::Webhook { .. } => AgentStage::Webhook,
+            StepAction::Store { .. } => AgentStage::Store,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    Bleu,
+    RougeL,
+    Accuracy,
+    Coherence,
+    Fluency,
+    FactCoverage,
+    Adherence,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Decision {
+    Approve,
+    #[default]
+    Reject,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Condition {
+    /// Path to the value tested, as inside a template (`score.value`, `input.tenant`).
+    #[serde(rename = "ref")]
+    pub reference: String,
+    pub op: ConditionOp,
+    /// Compared value; strings may be templates.
+    #[serde(default)]
+    pub value: Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConditionOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// Substring of a string, or element of an array.
+    Contains,
+    /// The path resolves to a non-null value.
+    Exists,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StepStatus {
+    Completed,
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepRecord {
+    pub id: String,
+    pub kind: String,
+    pub status: StepStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<Value>,
+    /// Why a skipped step did not run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    pub started_ms: i64,
+    pub finished_ms: i64,
+}
+
+impl StepPlan {
+    /// Steps in execution order; rejects duplicate ids, unknown dependencies or branch targets, and cycles.
+    pub fn order(&self) -> Result<Vec<&Step>> {
+        let index: HashMap<&str, usize> = self.steps.iter().enumerate().map(|(i, s)| (s.id.as_str(), i)).collect();
+        if index.len() != self.steps.len() {
+            let mut seen = HashSet::new();
+            let dup = self.steps.iter().find(|s| !seen.insert(&s.id)).map(|s| s.id.as_str()).unwrap_or_default();
+            bail!("duplicate step id '{}'", dup);
+        }
+        for s in &self.steps {
+            if s.id.is_empty() || s.id == "input" || s.id.contains(['.', '|', '$', '{', '}']) {
+                bail!("invalid step id '{}'", s.id);
+            }
+            if let Some(d) = s.depends_on.iter().find(|d| !index.contains_key(d.as_str())) {
+                bail!("step '{}' depends on unknown step '{}'", s.id, d);
+            }
+            if let StepAction::Branch { then, otherwise, .. } = &s.action {
+                for t in then.iter().chain(otherwise) {
+                    let target = index.get(t.as_str()).map(|i| &self.steps[*i]).ok_or_else(|| anyhow!("branch '{}' targets unknown step '{}'", s.id, t))?;
+                    if !target.depends_on.contains(&s.id) {
+                        bail!("branch target '{}' must depend on branch '{}'", t, s.id);
+                    }
+                }
+            }
+        }
+
+        let mut pending: Vec<usize> = self.steps.iter().map(|s| s.depends_on.len()).collect();
+        let mut done = vec![false; self.steps.len()];
+        let mut order = Vec::with_capacity(self.steps.len());
+        while let Some(i) = (0..self.steps.len()).find(|i| !done[*i] && pending[*i] == 0) {
+            done[i] = true;
+            order.push(&self.steps[i]);
+            for (j, s) in self.steps.iter().enumerate() {
+                pending[j] -= s.depends_on.iter().filter(|d| **d == self.steps[i].id).count();
+            }
+        }
+        if order.len() != self.steps.len() {
+            let stuck: Vec<&str> = self.steps.iter().zip(&done).filter(|(_, d)| !**d).map(|(s, _)| s.id.as_str()).collect();
+            bail!("steps form a cycle: {}", stuck.join(", "));
+        }
+        Ok(order)
+    }
+}
+
+// ---- Approvals ----
+
+#[derive(Debug, Clone)]
+struct Approval {
+    approved: bool,
+    note: Option<String>,
+}
+
+static APPROVALS: OnceCell<Mutex<HashMap<(String, String), Approval>>> = OnceCell::new();
+
+fn approvals() -> &'static Mutex<HashMap<(String, String), Approval>> { APPROVALS.get_or_init(|| Mutex::new(HashMap::new())) }
+
+pub(crate) fn record_approval(run_id: &str, step_id: &str, approved: bool, note: Option<String>) {
+    let mut map = approvals().lock().unwrap_or_else(|e| e.into_inner());
+    map.insert((run_id.to_string(), step_id.to_string()), Approval { approved, note });
+}
+
+/// Drops decisions left for a finished run.
+pub(crate) fn forget_approvals(run_id: &str) { approvals().lock().unwrap_or_else(|e| e.into_inner()).retain(|(run, _), _| run != run_id); }
+
+fn take_approval(run_id: &str, step_id: &str) -> Option<Approval> {
+    approvals().lock().unwrap_or_else(|e| e.into_inner()).remove(&(run_id.to_string(), step_id.to_string()))
+}
+
+// ---- Templates ----
+
+struct Scope<'a> {
+    input: Value,
+    outputs: &'a HashMap<String, Value>,
+}
+
+impl Scope<'_> {
+    fn resolve(&self, path: &str) -> Option<Value> {
+        let mut parts = path.trim().split('.');
+        let root = parts.next()?;
+        let mut v = if root == "input" { &self.input } else { self.outputs.get(root)? };
+        for p in parts {
+            v = match v {
+                Value::Array(a) => a.get(p.parse::<usize>().ok()?)?,
+                _ => v.get(p)?,
+            };
+        }
+        (!v.is_null()).then(|| v.clone())
+    }
+
+    fn lookup(&self, expr: &str) -> Result<Value> {
+        expr.split('|').find_map(|alt| self.resolve(alt)).ok_or_else(|| anyhow!("unresolved reference '${{{}}}'", expr))
+    }
+
+    fn render(&self, template: &str) -> Result<Value> {
+        if let Some(expr) = template.strip_prefix("${").and_then(|t| t.strip_suffix('}')).filter(|e| !e.contains('}')) {
+            return self.lookup(expr);
+        }
+        let mut out = String::new();
+        let mut rest = template;
+        while let Some(start) = rest.find("${") {
+            out.push_str(&rest[..start]);
+            let end = rest[start..].find('}').ok_or_else(|| anyhow!("unclosed '${{' in '{}'", template))? + start;
+            match self.lookup(&rest[start + 2..end])? {
+                Value::String(s) => out.push_str(&s),
+                v => out.push_str(&v.to_string()),
+            }
+            rest = &rest[end + 1..];
+        }
+        out.push_str(rest);
+        Ok(Value::String(out))
+    }
+
+    fn text(&self, template: &str) -> Result<String> {
+        Ok(match self.render(template)? {
+            Value::String(s) => s,
+            v => v.to_string(),
+        })
+    }
+
+    fn render_value(&self, v: &Value) -> Result<Value> {
+        Ok(match v {
+            Value::String(s) => self.render(s)?,
+            Value::Array(a) => Value::Array(a.iter().map(|x| self.render_value(x)).collect::<Result<_>>()?),
+            Value::Object(o) => Value::Object(o.iter().map(|(k, x)| Ok((k.clone(), self.render_value(x)?))).collect::<Result<_>>()?),
+            v => v.clone(),
+        })
+    }
+}
+
+fn number(v: &Value) -> Option<f64> {
+    match v {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        Value::Bool(b) => Some(f64::from(u8::from(*b))),
+        _ => None,
+    }
+}
+
+fn same(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(_), _) | (_, Value::Number(_)) => number(a).zip(number(b)).is_some_and(|(x, y)| x == y),
+        _ => a == b,
+    }
+}
+
+impl Condition {
+    fn holds(&self, scope: &Scope<'_>) -> Result<bool> {
+        let actual = scope.resolve(&self.reference);
+        let expected = scope.render_value(&self.value)?;
+        Ok(match self.op {
+            ConditionOp::Exists => actual.is_some(),
+            ConditionOp::Eq => actual.is_some_and(|a| same(&a, &expected)),
+            ConditionOp::Ne => !actual.is_some_and(|a| same(&a, &expected)),
+            ConditionOp::Contains => match (actual, &expected) {
+                (Some(Value::String(s)), Value::String(e)) => s.contains(e.as_str()),
+                (Some(Value::Array(a)), e) => a.iter().any(|x| same(x, e)),
+                _ => false,
+            },
+            op => {
+                let (a, b) = actual
+                    .as_ref()
+                    .and_then(number)
+                    .zip(number(&expected))
+                    .ok_or_else(|| anyhow!("condition on '{}' compares non-numeric values", self.reference))?;
+                match op {
+                    ConditionOp::Lt => a < b,
+                    ConditionOp::Le => a <= b,
+                    ConditionOp::Gt => a > b,
+                    _ => a >= b,
+                }
+            }
+        })
+    }
+}
+
+// ---- Execution ----
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Validated {
+    prompt: String,
+    guidelines_json: String,
+    results: Vec<ValidationResult>,
+}
+
+struct Run<'a> {
+    plan: &'a StepPlan,
+    input: &'a AgentInput,
+    run_id: &'a str,
+    budget: Option<BudgetCtl>,
+    outputs: HashMap<String, Value>,
+    validated: HashMap<String, Validated>,
+    last_validated: Option<String>,
+    anchoring: Option<String>,
+    outcome: AgentOutcome,
+    save: Option<SaveCheckpoint<'a>>,
+}
+
+fn step_event(step: &Step, message: impl Into<String>, data: Option<Value>) -> AgentEvent {
+    let mut ev = event(step.action.stage(), message, data);
+    ev.step = Some(step.id.clone());
+    ev
+}
+
+fn score_text(text: &str, guidelines_json: &str) -> Result<Scored> {
+    let guidelines = GuidelinePack::parse(guidelines_json)?.guidelines();
+    Ok(score_guidelines(text, &guidelines, &ScoringConfig::default()))
+}
+
+// Targets of the branch `step` did not take, given its output.
+fn not_taken<'p>(step: &'p Step, output: &Value) -> &'p [String] {
+    match &step.action {
+        StepAction::Branch { then, otherwise, .. } if output["condition"].as_bool().unwrap_or(false) => otherwise,
+        StepAction::Branch { then, .. } => then,
+        _ => &[],
+    }
+}
+
+/// Runs `plan` from `from`, emitting events as it goes; the outcome's `steps` records every step.
+pub(crate) async fn run(plan: StepPlan, input: &AgentInput, emit: Emitter<'_>, from: Checkpoint, save: Option<SaveCheckpoint<'_>>) -> Result<AgentOutcome> {
+    let order = plan.order()?;
+    let run_id = emit.run_id;
+    let recorded: HashMap<String, StepRecord> = from.outcome.steps.iter().map(|r| (r.id.clone(), r.clone())).collect();
+    let mut run = Run {
+        plan: &plan,
+        input,
+        run_id,
+        budget: budget_for_run(plan.budgets.clone(), input, run_id),
+        outputs: HashMap::new(),
+        validated: from.validated,
+        last_validated: from.last_validated,
+        anchoring: from.anchoring,
+        outcome: from.outcome,
+        save,
+    };
+    let mut not_taken_by: HashSet<&str> = HashSet::new();
+    let mut skipped: HashSet<&str> = HashSet::new();
+    if !recorded.is_empty() {
+        emit.emit(event(AgentStage::Steps, format!("resuming after {} recorded steps", recorded.len()), None));
+    }
+
+    for step in order {
+        if let Some(record) = recorded.get(&step.id) {
+            match (&record.status, &record.output) {
+                (StepStatus::Completed, Some(output)) => {
+                    not_taken_by.extend(not_taken(step, output).iter().map(String::as_str));
+                    run.outputs.insert(step.id.clone(), output.clone());
+                }
+                _ => {
+                    skipped.insert(&step.id);
+                }
+            }
+            continue;
+        }
+        let started_ms = now_ms();
+        let reason = if not_taken_by.contains(step.id.as_str()) {
+            Some("branch not taken")
+        } else if !step.depends_on.is_empty() && step.depends_on.iter().all(|d| skipped.contains(d.as_str())) {
+            Some("all dependencies skipped")
+        } else {
+            match &step.when {
+                Some(c) if !c.holds(&run.scope()).with_context(|| format!("step '{}'", step.id))? => Some("condition not met"),
+                _ => None,
+            }
+        };
+        if let Some(reason) = reason {
+            emit.emit(step_event(step, "step skipped", Some(json!({ "reason": reason }))));
+            skipped.insert(&step.id);
+            let record = StepRecord { id: step.id.clone(), kind: step.action.kind().into(), status: StepStatus::Skipped, output: None, detail: Some(reason.into()), started_ms, finished_ms: now_ms() };
+            run.record(record);
+            continue;
+        }
+
+        emit.emit(step_event(step, "step started", None));
+        let output = run.execute(step, emit).await.with_context(|| format!("step '{}' failed", step.id))?;
+        not_taken_by.extend(not_taken(step, &output).iter().map(String::as_str));
+        emit.emit(step_event(step, "step completed", Some(output.clone())));
+        run.outputs.insert(step.id.clone(), output.clone());
+        let record = StepRecord { id: step.id.clone(), kind: step.action.kind().into(), status: StepStatus::Completed, output: Some(output), detail: None, started_ms, finished_ms: now_ms() };
+        run.record(record);
+    }
+    Ok(run.outcome)
+}
+
+impl Run<'_> {
+    fn record(&mut self, record: StepRecord) {
+        self.outcome.steps.push(record);
+        self.anchoring = None;
+        self.checkpoint();
+    }
+
+    fn checkpoint(&self) {
+        if let Some(save) = self.save {
+            let (validated, last_validated, anchoring) = (self.validated.clone(), self.last_validated.clone(), self.anchoring.clone());
+            save(&Checkpoint { outcome: self.outcome.clone(), validated, last_validated, anchoring });
+        }
+    }
+
+    fn scope(&self) -> Scope<'_> {
+        let input = json!({ "prompt": self.input.prompt, "salt": self.input.salt, "tenant": self.input.tenant, "run_id": self.run_id });
+        Scope { input, outputs: &self.outputs }
+    }
+
+    fn guidelines(&self, own: &Option<String>) -> Result<String> {
+        match own.as_deref().or(self.plan.guidelines_json.as_deref()) {
+            Some(g) => self.scope().text(g),
+            None => Ok(DEFAULT_GUIDELINES_JSON.to_string()),
+        }
+    }
+
+    async fn execute(&mut self, step: &Step, emit: Emitter<'_>) -> Result<Value> {
+        match &step.action {
+            StepAction::Approval { message, on_timeout } => return self.approval(step, message, *on_timeout, emit).await,
+            StepAction::Anchor { hash, anchor } => return self.anchor(step, hash, anchor, emit).await,
+            _ => {}
+        }
+        let timeout = step.timeout_ms.unwrap_or(DEFAULT_STEP_TIMEOUT_MS);
+        let retries = step.retries.unwrap_or(0);
+        let mut attempt = 0u32;
+        loop {
+            let err = match tokio::time::timeout(Duration::from_millis(timeout), self.action(step, emit)).await {
+                Ok(Ok(v)) => return Ok(v),
+                Ok(Err(e)) => e,
+                Err(_) => anyhow!("timeout: step exceeded {} ms", timeout),
+            };
+            if attempt >= retries {
+                return Err(err);
+            }
+            emit.emit(step_event(step, format!("attempt {} failed: {}", attempt + 1, err), None));
+            // backoff with jitter
+            let wait = (200u64.saturating_mul(1 << attempt.min(4))) + ((attempt as u64 * 37) % 120);
+            tokio::time::sleep(Duration::from_millis(wait.min(2_000))).await;
+            attempt += 1;
+        }
+    }
+
+    async fn action(&mut self, step: &Step, emit: Emitter<'_>) -> Result<Value> {
+        match &step.action {
+            StepAction::Generate { prompt, provider } => {
+                let prompt = self.scope().text(prompt)?;
+                self.complete(provider.as_deref(), prompt).await
+            }
+            StepAction::Rewrite { text, instructions, provider } => {
+                let scope = self.scope();
+                let (text, instructions) = (scope.text(text)?, scope.text(instructions)?);
+                let prompt = format!("{}\n\nRewrite the text below accordingly and answer with the rewritten text only.\n\n{}", instructions, text);
+                let mut out = self.complete(provider.as_deref(), prompt).await?;
+                out["original"] = Value::String(text);
+                Ok(out)
+            }
+            StepAction::Validate { prompt, text, guidelines_json } => {
+                let guidelines_json = self.guidelines(guidelines_json)?;
+                if let Some(text) = text {
+                    let scored = score_text(&self.scope().text(text)?, &guidelines_json)?;
+                    return Ok(json!({ "score": scored.overall, "missing_terms": scored.missing_terms, "topics": scored.topics, "violations": scored.violations }));
+                }
+                let prompt = match prompt {
+                    Some(p) => self.scope().text(p)?,
+                    None => self.input.prompt.clone(),
+                };
+                emit.emit(step_event(step, "starting validation", None));
+                let results = do_validate(&prompt, &self.input.providers, &guidelines_json, self.budget.clone()).await?;
+                emit.emit(step_event(step, "validation complete", Some(serde_json::to_value(&results).unwrap_or(Value::Null))));
+                ensure_within_budget(&results, &self.budget)?;
+                let best = results.first().map(|r| json!({ "provider": r.provider_name, "score": r.adherence_score, "text": r.raw_text }));
+                let out = json!({ "results": results, "best": best });
+                self.outcome.results = Some(results.clone());
+                self.validated.insert(step.id.clone(), Validated { prompt, guidelines_json, results });
+                self.last_validated = Some(step.id.clone());
+                Ok(out)
+            }
+            StepAction::Score { metric, text, reference, facts, guidelines_json } => {
+                let scope = self.scope();
+                let text = scope.text(text)?;
+                let reference = || -> Result<String> { scope.text(reference.as_deref().ok_or_else(|| anyhow!("metric {:?} needs a reference", metric))?) };
+                let value = match metric {
+                    Metric::Bleu => panther_metrics_content::evaluate_bleu(&reference()?, &text),
+                    Metric::RougeL => panther_metrics_content::evaluate_rouge_l(&reference()?, &text),
+                    Metric::Accuracy => panther_metrics_content::evaluate_accuracy(&reference()?, &text),
+                    Metric::Coherence => panther_metrics_content::evaluate_coherence(&text),
+                    Metric::Fluency => panther_metrics_content::evaluate_fluency(&text),
+                    Metric::FactCoverage => {
+                        anyhow::ensure!(!facts.is_empty(), "metric fact_coverage needs facts");
+                        let facts = facts.iter().map(|f| scope.text(f)).collect::<Result<Vec<_>>>()?;
+                        panther_metrics_content::evaluate_fact_coverage(&facts, &text)
+                    }
+                    Metric::Adherence => score_text(&text, &self.guidelines(guidelines_json)?)?.overall,
+                };
+                Ok(json!({ "metric": metric, "value": value }))
+            }
+            StepAction::Branch { condition, .. } => {
+                let holds = condition.holds(&self.scope())?;
+                Ok(json!({ "condition": holds, "taken": if holds { "then" } else { "else" } }))
+            }
+            StepAction::Seal { from } => {
+                let from = from.clone().or_else(|| self.last_validated.clone()).ok_or_else(|| anyhow!("no validate step ran before this seal"))?;
+                let v = self.validated.get(&from).ok_or_else(|| anyhow!("'{}' has no validation results to seal", from))?;
+                emit.emit(step_event(step, "computing proof", None));
+                let providers_json = serde_json::to_string(&self.input.providers).unwrap_or_else(|_| "[]".to_string());
+                let results_json = serde_json::to_string(&v.results).unwrap_or_else(|_| "[]".to_string());
+                let ctx = panther_validation::proof::ProofContext { sdk_version: env!("CARGO_PKG_VERSION").to_string(), salt: self.input.salt.clone() };
+                let proof = panther_validation::proof::compute_proof(&v.prompt, &providers_json, &v.guidelines_json, &results_json, &ctx)?;
+                let out = serde_json::to_value(&proof)?;
+                emit.emit(step_event(step, "proof computed", Some(out.clone())));
+                self.outcome.proof = Some(proof);
+                Ok(out)
+            }
+            StepAction::Webhook { url, headers, body } => {
+                let scope = self.scope();
+                let body = match body {
+                    Some(b) => scope.render_value(b)?,
+                    None => json!({ "run_id": self.run_id, "outputs": self.outputs }),
+                };
+                let mut req = reqwest::Client::new().post(scope.text(url)?).json(&body);
+                for (k, v) in headers {
+                    req = req.header(k.as_str(), scope.text(v)?);
+                }
+                let resp = req.send().await?;
+                let status = resp.status();
+                let text = resp.text().await.unwrap_or_default();
+                if !status.is_success() {
+                    bail!("webhook returned {}: {}", status, text.chars().take(200).collect::<String>());
+                }
+                let body = serde_json::from_str(&text).unwrap_or(Value::String(text));
+                Ok(json!({ "status": status.as_u16(), "body": body }))
+            }
+            StepAction::Store { key, value } => {
+                let scope = self.scope();
+                let key = format!("agent:{}", scope.text(key)?);
+                agent_store().set(&key, serde_json::to_string(&scope.render_value(value)?)?)?;
+                Ok(json!({ "stored_at": key }))
+            }
+            StepAction::Approval { .. } | StepAction::Anchor { .. } => unreachable!("handled by execute"),
+        }
+    }
+
+    // Budgets are reserved and settled per call, as the validators do
+    async fn complete(&self, label: Option<&str>, prompt: String) -> Result<Value> {
+        let (name, provider) = text_provider(&self.input.providers, label)?;
+        let tokens_in = estimate_tokens(&prompt);
+        let reservation = BudgetCtl::admit_opt(&self.budget, &name, &prompt, None)?;
+        let completion = provider.generate(&Prompt { text: prompt }).await?;
+        if let Some(r) = reservation {
+            r.settle(tokens_in, estimate_tokens(&completion.text))?;
+        }
+        Ok(json!({ "text": completion.text, "provider": name, "model": completion.model }))
+    }
+
+    async fn approval(&mut self, step: &Step, message: &Option<String>, on_timeout: Decision, emit: Emitter<'_>) -> Result<Value> {
+        let timeout = step.timeout_ms.unwrap_or(DEFAULT_APPROVAL_TIMEOUT_MS);
+        let message = message.as_deref().map(|m| self.scope().text(m)).transpose()?;
+        emit.emit(step_event(step, "awaiting approval", Some(json!({ "run_id": self.run_id, "message": message, "timeout_ms": timeout }))));
+        let deadline = now_ms().saturating_add(timeout as i64);
+        let (approval, timed_out) = loop {
+            if let Some(a) = take_approval(self.run_id, &step.id) {
+                break (a, false);
+            }
+            if now_ms() >= deadline {
+                break (Approval { approved: on_timeout == Decision::Approve, note: None }, true);
+            }
+            tokio::time::sleep(Duration::from_millis(APPROVAL_POLL_MS)).await;
+        };
+        if !approval.approved {
+            match (timed_out, approval.note) {
+                (true, _) => bail!("no decision within {} ms", timeout),
+                (false, Some(note)) => bail!("rejected: {}", note),
+                (false, None) => bail!("rejected"),
+            }
+        }
+        Ok(json!({ "approved": true, "note": approval.note, "timed_out": timed_out }))
+    }
+
+    async fn anchor(&mut self, step: &Step, hash: &Option<String>, cfg: &AnchorCfg, emit: Emitter<'_>) -> Result<Value> {
+        let hash = match hash {
+            Some(h) => self.scope().text(h)?,
+            None => self.outcome.proof.as_ref().map(|p| p.combined_hash.clone()).ok_or_else(|| anyhow!("no hash given and no proof sealed yet"))?,
+        };
+        let backend = cfg.backend()?;
+        let limits = ((step.timeout_ms.unwrap_or(DEFAULT_STEP_TIMEOUT_MS), step.retries.unwrap_or(0)), (STATUS_TIMEOUT_MS, 0));
+        let maybe_sent = self.anchoring.as_ref() == Some(&hash);
+        self.anchoring = Some(hash.clone());
+        self.checkpoint();
+        let (receipt, anchored) = anchor_stage(backend.as_ref(), &hash, limits, &emit.for_step(&step.id), maybe_sent).await?;
+        self.outcome.tx_hash = receipt.as_ref().map(|r| r.tx_id.clone());
+        self.outcome.anchored = Some(anchored);
+        self.outcome.receipt = receipt.clone();
+        Ok(json!({ "hash": hash, "receipt": receipt, "anchored": anchored }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{run_plan_with_sink, AgentPlan, MemorySink};
+
+    fn plan(steps: Value) -> StepPlan { serde_json::from_value(json!({ "steps": steps })).unwrap() }
+
+    fn store(id: &str, depends_on: &[&str]) -> Value { json!({ "id": id, "type": "store", "depends_on": depends_on, "key": id, "value": 1 }) }
+
+    fn order_err(steps: Value) -> String { plan(steps).order().unwrap_err().to_string() }
+
+    #[test]
+    fn order_follows_dependencies_then_declaration() {
+        let p = plan(json!([store("a", &["c"]), store("b", &[]), store("c", &[])]));
+        let ids: Vec<&str> = p.order().unwrap().iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, ["b", "c", "a"]);
+    }
+
+    #[test]
+    fn order_rejects_malformed_plans() {
+        assert_eq!(order_err(json!([store("a", &[]), store("a", &[])])), "duplicate step id 'a'");
+        assert_eq!(order_err(json!([store("a", &["ghost"])])), "step 'a' depends on unknown step 'ghost'");
+        assert_eq!(order_err(json!([store("input", &[])])), "invalid step id 'input'");
+        let branch = json!({ "id": "br", "type": "branch", "condition": { "ref": "input.prompt", "op": "exists" }, "then": ["x"] });
+        assert_eq!(order_err(json!([branch, store("x", &[])])), "branch target 'x' must depend on branch 'br'");
+        let branch = json!({ "id": "br", "type": "branch", "condition": { "ref": "input.prompt", "op": "exists" }, "else": ["ghost"] });
+        assert_eq!(order_err(json!([branch])), "branch 'br' targets unknown step 'ghost'");
+        assert_eq!(order_err(json!([store("a", &["b"]), store("b", &["a"]), store("c", &[])])), "steps form a cycle: a, b");
+    }
+
+    fn with_scope<T>(f: impl FnOnce(&Scope<'_>) -> T) -> T {
+        let outputs = HashMap::from([("draft".to_string(), json!({ "text": "hello", "n": 3, "flag": null, "items": [{ "v": "first" }], "tags": ["a", "b"] }))]);
+        f(&Scope { input: json!({ "prompt": "p", "tenant": null }), outputs: &outputs })
+    }
+
+    #[test]
+    fn templates_keep_exact_references_and_interpolate_the_rest() {
+        with_scope(|s| {
+            assert_eq!(s.render("${draft.n}").unwrap(), json!(3));
+            assert_eq!(s.render("${draft.tags}").unwrap(), json!(["a", "b"]));
+            assert_eq!(s.render("n=${draft.n}").unwrap(), json!("n=3"));
+            assert_eq!(s.render("${draft.text}${draft.n}").unwrap(), json!("hello3"));
+            assert_eq!(s.render("${ input.prompt }").unwrap(), json!("p"));
+            assert_eq!(s.render("${draft.items.0.v}").unwrap(), json!("first"));
+            assert_eq!(s.render("plain").unwrap(), json!("plain"));
+        });
+    }
+
+    #[test]
+    fn lookups_fall_back_and_report_unresolved_references() {
+        with_scope(|s| {
+            assert_eq!(s.lookup("rewrite.text|draft.text").unwrap(), json!("hello"));
+            assert_eq!(s.lookup("draft.flag|input.prompt").unwrap(), json!("p"));
+            assert!(s.lookup("draft.items.1.v").is_err());
+            assert!(s.lookup("draft.items.x").is_err());
+            assert_eq!(s.lookup("input.tenant|draft.missing").unwrap_err().to_string(), "unresolved reference '${input.tenant|draft.missing}'");
+            assert_eq!(s.render("a ${draft.text").unwrap_err().to_string(), "unclosed '${' in 'a ${draft.text'");
+        });
+    }
+
+    fn holds(reference: &str, op: &str, value: Value) -> Result<bool> {
+        let c: Condition = serde_json::from_value(json!({ "ref": reference, "op": op, "value": value })).unwrap();
+        with_scope(|s| c.holds(s))
+    }
+
+    #[test]
+    fn conditions_cover_every_op() {
+        assert!(holds("draft.n", "eq", json!("3")).unwrap());
+        assert!(holds("draft.text", "eq", json!("hello")).unwrap());
+        assert!(!holds("draft.missing", "eq", Value::Null).unwrap());
+        assert!(holds("draft.n", "ne", json!(4)).unwrap());
+        assert!(holds("draft.missing", "ne", json!(4)).unwrap());
+        assert!(!holds("draft.n", "ne", json!(3.0)).unwrap());
+        assert!(holds("draft.n", "lt", json!(4)).unwrap() && !holds("draft.n", "lt", json!(3)).unwrap());
+        assert!(holds("draft.n", "le", json!(3)).unwrap() && !holds("draft.n", "le", json!(2)).unwrap());
+        assert!(holds("draft.n", "gt", json!(2)).unwrap() && !holds("draft.n", "gt", json!(3)).unwrap());
+        assert!(holds("draft.n", "ge", json!("${draft.n}")).unwrap() && !holds("draft.n", "ge", json!(4)).unwrap());
+        assert!(holds("draft.text", "contains", json!("ell")).unwrap());
+        assert!(holds("draft.tags", "contains", json!("b")).unwrap() && !holds("draft.tags", "contains", json!("c")).unwrap());
+        assert!(!holds("draft.n", "contains", json!("3")).unwrap());
+        assert!(holds("draft.text", "exists", Value::Null).unwrap());
+        assert!(!holds("draft.flag", "exists", Value::Null).unwrap() && !holds("draft.missing", "exists", Value::Null).unwrap());
+        assert!(holds("draft.text", "gt", json!(1)).is_err());
+        assert!(holds("draft.missing", "lt", json!(1)).is_err());
+    }
+
+    // gen -> check branches to escalate (-> notify) or reply; audit only runs for long drafts
+    fn triage() -> StepPlan {
+        plan(json!([
+            { "id": "gen", "type": "generate", "prompt": "Draft: ${input.prompt}" },
+            { "id": "check", "type": "branch", "depends_on": ["gen"], "condition": { "ref": "gen.text", "op": "contains", "value": "urgent" }, "then": ["escalate"], "else": ["reply"] },
+            store("escalate", &["check"]),
+            store("notify", &["escalate"]),
+            { "id": "reply", "type": "generate", "depends_on": ["check"], "prompt": "Reply to ${gen.text}" },
+            { "id": "audit", "type": "store", "depends_on": ["gen"], "key": "audit", "value": "${gen.text}", "when": { "ref": "gen.text", "op": "contains", "value": "audit" } },
+        ]))
+    }
+
+    async fn run_triage(prompt: &str) -> (AgentOutcome, Vec<AgentEvent>) {
+        let input: AgentInput = serde_json::from_value(json!({ "prompt": prompt, "providers": [{ "type": "stub" }], "salt": null })).unwrap();
+        let sink = MemorySink::default();
+        let outcome = run_plan_with_sink(AgentPlan::Steps(triage()), input, "steps-test", &sink).await.unwrap();
+        (outcome, sink.take())
+    }
+
+    fn statuses(outcome: &AgentOutcome) -> Vec<(&str, StepStatus, Option<&str>)> {
+        outcome.steps.iter().map(|r| (r.id.as_str(), r.status, r.detail.as_deref())).collect()
+    }
+
+    #[tokio::test]
+    async fn branches_skip_the_untaken_side_and_everything_behind_it() {
+        let (outcome, events) = run_triage("routine question").await;
+        assert_eq!(
+            statuses(&outcome),
+            [
+                ("gen", StepStatus::Completed, None),
+                ("check", StepStatus::Completed, None),
+                ("escalate", StepStatus::Skipped, Some("branch not taken")),
+                ("notify", StepStatus::Skipped, Some("all dependencies skipped")),
+                ("reply", StepStatus::Completed, None),
+                ("audit", StepStatus::Skipped, Some("condition not met")),
+            ]
+        );
+        let reply = outcome.steps.iter().find(|r| r.id == "reply").and_then(|r| r.output.clone()).unwrap();
+        assert_eq!((reply["text"].as_str(), reply["provider"].as_str()), (Some("Reply to Draft: routine question"), Some("stub:echo")));
+        let skipped: Vec<(&str, &str)> =
+            events.iter().filter(|e| e.message == "step skipped").map(|e| (e.step.as_deref().unwrap(), e.data.as_ref().unwrap()["reason"].as_str().unwrap())).collect();
+        assert_eq!(skipped, [("escalate", "branch not taken"), ("notify", "all dependencies skipped"), ("audit", "condition not met")]);
+        assert!(events.iter().enumerate().all(|(i, e)| e.seq == i as u64));
+
+        let (outcome, _) = run_triage("urgent audit").await;
+        assert_eq!(
+            statuses(&outcome),
+            [
+                ("gen", StepStatus::Completed, None),
+                ("check", StepStatus::Completed, None),
+                ("escalate", StepStatus::Completed, None),
+                ("notify", StepStatus::Completed, None),
+                ("reply", StepStatus::Skipped, Some("branch not taken")),
+                ("audit", StepStatus::Completed, None),
+            ]
+        );
+    }
+}
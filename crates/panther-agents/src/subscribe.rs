@@ -0,0 +1,249 @@
+//! Push-based access to the events of runs started with [`crate::agent_start`].
+//!
+//! [`agent_wait`] is `agent_poll` that waits for the next event instead of returning empty,
+//! [`agent_subscribe`] turns a run's events into a [`Stream`], and [`agent_on_event`] calls a
+//! function for each event on the runner's threads (behind `panther_agent_subscribe`). All of them
+//! start at a cursor, so a client can pick up where an earlier poll stopped.
+
+use super::runs::{agent_poll, changes, runtime};
+use super::AgentEvent;
+use anyhow::Result;
+use futures::{Stream, StreamExt};
+use once_cell::sync::OnceCell;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// How long a subscription waits per round before checking its run again.
+const SUBSCRIBE_WAIT_MS: u64 = 30_000;
+
+/// What `agent_poll` returns: new events, whether the run finished, the next cursor and the status.
+pub type Polled = (Vec<AgentEvent>, bool, usize, String);
+
+/// Like `agent_poll`, but waits up to `wait_ms` for an event when none is pending. Returns at
+/// once when the run has finished.
+pub async fn agent_wait_async(run_id: &str, cursor: usize, wait_ms: u64) -> Result<Polled> {
+    let deadline = tokio::time::Instant::now() + Duration::from_millis(wait_ms);
+    loop {
+        let changed = changes(run_id)?;
+        let notified = changed.notified();
+        tokio::pin!(notified);
+        // Registered before polling, so an event pushed in between still wakes us
+        notified.as_mut().enable();
+        let polled = agent_poll(run_id, cursor)?;
+        if !polled.0.is_empty() || polled.1 {
+            return Ok(polled);
+        }
+        if tokio::time::timeout_at(deadline, notified).await.is_err() {
+            return Ok(polled);
+        }
+    }
+}
+
+/// Blocking [`agent_wait_async`]; must not be called from async code.
+pub fn agent_wait(run_id: &str, cursor: usize, wait_ms: u64) -> Result<Polled> { runtime()?.block_on(agent_wait_async(run_id, cursor, wait_ms)) }
+
+/// The events of `run_id` from `cursor` on, as they are emitted. Ends once the run has finished
+/// and every event was yielded, or when the run is no longer known.
+pub fn agent_subscribe(run_id: &str, cursor: usize) -> impl Stream<Item = AgentEvent> + Send + 'static {
+    let state = (run_id.to_string(), cursor, VecDeque::new(), false);
+    futures::stream::unfold(state, |(run_id, mut cursor, mut pending, mut done)| async move {
+        loop {
+            if let Some(ev) = pending.pop_front() {
+                return Some((ev, (run_id, cursor, pending, done)));
+            }
+            if done {
+                return None;
+            }
+            let (events, finished, next, _) = agent_wait_async(&run_id, cursor, SUBSCRIBE_WAIT_MS).await.ok()?;
+            pending.extend(events);
+            cursor = next;
+            done = finished;
+        }
+    })
+}
+
+static SUBSCRIPTIONS: OnceCell<Mutex<HashMap<u64, CancellationToken>>> = OnceCell::new();
+static NEXT_SUBSCRIPTION: AtomicU64 = AtomicU64::new(1);
+
+fn subscriptions() -> std::sync::MutexGuard<'static, HashMap<u64, CancellationToken>> {
+    SUBSCRIPTIONS.get_or_init(|| Mutex::new(HashMap::new())).lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// Calls the subscriber with `None` when dropped, so the last call comes however the task ends
+struct Last<F: Fn(Option<&AgentEvent>)>(F);
+
+impl<F: Fn(Option<&AgentEvent>)> Drop for Last<F> {
+    fn drop(&mut self) { (self.0)(None) }
+}
+
+/// Calls `on_event` with each event of `run_id` from `cursor` on. Calls happen in order on a
+/// runner thread, so `on_event` must return quickly. The subscription ends when the run has
+/// finished or after [`agent_unsubscribe`]; either way `on_event` is then called exactly once
+/// with `None` and never again, so whatever it holds can be released from that call. On error
+/// it is never called. Returns the id to pass to [`agent_unsubscribe`].
+pub fn agent_on_event(run_id: &str, cursor: usize, on_event: impl Fn(Option<&AgentEvent>) + Send + 'static) -> Result<u64> {
+    changes(run_id)?;
+    let rt = runtime()?;
+    let id = NEXT_SUBSCRIPTION.fetch_add(1, Ordering::Relaxed);
+    let cancel = CancellationToken::new();
+    subscriptions().insert(id, cancel.clone());
+    let events = agent_subscribe(run_id, cursor);
+    let on_event = Last(on_event);
+    rt.spawn(async move {
+        tokio::pin!(events);
+        loop {
+            tokio::select! {
+                biased;
+                _ = cancel.cancelled() => break,
+                ev = events.next() => match ev {
+                    Some(ev) if !cancel.is_cancelled() => (on_event.0)(Some(&ev)),
+                    _ => break,
+                },
+            }
+        }
+        subscriptions().remove(&id);
+    });
+    Ok(id)
+}
+
+/// Stops a subscription; `false` when it had already ended. A call already under way still
+/// completes, then the subscriber gets its final `None`.
+pub fn agent_unsubscribe(id: u64) -> bool {
+    match subscriptions().remove(&id) {
+        Some(cancel) => {
+            cancel.cancel();
+            true
+        }
+        None => false,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{agent_approve, agent_start};
+    use serde_json::{json, Value};
+    use std::sync::{mpsc, Arc};
+    use std::time::Instant;
+
+    fn start(steps: Value) -> String {
+        let input = json!({ "prompt": "p", "providers": [{ "type": "stub" }], "salt": null });
+        agent_start(&json!({ "type": "Steps", "steps": steps }).to_string(), &input.to_string()).unwrap()
+    }
+
+    // Runs until its gate is approved
+    fn gated() -> String { start(json!([{ "id": "gate", "type": "approval", "timeout_ms": 60_000 }])) }
+
+    fn quick() -> String { start(json!([{ "id": "draft", "type": "generate", "prompt": "About ${input.prompt}" }])) }
+
+    fn seqs(events: &[AgentEvent]) -> Vec<u64> { events.iter().map(|e| e.seq).collect() }
+
+    // The cursor after the gate started waiting
+    async fn at_gate(run_id: &str) -> usize {
+        let mut cursor = 0;
+        loop {
+            let (events, _, next, _) = agent_wait_async(run_id, cursor, 5_000).await.unwrap();
+            cursor = next;
+            if events.iter().any(|e| e.message == "awaiting approval") {
+                return cursor;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn wait_times_out_or_wakes_on_the_next_event() {
+        let run_id = gated();
+        let cursor = at_gate(&run_id).await;
+
+        let t = Instant::now();
+        let (events, finished, next, status) = agent_wait_async(&run_id, cursor, 100).await.unwrap();
+        assert!(t.elapsed() >= Duration::from_millis(100));
+        assert_eq!((events.len(), finished, next, status.as_str()), (0, false, cursor, "running"));
+
+        let approver = run_id.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            agent_approve(&approver, "gate", true, None).unwrap();
+        });
+        let t = Instant::now();
+        let (events, _, next, _) = agent_wait_async(&run_id, cursor, 10_000).await.unwrap();
+        assert!(!events.is_empty() && next > cursor);
+        assert!(t.elapsed() < Duration::from_secs(5));
+
+        let end = agent_subscribe(&run_id, next).count().await + next;
+        let t = Instant::now();
+        let (events, finished, _, status) = agent_wait_async(&run_id, end, 10_000).await.unwrap();
+        assert!(events.is_empty() && finished && status == "succeeded");
+        assert!(t.elapsed() < Duration::from_secs(1));
+        assert!(agent_wait_async("no-such-run", 0, 10).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn subscriptions_end_after_the_final_event_and_start_at_the_cursor() {
+        let run_id = quick();
+        let all: Vec<AgentEvent> = agent_subscribe(&run_id, 0).collect().await;
+        assert_eq!(all.last().unwrap().message, "run succeeded");
+        assert_eq!(seqs(&all), (0..all.len() as u64).collect::<Vec<_>>());
+        assert_eq!(seqs(&all), seqs(&agent_poll(&run_id, 0).unwrap().0));
+
+        let from: Vec<AgentEvent> = agent_subscribe(&run_id, 2).collect().await;
+        assert_eq!(seqs(&from), seqs(&all[2..]));
+        assert_eq!(agent_subscribe(&run_id, all.len() + 5).count().await, 0);
+        assert_eq!(agent_subscribe("no-such-run", 0).count().await, 0);
+    }
+
+    type Calls = Arc<Mutex<Vec<Option<u64>>>>;
+
+    // Records each call's seq (`None` for the last) and signals the calls
+    fn recorder() -> (Calls, mpsc::Receiver<Option<u64>>, impl Fn(Option<&AgentEvent>) + Send + 'static) {
+        let calls = Calls::default();
+        let (tx, rx) = mpsc::channel();
+        let record = calls.clone();
+        let on_event = move |ev: Option<&AgentEvent>| {
+            record.lock().unwrap().push(ev.map(|e| e.seq));
+            let _ = tx.send(ev.map(|e| e.seq));
+        };
+        (calls, rx, on_event)
+    }
+
+    fn until_last(rx: &mpsc::Receiver<Option<u64>>) { while rx.recv_timeout(Duration::from_secs(10)).expect("subscription never ended").is_some() {} }
+
+    #[test]
+    fn on_event_calls_every_event_then_none_once() {
+        let run_id = quick();
+        let (calls, rx, on_event) = recorder();
+        let id = agent_on_event(&run_id, 0, on_event).unwrap();
+        until_last(&rx);
+        std::thread::sleep(Duration::from_millis(50));
+        let total = agent_poll(&run_id, 0).unwrap().0.len() as u64;
+        let expected: Vec<Option<u64>> = (0..total).map(Some).chain([None]).collect();
+        assert_eq!(*calls.lock().unwrap(), expected);
+        assert!(!agent_unsubscribe(id));
+    }
+
+    #[test]
+    fn unsubscribing_ends_with_a_final_none() {
+        let run_id = gated();
+        let (calls, rx, on_event) = recorder();
+        let id = agent_on_event(&run_id, 0, on_event).unwrap();
+        assert_eq!(rx.recv_timeout(Duration::from_secs(10)).unwrap(), Some(0));
+        assert!(agent_unsubscribe(id));
+        until_last(&rx);
+        assert!(!agent_unsubscribe(id));
+
+        agent_approve(&run_id, "gate", true, None).unwrap();
+        while !agent_wait(&run_id, 0, 5_000).unwrap().1 {}
+        std::thread::sleep(Duration::from_millis(50));
+        let calls = calls.lock().unwrap();
+        assert_eq!(calls.last(), Some(&None));
+        assert_eq!(calls.iter().filter(|c| c.is_none()).count(), 1);
+
+        let (calls, _rx, on_event) = recorder();
+        assert!(agent_on_event("no-such-run", 0, on_event).is_err());
+        assert!(calls.lock().unwrap().is_empty());
+    }
+}
@@ -1,8 +1,15 @@
 use anyhow::{anyhow, Result};
 use clap::Parser;
+use panther_core::budget::{BudgetConfig, BudgetCtl, BudgetManager, BudgetScope, PriceRule, SpendContext};
+use panther_core::context::{ContextPolicy, ContextStrategy, ModelRegistry};
+use panther_validation::judge::JudgeConfig;
+use panther_validation::pack::GuidelinePack;
+use panther_validation::proof::sign::ProofSigner;
+use panther_validation::regression::{self, RegressionThresholds};
+use panther_validation::stats::SamplingConfig;
 use panther_validation::{LLMValidator, ProviderFactory, ValidationResult};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
  
 
@@ -27,6 +34,9 @@ struct Cli {
     /// Compute and include proof per item
     #[arg(long)]
     with_proof: bool,
+    /// Sign proofs with this key file (default $PANTHER_PROOF_KEY / $PANTHER_PROOF_KEY_FILE, if set)
+    #[arg(long)]
+    sign_key: Option<PathBuf>,
     /// Comma-separated metrics to compute (e.g., rouge,factcheck)
     #[arg(long)]
     metrics: Option<String>,
@@ -128,10 +138,63 @@ struct Cli {
     /// Generate HTML report for consistency (`consistency_report.html`)
     #[arg(long)]
     report_html: bool,
+    /// Budgets file (JSON/YAML: {budgets:[...], pricing:[...]}); pricing defaults to `--costs`
+    #[arg(long)]
+    budgets: Option<PathBuf>,
+    /// Directory of a sled store persisting budget counters across runs (default: in-memory)
+    #[arg(long)]
+    budget_store: Option<PathBuf>,
+    /// Tenant charged for this run (tenant-scoped budgets)
+    #[arg(long)]
+    tenant: Option<String>,
+    /// Fit prompts to the model context window (reject|truncate-middle|truncate-oldest|summarize)
+    #[arg(long)]
+    context_strategy: Option<String>,
+    /// Model capabilities JSON (array of {provider,model,context_window,max_output_tokens,...}) extending the built-in registry
+    #[arg(long)]
+    models: Option<PathBuf>,
+    /// Tokens reserved for the answer when fitting prompts (default: model max output)
+    #[arg(long)]
+    reserve_output_tokens: Option<u32>,
+    /// Judge providers JSON (same format as --providers) grading each answer against the guidelines
+    #[arg(long)]
+    judges: Option<PathBuf>,
+    /// Share of the judge score in adherence_score, 0..1 (default 0.5)
+    #[arg(long)]
+    judge_weight: Option<f64>,
+    /// Text file with extra grading instructions for the judges
+    #[arg(long)]
+    judge_rubric: Option<PathBuf>,
+    /// Calls per provider and prompt; >1 adds mean/std/95% CI to results and pairwise significance
+    #[arg(long, default_value = "1")]
+    samples: usize,
+    /// Temperatures cycled across samples (comma-separated)
+    #[arg(long, value_delimiter = ',')]
+    temperatures: Vec<f32>,
+    /// Base seed for sampling; sample i uses seed+i
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Output directory of a previous run to compare against; exits 3 on regression
+    #[arg(long)]
+    baseline: Option<PathBuf>,
+    /// Largest tolerated drop of a provider's mean score, in points (default 5)
+    #[arg(long)]
+    max_drop: Option<f64>,
+    /// Largest tolerated drop relative to the baseline mean, in percent
+    #[arg(long)]
+    max_drop_pct: Option<f64>,
+    /// Also flag drops that are significant at this level (paired permutation test)
+    #[arg(long)]
+    regression_alpha: Option<f64>,
+    /// Largest tolerated share of baseline items missing from this run, in percent (default 10)
+    #[arg(long)]
+    max_missing_pct: Option<f64>,
+    /// Do not fail the comparison when a baseline provider is missing from this run
+    #[arg(long)]
+    allow_missing_providers: bool,
 }
 
-#[derive(serde::Deserialize)]
-struct ProviderCfg { #[serde(rename = "type")] ty: String, base_url: Option<String>, model: Option<String>, api_key: Option<String> }
+use panther_validation::secrets::ProviderConfig as ProviderCfg;
 
 #[derive(Debug, Clone, serde::Deserialize)]
 struct JsonlItem { prompt: String, #[serde(default)] salt: Option<String>, #[serde(default)] labels: Option<Vec<String>> }
@@ -160,13 +223,18 @@ async fn main() -> Result<()> {
         v
     };
 
+    let labels: Vec<String> = providers.iter().map(|(l, _)| l.clone()).collect();
+    let budget = load_budget(&cli)?;
+    let context = load_context_policy(&cli)?;
+
     // Guidelines
     let guides_path = cli.guidelines.clone().unwrap_or_else(|| std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../panther-validation/guidelines/anvisa.json"));
-    let validator = LLMValidator::from_path(&guides_path, providers)?;
+    let validator = apply_policies(LLMValidator::from_path(&guides_path, providers)?, &budget, &context);
+    let validator = apply_sampling(apply_judges(validator, &cli)?, &cli);
     let validator = Arc::new(validator);
 
-    if let Some(dir) = cli.scenarios.clone() {
-        run_scenarios(&cli, &dir).await
+    let res = if let Some(dir) = cli.scenarios.clone() {
+        run_scenarios(&cli, &dir, &budget, &context).await
     } else if cli.input.is_some() {
         run_batch(&cli, validator).await
     } else {
@@ -176,10 +244,116 @@ async fn main() -> Result<()> {
         print_table(&results);
         fs::write(cli.out.join("validation_results.json"), serde_json::to_string_pretty(&results)?)?;
         Ok(())
+    };
+    if let Some(ctl) = &budget {
+        write_budget_status(&cli.out, &ctl.manager, &ctl.ctx, &labels).ok();
+    }
+    res?;
+    if let Some(baseline) = &cli.baseline {
+        if compare_with_baseline(&cli, baseline)? { std::process::exit(3); }
+    }
+    Ok(())
+}
+
+/// Writes `regression.{json,md,html}` into the output directory; returns whether a regression was found.
+fn compare_with_baseline(cli: &Cli, baseline: &Path) -> Result<bool> {
+    let defaults = RegressionThresholds::default();
+    let thresholds = RegressionThresholds {
+        max_drop: cli.max_drop.or(defaults.max_drop),
+        max_drop_pct: cli.max_drop_pct,
+        alpha: cli.regression_alpha,
+        max_missing_pct: cli.max_missing_pct.or(defaults.max_missing_pct),
+        allow_missing_providers: cli.allow_missing_providers,
+    };
+    let report = regression::compare(&regression::load_run(baseline)?, &regression::load_run(&cli.out)?, &thresholds);
+    fs::write(cli.out.join("regression.json"), serde_json::to_string_pretty(&report)?)?;
+    fs::write(cli.out.join("regression.md"), report.to_markdown())?;
+    fs::write(cli.out.join("regression.html"), report.to_html())?;
+    println!("\nBaseline comparison ({}):", baseline.display());
+    for p in &report.providers {
+        let status = if p.regressed { format!("REGRESSION: {}", p.reasons.join("; ")) } else { "ok".into() };
+        println!("  {:<18} {:>5.1} → {:>5.1} ({:+.1}, p={:.3}) {}", p.provider, p.baseline_mean, p.current_mean, p.delta, p.p_value, status);
+    }
+    if !report.missing_items.is_empty() { println!("  {} baseline item(s) missing from this run", report.missing_items.len()); }
+    for r in &report.reasons { println!("  REGRESSION: {}", r); }
+    println!("Verdict: {:?} → {}", report.verdict, cli.out.join("regression.md").display());
+    Ok(report.regressed())
+}
+
+fn load_budget(cli: &Cli) -> Result<Option<BudgetCtl>> {
+    let Some(path) = cli.budgets.clone() else { return Ok(None) };
+    let text = fs::read_to_string(&path)?;
+    let cfg: BudgetConfig = match path.extension().and_then(|s| s.to_str()) {
+        Some("yaml") | Some("yml") => serde_yaml::from_str(&text)?,
+        _ => serde_json::from_str(&text)?,
+    };
+    let store: Arc<dyn panther_domain::ports::KeyValueStore> = match &cli.budget_store {
+        Some(dir) => Arc::new(panther_storage_sled::SledStore::open(&dir.to_string_lossy())?),
+        None => Arc::new(panther_storage::InMemoryStore::default()),
+    };
+    let pricing = load_cost_rules(cli)
+        .into_iter()
+        .map(|r| PriceRule { provider: r.provider, usd_per_1k_in: r.usd_per_1k_in.unwrap_or(0.0), usd_per_1k_out: r.usd_per_1k_out.unwrap_or(0.0) })
+        .collect();
+    let manager = BudgetManager::new(store, cfg).with_pricing(pricing);
+    let run_id = format!("eval-{}", std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_millis());
+    Ok(Some(BudgetCtl::new(Arc::new(manager), SpendContext { tenant: cli.tenant.clone(), run: Some(run_id) })))
+}
+
+fn load_context_policy(cli: &Cli) -> Result<Option<ContextPolicy>> {
+    let Some(strategy) = cli.context_strategy.as_deref() else { return Ok(None) };
+    let strategy: ContextStrategy = strategy.parse()?;
+    let registry = match &cli.models {
+        Some(p) => ModelRegistry::from_json_str(&fs::read_to_string(p)?)?,
+        None => ModelRegistry::builtin(),
+    };
+    let mut policy = ContextPolicy::new(Arc::new(registry), strategy);
+    if let Some(n) = cli.reserve_output_tokens { policy = policy.with_reserve_output(n); }
+    Ok(Some(policy))
+}
+
+fn apply_policies(validator: LLMValidator, budget: &Option<BudgetCtl>, context: &Option<ContextPolicy>) -> LLMValidator {
+    let validator = match budget {
+        Some(ctl) => validator.with_budget(ctl.manager.clone(), ctl.ctx.clone()),
+        None => validator,
+    };
+    match context {
+        Some(p) => validator.with_context_policy(p.clone()),
+        None => validator,
     }
 }
 
-async fn run_scenarios(cli: &Cli, dir: &PathBuf) -> Result<()> {
+fn apply_judges(validator: LLMValidator, cli: &Cli) -> Result<LLMValidator> {
+    let Some(path) = cli.judges.as_ref() else { return Ok(validator) };
+    let cfgs: Vec<ProviderCfg> = serde_json::from_str(&fs::read_to_string(path)?)?;
+    let rubric = cli.judge_rubric.as_ref().map(fs::read_to_string).transpose()?;
+    Ok(validator.with_judges(build_providers_from_cfg(cfgs)?, JudgeConfig { rubric, weight: cli.judge_weight }))
+}
+
+fn apply_sampling(validator: LLMValidator, cli: &Cli) -> LLMValidator {
+    validator.with_sampling(SamplingConfig { n_samples: cli.samples, temperatures: cli.temperatures.clone(), seed: cli.seed })
+}
+
+fn write_budget_status(out: &Path, m: &BudgetManager, ctx: &SpendContext, labels: &[String]) -> Result<()> {
+    let mut all = Vec::new();
+    for l in labels { all.extend(m.remaining_for(BudgetScope::Provider, l)?); }
+    if let Some(t) = &ctx.tenant { all.extend(m.remaining_for(BudgetScope::Tenant, t)?); }
+    if let Some(r) = &ctx.run { all.extend(m.remaining_for(BudgetScope::Run, r)?); }
+    for st in &all {
+        println!(
+            "budget {:?} {:<18} spent=${:.4} remaining_usd={} remaining_tokens={}",
+            st.scope,
+            st.subject,
+            st.spent_usd,
+            st.remaining_usd.map(|v| format!("{:.4}", v)).unwrap_or_else(|| "-".into()),
+            st.remaining_tokens.map(|v| v.to_string()).unwrap_or_else(|| "-".into()),
+        );
+    }
+    fs::write(out.join("budget_status.json"), serde_json::to_string_pretty(&all)?)?;
+    Ok(())
+}
+
+async fn run_scenarios(cli: &Cli, dir: &PathBuf, budget: &Option<BudgetCtl>, context: &Option<ContextPolicy>) -> Result<()> {
     // Load from config if provided; else list files from dir
     let mut scenario_files: Vec<(PathBuf, Option<Vec<String>>)> = Vec::new();
     if let Some(cfgp) = cli.scenarios_config.clone() {
@@ -224,6 +398,7 @@ async fn run_scenarios(cli: &Cli, dir: &PathBuf) -> Result<()> {
             out: out_dir.clone(),
             max_concurrency: cli.max_concurrency,
             with_proof: cli.with_proof,
+            sign_key: cli.sign_key.clone(),
             metrics: cli.metrics.clone(),
             usd_per_1k: cli.usd_per_1k,
             rag_index: cli.rag_index.clone(),
@@ -239,6 +414,25 @@ async fn run_scenarios(cli: &Cli, dir: &PathBuf) -> Result<()> {
             scenarios: None,
             scenarios_config: None,
             report_html: cli.report_html,
+            budgets: cli.budgets.clone(),
+            budget_store: cli.budget_store.clone(),
+            tenant: cli.tenant.clone(),
+            context_strategy: cli.context_strategy.clone(),
+            models: cli.models.clone(),
+            reserve_output_tokens: cli.reserve_output_tokens,
+            judges: cli.judges.clone(),
+            judge_weight: cli.judge_weight,
+            judge_rubric: cli.judge_rubric.clone(),
+            samples: cli.samples,
+            temperatures: cli.temperatures.clone(),
+            seed: cli.seed,
+            // Scenario outputs are compared once, from the top-level directory
+            baseline: None,
+            max_drop: cli.max_drop,
+            max_drop_pct: cli.max_drop_pct,
+            regression_alpha: cli.regression_alpha,
+            max_missing_pct: cli.max_missing_pct,
+            allow_missing_providers: cli.allow_missing_providers,
             report_advanced_html: cli.report_advanced_html,
             rewrite: cli.rewrite,
             rewrite_style: cli.rewrite_style.clone(),
@@ -271,7 +465,8 @@ async fn run_scenarios(cli: &Cli, dir: &PathBuf) -> Result<()> {
             v
         };
         let guides_path = sub.guidelines.clone().unwrap_or_else(|| std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../panther-validation/guidelines/anvisa.json"));
-        let validator = LLMValidator::from_path(&guides_path, providers)?;
+        let validator = apply_policies(LLMValidator::from_path(&guides_path, providers)?, budget, context);
+        let validator = apply_sampling(apply_judges(validator, &sub)?, &sub);
         let validator = Arc::new(validator);
         run_batch(&sub, validator).await?;
         // read summary_consistency.csv and capture mean_score per provider
@@ -312,7 +507,7 @@ fn build_providers_from_cfg(cfgs: Vec<ProviderCfg>) -> Result<Vec<(String, Arc<d
         match c.ty.as_str() {
             #[cfg(feature = "openai")]
             "openai" => {
-                if let (Some(key), Some(model)) = (c.api_key.clone(), c.model.clone()) {
+                if let (Some(key), Some(model)) = (c.api_key(), c.model.clone()) {
                     let base = c.base_url.unwrap_or_else(|| "https://api.openai.com".to_string());
                     let p = panther_providers::openai::OpenAiProvider { api_key: key, model: model.clone(), base_url: base };
                     providers.push((format!("openai:{}", model), Arc::new(p)));
@@ -362,12 +557,18 @@ async fn run_batch(cli: &Cli, validator: Arc<LLMValidator>) -> Result<()> {
         let corpus = load_corpus(&p).unwrap_or_default();
         (Some(serde_json::to_string(&corpus).unwrap_or_else(|_| "[]".to_string())), cli.plag_ngram.max(1))
     } else { (None, cli.plag_ngram.max(1)) };
+    let signer = match (&cli.sign_key, cli.with_proof) {
+        (_, false) => None,
+        (Some(path), true) => Some(ProofSigner::load(path)?),
+        (None, true) => ProofSigner::from_env()?,
+    }.map(Arc::new);
     let semaphore = Arc::new(tokio::sync::Semaphore::new(cli.max_concurrency.max(1)));
     let mut handles = Vec::new();
     for (idx, it) in items.into_iter().enumerate() {
         let validator = validator.clone();
         let out_dir = cli.out.clone();
         let with_proof = cli.with_proof;
+        let signer = signer.clone();
         let metrics = cli.metrics.clone().unwrap_or_default();
         let rewrite_flag = if cli.rewrite { Some("1".to_string()) } else { None };
         if rewrite_flag.is_some() { std::env::set_var("PANTHER_AI_EVAL_REWRITE_FLAG", "1"); }
@@ -375,8 +576,11 @@ async fn run_batch(cli: &Cli, validator: Arc<LLMValidator>) -> Result<()> {
         std::env::set_var("PANTHER_AI_EVAL_REWRITE_STYLE", cli.rewrite_style.clone());
         let providers_json = if let Some(pth) = cli.providers_path.clone() { fs::read_to_string(pth).unwrap_or_else(|_| "[]".to_string()) } else { "[]".to_string() };
         let guidelines_json = fs::read_to_string(cli.guidelines.clone().unwrap_or_else(|| std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../panther-validation/guidelines/anvisa.json"))).unwrap_or_else(|_| "[]".to_string());
+        // Expected terms from the guideline pack (or legacy array), used by fact metrics and rewrites
+        let guide_terms: Vec<String> = GuidelinePack::parse(&guidelines_json)
+            .map(|p| p.guidelines().into_iter().flat_map(|g| g.expected_terms).collect())
+            .unwrap_or_default();
         let plag_corpus_json = plag_corpus_json.clone();
-        let plag_ngram = plag_ngram;
         let permit = semaphore.clone().acquire_owned().await?;
         let handle = tokio::spawn(async move {
             let _permit = permit; // hold until end
@@ -384,13 +588,14 @@ async fn run_batch(cli: &Cli, validator: Arc<LLMValidator>) -> Result<()> {
                 "index": idx,
                 "prompt": it.prompt,
             });
-            match validator.validate(&out_obj["prompt"].as_str().unwrap()).await {
+            match validator.validate(out_obj["prompt"].as_str().unwrap()).await {
                 Ok(results) => {
                     out_obj["results"] = serde_json::to_value(&results).unwrap_or(serde_json::json!([]));
                     if with_proof {
                         let ctx = panther_validation::proof::ProofContext { sdk_version: env!("CARGO_PKG_VERSION").to_string(), salt: it.salt.clone() };
                         let results_json = serde_json::to_string(&results).unwrap_or_else(|_| "[]".to_string());
-                        if let Ok(proof) = panther_validation::proof::compute_proof(out_obj["prompt"].as_str().unwrap(), &providers_json, &guidelines_json, &results_json, &ctx) {
+                        if let Ok(mut proof) = panther_validation::proof::compute_proof(out_obj["prompt"].as_str().unwrap(), &providers_json, &guidelines_json, &results_json, &ctx) {
+                            if let Some(s) = &signer { s.sign(&mut proof); }
                             out_obj["proof"] = serde_json::to_value(proof).unwrap_or(serde_json::json!({}));
                         }
                     }
@@ -401,25 +606,17 @@ async fn run_batch(cli: &Cli, validator: Arc<LLMValidator>) -> Result<()> {
                         for m in metrics.split(',').map(|s| s.trim().to_lowercase()) {
                             match m.as_str() {
                                 "rouge" => {
-                                    let r = panthersdk::domain::metrics::evaluate_rouge_l(&out_obj["prompt"].as_str().unwrap(), &text_best);
+                                    let r = panthersdk::domain::metrics::evaluate_rouge_l(out_obj["prompt"].as_str().unwrap(), &text_best);
                                     extra.insert("rouge_l".to_string(), serde_json::json!(r));
                                 }
                                 "factcheck" => {
                                     // Extract expected terms from guidelines (best-effort)
-                                    let facts: Vec<String> = serde_json::from_str::<serde_json::Value>(&guidelines_json)
-                                        .ok()
-                                        .and_then(|v| v.as_array().cloned())
-                                        .map(|arr| arr.into_iter().flat_map(|g| g.get("expected_terms").and_then(|e| e.as_array()).cloned().unwrap_or_default()).filter_map(|x| x.as_str().map(|s| s.to_string())).collect())
-                                        .unwrap_or_default();
+                                    let facts: Vec<String> = guide_terms.clone();
                                     let score = panthersdk::domain::metrics::evaluate_fact_coverage(&facts, &text_best);
                                     extra.insert("fact_coverage".to_string(), serde_json::json!(score));
                                 }
                                 "factcheck-adv" | "factcheck_adv" => {
-                                    let facts: Vec<String> = serde_json::from_str::<serde_json::Value>(&guidelines_json)
-                                        .ok()
-                                        .and_then(|v| v.as_array().cloned())
-                                        .map(|arr| arr.into_iter().flat_map(|g| g.get("expected_terms").and_then(|e| e.as_array()).cloned().unwrap_or_default()).filter_map(|x| x.as_str().map(|s| s.to_string())).collect())
-                                        .unwrap_or_default();
+                                    let facts: Vec<String> = guide_terms.clone();
                                     let score = panthersdk::domain::metrics::evaluate_factcheck_adv(&facts, &text_best);
                                     extra.insert("factcheck_adv".to_string(), serde_json::json!(score));
                                 }
@@ -449,11 +646,7 @@ async fn run_batch(cli: &Cli, validator: Arc<LLMValidator>) -> Result<()> {
                             let style = std::env::var("PANTHER_AI_EVAL_REWRITE_STYLE").unwrap_or_else(|_| "neutral".to_string());
                             let locale = std::env::var("PANTHER_AI_EVAL_REWRITE_LOCALE").unwrap_or_else(|_| "en".to_string());
                             // Parse must terms from guidelines (best-effort)
-                            let must: Vec<String> = serde_json::from_str::<serde_json::Value>(&guidelines_json)
-                                .ok()
-                                .and_then(|v| v.as_array().cloned())
-                                .map(|arr| arr.into_iter().flat_map(|g| g.get("expected_terms").and_then(|e| e.as_array()).cloned().unwrap_or_default()).filter_map(|x| x.as_str().map(|s| s.to_string())).collect())
-                                .unwrap_or_default();
+                            let must: Vec<String> = guide_terms.clone();
                             let rewritten = rewrite_rule(&best_text, &must, &style, &locale);
                             let _ = append_line(out_dir.join("rewrites.jsonl"), &serde_json::json!({
                                 "index": idx,
@@ -476,6 +669,9 @@ async fn run_batch(cli: &Cli, validator: Arc<LLMValidator>) -> Result<()> {
         handles.push(handle);
     }
     for h in handles { let _ = h.await; }
+    if cli.with_proof {
+        write_batch_proof(&cli.out)?;
+    }
 
     // API-backed metrics on best output per item
     if cli.api_base.is_some() && cli.api_metric.is_some() {
@@ -527,8 +723,8 @@ async fn run_batch(cli: &Cli, validator: Arc<LLMValidator>) -> Result<()> {
 
     // Optional: RAG evaluation if index provided
     if let Some(index_path) = cli.rag_index.clone() {
-        run_rag_eval(&cli, &inputs_snapshot, &index_path).await.ok();
-        run_rag_experiments(&cli, &inputs_snapshot, &index_path).await.ok();
+        run_rag_eval(cli, &inputs_snapshot, &index_path).await.ok();
+        run_rag_experiments(cli, &inputs_snapshot, &index_path).await.ok();
     }
 
     // Consistency summary per provider (scores across prompts)
@@ -568,6 +764,29 @@ async fn run_batch(cli: &Cli, validator: Arc<LLMValidator>) -> Result<()> {
     Ok(())
 }
 
+/// Rolls the per-item proofs of `results.jsonl` into `batch_proof.json`, so a single anchor covers the whole run.
+fn write_batch_proof(out_dir: &std::path::Path) -> Result<()> {
+    let text = fs::read_to_string(out_dir.join("results.jsonl")).unwrap_or_default();
+    let mut items: Vec<(u64, panther_validation::proof::Proof)> = text
+        .lines()
+        .filter_map(|l| serde_json::from_str::<serde_json::Value>(l).ok())
+        .filter_map(|v| Some((v.get("index")?.as_u64()?, serde_json::from_value(v.get("proof")?.clone()).ok()?)))
+        .collect();
+    if items.is_empty() { return Ok(()); }
+    items.sort_by_key(|(i, _)| *i);
+    let proofs: Vec<_> = items.iter().map(|(_, p)| p.clone()).collect();
+    let (batch, inclusions) = panther_validation::proof::batch(&proofs, env!("CARGO_PKG_VERSION"));
+    let entries: Vec<serde_json::Value> = items
+        .iter()
+        .zip(inclusions)
+        .map(|((idx, p), inc)| serde_json::json!({"index": idx, "combined_hash": p.combined_hash, "inclusion": inc}))
+        .collect();
+    let out = serde_json::json!({"batch": batch, "items": entries});
+    fs::write(out_dir.join("batch_proof.json"), serde_json::to_string_pretty(&out)?)?;
+    println!("batch proof: root {} over {} items", batch.root, batch.leaf_count);
+    Ok(())
+}
+
 async fn run_api_metrics(cli: &Cli) -> Result<()> {
     use reqwest::Client;
     let base = cli.api_base.clone().unwrap();
@@ -658,7 +877,7 @@ async fn run_rag_eval(cli: &Cli, items: &Vec<JsonlItem>, index_path: &PathBuf) -
     let mut docs: Vec<RagDoc> = Vec::new();
     if index_path.is_file() {
         let text = fs::read_to_string(index_path)?;
-        for line in text.lines() { if line.trim().is_empty() { continue; } if let Ok(d) = serde_json::from_str::<RagDoc>(line) { docs.push(d); } }
+        for line in text.lines().filter(|l| !l.trim().is_empty()) { if let Ok(d) = serde_json::from_str::<RagDoc>(line) { docs.push(d); } }
     } else if index_path.is_dir() {
         for entry in fs::read_dir(index_path)? { let e = entry?; if e.path().extension().and_then(|s| s.to_str()) == Some("txt") { let id = e.file_name().to_string_lossy().to_string(); let text = fs::read_to_string(e.path())?; docs.push(RagDoc{ id, text }); } }
     }
@@ -710,7 +929,7 @@ fn chunk_words(text: &str, size: usize, overlap: usize) -> Vec<String> {
         let ch = toks[i..end].join(" ");
         chunks.push(ch);
         if end == toks.len() { break; }
-        i = i + size.saturating_sub(overlap).max(1);
+        i += size.saturating_sub(overlap).max(1);
     }
     chunks
 }
@@ -720,7 +939,7 @@ async fn run_rag_experiments(cli: &Cli, items: &Vec<JsonlItem>, index_path: &Pat
     let mut docs: Vec<RagDoc> = Vec::new();
     if index_path.is_file() {
         let text = fs::read_to_string(index_path)?;
-        for line in text.lines() { if line.trim().is_empty() { continue; } if let Ok(d) = serde_json::from_str::<RagDoc>(line) { docs.push(d); } }
+        for line in text.lines().filter(|l| !l.trim().is_empty()) { if let Ok(d) = serde_json::from_str::<RagDoc>(line) { docs.push(d); } }
     } else if index_path.is_dir() {
         for entry in fs::read_dir(index_path)? { let e = entry?; if e.path().extension().and_then(|s| s.to_str()) == Some("txt") { let id = e.file_name().to_string_lossy().to_string(); let text = fs::read_to_string(e.path())?; docs.push(RagDoc{ id, text }); } }
     }
@@ -829,7 +1048,7 @@ fn expand_variants(items: Vec<JsonlItem>, variants: Option<&str>) -> Vec<JsonlIt
     out
 }
 
-fn write_consistency_report_html(out_dir: &PathBuf, csv_path: &PathBuf) -> Result<()> {
+fn write_consistency_report_html(out_dir: &Path, csv_path: &Path) -> Result<()> {
     let html_path = out_dir.join("consistency_report.html");
     let csv_raw = fs::read_to_string(csv_path).unwrap_or_default();
     // Escape backticks and closing tags for safe embedding inside a JS template string
@@ -902,6 +1121,14 @@ fn print_table(results: &[ValidationResult]) {
             r.adherence_score,
             r.latency_ms
         );
+        if let Some(st) = &r.stats {
+            println!("    n={} sd={:.1} 95% CI [{:.1}, {:.1}]", st.n, st.std_dev, st.ci_low, st.ci_high);
+        }
+    }
+    if results.iter().any(|r| r.stats.is_some()) {
+        for c in panther_validation::stats::pairwise(results, panther_validation::stats::DEFAULT_ALPHA).iter().filter(|c| !c.significant) {
+            println!("  ≈ {} vs {}: not significant (p={:.2})", c.a, c.b, c.p_value);
+        }
     }
 }
 
@@ -924,17 +1151,15 @@ fn rewrite_rule(text: &str, must: &[String], style: &str, locale: &str) -> Strin
             t.push_str(&format!("\n\n(Suggested) Include terms: {}", missing.join(", ")));
         }
     }
-    if style.eq_ignore_ascii_case("formal") {
-        if !t.is_empty() {
-            let mut chars = t.chars();
-            if let Some(c0) = chars.next() {
-                let rest: String = chars.collect();
-                let cap = c0.to_uppercase().collect::<String>();
-                if locale.to_ascii_lowercase().starts_with("pt") {
-                    t = format!("Observação: {}{}", cap, rest);
-                } else {
-                    t = format!("Please note: {}{}", cap, rest);
-                }
+    if style.eq_ignore_ascii_case("formal") && !t.is_empty() {
+        let mut chars = t.chars();
+        if let Some(c0) = chars.next() {
+            let rest: String = chars.collect();
+            let cap = c0.to_uppercase().collect::<String>();
+            if locale.to_ascii_lowercase().starts_with("pt") {
+                t = format!("Observação: {}{}", cap, rest);
+            } else {
+                t = format!("Please note: {}{}", cap, rest);
             }
         }
     }
@@ -954,6 +1179,18 @@ fn match_rule<'a>(prov: &str, rules: &'a [CostRule]) -> Option<&'a CostRule> {
     best
 }
 
+fn load_cost_rules(cli: &Cli) -> Vec<CostRule> {
+    let mut rules: Vec<CostRule> = Vec::new();
+    if let Some(p) = cli.costs.clone() {
+        if let Ok(s) = fs::read_to_string(p) {
+            if let Ok(v) = serde_json::from_str::<serde_json::Value>(&s) {
+                if let Some(arr) = v.as_array() { rules = arr.iter().filter_map(|x| serde_json::from_value::<CostRule>(x.clone()).ok()).collect(); }
+            }
+        }
+    }
+    rules
+}
+
 fn generate_advanced_reports(cli: &Cli) -> Result<()> {
     use std::collections::HashMap;
     let res_path = cli.out.join("results.jsonl");
@@ -982,15 +1219,7 @@ fn generate_advanced_reports(cli: &Cli) -> Result<()> {
             }
         }
     }
-    // Load cost rules
-    let mut rules: Vec<CostRule> = Vec::new();
-    if let Some(p) = cli.costs.clone() {
-        if let Ok(s) = fs::read_to_string(p) {
-            if let Ok(v) = serde_json::from_str::<serde_json::Value>(&s) {
-                if let Some(arr) = v.as_array() { rules = arr.iter().filter_map(|x| serde_json::from_value::<CostRule>(x.clone()).ok()).collect(); }
-            }
-        }
-    }
+    let rules = load_cost_rules(cli);
     let mut w = csv::Writer::from_path(cli.out.join("advanced_summary.csv"))?;
     w.write_record(["provider","total","errors","mean_score","std_score","cv_score","p50_ms","p95_ms","tokens_in","tokens_out","est_cost_usd"]).ok();
     // Prepare HTML rows
@@ -1029,7 +1258,7 @@ fn generate_advanced_reports(cli: &Cli) -> Result<()> {
         s.push_str("<h2>Advanced Provider Report</h2><p>Mean score (blue) and estimated cost (orange). Cost scale is relative to max.</p>");
         let max_cost = html_rows.iter().map(|(_,_,c)| *c).fold(0.0, f64::max).max(1e-9);
         for (prov, mean, cost) in html_rows {
-            let w1 = (mean.max(0.0).min(100.0))/100.0*300.0;
+            let w1 = mean.clamp(0.0, 100.0)/100.0*300.0;
             let w2 = (cost/max_cost*300.0).min(300.0);
             s.push_str(&format!("<div class='row'><span style='display:inline-block;width:140px'>{}</span><span class='bar' style='width:{:.0}px'></span>{:.1}% <span class='bar2' style='width:{:.0}px'></span>${:.2}</div>", prov, w1, mean, w2, cost));
         }
@@ -1,7 +1,23 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
+use panther_domain::entities::RunQuery;
+use panther_domain::ports::RunRepository;
+use panther_domain::ports::KeyValueStore;
+use panther_storage::InMemoryStore;
+use panther_storage_sled::{SledRunRepository, SledStore};
+use panther_validation::anchor::queue::AnchorQueue;
+use panther_validation::anchor::AnchorConfig;
+use panther_validation::history;
+use panther_validation::ingest::{self, IngestOptions};
+use panther_validation::pack::{GuidelinePack, LintLevel};
+use panther_validation::proof::bundle::{CheckStatus, ProofBundle, BUNDLE_EXTENSION};
+use panther_validation::proof::sign::{self, ProofSigner, TrustedKeys};
+use panther_validation::proof::timestamp::{self, ChainStatus, HttpTsa, TsaRoots};
+use panther_validation::proof::{self, Proof, ProofContext};
+use panther_validation::stats::{self, SamplingConfig};
 use panther_validation::{LLMValidator, ProviderFactory};
 use std::path::PathBuf;
+use std::sync::Arc;
 
 #[derive(Parser, Debug)]
 #[command(name = "panther", version, author, about = "PantherSDK CLI")]
@@ -19,12 +35,114 @@ enum Commands {
         /// Path to guidelines JSON (defaults to ANVISA example)
         #[arg(short, long)]
         guidelines: Option<PathBuf>,
+        /// Calls per provider; >1 reports mean, 95% CI and significance
+        #[arg(short = 'n', long, default_value = "1")]
+        samples: usize,
+        /// Temperatures cycled across samples (comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        temperatures: Vec<f32>,
+        /// Base seed; sample i uses seed+i
+        #[arg(long)]
+        seed: Option<u64>,
+        /// Record the run in the history database
+        #[arg(long)]
+        save: bool,
+        /// History database (default $PANTHER_HISTORY_PATH or ./panther_history)
+        #[arg(long)]
+        db: Option<PathBuf>,
+    },
+    /// Browse saved validation runs
+    History {
+        #[command(subcommand)]
+        cmd: HistoryCmd,
     },
-    /// Proof operations (Stage 1/2/3)
+    /// Proof operations: offline bundles and signatures, plus backend status/history
     Proof {
         #[command(subcommand)]
         cmd: ProofCmd,
     },
+    /// Guideline pack tools
+    Guidelines {
+        #[command(subcommand)]
+        cmd: GuidelinesCmd,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum GuidelinesCmd {
+    /// Check a guideline pack (JSON/YAML or legacy array); exits 1 on errors
+    Lint {
+        /// Path to the pack
+        path: PathBuf,
+        /// Print issues as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Draft a pack from local documents (text extracts of PDF/DOCX, Markdown, HTML) for review
+    Ingest {
+        /// Source documents
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Output pack (.yaml/.yml or .json); review notes go to <out>.review.json
+        #[arg(short, long)]
+        out: PathBuf,
+        /// Pack id
+        #[arg(long, default_value = "draft")]
+        id: String,
+        /// Pack locale, e.g. pt-BR
+        #[arg(long)]
+        locale: Option<String>,
+        /// Jurisdiction, e.g. BR
+        #[arg(long)]
+        jurisdiction: Option<String>,
+        /// Candidate expected terms per topic
+        #[arg(long, default_value = "8")]
+        max_terms: usize,
+        /// Ask a provider (openai|ollama) to suggest extra terms
+        #[arg(long)]
+        llm: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum HistoryCmd {
+    /// List runs, newest first
+    List {
+        /// Only runs that include this provider (e.g. openai:gpt-4o-mini)
+        #[arg(long)]
+        provider: Option<String>,
+        /// Only runs with this guidelines hash (or prefix)
+        #[arg(long)]
+        guidelines: Option<String>,
+        #[arg(long, default_value = "20")]
+        limit: usize,
+        #[arg(long)]
+        json: bool,
+        #[arg(long)]
+        db: Option<PathBuf>,
+    },
+    /// Print a run as JSON
+    Show {
+        id: String,
+        #[arg(long)]
+        db: Option<PathBuf>,
+    },
+    /// Score changes between two runs
+    Diff {
+        from: String,
+        to: String,
+        #[arg(long)]
+        json: bool,
+        #[arg(long)]
+        db: Option<PathBuf>,
+    },
+}
+
+fn open_history(db: Option<PathBuf>) -> Result<SledRunRepository> {
+    let path = db
+        .or_else(|| std::env::var_os("PANTHER_HISTORY_PATH").map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from("./panther_history"));
+    SledRunRepository::open(&path.to_string_lossy())
 }
 
 #[derive(Subcommand, Debug)]
@@ -55,13 +173,99 @@ enum ProofCmd {
         #[arg(long)]
         api_key: Option<String>,
     },
+    /// Sign a proof JSON file in place (or into --out)
+    Sign {
+        proof: PathBuf,
+        /// Key file (default $PANTHER_PROOF_KEY / $PANTHER_PROOF_KEY_FILE)
+        #[arg(long)]
+        key: Option<PathBuf>,
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+    },
+    /// Bundle a proof with all of its inputs into a .pantherproof file (computes the proof unless --proof is given)
+    Seal {
+        /// Prompt text
+        #[arg(long)]
+        prompt: String,
+        /// Providers JSON file
+        #[arg(long)]
+        providers: PathBuf,
+        /// Guidelines pack or legacy array
+        #[arg(long)]
+        guidelines: PathBuf,
+        /// Results JSON file
+        #[arg(long)]
+        results: PathBuf,
+        #[arg(long)]
+        salt: Option<String>,
+        /// Existing proof to seal; must match the inputs
+        #[arg(long)]
+        proof: Option<PathBuf>,
+        /// Sign the proof with this key file (or $PANTHER_PROOF_KEY / $PANTHER_PROOF_KEY_FILE when set)
+        #[arg(long)]
+        key: Option<PathBuf>,
+        /// Anchor receipt JSON to include
+        #[arg(long)]
+        anchor: Option<PathBuf>,
+        /// Output bundle (default proof.pantherproof)
+        #[arg(short, long, default_value = "proof.pantherproof")]
+        out: PathBuf,
+    },
+    /// Anchor bundles' combined hashes and store the receipt in each bundle (several bundles share one Merkle root)
+    Anchor {
+        #[arg(required = true)]
+        bundles: Vec<PathBuf>,
+        /// Keep the batch queue in this sled database so an interrupted run resumes on the next call
+        #[arg(long)]
+        queue_db: Option<PathBuf>,
+        /// Append to this local hash-chained ledger file
+        #[arg(long, conflicts_with = "config")]
+        ledger: Option<PathBuf>,
+        /// Anchor config JSON ({"backend": "eth"|"file"|"devchain", ...})
+        #[arg(long, required_unless_present = "ledger")]
+        config: Option<PathBuf>,
+    },
+    /// Get an RFC 3161 timestamp token for a proof JSON file or .pantherproof bundle, in place (or into --out)
+    Timestamp {
+        file: PathBuf,
+        /// TSA URL (e.g. https://freetsa.org/tsr)
+        #[arg(long)]
+        tsa: String,
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+    },
+    /// Verify a .pantherproof bundle offline; exits 1 and names the mismatched component
+    Verify {
+        bundle: PathBuf,
+        /// JSON map of key id → {alg, public_key}; the proof must be signed by one of them
+        #[arg(long)]
+        trusted: Option<PathBuf>,
+        /// PEM/DER root certificates; the proof's timestamp must come from a TSA chaining to one of them
+        #[arg(long)]
+        tsa_roots: Option<PathBuf>,
+        #[arg(long)]
+        json: bool,
+    },
+    /// Check a proof's timestamp token; with --tsa-roots, also the TSA's certificate chain
+    CheckTimestamp {
+        proof: PathBuf,
+        #[arg(long)]
+        tsa_roots: Option<PathBuf>,
+    },
+    /// Check a proof's signature; with --trusted, also that the key id is pinned to that key
+    CheckSignature {
+        proof: PathBuf,
+        /// JSON map of key id → {alg, public_key}
+        #[arg(long)]
+        trusted: Option<PathBuf>,
+    },
 }
 
 #[tokio::main(flavor = "multi_thread")]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
     match cli.command {
-        Commands::Validate { prompt, guidelines } => {
+        Commands::Validate { prompt, guidelines, samples, temperatures, seed, save, db } => {
             let mut providers = Vec::new();
             if let Ok(p) = ProviderFactory::openai_from_env() { providers.push(p); }
             if let Ok(p) = ProviderFactory::ollama_from_env() { providers.push(p); }
@@ -73,7 +277,9 @@ async fn main() -> Result<()> {
             let default_guides = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
                 .join("../panther-validation/guidelines/anvisa.json");
             let guide_path = guidelines.unwrap_or(default_guides);
-            let validator = LLMValidator::from_path(&guide_path, providers)?;
+            let labels: Vec<String> = providers.iter().map(|(name, _)| name.clone()).collect();
+            let validator = LLMValidator::from_path(&guide_path, providers)?
+                .with_sampling(SamplingConfig { n_samples: samples, temperatures, seed });
             let results = validator.validate(&prompt).await?;
 
             println!("\n🧩 LLM Validation Summary\n────────────────────────────");
@@ -84,6 +290,38 @@ async fn main() -> Result<()> {
                     r.adherence_score,
                     r.latency_ms
                 );
+                if let Some(st) = &r.stats {
+                    println!("    n={} sd={:.1} 95% CI [{:.1}, {:.1}]{}", st.n, st.std_dev, st.ci_low, st.ci_high,
+                        if st.errors > 0 { format!(" ({} failed)", st.errors) } else { String::new() });
+                }
+                for t in &r.topic_scores {
+                    println!("    · {:<28} {:>5.1}%", t.topic, t.score);
+                }
+                for g in r.guideline_scores.iter().filter(|g| !g.missing_required.is_empty()) {
+                    println!("    ! {}: missing required {}", g.topic, g.missing_required.join(", "));
+                }
+                for v in &r.violations {
+                    println!("    ✗ {}: {:?} \"{}\" in \"{}\"", v.topic, v.kind, v.rule, v.snippet);
+                }
+            }
+            if results.len() > 1 {
+                // Results are sorted; flag neighbours the samples cannot tell apart
+                let cmp = stats::pairwise(&results, stats::DEFAULT_ALPHA);
+                println!("\nRanking:");
+                for (i, r) in results.iter().enumerate() {
+                    let tie = i.checked_sub(1).and_then(|j| {
+                        let above = &results[j].provider_name;
+                        cmp.iter().find(|c| (&c.a == above && c.b == r.provider_name) || (c.a == r.provider_name && &c.b == above))
+                    });
+                    match tie {
+                        Some(c) if !c.significant => println!(
+                            "  {}. {:<18} {:>5.1}%  ≈ not significantly different from {} (p={:.2})",
+                            i + 1, r.provider_name, r.adherence_score, results[i - 1].provider_name, c.p_value
+                        ),
+                        _ => println!("  {}. {:<18} {:>5.1}%", i + 1, r.provider_name, r.adherence_score),
+                    }
+                }
+                if samples <= 1 { println!("  (single sample per provider; use --samples N to test significance)"); }
             }
             // Summary p50/p95 and error counts
             if !results.is_empty() {
@@ -114,7 +352,91 @@ async fn main() -> Result<()> {
                     println!("  - {:<18} total={} errors={} p50={} ms", prov, tot, errs, mid);
                 }
             }
+            if save {
+                let guidelines_json = std::fs::read_to_string(&guide_path)?;
+                let run = history::new_run(&prompt, &history::providers_from_labels(&labels), &guidelines_json, &results, None)?;
+                open_history(db)?.save(&run)?;
+                println!("\nSaved run {}", run.id);
+            }
         }
+        Commands::History { cmd } => match cmd {
+            HistoryCmd::List { provider, guidelines, limit, json, db } => {
+                let query = RunQuery { provider, guidelines_hash: guidelines, since_ms: None, limit: Some(limit) };
+                let runs: Vec<_> = open_history(db)?.list(&query)?.iter().map(|r| r.summary()).collect();
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&runs)?);
+                } else {
+                    for r in &runs {
+                        let hash = r.guidelines_hash.as_deref().map(|h| &h[..h.len().min(12)]).unwrap_or("-");
+                        let scores: Vec<String> = r.scores.iter().map(|(p, s)| format!("{}={:.1}", p, s)).collect();
+                        let prompt: String = r.prompt.chars().take(40).collect();
+                        println!("{}  guidelines={}  {}  \"{}\"", r.id, hash, scores.join(" "), prompt);
+                    }
+                    if runs.is_empty() { println!("No runs."); }
+                }
+            }
+            HistoryCmd::Show { id, db } => match open_history(db)?.get(&id)? {
+                Some(run) => println!("{}", serde_json::to_string_pretty(&run)?),
+                None => anyhow::bail!("run not found: {}", id),
+            },
+            HistoryCmd::Diff { from, to, json, db } => {
+                let d = open_history(db)?.diff(&from, &to)?;
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&d)?);
+                } else {
+                    println!("{} → {}", d.from, d.to);
+                    if !d.same_prompt { println!("  prompt changed"); }
+                    if !d.same_guidelines { println!("  guidelines changed"); }
+                    let fmt = |v: Option<f64>| v.map(|x| format!("{:.1}", x)).unwrap_or_else(|| "-".into());
+                    for s in &d.scores {
+                        let delta = s.delta.map(|x| format!("{:+.1}", x)).unwrap_or_default();
+                        println!("  {:<24} {:>6} → {:>6}  {}", s.provider, fmt(s.before), fmt(s.after), delta);
+                    }
+                }
+            }
+        },
+        Commands::Guidelines { cmd } => match cmd {
+            GuidelinesCmd::Lint { path, json } => {
+                let pack = GuidelinePack::load(&path)?;
+                let issues = pack.lint();
+                let errors = issues.iter().filter(|i| i.level == LintLevel::Error).count();
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&issues)?);
+                } else {
+                    let m = &pack.metadata;
+                    println!("{} {} ({} topics, {} rules)", m.id, m.version, pack.topics.len(), pack.rules.len());
+                    for i in &issues {
+                        let level = if i.level == LintLevel::Error { "error" } else { "warning" };
+                        let topic = i.topic.as_deref().map(|t| format!(" [{}]", t)).unwrap_or_default();
+                        println!("  {}: {}{}: {}", level, i.code, topic, i.message);
+                    }
+                    println!("{} error(s), {} warning(s)", errors, issues.len() - errors);
+                }
+                if errors > 0 { std::process::exit(1); }
+            }
+            GuidelinesCmd::Ingest { files, out, id, locale, jurisdiction, max_terms, llm } => {
+                let mut sections = Vec::new();
+                for f in &files {
+                    sections.extend(ingest::load_sections(f)?);
+                }
+                let provider = match llm.as_deref() {
+                    None => None,
+                    Some("openai") => Some(ProviderFactory::openai_from_env()?.1),
+                    Some("ollama") => Some(ProviderFactory::ollama_from_env()?.1),
+                    Some(other) => anyhow::bail!("unknown provider '{}' (openai|ollama)", other),
+                };
+                let mut options = IngestOptions { max_terms, provider, ..Default::default() };
+                options.metadata.id = id;
+                options.metadata.locale = locale;
+                options.metadata.jurisdiction = jurisdiction;
+                let draft = tokio::task::spawn_blocking(move || ingest::draft(&sections, &options)).await?;
+                let review = draft.write(&out)?;
+                let terms: usize = draft.pack.topics.iter().map(|g| g.expected_terms.len() + g.forbidden_terms.len()).sum();
+                println!("{} topics, {} candidate terms → {}", draft.pack.topics.len(), terms, out.display());
+                println!("Review notes → {}", review.display());
+                println!("Edit the draft, then run `panther guidelines lint {}`", out.display());
+            }
+        },
         Commands::Proof { cmd } => {
             match cmd {
                 ProofCmd::Status { hash, api_base, api_key } => {
@@ -147,6 +469,158 @@ async fn main() -> Result<()> {
                     let v: serde_json::Value = resp.json()?;
                     println!("{}", serde_json::to_string_pretty(&v)?);
                 }
+                ProofCmd::Sign { proof, key, out } => {
+                    let signer = match key {
+                        Some(path) => ProofSigner::load(path)?,
+                        None => ProofSigner::from_env()?.ok_or_else(|| anyhow::anyhow!("no signing key: pass --key or set {} / {}", sign::ENV_KEY, sign::ENV_KEY_FILE))?,
+                    };
+                    let mut p: Proof = serde_json::from_str(&std::fs::read_to_string(&proof)?)?;
+                    signer.sign(&mut p);
+                    let out = out.unwrap_or(proof);
+                    std::fs::write(&out, serde_json::to_string_pretty(&p)?)?;
+                    println!("Signed {} as {} ({})", out.display(), signer.key_id(), signer.public_key_hex());
+                }
+                ProofCmd::Seal { prompt, providers, guidelines, results, salt, proof, key, anchor, out } => {
+                    let providers_json = std::fs::read_to_string(&providers)?;
+                    let guidelines_json = std::fs::read_to_string(&guidelines)?;
+                    let results_json = std::fs::read_to_string(&results)?;
+                    let mut p = match proof {
+                        Some(path) => {
+                            let p: Proof = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+                            if !proof::verify_proof_local(&p, &prompt, &providers_json, &guidelines_json, &results_json, salt.clone()) {
+                                anyhow::bail!("{} does not match the given inputs", path.display());
+                            }
+                            p
+                        }
+                        None => {
+                            let ctx = ProofContext { sdk_version: env!("CARGO_PKG_VERSION").to_string(), salt: salt.clone() };
+                            proof::compute_proof(&prompt, &providers_json, &guidelines_json, &results_json, &ctx)?
+                        }
+                    };
+                    let signer = match key {
+                        Some(path) => Some(ProofSigner::load(path)?),
+                        None => ProofSigner::from_env()?,
+                    };
+                    if let Some(s) = &signer { s.sign(&mut p); }
+                    let mut b = ProofBundle::seal(p, &prompt, &providers_json, &guidelines_json, &results_json, salt)?;
+                    if let Some(path) = anchor {
+                        b = b.with_anchor(serde_json::from_str(&std::fs::read_to_string(path)?)?);
+                    }
+                    b.write(&out)?;
+                    println!("Sealed {} (combined {})", out.display(), b.proof.combined_hash);
+                    if let Some(s) = &signer { println!("Signed by {}", s.key_id()); }
+                }
+                ProofCmd::Anchor { bundles, queue_db, ledger, config } => {
+                    let cfg = match (ledger, config) {
+                        (Some(path), _) => AnchorConfig::file(path.display().to_string()),
+                        (None, Some(path)) => serde_json::from_str(&std::fs::read_to_string(path)?)?,
+                        (None, None) => unreachable!("clap requires --ledger or --config"),
+                    };
+                    let backend = cfg.backend()?;
+                    let mut loaded = Vec::new();
+                    for path in bundles {
+                        let b = ProofBundle::read(&path)?;
+                        loaded.push((path, b));
+                    }
+                    if let [(path, b)] = loaded.as_mut_slice() {
+                        let receipt = backend.anchor(&b.proof.combined_hash).await?;
+                        b.anchor = Some(serde_json::to_value(&receipt)?);
+                        b.write(&*path)?;
+                        println!("Anchored {} via {} ({}{})", path.display(), backend.name(), receipt.tx_id, receipt.block.map(|n| format!(" @ {}", n)).unwrap_or_default());
+                    } else {
+                        let store: Arc<dyn KeyValueStore> = match queue_db {
+                            Some(path) => Arc::new(SledStore::open(&path.display().to_string())?),
+                            None => Arc::new(InMemoryStore::default()),
+                        };
+                        let queue = AnchorQueue::new(store, backend.clone()).with_wait(cfg.wait());
+                        let resumed = queue.resume().await?;
+                        if !resumed.is_empty() { println!("Finished {} hashes from an interrupted batch", resumed.len()); }
+                        for (_, b) in &loaded {
+                            queue.enqueue(&b.proof.combined_hash)?;
+                        }
+                        queue.flush().await?;
+                        for (path, b) in &mut loaded {
+                            let a = queue.get(&b.proof.combined_hash)?.ok_or_else(|| anyhow::anyhow!("{} was not anchored", path.display()))?;
+                            b.anchor = Some(a.bundle_receipt());
+                            b.write(&*path)?;
+                            println!("Anchored {} (leaf {} of {})", path.display(), a.inclusion.leaf_index, a.root);
+                        }
+                        if let Some(r) = loaded.first().and_then(|(_, b)| b.anchor.as_ref()) {
+                            println!("Batch root anchored via {} ({})", backend.name(), r.get("tx_id").and_then(|t| t.as_str()).unwrap_or("receipt unavailable"));
+                        }
+                    }
+                }
+                ProofCmd::Timestamp { file, tsa, out } => {
+                    let tsa = HttpTsa::new(tsa);
+                    let out = out.unwrap_or_else(|| file.clone());
+                    let report = if file.extension().is_some_and(|e| e == BUNDLE_EXTENSION) {
+                        let mut b = ProofBundle::read(&file)?;
+                        let report = timestamp::stamp(&mut b.proof, &tsa).await?;
+                        b.write(&out)?;
+                        report
+                    } else {
+                        let mut p: Proof = serde_json::from_str(&std::fs::read_to_string(&file)?)?;
+                        let report = timestamp::stamp(&mut p, &tsa).await?;
+                        std::fs::write(&out, serde_json::to_string_pretty(&p)?)?;
+                        report
+                    };
+                    println!("Timestamped {} by {} (serial {}, skew {} ms)", out.display(), report.tsa, report.serial, report.skew_ms);
+                }
+                ProofCmd::Verify { bundle, trusted, tsa_roots, json } => {
+                    let b = ProofBundle::read(&bundle)?;
+                    let trusted = trusted.map(TrustedKeys::load).transpose()?;
+                    let tsa_roots = tsa_roots.map(TsaRoots::load).transpose()?;
+                    let report = b.verify_with_roots(trusted.as_ref(), tsa_roots.as_ref());
+                    if json {
+                        println!("{}", serde_json::to_string_pretty(&report)?);
+                    } else {
+                        println!("{} ({})", bundle.display(), b.proof.scheme);
+                        for c in &report.checks {
+                            let mark = match c.status {
+                                CheckStatus::Ok => "✓",
+                                CheckStatus::Mismatch => "✗",
+                                CheckStatus::Skipped => "-",
+                            };
+                            println!("  {} {:<10} {}", mark, c.component, c.detail.as_deref().unwrap_or(""));
+                        }
+                        let bad: Vec<&str> = report.mismatches().map(|c| c.component.as_str()).collect();
+                        if bad.is_empty() { println!("OK"); } else { println!("MISMATCH: {}", bad.join(", ")); }
+                    }
+                    if !report.ok() { std::process::exit(1); }
+                }
+                ProofCmd::CheckTimestamp { proof, tsa_roots } => {
+                    let p: Proof = serde_json::from_str(&std::fs::read_to_string(&proof)?)?;
+                    let roots = tsa_roots.map(TsaRoots::load).transpose()?;
+                    match timestamp::verify_timestamp(&p, roots.as_ref()) {
+                        Ok(r) if r.chain == ChainStatus::Untrusted => {
+                            eprintln!("✗ untrusted TSA {}: {}", r.tsa, r.chain_detail.unwrap_or_default());
+                            std::process::exit(1);
+                        }
+                        Ok(r) => {
+                            println!("✓ stamped at {} ms by {} (policy {}, serial {})", r.gen_time_ms, r.tsa, r.policy, r.serial);
+                            println!("  chain {:?}{}", r.chain, r.chain_detail.map(|d| format!(": {}", d)).unwrap_or_default());
+                        }
+                        Err(e) => {
+                            eprintln!("✗ {}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                ProofCmd::CheckSignature { proof, trusted } => {
+                    let p: Proof = serde_json::from_str(&std::fs::read_to_string(&proof)?)?;
+                    let checked = match trusted {
+                        Some(path) => TrustedKeys::load(path)?.check(&p),
+                        None if sign::verify_signature(&p) => Ok(p.signature.as_ref().map(|s| s.key_id.clone()).unwrap_or_default()),
+                        None => Err(anyhow::anyhow!(if p.signature.is_some() { "invalid signature" } else { "proof is not signed" })),
+                    };
+                    match checked {
+                        Ok(key_id) => println!("✓ signed by {}", key_id),
+                        Err(e) => {
+                            eprintln!("✗ {}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
             }
         }
     }
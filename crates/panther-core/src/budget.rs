@@ -0,0 +1,469 @@
+//! Spending budgets persisted in a `KeyValueStore`.
+//!
+//! A budget caps USD and/or tokens for a provider, a tenant or a single run
+//! over a daily, monthly or all-time window. Callers reserve the estimated
+//! cost of a request before sending it (hard limit rejects, soft limit warns)
+//! and settle the reservation against the actual usage afterwards, so
+//! concurrent calls cannot all pass a cap that only one of them fits.
+
+use panther_domain::errors::PantherError;
+use panther_domain::ports::KeyValueStore;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+/// Output tokens reserved for a call that sets no `max_tokens`.
+pub const DEFAULT_OUTPUT_TOKENS: u64 = 512;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetScope {
+    Provider,
+    Tenant,
+    Run,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetWindow {
+    Daily,
+    Monthly,
+    #[default]
+    Total,
+}
+
+/// A single cap. `key` matches the provider label, tenant or run id:
+/// exact value, `prefix:` (e.g. `openai:`) or `*` for every subject.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Budget {
+    pub scope: BudgetScope,
+    #[serde(default = "default_key")]
+    pub key: String,
+    #[serde(default)]
+    pub window: BudgetWindow,
+    #[serde(default)]
+    pub max_usd: Option<f64>,
+    #[serde(default)]
+    pub max_tokens: Option<u64>,
+    /// Fraction of the limit that triggers a warning (default 0.8).
+    #[serde(default)]
+    pub soft_limit_ratio: Option<f64>,
+}
+
+fn default_key() -> String { "*".to_string() }
+
+/// Per-provider price, same shape as the `panther-ai-eval --costs` rules.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceRule {
+    pub provider: String,
+    pub usd_per_1k_in: f64,
+    pub usd_per_1k_out: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct BudgetConfig {
+    #[serde(default)]
+    pub budgets: Vec<Budget>,
+    #[serde(default)]
+    pub pricing: Vec<PriceRule>,
+    /// Output tokens reserved per call without `max_tokens` (default [`DEFAULT_OUTPUT_TOKENS`]).
+    #[serde(default)]
+    pub output_tokens: Option<u64>,
+}
+
+/// Who is spending: the provider label is passed per call, tenant and run are fixed.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SpendContext {
+    pub tenant: Option<String>,
+    pub run: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct Usage {
+    pub usd: f64,
+    pub tokens: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BudgetStatus {
+    pub scope: BudgetScope,
+    pub subject: String,
+    pub window: BudgetWindow,
+    pub period: String,
+    pub spent_usd: f64,
+    pub spent_tokens: u64,
+    pub max_usd: Option<f64>,
+    pub max_tokens: Option<u64>,
+    pub remaining_usd: Option<f64>,
+    pub remaining_tokens: Option<u64>,
+}
+
+impl BudgetStatus {
+    fn describe(&self) -> String {
+        format!(
+            "{:?} '{}' ({:?} {}): spent ${:.4}/{} tokens, limit {}/{}",
+            self.scope,
+            self.subject,
+            self.window,
+            self.period,
+            self.spent_usd,
+            self.spent_tokens,
+            self.max_usd.map(|v| format!("${:.4}", v)).unwrap_or_else(|| "-".into()),
+            self.max_tokens.map(|v| v.to_string()).unwrap_or_else(|| "-".into()),
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum BudgetDecision {
+    Allow,
+    Warn(Vec<BudgetStatus>),
+    Reject(BudgetStatus),
+}
+
+pub struct BudgetManager {
+    store: Arc<dyn KeyValueStore>,
+    budgets: Vec<Budget>,
+    pricing: Vec<PriceRule>,
+    output_tokens: u64,
+    // Serializes check-and-reserve and read-modify-write of counters within this process.
+    lock: Mutex<()>,
+}
+
+/// Spend held against every applicable budget while a call is in flight.
+/// [`Reservation::settle`] replaces it with the actual usage; dropping it
+/// unsettled (the call failed, timed out or was cancelled) releases it.
+#[must_use = "dropping a reservation releases it"]
+pub struct Reservation {
+    manager: Arc<BudgetManager>,
+    provider: String,
+    keys: Vec<String>,
+    held: Usage,
+    settled: bool,
+    /// Soft limits this call reaches.
+    pub warnings: Vec<BudgetStatus>,
+}
+
+impl Reservation {
+    /// Charges the actual usage in place of the reserved estimate; returns the USD cost.
+    pub fn settle(mut self, tokens_in: u64, tokens_out: u64) -> anyhow::Result<f64> {
+        self.settled = true;
+        let cost = self.manager.estimate_cost(&self.provider, tokens_in, tokens_out);
+        let actual = Usage { usd: cost, tokens: tokens_in + tokens_out };
+        self.manager.adjust(&self.keys, &actual, &self.held)?;
+        Ok(cost)
+    }
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        if !self.settled {
+            let _ = self.manager.adjust(&self.keys, &Usage::default(), &self.held);
+        }
+    }
+}
+
+impl BudgetManager {
+    pub fn new(store: Arc<dyn KeyValueStore>, config: BudgetConfig) -> Self {
+        let output_tokens = config.output_tokens.unwrap_or(DEFAULT_OUTPUT_TOKENS);
+        Self { store, budgets: config.budgets, pricing: config.pricing, output_tokens, lock: Mutex::new(()) }
+    }
+
+    pub fn from_json_str(store: Arc<dyn KeyValueStore>, json: &str) -> anyhow::Result<Self> {
+        let config: BudgetConfig = serde_json::from_str(json)?;
+        Ok(Self::new(store, config))
+    }
+
+    /// Adds price rules used when the config did not define any.
+    pub fn with_pricing(mut self, pricing: Vec<PriceRule>) -> Self {
+        if self.pricing.is_empty() { self.pricing = pricing; }
+        self
+    }
+
+    pub fn budgets(&self) -> &[Budget] { &self.budgets }
+
+    pub fn estimate_cost(&self, provider: &str, tokens_in: u64, tokens_out: u64) -> f64 {
+        match select_price(&self.pricing, provider) {
+            Some(r) => (tokens_in as f64 / 1000.0) * r.usd_per_1k_in + (tokens_out as f64 / 1000.0) * r.usd_per_1k_out,
+            None => 0.0,
+        }
+    }
+
+    /// Checks whether a request of `tokens_in` input tokens and up to `max_tokens_out` output
+    /// tokens (default `output_tokens`) fits every applicable budget, without reserving it.
+    pub fn check(&self, provider: &str, ctx: &SpendContext, tokens_in: u64, max_tokens_out: Option<u64>) -> anyhow::Result<BudgetDecision> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        self.decide(provider, ctx, &self.pending(provider, tokens_in, max_tokens_out))
+    }
+
+    /// Checks the request like `check` and, when it fits, reserves its estimated cost in the
+    /// same critical section. A hard-limit hit is `PantherError::BudgetExceeded`; soft-limit
+    /// hits are the reservation's `warnings`.
+    pub fn enforce(self: &Arc<Self>, provider: &str, ctx: &SpendContext, tokens_in: u64, max_tokens_out: Option<u64>) -> anyhow::Result<Reservation> {
+        let held = self.pending(provider, tokens_in, max_tokens_out);
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let warnings = match self.decide(provider, ctx, &held)? {
+            BudgetDecision::Allow => Vec::new(),
+            BudgetDecision::Warn(w) => w,
+            BudgetDecision::Reject(st) => return Err(PantherError::BudgetExceeded(st.describe()).into()),
+        };
+        let keys = self.counter_keys(provider, ctx);
+        self.add(&keys, &held, &Usage::default())?;
+        Ok(Reservation { manager: self.clone(), provider: provider.to_string(), keys, held, settled: false, warnings })
+    }
+
+    fn pending(&self, provider: &str, tokens_in: u64, max_tokens_out: Option<u64>) -> Usage {
+        let tokens_out = max_tokens_out.unwrap_or(self.output_tokens);
+        Usage { usd: self.estimate_cost(provider, tokens_in, tokens_out), tokens: tokens_in + tokens_out }
+    }
+
+    fn decide(&self, provider: &str, ctx: &SpendContext, pending: &Usage) -> anyhow::Result<BudgetDecision> {
+        let mut warnings = Vec::new();
+        let now = now_ms();
+        for (b, subject) in self.applicable(provider, ctx) {
+            let st = self.status(b, &subject, now)?;
+            let usd = st.spent_usd + pending.usd;
+            let tokens = st.spent_tokens + pending.tokens;
+            let over_usd = b.max_usd.map(|m| usd > m || st.spent_usd >= m).unwrap_or(false);
+            let over_tok = b.max_tokens.map(|m| tokens > m || st.spent_tokens >= m).unwrap_or(false);
+            if over_usd || over_tok {
+                return Ok(BudgetDecision::Reject(st));
+            }
+            let ratio = b.soft_limit_ratio.unwrap_or(0.8);
+            let soft_usd = b.max_usd.map(|m| usd >= m * ratio).unwrap_or(false);
+            let soft_tok = b.max_tokens.map(|m| tokens as f64 >= m as f64 * ratio).unwrap_or(false);
+            if soft_usd || soft_tok {
+                warnings.push(st);
+            }
+        }
+        if warnings.is_empty() { Ok(BudgetDecision::Allow) } else { Ok(BudgetDecision::Warn(warnings)) }
+    }
+
+    /// Adds usage of a call made without a reservation to every applicable budget and
+    /// returns the USD cost.
+    pub fn record(&self, provider: &str, ctx: &SpendContext, tokens_in: u64, tokens_out: u64) -> anyhow::Result<f64> {
+        let cost = self.estimate_cost(provider, tokens_in, tokens_out);
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        self.add(&self.counter_keys(provider, ctx), &Usage { usd: cost, tokens: tokens_in + tokens_out }, &Usage::default())?;
+        Ok(cost)
+    }
+
+    fn adjust(&self, keys: &[String], plus: &Usage, minus: &Usage) -> anyhow::Result<()> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        self.add(keys, plus, minus)
+    }
+
+    // Callers hold `lock`
+    fn add(&self, keys: &[String], plus: &Usage, minus: &Usage) -> anyhow::Result<()> {
+        for key in keys {
+            let mut u = self.load(key)?;
+            u.usd = (u.usd + plus.usd - minus.usd).max(0.0);
+            u.tokens = (u.tokens + plus.tokens).saturating_sub(minus.tokens);
+            self.store.set(key, serde_json::to_string(&u)?)?;
+        }
+        Ok(())
+    }
+
+    fn counter_keys(&self, provider: &str, ctx: &SpendContext) -> Vec<String> {
+        let now = now_ms();
+        let mut keys: Vec<String> = self.applicable(provider, ctx).into_iter().map(|(b, s)| counter_key(b, &s, now)).collect();
+        // budgets sharing scope, subject and window share one counter
+        keys.sort();
+        keys.dedup();
+        keys
+    }
+
+    /// Remaining budget for every cap that applies to `provider` and `ctx`.
+    pub fn remaining(&self, provider: &str, ctx: &SpendContext) -> anyhow::Result<Vec<BudgetStatus>> {
+        let now = now_ms();
+        self.applicable(provider, ctx).into_iter().map(|(b, s)| self.status(b, &s, now)).collect()
+    }
+
+    /// Status of a single subject (e.g. a tenant id) for every budget of `scope`.
+    pub fn remaining_for(&self, scope: BudgetScope, subject: &str) -> anyhow::Result<Vec<BudgetStatus>> {
+        let now = now_ms();
+        let mut out = Vec::new();
+        for b in self.budgets.iter().filter(|b| b.scope == scope && key_matches(&b.key, subject)) {
+            out.push(self.status(b, subject, now)?);
+        }
+        Ok(out)
+    }
+
+    fn applicable(&self, provider: &str, ctx: &SpendContext) -> Vec<(&Budget, String)> {
+        let mut out = Vec::new();
+        for b in &self.budgets {
+            let subject = match b.scope {
+                BudgetScope::Provider => Some(provider),
+                BudgetScope::Tenant => ctx.tenant.as_deref(),
+                BudgetScope::Run => ctx.run.as_deref(),
+            };
+            if let Some(s) = subject {
+                if key_matches(&b.key, s) { out.push((b, s.to_string())); }
+            }
+        }
+        out
+    }
+
+    fn status(&self, b: &Budget, subject: &str, now: i64) -> anyhow::Result<BudgetStatus> {
+        let u = self.load(&counter_key(b, subject, now))?;
+        Ok(BudgetStatus {
+            scope: b.scope,
+            subject: subject.to_string(),
+            window: b.window,
+            period: period_label(b.window, now),
+            spent_usd: u.usd,
+            spent_tokens: u.tokens,
+            max_usd: b.max_usd,
+            max_tokens: b.max_tokens,
+            remaining_usd: b.max_usd.map(|m| (m - u.usd).max(0.0)),
+            remaining_tokens: b.max_tokens.map(|m| m.saturating_sub(u.tokens)),
+        })
+    }
+
+    fn load(&self, key: &str) -> anyhow::Result<Usage> {
+        Ok(self.store.get(key)?.and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default())
+    }
+}
+
+/// A [`BudgetManager`] bound to who is spending: what the engine, the validators and their
+/// context-fitting calls charge provider calls through.
+#[derive(Clone)]
+pub struct BudgetCtl {
+    pub manager: Arc<BudgetManager>,
+    pub ctx: SpendContext,
+}
+
+impl BudgetCtl {
+    pub fn new(manager: Arc<BudgetManager>, ctx: SpendContext) -> Self { Self { manager, ctx } }
+
+    /// Reserves a call sending `prompt` to `provider`; soft-limit hits are logged.
+    pub fn admit(&self, provider: &str, prompt: &str, max_tokens: Option<u32>) -> anyhow::Result<Reservation> {
+        let r = self.manager.enforce(provider, &self.ctx, estimate_tokens(prompt), max_tokens.map(u64::from))?;
+        for w in &r.warnings {
+            warn!(target: "panther", provider, subject = %w.subject, "budget soft limit reached");
+        }
+        Ok(r)
+    }
+
+    /// [`BudgetCtl::admit`] for an optional budget.
+    pub fn admit_opt(budget: &Option<BudgetCtl>, provider: &str, prompt: &str, max_tokens: Option<u32>) -> anyhow::Result<Option<Reservation>> {
+        budget.as_ref().map(|b| b.admit(provider, prompt, max_tokens)).transpose()
+    }
+}
+
+/// Whitespace token estimate, consistent with `Engine` metrics.
+pub fn estimate_tokens(text: &str) -> u64 { text.split_whitespace().count() as u64 }
+
+fn key_matches(pattern: &str, subject: &str) -> bool {
+    let p = pattern.to_lowercase();
+    let s = subject.to_lowercase();
+    if p == "*" { return true; }
+    if p.ends_with(':') { return s.starts_with(&p); }
+    s == p
+}
+
+fn select_price<'a>(rules: &'a [PriceRule], provider: &str) -> Option<&'a PriceRule> {
+    rules
+        .iter()
+        .find(|r| r.provider.eq_ignore_ascii_case(provider))
+        .or_else(|| rules.iter().find(|r| r.provider != "*" && key_matches(&r.provider, provider)))
+        .or_else(|| rules.iter().find(|r| r.provider == "*"))
+}
+
+fn counter_key(b: &Budget, subject: &str, now: i64) -> String {
+    let scope = match b.scope {
+        BudgetScope::Provider => "provider",
+        BudgetScope::Tenant => "tenant",
+        BudgetScope::Run => "run",
+    };
+    format!("budget:{}:{}:{}", scope, subject, period_label(b.window, now))
+}
+
+fn period_label(window: BudgetWindow, now: i64) -> String {
+    let (y, m, d) = civil_from_days(now.div_euclid(86_400_000));
+    match window {
+        BudgetWindow::Daily => format!("{:04}-{:02}-{:02}", y, m, d),
+        BudgetWindow::Monthly => format!("{:04}-{:02}", y, m),
+        BudgetWindow::Total => "total".to_string(),
+    }
+}
+
+// Days since 1970-01-01 to a proleptic Gregorian (year, month, day).
+fn civil_from_days(z: i64) -> (i64, u32, u32) {
+    let z = z + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    (y, m, d)
+}
+
+fn now_ms() -> i64 { crate::chrono::Utc::now().timestamp_millis() }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager(json: &str) -> Arc<BudgetManager> {
+        Arc::new(BudgetManager::from_json_str(Arc::new(panther_storage::InMemoryStore::default()), json).unwrap())
+    }
+
+    #[test]
+    fn civil_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(19_723), (2024, 1, 1));
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+    }
+
+    #[test]
+    fn warns_then_rejects_on_token_cap() {
+        let m = manager(r#"{"budgets":[{"scope":"provider","key":"openai:","window":"daily","max_tokens":100}]}"#);
+        let ctx = SpendContext::default();
+        assert!(matches!(m.check("openai:gpt-4o", &ctx, 10, Some(10)).unwrap(), BudgetDecision::Allow));
+        // without max_tokens the default output estimate alone is over the cap
+        assert!(matches!(m.check("openai:gpt-4o", &ctx, 10, None).unwrap(), BudgetDecision::Reject(_)));
+        m.record("openai:gpt-4o", &ctx, 40, 45).unwrap();
+        assert!(matches!(m.check("openai:gpt-4o", &ctx, 5, Some(0)).unwrap(), BudgetDecision::Warn(_)));
+        let err = m.enforce("openai:gpt-4o", &ctx, 20, Some(0)).err().unwrap();
+        assert!(err.to_string().starts_with("budget_exceeded:"));
+        // other providers are not covered by the `openai:` budget
+        assert!(matches!(m.check("ollama:llama3", &ctx, 500, None).unwrap(), BudgetDecision::Allow));
+    }
+
+    #[test]
+    fn usd_cap_per_tenant_and_remaining() {
+        let m = manager(r#"{
+            "budgets":[{"scope":"tenant","key":"*","window":"monthly","max_usd":1.0}],
+            "pricing":[{"provider":"*","usd_per_1k_in":0.5,"usd_per_1k_out":0.5}]
+        }"#);
+        let acme = SpendContext { tenant: Some("acme".into()), run: None };
+        let cost = m.record("openai:gpt-4o", &acme, 1000, 1000).unwrap();
+        assert!((cost - 1.0).abs() < 1e-9);
+        assert!(matches!(m.check("openai:gpt-4o", &acme, 1, Some(0)).unwrap(), BudgetDecision::Reject(_)));
+        let rem = m.remaining_for(BudgetScope::Tenant, "acme").unwrap();
+        assert_eq!(rem[0].remaining_usd, Some(0.0));
+        // a different tenant has its own counter
+        let other = SpendContext { tenant: Some("other".into()), run: None };
+        assert_eq!(m.remaining("openai:gpt-4o", &other).unwrap()[0].remaining_usd, Some(1.0));
+    }
+
+    #[test]
+    fn reservations_hold_until_settled_or_dropped() {
+        let m = manager(r#"{"budgets":[{"scope":"provider","key":"*","max_tokens":100}]}"#);
+        let ctx = SpendContext::default();
+        let left = |m: &BudgetManager| m.remaining("mock", &ctx).unwrap()[0].remaining_tokens;
+        let first = m.enforce("mock", &ctx, 30, Some(30)).unwrap();
+        // the held 60 tokens leave no room for a second call of the same size
+        assert!(m.enforce("mock", &ctx, 30, Some(30)).is_err());
+        assert_eq!(left(&m), Some(40));
+        first.settle(30, 5).unwrap();
+        assert_eq!(left(&m), Some(65));
+        let second = m.enforce("mock", &ctx, 30, Some(30)).unwrap();
+        drop(second);
+        assert_eq!(left(&m), Some(65));
+    }
+}
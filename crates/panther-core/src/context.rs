@@ -0,0 +1,365 @@
+//! Model capabilities registry and context-window fitting.
+//!
+//! `ContextPolicy` estimates the prompt size, compares it with the model's
+//! context window (minus room reserved for the answer) and, depending on the
+//! strategy, rejects the prompt or shrinks it before it reaches the vendor.
+
+use panther_domain::errors::PantherError;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelCapabilities {
+    pub provider: String,
+    pub model: String,
+    pub context_window: u32,
+    pub max_output_tokens: u32,
+    #[serde(default)]
+    pub supports_tools: bool,
+    #[serde(default)]
+    pub supports_json: bool,
+    #[serde(default)]
+    pub supports_streaming: bool,
+    #[serde(default)]
+    pub supports_vision: bool,
+}
+
+type BuiltinRow = (&'static str, &'static str, u32, u32, [bool; 4]);
+
+/// Capabilities keyed by provider + model. Lookups fall back to the longest
+/// registered model prefix, so `gpt-4o` also covers `gpt-4o-2024-08-06`.
+#[derive(Debug, Clone, Default)]
+pub struct ModelRegistry {
+    entries: Vec<ModelCapabilities>,
+}
+
+impl ModelRegistry {
+    pub fn empty() -> Self { Self::default() }
+
+    /// Registry preloaded with well-known OpenAI, Anthropic and Ollama models.
+    pub fn builtin() -> Self {
+        // (provider, model, context, max_out, [tools, json, streaming, vision])
+        let rows: [BuiltinRow; 13] = [
+            ("openai", "gpt-4o", 128_000, 16_384, [true, true, true, true]),
+            ("openai", "gpt-4o-mini", 128_000, 16_384, [true, true, true, true]),
+            ("openai", "gpt-4.1", 1_047_576, 32_768, [true, true, true, true]),
+            ("openai", "gpt-4-turbo", 128_000, 4_096, [true, true, true, true]),
+            ("openai", "gpt-3.5-turbo", 16_385, 4_096, [true, true, true, false]),
+            ("anthropic", "claude-3-5-sonnet", 200_000, 8_192, [true, false, true, true]),
+            ("anthropic", "claude-3-5-haiku", 200_000, 8_192, [true, false, true, false]),
+            ("anthropic", "claude-3-opus", 200_000, 4_096, [true, false, true, true]),
+            ("ollama", "llama3", 8_192, 2_048, [false, true, true, false]),
+            ("ollama", "llama3.1", 131_072, 2_048, [true, true, true, false]),
+            ("ollama", "mistral", 32_768, 2_048, [true, true, true, false]),
+            ("ollama", "qwen2.5", 32_768, 2_048, [true, true, true, false]),
+            ("ollama", "llava", 4_096, 1_024, [false, true, true, true]),
+        ];
+        let entries = rows
+            .iter()
+            .map(|&(provider, model, ctx, out, [tools, json, streaming, vision])| ModelCapabilities {
+                provider: provider.into(),
+                model: model.into(),
+                context_window: ctx,
+                max_output_tokens: out,
+                supports_tools: tools,
+                supports_json: json,
+                supports_streaming: streaming,
+                supports_vision: vision,
+            })
+            .collect();
+        Self { entries }
+    }
+
+    /// Built-in registry extended/overridden by a JSON array of `ModelCapabilities`.
+    pub fn from_json_str(json: &str) -> anyhow::Result<Self> {
+        let extra: Vec<ModelCapabilities> = serde_json::from_str(json)?;
+        let mut reg = Self::builtin();
+        for c in extra { reg.register(c); }
+        Ok(reg)
+    }
+
+    /// Adds or replaces the entry for `caps.provider` + `caps.model`.
+    pub fn register(&mut self, caps: ModelCapabilities) {
+        self.entries.retain(|e| {
+            !(e.provider.eq_ignore_ascii_case(&caps.provider) && e.model.eq_ignore_ascii_case(&caps.model))
+        });
+        self.entries.push(caps);
+    }
+
+    pub fn lookup(&self, provider: &str, model: &str) -> Option<&ModelCapabilities> {
+        let model = model.to_lowercase();
+        // Ollama tags (`llama3:8b`) share the base model's capabilities
+        let base = model.split(':').next().unwrap_or(&model);
+        self.entries
+            .iter()
+            .filter(|e| e.provider.eq_ignore_ascii_case(provider))
+            .filter(|e| {
+                let m = e.model.to_lowercase();
+                model == m || base == m || model.starts_with(&format!("{}-", m))
+            })
+            .max_by_key(|e| e.model.len())
+    }
+
+    /// Lookup by a validator label such as `openai:gpt-4o-mini`.
+    pub fn lookup_label(&self, label: &str) -> Option<&ModelCapabilities> {
+        let (provider, model) = label.split_once(':')?;
+        self.lookup(provider, model)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContextStrategy {
+    /// Fail with `PantherError::ContextLengthExceeded` before sending.
+    Reject,
+    /// Keep the beginning and the end, drop the middle.
+    TruncateMiddle,
+    /// Drop the oldest messages (blank-line separated blocks), keep the latest.
+    TruncateOldest,
+    /// Ask the model to summarize older messages, keep the latest verbatim.
+    Summarize,
+}
+
+impl FromStr for ContextStrategy {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+            "reject" => Ok(Self::Reject),
+            "truncate_middle" => Ok(Self::TruncateMiddle),
+            "truncate_oldest" => Ok(Self::TruncateOldest),
+            "summarize" => Ok(Self::Summarize),
+            other => Err(anyhow::anyhow!("unknown context strategy: {}", other)),
+        }
+    }
+}
+
+/// Outcome of `ContextPolicy::plan`.
+#[derive(Debug, Clone)]
+pub enum FitPlan {
+    Keep,
+    Replace(String),
+    Summarize(SummaryRequest),
+}
+
+/// Older context to be summarized by the model, plus the latest message kept verbatim.
+#[derive(Debug, Clone)]
+pub struct SummaryRequest {
+    older: String,
+    recent: String,
+    limit: usize,
+    target_tokens: usize,
+}
+
+impl SummaryRequest {
+    /// Prompt sent to the model to produce the summary.
+    pub fn prompt(&self) -> String {
+        let header = format!(
+            "Summarize the following context in at most {} words. Keep facts, names and numbers.\n\n",
+            self.target_tokens
+        );
+        let room = self.limit.saturating_sub(estimate_tokens(&header)).max(1);
+        format!("{}{}", header, truncate_middle(&self.older, room))
+    }
+
+    /// Words the summary is asked to stay within.
+    pub fn target_tokens(&self) -> usize { self.target_tokens }
+
+    /// Final prompt: summary followed by the latest message, truncated if still too long.
+    pub fn combine(&self, summary: &str) -> String {
+        let text = format!("Summary of earlier context:\n{}\n\n{}", summary.trim(), self.recent);
+        truncate_middle(&text, self.limit)
+    }
+}
+
+#[derive(Clone)]
+pub struct ContextPolicy {
+    registry: Arc<ModelRegistry>,
+    strategy: ContextStrategy,
+    reserve_output_tokens: Option<u32>,
+}
+
+impl ContextPolicy {
+    pub fn new(registry: Arc<ModelRegistry>, strategy: ContextStrategy) -> Self {
+        Self { registry, strategy, reserve_output_tokens: None }
+    }
+
+    /// Tokens kept free for the answer (default: the model's max output, capped at half the window).
+    pub fn with_reserve_output(mut self, tokens: u32) -> Self {
+        self.reserve_output_tokens = Some(tokens);
+        self
+    }
+
+    pub fn strategy(&self) -> ContextStrategy { self.strategy }
+
+    pub fn registry(&self) -> &ModelRegistry { &self.registry }
+
+    /// Maximum prompt tokens for a model, or `None` when the model is unknown.
+    pub fn input_limit(&self, provider: &str, model: Option<&str>) -> Option<usize> {
+        let caps = self.registry.lookup(provider, model?)?;
+        let reserve = self
+            .reserve_output_tokens
+            .unwrap_or(caps.max_output_tokens)
+            .min(caps.context_window / 2);
+        Some(caps.context_window.saturating_sub(reserve) as usize)
+    }
+
+    /// Decides how `text` must change to fit the model's context window.
+    pub fn plan(&self, provider: &str, model: Option<&str>, text: &str) -> anyhow::Result<FitPlan> {
+        let Some(limit) = self.input_limit(provider, model) else { return Ok(FitPlan::Keep) };
+        let tokens = estimate_tokens(text);
+        if tokens <= limit {
+            return Ok(FitPlan::Keep);
+        }
+        match self.strategy {
+            ContextStrategy::Reject => Err(PantherError::ContextLengthExceeded(format!(
+                "{}:{} prompt ~{} tokens exceeds input limit {}",
+                provider,
+                model.unwrap_or_default(),
+                tokens,
+                limit
+            ))
+            .into()),
+            ContextStrategy::TruncateMiddle => Ok(FitPlan::Replace(truncate_middle(text, limit))),
+            ContextStrategy::TruncateOldest => Ok(FitPlan::Replace(truncate_oldest(text, limit))),
+            ContextStrategy::Summarize => {
+                let mut blocks = split_messages(text);
+                let recent = blocks.pop().unwrap_or_default();
+                let recent_tokens = estimate_tokens(&recent);
+                // Nothing older to summarize, or the latest message alone overflows
+                if blocks.is_empty() || recent_tokens >= limit {
+                    return Ok(FitPlan::Replace(truncate_middle(text, limit)));
+                }
+                let target_tokens = ((limit - recent_tokens) / 2).clamp(1, 1_024);
+                Ok(FitPlan::Summarize(SummaryRequest { older: blocks.join("\n\n"), recent, limit, target_tokens }))
+            }
+        }
+    }
+}
+
+/// Conservative token estimate: the larger of the word count and chars/4.
+pub fn estimate_tokens(text: &str) -> usize {
+    let words = text.split_whitespace().count();
+    let chars = text.chars().count().div_ceil(4);
+    words.max(chars)
+}
+
+const ELISION: &str = "\n[…]\n";
+
+/// Keeps the head and tail of `text` so that it fits in `limit` tokens.
+pub fn truncate_middle(text: &str, limit: usize) -> String {
+    if estimate_tokens(text) <= limit {
+        return text.to_string();
+    }
+    let chars: Vec<char> = text.chars().collect();
+    // chars/4 dominates for normal prose; shrink until the word count also fits
+    let mut keep = limit.saturating_sub(estimate_tokens(ELISION)).saturating_mul(4).min(chars.len());
+    loop {
+        let head: String = chars[..keep / 2].iter().collect();
+        let tail: String = chars[chars.len() - (keep - keep / 2)..].iter().collect();
+        let out = format!("{}{}{}", head, ELISION, tail);
+        if estimate_tokens(&out) <= limit || keep == 0 {
+            return out;
+        }
+        keep = keep * 9 / 10;
+    }
+}
+
+/// Drops the oldest blank-line separated messages until `text` fits.
+pub fn truncate_oldest(text: &str, limit: usize) -> String {
+    let mut blocks = split_messages(text);
+    while blocks.len() > 1 && estimate_tokens(&blocks.join("\n\n")) > limit {
+        blocks.remove(0);
+    }
+    let joined = blocks.join("\n\n");
+    if estimate_tokens(&joined) <= limit {
+        return joined;
+    }
+    // The latest message alone is too long: keep its end (usually the question)
+    let chars: Vec<char> = joined.chars().collect();
+    let mut keep = limit.saturating_mul(4).min(chars.len());
+    loop {
+        let out: String = chars[chars.len() - keep..].iter().collect();
+        if estimate_tokens(&out) <= limit || keep == 0 {
+            return out;
+        }
+        keep = keep * 9 / 10;
+    }
+}
+
+fn split_messages(text: &str) -> Vec<String> {
+    text.split("\n\n").map(|s| s.trim()).filter(|s| !s.is_empty()).map(|s| s.to_string()).collect()
+}
+
+/// Policy over a registry holding one text-only model, for tests.
+#[cfg(test)]
+pub(crate) fn test_policy(provider: &str, model: &str, context_window: u32, max_output_tokens: u32, strategy: ContextStrategy) -> ContextPolicy {
+    let mut reg = ModelRegistry::empty();
+    reg.register(ModelCapabilities {
+        provider: provider.into(),
+        model: model.into(),
+        context_window,
+        max_output_tokens,
+        supports_tools: false,
+        supports_json: false,
+        supports_streaming: false,
+        supports_vision: false,
+    });
+    ContextPolicy::new(Arc::new(reg), strategy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(strategy: ContextStrategy) -> ContextPolicy { test_policy("mock", "tiny", 200, 50, strategy) }
+
+    #[test]
+    fn lookup_prefers_longest_prefix() {
+        let reg = ModelRegistry::builtin();
+        assert_eq!(reg.lookup("openai", "gpt-4o-mini").unwrap().model, "gpt-4o-mini");
+        assert_eq!(reg.lookup("openai", "gpt-4o-2024-08-06").unwrap().model, "gpt-4o");
+        assert_eq!(reg.lookup_label("ollama:llama3:8b").unwrap().context_window, 8_192);
+        assert!(reg.lookup("openai", "unknown-model").is_none());
+    }
+
+    #[test]
+    fn reject_and_truncate() {
+        let long = "word ".repeat(400);
+        let err = policy(ContextStrategy::Reject).plan("mock", Some("tiny"), &long).unwrap_err();
+        assert!(err.to_string().starts_with("context_length_exceeded:"));
+
+        match policy(ContextStrategy::TruncateMiddle).plan("mock", Some("tiny"), &long).unwrap() {
+            FitPlan::Replace(t) => assert!(estimate_tokens(&t) <= 150 && t.contains("[…]")),
+            other => panic!("unexpected plan: {:?}", other),
+        }
+        // unknown model: no limit applies
+        assert!(matches!(policy(ContextStrategy::Reject).plan("mock", Some("other"), &long).unwrap(), FitPlan::Keep));
+    }
+
+    #[test]
+    fn truncate_oldest_keeps_latest_message() {
+        let text = format!("{}\n\n{}\n\nWhat is insulin?", "a ".repeat(100), "b ".repeat(100));
+        match policy(ContextStrategy::TruncateOldest).plan("mock", Some("tiny"), &text).unwrap() {
+            FitPlan::Replace(t) => {
+                assert!(t.ends_with("What is insulin?"));
+                assert!(!t.contains("a a"));
+            }
+            other => panic!("unexpected plan: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn summarize_combines_summary_with_latest() {
+        let text = format!("{}\n\nWhat is insulin?", "context ".repeat(300));
+        match policy(ContextStrategy::Summarize).plan("mock", Some("tiny"), &text).unwrap() {
+            FitPlan::Summarize(req) => {
+                assert!(estimate_tokens(&req.prompt()) <= 150);
+                let out = req.combine("short summary");
+                assert!(out.starts_with("Summary of earlier context:\nshort summary"));
+                assert!(out.ends_with("What is insulin?"));
+            }
+            other => panic!("unexpected plan: {:?}", other),
+        }
+    }
+}
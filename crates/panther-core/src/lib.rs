@@ -3,17 +3,25 @@ use panther_domain::ports::{KeyValueStore, LlmProvider, LlmProviderAsync, Metric
 use std::sync::Arc;
 use tracing::info;
 
+pub mod budget;
+pub mod context;
+
+use budget::{BudgetCtl, BudgetManager, Reservation, SpendContext};
+use context::{ContextPolicy, FitPlan};
+
 pub struct Engine {
     provider: Arc<dyn LlmProvider>,
     provider_async: Option<Arc<dyn LlmProviderAsync>>,
     telemetry: Option<Arc<dyn TelemetrySink>>, 
     metrics: Option<Arc<dyn MetricsSink>>,
     storage: Option<Arc<dyn KeyValueStore>>,
+    budget: Option<BudgetCtl>,
+    context: Option<ContextPolicy>,
 }
 
 impl Engine {
     pub fn new(provider: Arc<dyn LlmProvider>, telemetry: Option<Arc<dyn TelemetrySink>>) -> Self {
-        Self { provider, provider_async: None, telemetry, metrics: None, storage: None }
+        Self { provider, provider_async: None, telemetry, metrics: None, storage: None, budget: None, context: None }
     }
 
     pub fn with_metrics(mut self, metrics: Arc<dyn MetricsSink>) -> Self {
@@ -26,6 +34,21 @@ impl Engine {
         self
     }
 
+    /// Enforces spending budgets: requests over a hard limit fail with
+    /// `PantherError::BudgetExceeded`; the estimate reserved before each call
+    /// is settled against the actual usage after it.
+    pub fn with_budget(mut self, manager: Arc<BudgetManager>, ctx: SpendContext) -> Self {
+        self.budget = Some(BudgetCtl::new(manager, ctx));
+        self
+    }
+
+    /// Checks prompts against the model's context window before sending
+    /// and rejects or shrinks them according to the policy's strategy.
+    pub fn with_context_policy(mut self, policy: ContextPolicy) -> Self {
+        self.context = Some(policy);
+        self
+    }
+
     pub fn with_async_provider(mut self, provider: Arc<dyn LlmProviderAsync>) -> Self {
         self.provider_async = Some(provider);
         self
@@ -33,6 +56,8 @@ impl Engine {
 
     pub fn generate(&self, prompt: Prompt) -> anyhow::Result<Completion> {
         info!(target: "panther", provider = self.provider.name(), "generating");
+        let prompt = fit_prompt(&self.context, &self.budget, self.provider.as_ref(), prompt)?;
+        let reservation = budget_precheck(&self.budget, self.provider.name(), &prompt, &self.telemetry)?;
         let start_ms = chrono::Utc::now().timestamp_millis();
         if let Some(m) = &self.metrics { m.inc_counter("panther.generate.calls", 1.0); }
         let result = self.provider.generate(&prompt);
@@ -55,6 +80,8 @@ impl Engine {
             let output_tokens = token_count(&c.text) as f64;
             let total_tokens = input_tokens + output_tokens;
 
+            budget_record(reservation, &prompt, c);
+
             if let Some(m) = &self.metrics {
                 m.observe_histogram("panther.latency_ms", latency_ms);
                 m.observe_histogram("panther.tokens.input", input_tokens);
@@ -77,6 +104,8 @@ impl Engine {
 
     pub async fn generate_async(&self, prompt: Prompt) -> anyhow::Result<Completion> {
         if let Some(p) = &self.provider_async {
+            let prompt = fit_prompt_async(&self.context, &self.budget, p.as_ref(), prompt).await?;
+            let reservation = budget_precheck(&self.budget, p.name(), &prompt, &self.telemetry)?;
             let start_ms = chrono::Utc::now().timestamp_millis();
             if let Some(m) = &self.metrics { m.inc_counter("panther.generate.calls", 1.0); }
             let result = p.generate(&prompt).await;
@@ -99,6 +128,8 @@ impl Engine {
                 let output_tokens = token_count(&c.text) as f64;
                 let total_tokens = input_tokens + output_tokens;
 
+                budget_record(reservation, &prompt, c);
+
                 if let Some(m) = &self.metrics {
                     m.observe_histogram("panther.latency_ms", latency_ms);
                     m.observe_histogram("panther.tokens.input", input_tokens);
@@ -121,8 +152,10 @@ impl Engine {
             let telemetry = self.telemetry.clone();
             let metrics = self.metrics.clone();
             let storage = self.storage.clone();
+            let budget = self.budget.clone();
+            let context = self.context.clone();
             tokio::task::spawn_blocking(move || {
-                generate_with_parts(provider, telemetry, metrics, storage, prompt)
+                generate_with_parts(provider, telemetry, metrics, storage, budget, context, prompt)
             })
             .await
             .unwrap_or_else(|e| Err(anyhow::anyhow!("join error: {}", e)))
@@ -176,15 +209,87 @@ fn save_metric(store: &dyn KeyValueStore, name: &str, value: f64, timestamp_ms:
     Ok(())
 }
 
+/// Applies the context policy, summarizing with the same provider when asked to. The
+/// summarization call is charged to `budget` like any other call.
+pub fn fit_prompt(
+    policy: &Option<ContextPolicy>,
+    budget: &Option<BudgetCtl>,
+    provider: &dyn LlmProvider,
+    prompt: Prompt,
+) -> anyhow::Result<Prompt> {
+    let Some(p) = policy else { return Ok(prompt) };
+    match p.plan(provider.name(), provider.model(), &prompt.text)? {
+        FitPlan::Keep => Ok(prompt),
+        FitPlan::Replace(text) => Ok(Prompt { text }),
+        FitPlan::Summarize(req) => {
+            let request = Prompt { text: req.prompt() };
+            let reservation = BudgetCtl::admit_opt(budget, provider.name(), &request.text, Some(req.target_tokens() as u32))?;
+            let summary = provider.generate(&request)?;
+            budget_record(reservation, &request, &summary);
+            Ok(Prompt { text: req.combine(&summary.text) })
+        }
+    }
+}
+
+pub async fn fit_prompt_async(
+    policy: &Option<ContextPolicy>,
+    budget: &Option<BudgetCtl>,
+    provider: &dyn LlmProviderAsync,
+    prompt: Prompt,
+) -> anyhow::Result<Prompt> {
+    let Some(p) = policy else { return Ok(prompt) };
+    match p.plan(provider.name(), provider.model(), &prompt.text)? {
+        FitPlan::Keep => Ok(prompt),
+        FitPlan::Replace(text) => Ok(Prompt { text }),
+        FitPlan::Summarize(req) => {
+            let request = Prompt { text: req.prompt() };
+            let reservation = BudgetCtl::admit_opt(budget, provider.name(), &request.text, Some(req.target_tokens() as u32))?;
+            let summary = provider.generate(&request).await?;
+            budget_record(reservation, &request, &summary);
+            Ok(Prompt { text: req.combine(&summary.text) })
+        }
+    }
+}
+
+fn budget_precheck(
+    budget: &Option<BudgetCtl>,
+    provider: &str,
+    prompt: &Prompt,
+    telemetry: &Option<Arc<dyn TelemetrySink>>,
+) -> anyhow::Result<Option<Reservation>> {
+    let Some(r) = BudgetCtl::admit_opt(budget, provider, &prompt.text, None)? else { return Ok(None) };
+    if let Some(sink) = telemetry {
+        for w in &r.warnings {
+            sink.record(panther_domain::entities::TraceEvent {
+                name: "budget_warning".into(),
+                message: format!("soft limit reached for {}", w.subject),
+                timestamp_ms: chrono::Utc::now().timestamp_millis(),
+                attributes: serde_json::to_value(w).unwrap_or_default(),
+            });
+        }
+    }
+    Ok(Some(r))
+}
+
+fn budget_record(reservation: Option<Reservation>, prompt: &Prompt, c: &Completion) {
+    if let Some(r) = reservation {
+        let _ = r.settle(token_count(&prompt.text) as u64, token_count(&c.text) as u64);
+    }
+}
+
 // Helper used by the async fallback to avoid borrowing `&self` into a 'static closure
 fn generate_with_parts(
     provider: Arc<dyn LlmProvider>,
     telemetry: Option<Arc<dyn TelemetrySink>>,
     metrics: Option<Arc<dyn MetricsSink>>,
     storage: Option<Arc<dyn KeyValueStore>>,
+    budget: Option<BudgetCtl>,
+    context: Option<ContextPolicy>,
     prompt: Prompt,
 ) -> anyhow::Result<Completion> {
     info!(target: "panther", provider = provider.name(), "generating");
+    let prompt = fit_prompt(&context, &budget, provider.as_ref(), prompt)?;
+    let reservation = budget_precheck(&budget, provider.name(), &prompt, &telemetry)?;
     let start_ms = chrono::Utc::now().timestamp_millis();
     if let Some(m) = &metrics { m.inc_counter("panther.generate.calls", 1.0); }
     let result = provider.generate(&prompt);
@@ -207,6 +312,8 @@ fn generate_with_parts(
         let output_tokens = token_count(&c.text) as f64;
         let total_tokens = input_tokens + output_tokens;
 
+        budget_record(reservation, &prompt, c);
+
         if let Some(m) = &metrics {
             m.observe_histogram("panther.latency_ms", latency_ms);
             m.observe_histogram("panther.tokens.input", input_tokens);
@@ -265,4 +372,78 @@ mod tests {
         assert_eq!(out.model.as_deref(), Some("mock"));
         assert_eq!(sink_inner.count(), 1, "should record one telemetry event");
     }
+
+    #[test]
+    fn engine_truncates_prompt_to_context_window() {
+        use context::ContextStrategy;
+
+        struct SizedProvider;
+        impl LlmProvider for SizedProvider {
+            fn generate(&self, prompt: &Prompt) -> anyhow::Result<Completion> {
+                Ok(Completion { text: prompt.text.split_whitespace().count().to_string(), model: None })
+            }
+            fn name(&self) -> &'static str { "mock" }
+            fn model(&self) -> Option<&str> { Some("small") }
+        }
+
+        let policy = context::test_policy("mock", "small", 100, 20, ContextStrategy::TruncateMiddle);
+        let engine = Engine::new(Arc::new(SizedProvider), None).with_context_policy(policy.clone());
+        let out = engine.generate(Prompt { text: "w ".repeat(500) }).unwrap();
+        assert!(out.text.parse::<usize>().unwrap() <= 80);
+
+        let strict = ContextPolicy::new(Arc::new(policy.registry().clone()), ContextStrategy::Reject);
+        let engine = Engine::new(Arc::new(SizedProvider), None).with_context_policy(strict);
+        let err = engine.generate(Prompt { text: "w ".repeat(500) }).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<panther_domain::errors::PantherError>(),
+            Some(panther_domain::errors::PantherError::ContextLengthExceeded(_))
+        ));
+    }
+
+    #[test]
+    fn engine_rejects_over_budget() {
+        let store: Arc<dyn KeyValueStore> = Arc::new(panther_storage::InMemoryStore::default());
+        let manager = BudgetManager::from_json_str(
+            store,
+            r#"{"budgets":[{"scope":"provider","key":"mock","max_tokens":5}],"output_tokens":2}"#,
+        )
+        .unwrap();
+        let engine = Engine::new(Arc::new(MockProvider), None).with_budget(Arc::new(manager), SpendContext::default());
+        // 1 input + 2 reserved output tokens fit; 1 + 2 are recorded
+        engine.generate(Prompt { text: "hello".into() }).unwrap();
+        let err = engine.generate(Prompt { text: "one two three".into() }).unwrap_err();
+        let pe = err.downcast_ref::<panther_domain::errors::PantherError>();
+        assert!(matches!(pe, Some(panther_domain::errors::PantherError::BudgetExceeded(_))));
+    }
+
+    #[test]
+    fn summarization_is_charged_to_the_budget() {
+        use context::ContextStrategy;
+
+        #[derive(Default)]
+        struct Recorder { calls: Mutex<Vec<(String, String)>> }
+        impl LlmProvider for Recorder {
+            fn generate(&self, prompt: &Prompt) -> anyhow::Result<Completion> {
+                let text = "short answer".to_string();
+                self.calls.lock().unwrap().push((prompt.text.clone(), text.clone()));
+                Ok(Completion { text, model: None })
+            }
+            fn name(&self) -> &'static str { "mock" }
+            fn model(&self) -> Option<&str> { Some("small") }
+        }
+
+        let store: Arc<dyn KeyValueStore> = Arc::new(panther_storage::InMemoryStore::default());
+        let manager = Arc::new(BudgetManager::from_json_str(store, r#"{"budgets":[{"scope":"provider","key":"mock","max_tokens":10000}]}"#).unwrap());
+        let provider = Arc::new(Recorder::default());
+        let engine = Engine::new(provider.clone(), None)
+            .with_context_policy(context::test_policy("mock", "small", 100, 20, ContextStrategy::Summarize))
+            .with_budget(manager.clone(), SpendContext::default());
+        engine.generate(Prompt { text: format!("{}\n\nlatest question", "w ".repeat(300)) }).unwrap();
+
+        let calls = provider.calls.lock().unwrap();
+        assert_eq!(calls.len(), 2, "summary call plus the main call");
+        let used: usize = calls.iter().map(|(p, c)| token_count(p) + token_count(c)).sum();
+        let left = manager.remaining("mock", &SpendContext::default()).unwrap()[0].remaining_tokens;
+        assert_eq!(left, Some(10_000 - used as u64));
+    }
 }
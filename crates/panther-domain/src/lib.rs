@@ -1,5 +1,6 @@
 pub mod entities {
     use serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
     pub struct ModelSpec {
@@ -13,6 +14,13 @@ pub mod entities {
         pub text: String,
     }
 
+    /// Per-request sampling overrides; `None` keeps the provider default.
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+    pub struct SamplingParams {
+        pub temperature: Option<f32>,
+        pub seed: Option<u64>,
+    }
+
     #[derive(Debug, Clone, Serialize, Deserialize)]
     pub struct Completion {
         pub text: String,
@@ -26,15 +34,155 @@ pub mod entities {
         pub timestamp_ms: i64,
         pub attributes: serde_json::Value,
     }
+
+    /// A persisted validation run. Results and proof are kept as JSON so storage
+    /// adapters do not depend on the validator's types.
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    pub struct ValidationRun {
+        /// Sorts by creation time.
+        pub id: String,
+        pub timestamp_ms: i64,
+        pub prompt: String,
+        /// Provider configs with secrets removed.
+        pub providers: serde_json::Value,
+        pub guidelines_hash: Option<String>,
+        /// Provider name → adherence score, for listing and diffs without decoding `results`.
+        pub scores: BTreeMap<String, f64>,
+        pub results: serde_json::Value,
+        #[serde(default)]
+        pub proof: Option<serde_json::Value>,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    pub struct RunSummary {
+        pub id: String,
+        pub timestamp_ms: i64,
+        pub prompt: String,
+        pub guidelines_hash: Option<String>,
+        pub scores: BTreeMap<String, f64>,
+    }
+
+    /// Filters for [`crate::ports::RunRepository::list`]; empty fields match everything.
+    #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+    pub struct RunQuery {
+        #[serde(default)]
+        pub provider: Option<String>,
+        /// Full hash or a prefix of it.
+        #[serde(default)]
+        pub guidelines_hash: Option<String>,
+        #[serde(default)]
+        pub since_ms: Option<i64>,
+        #[serde(default)]
+        pub limit: Option<usize>,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    pub struct ScoreDelta {
+        pub provider: String,
+        pub before: Option<f64>,
+        pub after: Option<f64>,
+        /// `after - before` when the provider is in both runs.
+        pub delta: Option<f64>,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    pub struct RunDiff {
+        pub from: String,
+        pub to: String,
+        pub same_prompt: bool,
+        pub same_guidelines: bool,
+        pub scores: Vec<ScoreDelta>,
+    }
+
+    /// Where and when a proof hash was anchored.
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    pub struct AnchorReceipt {
+        /// Backend name, e.g. `eth`, `file`, `devchain`.
+        pub backend: String,
+        /// The anchored hash (hex, no `0x`).
+        pub hash: String,
+        /// Transaction hash, or the entry hash for a ledger.
+        pub tx_id: String,
+        /// Contract address, chain RPC or ledger path.
+        #[serde(default)]
+        pub location: Option<String>,
+        /// Block number or ledger index.
+        #[serde(default)]
+        pub block: Option<u64>,
+        /// Value actually stored when the backend cannot hold the full hash (bytes32 contracts).
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub digest: Option<String>,
+        pub timestamp_ms: i64,
+    }
+
+    #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+    pub struct AnchorStatus {
+        pub anchored: bool,
+        /// Blocks (or ledger entries) since the anchor, itself included.
+        #[serde(default)]
+        pub confirmations: Option<u64>,
+    }
+
+    impl ValidationRun {
+        pub fn summary(&self) -> RunSummary {
+            RunSummary {
+                id: self.id.clone(),
+                timestamp_ms: self.timestamp_ms,
+                prompt: self.prompt.clone(),
+                guidelines_hash: self.guidelines_hash.clone(),
+                scores: self.scores.clone(),
+            }
+        }
+
+        /// Score changes from `self` to `other`, per provider.
+        pub fn diff(&self, other: &ValidationRun) -> RunDiff {
+            let mut providers: Vec<&String> = self.scores.keys().chain(other.scores.keys()).collect();
+            providers.sort();
+            providers.dedup();
+            let scores = providers
+                .into_iter()
+                .map(|p| {
+                    let (before, after) = (self.scores.get(p).copied(), other.scores.get(p).copied());
+                    let delta = before.zip(after).map(|(b, a)| a - b);
+                    ScoreDelta { provider: p.clone(), before, after, delta }
+                })
+                .collect();
+            RunDiff {
+                from: self.id.clone(),
+                to: other.id.clone(),
+                same_prompt: self.prompt == other.prompt,
+                same_guidelines: self.guidelines_hash == other.guidelines_hash,
+                scores,
+            }
+        }
+    }
+
+    impl RunQuery {
+        pub fn matches(&self, run: &ValidationRun) -> bool {
+            self.provider.as_ref().is_none_or(|p| run.scores.contains_key(p))
+                && self
+                    .guidelines_hash
+                    .as_ref()
+                    .is_none_or(|h| run.guidelines_hash.as_deref().is_some_and(|g| g.starts_with(h.as_str())))
+                && self.since_ms.is_none_or(|t| run.timestamp_ms >= t)
+        }
+    }
 }
 
 pub mod ports {
-    use crate::entities::{Completion, Prompt, TraceEvent};
+    use crate::entities::{AnchorReceipt, AnchorStatus, Completion, Prompt, RunDiff, RunQuery, SamplingParams, TraceEvent, ValidationRun};
     use async_trait::async_trait;
 
     pub trait LlmProvider: Send + Sync {
         fn generate(&self, prompt: &Prompt) -> anyhow::Result<Completion>;
+        /// Generates with sampling overrides; providers without support ignore them.
+        fn generate_sampled(&self, prompt: &Prompt, params: &SamplingParams) -> anyhow::Result<Completion> {
+            let _ = params;
+            self.generate(prompt)
+        }
         fn name(&self) -> &'static str { "unknown" }
+        /// Model identifier, used to look up context-window capabilities.
+        fn model(&self) -> Option<&str> { None }
     }
 
     pub trait TelemetrySink: Send + Sync {
@@ -44,7 +192,12 @@ pub mod ports {
     #[async_trait]
     pub trait LlmProviderAsync: Send + Sync {
         async fn generate(&self, prompt: &Prompt) -> anyhow::Result<Completion>;
+        async fn generate_sampled(&self, prompt: &Prompt, params: &SamplingParams) -> anyhow::Result<Completion> {
+            let _ = params;
+            self.generate(prompt).await
+        }
         fn name(&self) -> &'static str { "unknown" }
+        fn model(&self) -> Option<&str> { None }
     }
 
     pub trait MetricsSink: Send + Sync {
@@ -56,6 +209,35 @@ pub mod ports {
         fn get(&self, key: &str) -> anyhow::Result<Option<String>>;
         fn set(&self, key: &str, value: String) -> anyhow::Result<()>;
         fn delete(&self, key: &str) -> anyhow::Result<()>;
+        /// Writes several keys at once; stores that sync to disk sync once for the whole batch.
+        fn set_many(&self, entries: Vec<(String, String)>) -> anyhow::Result<()> {
+            for (key, value) in entries {
+                self.set(&key, value)?;
+            }
+            Ok(())
+        }
+    }
+
+    /// Validation run history.
+    pub trait RunRepository: Send + Sync {
+        fn save(&self, run: &ValidationRun) -> anyhow::Result<()>;
+        fn get(&self, id: &str) -> anyhow::Result<Option<ValidationRun>>;
+        /// Matching runs, newest first, up to `query.limit`.
+        fn list(&self, query: &RunQuery) -> anyhow::Result<Vec<ValidationRun>>;
+        fn diff(&self, from: &str, to: &str) -> anyhow::Result<RunDiff> {
+            let load = |id: &str| self.get(id)?.ok_or_else(|| anyhow::anyhow!("run not found: {}", id));
+            Ok(load(from)?.diff(&load(to)?))
+        }
+    }
+
+    /// Somewhere proof hashes can be recorded and later looked up (a chain, a ledger file, ...).
+    #[async_trait]
+    pub trait AnchorBackend: Send + Sync {
+        fn name(&self) -> &'static str;
+        /// Records `hash_hex`; anchoring an already anchored hash returns its existing receipt where the backend can tell.
+        async fn anchor(&self, hash_hex: &str) -> anyhow::Result<AnchorReceipt>;
+        async fn status(&self, hash_hex: &str) -> anyhow::Result<AnchorStatus>;
+        async fn receipt(&self, hash_hex: &str) -> anyhow::Result<Option<AnchorReceipt>>;
     }
 
     // Content metrics port for hexagonal architecture
@@ -93,5 +275,11 @@ pub mod errors {
         Provider(String),
         #[error("invalid input: {0}")]
         InvalidInput(String),
+        #[error("budget_exceeded: {0}")]
+        BudgetExceeded(String),
+        #[error("context_length_exceeded: {0}")]
+        ContextLengthExceeded(String),
+        #[error("cancelled: {0}")]
+        Cancelled(String),
     }
 }
@@ -1,3 +1,6 @@
+// C ABI entry points take raw C strings from the host; callers own pointer validity.
+#![allow(clippy::not_unsafe_ptr_arg_deref)]
+
 use ffi_support::rust_string_to_c;
 use once_cell::sync::OnceCell;
 use panther_core::Engine;
@@ -5,7 +8,7 @@ use panther_domain::entities::Prompt;
 use panther_observability::{init_logging, LogSink};
 use panther_providers::NullProvider;
 use std::sync::Arc;
-use panther_domain::ports::KeyValueStore;
+use panther_domain::ports::{KeyValueStore, RunRepository};
 use panthersdk as _; // ensure linkage to panthersdk for metrics/bias helpers
 use std::ffi::CStr;
 use std::os::raw::c_char;
@@ -13,6 +16,9 @@ use std::os::raw::c_char;
 static ENGINE: OnceCell<Engine> = OnceCell::new();
 static LOGS: OnceCell<std::sync::Mutex<Vec<String>>> = OnceCell::new();
 static STORAGE: OnceCell<Arc<dyn KeyValueStore>> = OnceCell::new();
+static RUNS: OnceCell<Arc<dyn RunRepository>> = OnceCell::new();
+#[cfg(feature = "validation")]
+static SIGNER: OnceCell<Option<panther_validation::proof::sign::ProofSigner>> = OnceCell::new();
 #[cfg(feature = "metrics-prometheus")]
 static PROM: OnceCell<Arc<panther_metrics::PrometheusMetrics>> = OnceCell::new();
 
@@ -44,6 +50,7 @@ pub extern "C" fn panther_init() -> i32 {
     let engine = {
         let store = Arc::new(panther_storage::InMemoryStore::default());
         let _ = STORAGE.set(store.clone());
+        let _ = RUNS.set(Arc::new(panther_storage::InMemoryRunRepository::default()));
         engine.with_storage(store)
     };
     #[cfg(not(feature = "storage-inmemory"))]
@@ -54,6 +61,9 @@ pub extern "C" fn panther_init() -> i32 {
         let path = std::env::var("PANTHER_SLED_PATH").unwrap_or_else(|_| "./panther_db".to_string());
         match panther_storage_sled::SledStore::open(&path) {
             Ok(store) => {
+                if let Ok(runs) = store.runs() {
+                    let _ = RUNS.set(Arc::new(runs));
+                }
                 let store = Arc::new(store);
                 let _ = STORAGE.set(store.clone());
                 engine.with_storage(store)
@@ -63,6 +73,14 @@ pub extern "C" fn panther_init() -> i32 {
     };
     #[cfg(not(feature = "storage-sled"))]
     let engine = engine;
+
+    // Agent plans keep budget counters and run checkpoints in the same store as metrics
+    #[cfg(feature = "agents")]
+    if let Some(store) = STORAGE.get() {
+        let _ = panther_agents::set_budget_store(store.clone());
+        let _ = panther_agents::set_agent_store(store.clone());
+        let _ = panther_agents::set_run_store(store.clone());
+    }
     match ENGINE.set(engine) {
         Ok(_) => 0,
         Err(_) => 1,
@@ -268,6 +286,87 @@ pub extern "C" fn panther_storage_list_metrics() -> *mut std::os::raw::c_char {
     }
 }
 
+// ---------- Run history ----------
+/// Run summaries, newest first. `query_json` is optional ({provider, guidelines_hash, since_ms, limit}).
+#[no_mangle]
+pub extern "C" fn panther_history_list(query_json_c: *const c_char) -> *mut std::os::raw::c_char {
+    let Some(repo) = RUNS.get() else {
+        return rust_string_to_c("{\"error\":\"history not initialized\"}".to_string());
+    };
+    let query: panther_domain::entities::RunQuery = if query_json_c.is_null() {
+        Default::default()
+    } else {
+        let s = unsafe { CStr::from_ptr(query_json_c).to_string_lossy().into_owned() };
+        match serde_json::from_str(&s) {
+            Ok(q) => q,
+            Err(e) => return rust_string_to_c(format!("{{\"error\":\"query json invalid: {}\"}}", e)),
+        }
+    };
+    match repo.list(&query) {
+        Ok(runs) => {
+            let summaries: Vec<_> = runs.iter().map(|r| r.summary()).collect();
+            rust_string_to_c(serde_json::to_string(&summaries).unwrap_or_else(|_| "[]".to_string()))
+        }
+        Err(e) => rust_string_to_c(format!("{{\"error\":\"{}\"}}", e)),
+    }
+}
+
+/// Full run (results and proof) by id.
+#[no_mangle]
+pub extern "C" fn panther_history_get(run_id_c: *const c_char) -> *mut std::os::raw::c_char {
+    let Some(repo) = RUNS.get() else {
+        return rust_string_to_c("{\"error\":\"history not initialized\"}".to_string());
+    };
+    let id = unsafe { CStr::from_ptr(run_id_c).to_string_lossy().into_owned() };
+    match repo.get(&id) {
+        Ok(Some(run)) => rust_string_to_c(serde_json::to_string(&run).unwrap_or_else(|_| "{}".to_string())),
+        Ok(None) => rust_string_to_c(format!("{{\"error\":\"run not found: {}\"}}", id)),
+        Err(e) => rust_string_to_c(format!("{{\"error\":\"{}\"}}", e)),
+    }
+}
+
+/// Per-provider score changes from one run to another.
+#[no_mangle]
+pub extern "C" fn panther_history_diff(from_c: *const c_char, to_c: *const c_char) -> *mut std::os::raw::c_char {
+    let Some(repo) = RUNS.get() else {
+        return rust_string_to_c("{\"error\":\"history not initialized\"}".to_string());
+    };
+    let from = unsafe { CStr::from_ptr(from_c).to_string_lossy().into_owned() };
+    let to = unsafe { CStr::from_ptr(to_c).to_string_lossy().into_owned() };
+    match repo.diff(&from, &to) {
+        Ok(d) => rust_string_to_c(serde_json::to_string(&d).unwrap_or_else(|_| "{}".to_string())),
+        Err(e) => rust_string_to_c(format!("{{\"error\":\"{}\"}}", e)),
+    }
+}
+
+// ---------- Budgets ----------
+/// Remaining budget for `provider` under `budgets_json` ({budgets, pricing}).
+/// `ctx_json` is optional ({"tenant": "...", "run": "..."}). Requires a storage feature.
+#[no_mangle]
+pub extern "C" fn panther_budget_remaining(
+    budgets_json_c: *const c_char,
+    provider_c: *const c_char,
+    ctx_json_c: *const c_char,
+) -> *mut std::os::raw::c_char {
+    let Some(store) = STORAGE.get() else {
+        return rust_string_to_c("{\"error\":\"storage not initialized\"}".to_string());
+    };
+    let budgets_json = unsafe { CStr::from_ptr(budgets_json_c).to_string_lossy().into_owned() };
+    let provider = unsafe { CStr::from_ptr(provider_c).to_string_lossy().into_owned() };
+    let ctx: panther_core::budget::SpendContext = if ctx_json_c.is_null() {
+        Default::default()
+    } else {
+        let s = unsafe { CStr::from_ptr(ctx_json_c).to_string_lossy().into_owned() };
+        serde_json::from_str(&s).unwrap_or_default()
+    };
+    let res = panther_core::budget::BudgetManager::from_json_str(store.clone(), &budgets_json)
+        .and_then(|m| m.remaining(&provider, &ctx));
+    match res {
+        Ok(list) => rust_string_to_c(serde_json::to_string(&list).unwrap_or_else(|_| "[]".to_string())),
+        Err(e) => rust_string_to_c(format!("{{\"error\":\"{}\"}}", e)),
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn panther_logs_get() -> *mut std::os::raw::c_char {
     if let Some(buf) = LOGS.get() {
@@ -424,14 +523,43 @@ pub extern "C" fn panther_agent_poll(run_id_c: *const c_char, cursor_c: *const c
 pub extern "C" fn panther_agent_status(run_id_c: *const c_char) -> *mut std::os::raw::c_char {
     let run_id = unsafe { CStr::from_ptr(run_id_c).to_string_lossy().into_owned() };
     match panther_agents::agent_status(&run_id) {
-        Ok((status, done)) => {
-            let out = serde_json::json!({"status": status, "done": done});
+        Ok(info) => {
+            let mut out = serde_json::to_value(&info).unwrap_or_default();
+            out["done"] = serde_json::Value::Bool(info.status.is_finished());
             rust_string_to_c(out.to_string())
         }
-        Err(e) => rust_string_to_c(format!("{{\"error\":\"{}\"}}", e)),
+        Err(e) => rust_string_to_c(serde_json::json!({"error": e.to_string()}).to_string()),
     }
 }
 
+#[cfg(feature = "agents")]
+#[no_mangle]
+pub extern "C" fn panther_agent_cancel(run_id_c: *const c_char) -> *mut std::os::raw::c_char {
+    let run_id = unsafe { CStr::from_ptr(run_id_c).to_string_lossy().into_owned() };
+    match panther_agents::agent_cancel(&run_id) {
+        Ok(cancelled) => rust_string_to_c(serde_json::json!({"cancelled": cancelled}).to_string()),
+        Err(e) => rust_string_to_c(serde_json::json!({"error": e.to_string()}).to_string()),
+    }
+}
+
+/// Sets the runner limits (`worker_threads`, `max_concurrent_runs`, `run_deadline_ms`,
+/// `finished_ttl_ms`); only effective before the first run.
+#[cfg(feature = "agents")]
+#[no_mangle]
+pub extern "C" fn panther_agent_configure(config_json_c: *const c_char) -> *mut std::os::raw::c_char {
+    let config_json = unsafe { CStr::from_ptr(config_json_c).to_string_lossy().into_owned() };
+    match serde_json::from_str::<panther_agents::RunnerConfig>(&config_json) {
+        Ok(cfg) => rust_string_to_c(serde_json::json!({"ok": panther_agents::configure_runner(cfg)}).to_string()),
+        Err(e) => rust_string_to_c(serde_json::json!({"error": e.to_string()}).to_string()),
+    }
+}
+
+#[cfg(feature = "agents")]
+#[no_mangle]
+pub extern "C" fn panther_agent_gc() -> *mut std::os::raw::c_char {
+    rust_string_to_c(serde_json::json!({"removed": panther_agents::agent_gc()}).to_string())
+}
+
 #[cfg(feature = "agents")]
 #[no_mangle]
 pub extern "C" fn panther_agent_result(run_id_c: *const c_char) -> *mut std::os::raw::c_char {
@@ -443,6 +571,112 @@ pub extern "C" fn panther_agent_result(run_id_c: *const c_char) -> *mut std::os:
     }
 }
 
+/// Decides an approval step of a running plan; `note_c` may be NULL.
+#[cfg(feature = "agents")]
+#[no_mangle]
+pub extern "C" fn panther_agent_approve(run_id_c: *const c_char, step_id_c: *const c_char, approved: i32, note_c: *const c_char) -> *mut std::os::raw::c_char {
+    let run_id = unsafe { CStr::from_ptr(run_id_c).to_string_lossy().into_owned() };
+    let step_id = unsafe { CStr::from_ptr(step_id_c).to_string_lossy().into_owned() };
+    let note = if note_c.is_null() { None } else { Some(unsafe { CStr::from_ptr(note_c).to_string_lossy().into_owned() }) };
+    match panther_agents::agent_approve(&run_id, &step_id, approved != 0, note) {
+        Ok(()) => rust_string_to_c(serde_json::json!({"ok": true}).to_string()),
+        Err(e) => rust_string_to_c(serde_json::json!({"error": e.to_string()}).to_string()),
+    }
+}
+
+/// Like `panther_agent_poll`, but waits up to `wait_ms` for the next event instead of returning
+/// an empty batch; returns at once when the run has finished.
+#[cfg(feature = "agents")]
+#[no_mangle]
+pub extern "C" fn panther_agent_wait(run_id_c: *const c_char, cursor_c: *const c_char, wait_ms: u64) -> *mut std::os::raw::c_char {
+    let run_id = unsafe { CStr::from_ptr(run_id_c).to_string_lossy().into_owned() };
+    let cursor_s = unsafe { CStr::from_ptr(cursor_c).to_string_lossy().into_owned() };
+    let cursor = cursor_s.parse::<usize>().unwrap_or(0);
+    match panther_agents::agent_wait(&run_id, cursor, wait_ms) {
+        Ok((events, done, new_cursor, status)) => {
+            let out = serde_json::json!({"events": events, "done": done, "cursor": new_cursor, "status": status});
+            rust_string_to_c(out.to_string())
+        }
+        Err(e) => rust_string_to_c(serde_json::json!({"error": e.to_string()}).to_string()),
+    }
+}
+
+/// Receives each event as JSON, then NULL once when the subscription ends (the run finished or
+/// it was unsubscribed). The string is only valid during the call; `user_data` is passed back
+/// unchanged.
+#[cfg(feature = "agents")]
+pub type PantherAgentEventCallback = extern "C" fn(event_json: *const c_char, user_data: *mut std::ffi::c_void);
+
+#[cfg(feature = "agents")]
+struct UserData(*mut std::ffi::c_void);
+
+// The caller owns `user_data` and keeps it valid until the NULL callback
+#[cfg(feature = "agents")]
+unsafe impl Send for UserData {}
+
+/// Calls `callback` from a runner thread for every event of `run_id` from `cursor_c` on, so
+/// Swift/Kotlin get events without polling. Returns `{"subscription": id}` for
+/// `panther_agent_unsubscribe`.
+#[cfg(feature = "agents")]
+#[no_mangle]
+pub extern "C" fn panther_agent_subscribe(
+    run_id_c: *const c_char,
+    cursor_c: *const c_char,
+    callback: Option<PantherAgentEventCallback>,
+    user_data: *mut std::ffi::c_void,
+) -> *mut std::os::raw::c_char {
+    let Some(callback) = callback else {
+        return rust_string_to_c(serde_json::json!({"error": "callback is NULL"}).to_string());
+    };
+    let run_id = unsafe { CStr::from_ptr(run_id_c).to_string_lossy().into_owned() };
+    let cursor = if cursor_c.is_null() { 0 } else { unsafe { CStr::from_ptr(cursor_c).to_string_lossy().parse::<usize>().unwrap_or(0) } };
+    let user_data = UserData(user_data);
+    let on_event = move |ev: Option<&panther_agents::AgentEvent>| {
+        let user_data = &user_data;
+        match ev.map(|ev| std::ffi::CString::new(serde_json::to_string(ev).unwrap_or_default()).unwrap_or_default()) {
+            Some(json) => callback(json.as_ptr(), user_data.0),
+            None => callback(std::ptr::null(), user_data.0),
+        }
+    };
+    match panther_agents::agent_on_event(&run_id, cursor, on_event) {
+        Ok(id) => rust_string_to_c(serde_json::json!({"subscription": id}).to_string()),
+        Err(e) => rust_string_to_c(serde_json::json!({"error": e.to_string()}).to_string()),
+    }
+}
+
+/// Stops a subscription; `{"unsubscribed": false}` when it had already ended. The callback still
+/// gets its final NULL call afterwards, from a runner thread.
+#[cfg(feature = "agents")]
+#[no_mangle]
+pub extern "C" fn panther_agent_unsubscribe(subscription: u64) -> *mut std::os::raw::c_char {
+    rust_string_to_c(serde_json::json!({"unsubscribed": panther_agents::agent_unsubscribe(subscription)}).to_string())
+}
+
+/// Resumes a run from its last checkpoint. Credentials are never persisted: `providers_json_c`
+/// supplies the providers' API keys and `anchor_key_c` the key of the plan's `eth` anchors
+/// (either may be NULL).
+#[cfg(feature = "agents")]
+#[no_mangle]
+pub extern "C" fn panther_agent_resume(run_id_c: *const c_char, providers_json_c: *const c_char, anchor_key_c: *const c_char) -> *mut std::os::raw::c_char {
+    let run_id = unsafe { CStr::from_ptr(run_id_c).to_string_lossy().into_owned() };
+    let providers = if providers_json_c.is_null() { None } else { Some(unsafe { CStr::from_ptr(providers_json_c).to_string_lossy().into_owned() }) };
+    let anchor_key = if anchor_key_c.is_null() { None } else { Some(unsafe { CStr::from_ptr(anchor_key_c).to_string_lossy().into_owned() }) };
+    match panther_agents::agent_resume(&run_id, providers.as_deref(), anchor_key.as_deref()) {
+        Ok(()) => rust_string_to_c(serde_json::json!({"ok": true}).to_string()),
+        Err(e) => rust_string_to_c(serde_json::json!({"error": e.to_string()}).to_string()),
+    }
+}
+
+/// Every known run as a JSON array of statuses, including runs persisted by an earlier process.
+#[cfg(feature = "agents")]
+#[no_mangle]
+pub extern "C" fn panther_agent_runs() -> *mut std::os::raw::c_char {
+    match panther_agents::agent_runs() {
+        Ok(runs) => rust_string_to_c(serde_json::to_string(&runs).unwrap_or_else(|_| "[]".to_string())),
+        Err(e) => rust_string_to_c(serde_json::json!({"error": e.to_string()}).to_string()),
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn panther_generate(prompt_c: *const c_char) -> *mut std::os::raw::c_char {
     let prompt = unsafe { CStr::from_ptr(prompt_c).to_string_lossy().into_owned() };
@@ -466,6 +700,232 @@ pub extern "C" fn panther_free_string(s: *mut std::os::raw::c_char) {
 }
 
 // ---------- Validation (optional) ----------
+/// `{results, proof, run_id?}`; the run is recorded in the history when a storage feature is enabled.
+#[cfg(feature = "validation")]
+fn proof_value(prompt: &str, providers_json: &str, guidelines_json: &str, results: &[panther_validation::ValidationResult]) -> anyhow::Result<serde_json::Value> {
+    let results_json = serde_json::to_string(results).unwrap_or_else(|_| "[]".to_string());
+    let ctx = panther_validation::proof::ProofContext { sdk_version: env!("CARGO_PKG_VERSION").to_string(), salt: None };
+    let mut proof = panther_validation::proof::compute_proof(prompt, providers_json, guidelines_json, &results_json, &ctx)
+        .map_err(|e| anyhow::anyhow!("compute proof failed: {}", e))?;
+    sign_if_configured(&mut proof);
+    let mut out = serde_json::json!({ "results": results, "proof": proof });
+    if let Some(repo) = RUNS.get() {
+        let providers: serde_json::Value = serde_json::from_str(providers_json).unwrap_or(serde_json::Value::Null);
+        let saved = panther_validation::history::new_run(prompt, &providers, guidelines_json, results, Some(&proof))
+            .and_then(|run| repo.save(&run).map(|_| run.id));
+        match saved {
+            Ok(id) => out["run_id"] = serde_json::Value::String(id),
+            Err(e) => tracing::warn!("run history not saved: {}", e),
+        }
+    }
+    Ok(out)
+}
+
+/// Signs with the deployment key from `PANTHER_PROOF_KEY*`, loaded once; unsigned when none is set.
+#[cfg(feature = "validation")]
+fn sign_if_configured(proof: &mut panther_validation::proof::Proof) {
+    let signer = SIGNER.get_or_init(|| match panther_validation::proof::sign::ProofSigner::from_env() {
+        Ok(s) => s,
+        Err(e) => {
+            tracing::warn!("proof signing key not loaded: {}", e);
+            None
+        }
+    });
+    if let Some(s) = signer {
+        s.sign(proof);
+    }
+}
+
+#[cfg(feature = "validation")]
+fn proof_output(prompt: &str, providers_json: &str, guidelines_json: &str, results: &[panther_validation::ValidationResult]) -> *mut std::os::raw::c_char {
+    match proof_value(prompt, providers_json, guidelines_json, results) {
+        Ok(out) => rust_string_to_c(serde_json::to_string(&out).unwrap_or_else(|_| "{}".to_string())),
+        Err(e) => rust_string_to_c(format!("{{\"error\":\"{}\"}}", e)),
+    }
+}
+
+#[cfg(feature = "validation")]
+use panther_validation::secrets::ProviderConfig as ProviderCfg;
+
+/// Async providers where the feature is enabled, otherwise blocking ones run on the blocking pool.
+#[cfg(feature = "validation")]
+fn validation_providers(cfgs: &[ProviderCfg]) -> Vec<(String, Arc<dyn panther_domain::ports::LlmProviderAsync>)> {
+    let mut providers: Vec<(String, Arc<dyn panther_domain::ports::LlmProviderAsync>)> = Vec::new();
+    for c in cfgs {
+        match c.ty.as_str() {
+            #[cfg(feature = "validation-openai-async")]
+            "openai" => {
+                if let (Some(key), Some(model)) = (c.api_key(), c.model.clone()) {
+                    let base = c.base_url.clone().unwrap_or_else(|| "https://api.openai.com".to_string());
+                    let p = panther_providers::openai_async::OpenAiProviderAsync { api_key: key, model: model.clone(), base_url: base, timeout_secs: 30, retries: 2 };
+                    providers.push((format!("openai:{}", model), Arc::new(p)));
+                }
+            }
+            #[cfg(all(feature = "validation-openai", not(feature = "validation-openai-async")))]
+            "openai" => {
+                if let (Some(key), Some(model)) = (c.api_key(), c.model.clone()) {
+                    let base = c.base_url.clone().unwrap_or_else(|| "https://api.openai.com".to_string());
+                    let p = panther_providers::openai::OpenAiProvider { api_key: key, model: model.clone(), base_url: base };
+                    providers.push((format!("openai:{}", model), panther_validation::blocking_provider(Arc::new(p))));
+                }
+            }
+            #[cfg(feature = "validation-ollama-async")]
+            "ollama" => {
+                if let (Some(base), Some(model)) = (c.base_url.clone(), c.model.clone()) {
+                    let p = panther_providers::ollama_async::OllamaProviderAsync { base_url: base, model: model.clone(), timeout_secs: 30, retries: 2 };
+                    providers.push((format!("ollama:{}", model), Arc::new(p)));
+                }
+            }
+            #[cfg(all(feature = "validation-ollama", not(feature = "validation-ollama-async")))]
+            "ollama" => {
+                if let (Some(base), Some(model)) = (c.base_url.clone(), c.model.clone()) {
+                    let p = panther_providers::ollama::OllamaProvider { base_url: base, model: model.clone() };
+                    providers.push((format!("ollama:{}", model), panther_validation::blocking_provider(Arc::new(p))));
+                }
+            }
+            #[cfg(feature = "validation-anthropic-async")]
+            "anthropic" => {
+                if let (Some(key), Some(model)) = (c.api_key(), c.model.clone()) {
+                    let base = c.base_url.clone().unwrap_or_else(|| "https://api.anthropic.com".to_string());
+                    let p = panther_providers::anthropic_async::AnthropicProviderAsync { api_key: key, model: model.clone(), base_url: base, version: "2023-06-01".to_string(), timeout_secs: 30, retries: 2 };
+                    providers.push((format!("anthropic:{}", model), Arc::new(p)));
+                }
+            }
+            #[cfg(all(feature = "validation-anthropic", not(feature = "validation-anthropic-async")))]
+            "anthropic" => {
+                if let (Some(key), Some(model)) = (c.api_key(), c.model.clone()) {
+                    let base = c.base_url.clone().unwrap_or_else(|| "https://api.anthropic.com".to_string());
+                    let p = panther_providers::anthropic::AnthropicProvider { api_key: key, model: model.clone(), base_url: base, version: "2023-06-01".to_string() };
+                    providers.push((format!("anthropic:{}", model), panther_validation::blocking_provider(Arc::new(p))));
+                }
+            }
+            _ => {}
+        }
+    }
+    providers
+}
+
+/// A validation started with `panther_validation_start`; results accumulate as providers finish.
+#[cfg(feature = "validation")]
+#[derive(Default)]
+struct ValidationJob {
+    results: Vec<panther_validation::ValidationResult>,
+    status: &'static str,
+    /// `{results, proof, run_id?}` once every provider has finished.
+    output: Option<serde_json::Value>,
+    error: Option<String>,
+    cancel: panther_validation::CancellationToken,
+}
+
+#[cfg(feature = "validation")]
+type Jobs = std::sync::Mutex<std::collections::HashMap<String, Arc<std::sync::Mutex<ValidationJob>>>>;
+
+#[cfg(feature = "validation")]
+static VALIDATIONS: OnceCell<Jobs> = OnceCell::new();
+
+#[cfg(feature = "validation")]
+fn validation_job(run_id: &str) -> Option<Arc<std::sync::Mutex<ValidationJob>>> {
+    VALIDATIONS.get()?.lock().ok()?.get(run_id).cloned()
+}
+
+/// Starts a validation in the background and returns `{"run_id"}`; `guidelines_json` may be NULL for the bundled ANVISA pack.
+/// Per-provider `timeout_ms` may be set in `providers_json`.
+#[cfg(feature = "validation")]
+#[no_mangle]
+pub extern "C" fn panther_validation_start(
+    prompt_c: *const c_char,
+    providers_json_c: *const c_char,
+    guidelines_json_c: *const c_char,
+) -> *mut std::os::raw::c_char {
+    let prompt = unsafe { CStr::from_ptr(prompt_c).to_string_lossy().into_owned() };
+    let providers_json = unsafe { CStr::from_ptr(providers_json_c).to_string_lossy().into_owned() };
+    let guidelines_json = if guidelines_json_c.is_null() {
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/../panther-validation/guidelines/anvisa.json")).to_string()
+    } else {
+        unsafe { CStr::from_ptr(guidelines_json_c).to_string_lossy().into_owned() }
+    };
+    let cfgs: Vec<ProviderCfg> = match serde_json::from_str(&providers_json) {
+        Ok(c) => c,
+        Err(e) => return rust_string_to_c(format!("{{\"error\":\"providers json invalid: {}\"}}", e)),
+    };
+    let providers = validation_providers(&cfgs);
+    if providers.is_empty() { return rust_string_to_c("{\"error\":\"no providers configured\"}".to_string()); }
+    let expected = providers.len();
+    let mut validator = match panther_validation::LLMValidatorAsync::from_json_str(&guidelines_json, providers) {
+        Ok(v) => v,
+        Err(e) => return rust_string_to_c(format!("{{\"error\":\"{}\"}}", e)),
+    };
+    for c in &cfgs {
+        if let (Some(ms), Some(model)) = (c.timeout_ms, &c.model) {
+            validator = validator.with_provider_timeout(&format!("{}:{}", c.ty, model), std::time::Duration::from_millis(ms));
+        }
+    }
+
+    let now_ms = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_millis() as i64;
+    let run_id = panther_validation::history::new_run_id(now_ms);
+    let job = Arc::new(std::sync::Mutex::new(ValidationJob { status: "running", ..Default::default() }));
+    let cancel = job.lock().map(|j| j.cancel.clone()).unwrap_or_default();
+    if let Ok(mut jobs) = VALIDATIONS.get_or_init(Default::default).lock() {
+        jobs.insert(run_id.clone(), job.clone());
+    }
+    std::thread::spawn(move || {
+        let rt = match tokio::runtime::Builder::new_multi_thread().enable_all().build() {
+            Ok(r) => r,
+            Err(e) => {
+                if let Ok(mut j) = job.lock() { (j.status, j.error) = ("failed", Some(format!("runtime init failed: {}", e))); }
+                return;
+            }
+        };
+        rt.block_on(async {
+            use futures::StreamExt;
+            let mut stream = std::pin::pin!(validator.validate_stream(&prompt, cancel.clone()));
+            while let Some(r) = stream.next().await {
+                if let Ok(mut j) = job.lock() { j.results.push(r); }
+            }
+        });
+        let Ok(mut j) = job.lock() else { return };
+        if cancel.is_cancelled() && j.results.len() < expected {
+            j.status = "cancelled";
+            return;
+        }
+        match proof_value(&prompt, &providers_json, &guidelines_json, &j.results) {
+            Ok(out) => (j.status, j.output) = ("completed", Some(out)),
+            Err(e) => (j.status, j.error) = ("failed", Some(e.to_string())),
+        }
+    });
+    rust_string_to_c(serde_json::json!({"run_id": run_id}).to_string())
+}
+
+/// Results finished since `cursor`: `{results, done, cursor, status, output?, error?}`.
+#[cfg(feature = "validation")]
+#[no_mangle]
+pub extern "C" fn panther_validation_poll(run_id_c: *const c_char, cursor_c: *const c_char) -> *mut std::os::raw::c_char {
+    let run_id = unsafe { CStr::from_ptr(run_id_c).to_string_lossy().into_owned() };
+    let cursor_s = unsafe { CStr::from_ptr(cursor_c).to_string_lossy().into_owned() };
+    let cursor = cursor_s.parse::<usize>().unwrap_or(0);
+    let Some(job) = validation_job(&run_id) else { return rust_string_to_c(format!("{{\"error\":\"unknown run: {}\"}}", run_id)) };
+    let Ok(j) = job.lock() else { return rust_string_to_c("{\"error\":\"run state poisoned\"}".to_string()) };
+    let new = j.results.get(cursor..).unwrap_or_default();
+    let mut out = serde_json::json!({"results": new, "done": j.status != "running", "cursor": j.results.len(), "status": j.status});
+    if let Some(o) = &j.output { out["output"] = o.clone(); }
+    if let Some(e) = &j.error { out["error"] = serde_json::Value::String(e.clone()); }
+    rust_string_to_c(out.to_string())
+}
+
+/// Stops a running validation; providers still in flight are dropped. Returns 0, or -1 for an unknown run.
+#[cfg(feature = "validation")]
+#[no_mangle]
+pub extern "C" fn panther_validation_cancel(run_id_c: *const c_char) -> i32 {
+    let run_id = unsafe { CStr::from_ptr(run_id_c).to_string_lossy().into_owned() };
+    match validation_job(&run_id).and_then(|job| job.lock().ok().map(|j| j.cancel.clone())) {
+        Some(cancel) => {
+            cancel.cancel();
+            0
+        }
+        None => -1,
+    }
+}
+
 #[cfg(feature = "validation")]
 #[no_mangle]
 pub extern "C" fn panther_validation_run_custom_with_proof(
@@ -477,8 +937,6 @@ pub extern "C" fn panther_validation_run_custom_with_proof(
     let providers_json = unsafe { CStr::from_ptr(providers_json_c).to_string_lossy().into_owned() };
     let guidelines_json = unsafe { CStr::from_ptr(guidelines_json_c).to_string_lossy().into_owned() };
 
-    #[derive(serde::Deserialize)]
-    struct ProviderCfg { #[serde(rename = "type")] ty: String, base_url: Option<String>, model: Option<String>, api_key: Option<String> }
     let cfgs: Result<Vec<ProviderCfg>, _> = serde_json::from_str(&providers_json);
     if let Err(e) = cfgs { return rust_string_to_c(format!("{{\"error\":\"providers json invalid: {}\"}}", e)); }
     let cfgs = cfgs.unwrap();
@@ -491,7 +949,7 @@ pub extern "C" fn panther_validation_run_custom_with_proof(
             match c.ty.as_str() {
                 #[cfg(feature = "validation-openai-async")]
                 "openai" => {
-                    if let (Some(key), Some(model)) = (c.api_key.clone(), c.model.clone()) {
+                    if let (Some(key), Some(model)) = (c.api_key(), c.model.clone()) {
                         let base = c.base_url.clone().unwrap_or_else(|| "https://api.openai.com".to_string());
                         let p = panther_providers::openai_async::OpenAiProviderAsync { api_key: key, model: model.clone(), base_url: base, timeout_secs: 30, retries: 2 };
                         providers_async.push((format!("openai:{}", model), Arc::new(p)));
@@ -506,7 +964,7 @@ pub extern "C" fn panther_validation_run_custom_with_proof(
                 }
                 #[cfg(feature = "validation-anthropic-async")]
                 "anthropic" => {
-                    if let (Some(key), Some(model)) = (c.api_key.clone(), c.model.clone()) {
+                    if let (Some(key), Some(model)) = (c.api_key(), c.model.clone()) {
                         let base = c.base_url.clone().unwrap_or_else(|| "https://api.anthropic.com".to_string());
                         let p = panther_providers::anthropic_async::AnthropicProviderAsync {
                             api_key: key,
@@ -537,14 +995,7 @@ pub extern "C" fn panther_validation_run_custom_with_proof(
             });
             return match res {
                 Ok(results) => {
-                    let results_json = serde_json::to_string(&results).unwrap_or_else(|_| "[]".to_string());
-                    let ctx = panther_validation::proof::ProofContext { sdk_version: env!("CARGO_PKG_VERSION").to_string(), salt: None };
-                    let proof = match panther_validation::proof::compute_proof(&prompt, &providers_json_clone, &guidelines_json, &results_json, &ctx) {
-                        Ok(p) => p,
-                        Err(e) => return rust_string_to_c(format!("{{\"error\":\"compute proof failed: {}\"}}", e)),
-                    };
-                    let out = serde_json::json!({ "results": results, "proof": proof });
-                    rust_string_to_c(serde_json::to_string(&out).unwrap_or_else(|_| "{}".to_string()))
+                    proof_output(&prompt, &providers_json_clone, &guidelines_json, &results)
                 }
                 Err(e) => rust_string_to_c(format!("{{\"error\":\"{}\"}}", e)),
             };
@@ -557,7 +1008,7 @@ pub extern "C" fn panther_validation_run_custom_with_proof(
         match c.ty.as_str() {
             #[cfg(feature = "validation-openai")]
             "openai" => {
-                if let (Some(key), Some(model)) = (c.api_key.clone(), c.model.clone()) {
+                if let (Some(key), Some(model)) = (c.api_key(), c.model.clone()) {
                     let base = c.base_url.unwrap_or_else(|| "https://api.openai.com".to_string());
                     let p = panther_providers::openai::OpenAiProvider { api_key: key, model: model.clone(), base_url: base };
                     providers.push((format!("openai:{}", model), Arc::new(p)));
@@ -572,7 +1023,7 @@ pub extern "C" fn panther_validation_run_custom_with_proof(
             }
             #[cfg(feature = "validation-anthropic")]
             "anthropic" => {
-                if let (Some(key), Some(model)) = (c.api_key.clone(), c.model.clone()) {
+                if let (Some(key), Some(model)) = (c.api_key(), c.model.clone()) {
                     let base = c.base_url.unwrap_or_else(|| "https://api.anthropic.com".to_string());
                     let p = panther_providers::anthropic::AnthropicProvider { api_key: key, model: model.clone(), base_url: base, version: "2023-06-01".to_string() };
                     providers.push((format!("anthropic:{}", model), Arc::new(p)));
@@ -595,14 +1046,7 @@ pub extern "C" fn panther_validation_run_custom_with_proof(
     });
     match res {
         Ok(results) => {
-            let results_json = serde_json::to_string(&results).unwrap_or_else(|_| "[]".to_string());
-            let ctx = panther_validation::proof::ProofContext { sdk_version: env!("CARGO_PKG_VERSION").to_string(), salt: None };
-            let proof = match panther_validation::proof::compute_proof(&prompt, &providers_json, &guidelines_json, &results_json, &ctx) {
-                Ok(p) => p,
-                Err(e) => return rust_string_to_c(format!("{{\"error\":\"compute proof failed: {}\"}}", e)),
-            };
-            let out = serde_json::json!({ "results": results, "proof": proof });
-            rust_string_to_c(serde_json::to_string(&out).unwrap_or_else(|_| "{}".to_string()))
+            proof_output(&prompt, &providers_json, &guidelines_json, &results)
         }
         Err(e) => rust_string_to_c(format!("{{\"error\":\"{}\"}}", e)),
     }
@@ -652,8 +1096,6 @@ pub extern "C" fn panther_validation_run_multi_with_proof(
     let guidelines_json: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/../panther-validation/guidelines/anvisa.json"));
 
     // Build providers per JSON using same logic as run_multi
-    #[derive(serde::Deserialize)]
-    struct ProviderCfg { #[serde(rename = "type")] ty: String, base_url: Option<String>, model: Option<String>, api_key: Option<String> }
     let cfgs: Result<Vec<ProviderCfg>, _> = serde_json::from_str(&providers_json);
     if let Err(e) = cfgs { return rust_string_to_c(format!("{{\"error\":\"providers json invalid: {}\"}}", e)); }
     let cfgs = cfgs.unwrap();
@@ -666,7 +1108,7 @@ pub extern "C" fn panther_validation_run_multi_with_proof(
             match c.ty.as_str() {
                 #[cfg(feature = "validation-openai-async")]
                 "openai" => {
-                    if let (Some(key), Some(model)) = (c.api_key.clone(), c.model.clone()) {
+                    if let (Some(key), Some(model)) = (c.api_key(), c.model.clone()) {
                         let base = c.base_url.clone().unwrap_or_else(|| "https://api.openai.com".to_string());
                         let p = panther_providers::openai_async::OpenAiProviderAsync { api_key: key, model: model.clone(), base_url: base, timeout_secs: 30, retries: 2 };
                         providers_async.push((format!("openai:{}", model), Arc::new(p)));
@@ -696,14 +1138,7 @@ pub extern "C" fn panther_validation_run_multi_with_proof(
             });
             return match res {
                 Ok(results) => {
-                    let results_json = serde_json::to_string(&results).unwrap_or_else(|_| "[]".to_string());
-                    let ctx = panther_validation::proof::ProofContext { sdk_version: env!("CARGO_PKG_VERSION").to_string(), salt: None };
-                    let proof = match panther_validation::proof::compute_proof(&prompt, &providers_json_clone, guidelines_json, &results_json, &ctx) {
-                        Ok(p) => p,
-                        Err(e) => return rust_string_to_c(format!("{{\"error\":\"compute proof failed: {}\"}}", e)),
-                    };
-                    let out = serde_json::json!({ "results": results, "proof": proof });
-                    rust_string_to_c(serde_json::to_string(&out).unwrap_or_else(|_| "{}".to_string()))
+                    proof_output(&prompt, &providers_json_clone, guidelines_json, &results)
                 }
                 Err(e) => rust_string_to_c(format!("{{\"error\":\"{}\"}}", e)),
             };
@@ -716,7 +1151,7 @@ pub extern "C" fn panther_validation_run_multi_with_proof(
         match c.ty.as_str() {
             #[cfg(feature = "validation-openai")]
             "openai" => {
-                if let (Some(key), Some(model)) = (c.api_key.clone(), c.model.clone()) {
+                if let (Some(key), Some(model)) = (c.api_key(), c.model.clone()) {
                     let base = c.base_url.unwrap_or_else(|| "https://api.openai.com".to_string());
                     let p = panther_providers::openai::OpenAiProvider { api_key: key, model: model.clone(), base_url: base };
                     providers.push((format!("openai:{}", model), Arc::new(p)));
@@ -731,7 +1166,7 @@ pub extern "C" fn panther_validation_run_multi_with_proof(
             }
             #[cfg(feature = "validation-anthropic")]
             "anthropic" => {
-                if let (Some(key), Some(model)) = (c.api_key.clone(), c.model.clone()) {
+                if let (Some(key), Some(model)) = (c.api_key(), c.model.clone()) {
                     let base = c.base_url.unwrap_or_else(|| "https://api.anthropic.com".to_string());
                     let p = panther_providers::anthropic::AnthropicProvider { api_key: key, model: model.clone(), base_url: base, version: "2023-06-01".to_string() };
                     providers.push((format!("anthropic:{}", model), Arc::new(p)));
@@ -754,14 +1189,7 @@ pub extern "C" fn panther_validation_run_multi_with_proof(
     });
     match res {
         Ok(results) => {
-            let results_json = serde_json::to_string(&results).unwrap_or_else(|_| "[]".to_string());
-            let ctx = panther_validation::proof::ProofContext { sdk_version: env!("CARGO_PKG_VERSION").to_string(), salt: None };
-            let proof = match panther_validation::proof::compute_proof(&prompt, &providers_json, guidelines_json, &results_json, &ctx) {
-                Ok(p) => p,
-                Err(e) => return rust_string_to_c(format!("{{\"error\":\"compute proof failed: {}\"}}", e)),
-            };
-            let out = serde_json::json!({ "results": results, "proof": proof });
-            rust_string_to_c(serde_json::to_string(&out).unwrap_or_else(|_| "{}".to_string()))
+            proof_output(&prompt, &providers_json, guidelines_json, &results)
         }
         Err(e) => rust_string_to_c(format!("{{\"error\":\"{}\"}}", e)),
     }
@@ -786,7 +1214,10 @@ pub extern "C" fn panther_proof_compute(
     let ctx = panther_validation::proof::ProofContext { sdk_version: env!("CARGO_PKG_VERSION").to_string(), salt };
     let proof = panther_validation::proof::compute_proof(&prompt, &providers_json, &guidelines_json, &results_json, &ctx);
     match proof {
-        Ok(p) => rust_string_to_c(serde_json::to_string(&p).unwrap_or_else(|_| "{}".to_string())),
+        Ok(mut p) => {
+            sign_if_configured(&mut p);
+            rust_string_to_c(serde_json::to_string(&p).unwrap_or_else(|_| "{}".to_string()))
+        }
         Err(e) => rust_string_to_c(format!("{{\"error\":\"{}\"}}", e)),
     }
 }
@@ -815,6 +1246,223 @@ pub extern "C" fn panther_proof_verify_local(
     0
 }
 
+/// Same as `panther_proof_compute` with the legacy scheme `panther-proof-v2` (sorted-key JSON,
+/// credentials hashed, no timestamp). Deprecated: new integrations should use `panther_proof_compute`.
+#[cfg(feature = "validation")]
+#[no_mangle]
+#[allow(deprecated)]
+pub extern "C" fn panther_proof_compute_v2(
+    prompt_c: *const c_char,
+    providers_json_c: *const c_char,
+    guidelines_json_c: *const c_char,
+    results_json_c: *const c_char,
+    salt_c: *const c_char,
+) -> *mut std::os::raw::c_char {
+    let prompt = unsafe { CStr::from_ptr(prompt_c).to_string_lossy().into_owned() };
+    let providers_json = unsafe { CStr::from_ptr(providers_json_c).to_string_lossy().into_owned() };
+    let guidelines_json = unsafe { CStr::from_ptr(guidelines_json_c).to_string_lossy().into_owned() };
+    let results_json = unsafe { CStr::from_ptr(results_json_c).to_string_lossy().into_owned() };
+    let salt = unsafe { if salt_c.is_null() { None } else { Some(CStr::from_ptr(salt_c).to_string_lossy().into_owned()) } };
+    let ctx = panther_validation::proof::ProofContext { sdk_version: env!("CARGO_PKG_VERSION").to_string(), salt };
+    match panther_validation::proof::compute_proof_v2(&prompt, &providers_json, &guidelines_json, &results_json, &ctx) {
+        Ok(p) => rust_string_to_c(serde_json::to_string(&p).unwrap_or_else(|_| "{}".to_string())),
+        Err(e) => rust_string_to_c(format!("{{\"error\":\"{}\"}}", e)),
+    }
+}
+
+/// Inclusion proof for `results[index]` of a v2 proof.
+#[cfg(feature = "validation")]
+#[no_mangle]
+pub extern "C" fn panther_proof_prove_result(proof_json_c: *const c_char, results_json_c: *const c_char, index: i32) -> *mut std::os::raw::c_char {
+    let proof_json = unsafe { CStr::from_ptr(proof_json_c).to_string_lossy().into_owned() };
+    let results_json = unsafe { CStr::from_ptr(results_json_c).to_string_lossy().into_owned() };
+    let res = serde_json::from_str::<panther_validation::proof::Proof>(&proof_json)
+        .map_err(anyhow::Error::from)
+        .and_then(|p| panther_validation::proof::prove_result(&p, &results_json, index.max(0) as usize));
+    match res {
+        Ok(inc) => rust_string_to_c(serde_json::to_string(&inc).unwrap_or_else(|_| "{}".to_string())),
+        Err(e) => rust_string_to_c(format!("{{\"error\":\"{}\"}}", e)),
+    }
+}
+
+/// 1 when `result_json` (a single result) is included in the v2 proof, else 0.
+#[cfg(feature = "validation")]
+#[no_mangle]
+pub extern "C" fn panther_proof_verify_result(proof_json_c: *const c_char, result_json_c: *const c_char, inclusion_json_c: *const c_char) -> i32 {
+    let proof_json = unsafe { CStr::from_ptr(proof_json_c).to_string_lossy().into_owned() };
+    let result_json = unsafe { CStr::from_ptr(result_json_c).to_string_lossy().into_owned() };
+    let inclusion_json = unsafe { CStr::from_ptr(inclusion_json_c).to_string_lossy().into_owned() };
+    let (Ok(proof), Ok(result), Ok(inclusion)) = (
+        serde_json::from_str::<panther_validation::proof::Proof>(&proof_json),
+        serde_json::from_str::<serde_json::Value>(&result_json),
+        serde_json::from_str::<panther_validation::proof::merkle::InclusionProof>(&inclusion_json),
+    ) else {
+        return 0;
+    };
+    panther_validation::proof::verify_result(&proof, &result, &inclusion) as i32
+}
+
+/// Rolls a JSON array of proofs into `{"batch": {root, ...}, "inclusions": [...]}`; anchor `batch.root` once for all of them.
+#[cfg(feature = "validation")]
+#[no_mangle]
+pub extern "C" fn panther_proof_batch(proofs_json_c: *const c_char) -> *mut std::os::raw::c_char {
+    let proofs_json = unsafe { CStr::from_ptr(proofs_json_c).to_string_lossy().into_owned() };
+    match serde_json::from_str::<Vec<panther_validation::proof::Proof>>(&proofs_json) {
+        Ok(proofs) => {
+            let (batch, inclusions) = panther_validation::proof::batch(&proofs, env!("CARGO_PKG_VERSION"));
+            rust_string_to_c(serde_json::json!({"batch": batch, "inclusions": inclusions}).to_string())
+        }
+        Err(e) => rust_string_to_c(format!("{{\"error\":\"proofs json invalid: {}\"}}", e)),
+    }
+}
+
+/// 1 when the proof is a member of the batch with root `batch_root`, else 0.
+#[cfg(feature = "validation")]
+#[no_mangle]
+pub extern "C" fn panther_proof_verify_batch(proof_json_c: *const c_char, inclusion_json_c: *const c_char, batch_root_c: *const c_char) -> i32 {
+    let proof_json = unsafe { CStr::from_ptr(proof_json_c).to_string_lossy().into_owned() };
+    let inclusion_json = unsafe { CStr::from_ptr(inclusion_json_c).to_string_lossy().into_owned() };
+    let batch_root = unsafe { CStr::from_ptr(batch_root_c).to_string_lossy().into_owned() };
+    let (Ok(proof), Ok(inclusion)) = (
+        serde_json::from_str::<panther_validation::proof::Proof>(&proof_json),
+        serde_json::from_str::<panther_validation::proof::merkle::InclusionProof>(&inclusion_json),
+    ) else {
+        return 0;
+    };
+    panther_validation::proof::verify_in_batch(&proof, &inclusion, &batch_root) as i32
+}
+
+/// Signs a proof with the key file at `key_path` (NULL = the `PANTHER_PROOF_KEY*` environment).
+#[cfg(feature = "validation")]
+#[no_mangle]
+pub extern "C" fn panther_proof_sign(proof_json_c: *const c_char, key_path_c: *const c_char) -> *mut std::os::raw::c_char {
+    use panther_validation::proof::sign::ProofSigner;
+    let proof_json = unsafe { CStr::from_ptr(proof_json_c).to_string_lossy().into_owned() };
+    let key_path = unsafe { if key_path_c.is_null() { None } else { Some(CStr::from_ptr(key_path_c).to_string_lossy().into_owned()) } };
+    let res = (|| {
+        let mut proof: panther_validation::proof::Proof = serde_json::from_str(&proof_json)?;
+        let signer = match key_path {
+            Some(path) => ProofSigner::load(path)?,
+            None => ProofSigner::from_env()?.ok_or_else(|| anyhow::anyhow!("no signing key configured"))?,
+        };
+        signer.sign(&mut proof);
+        Ok::<_, anyhow::Error>(proof)
+    })();
+    match res {
+        Ok(p) => rust_string_to_c(serde_json::to_string(&p).unwrap_or_else(|_| "{}".to_string())),
+        Err(e) => rust_string_to_c(serde_json::json!({ "error": e.to_string() }).to_string()),
+    }
+}
+
+/// `{"valid": bool, "key_id"?, "error"?}`; with `trusted_json` (key id → {alg, public_key}) the key must be pinned there.
+#[cfg(feature = "validation")]
+#[no_mangle]
+pub extern "C" fn panther_proof_verify_signature(proof_json_c: *const c_char, trusted_json_c: *const c_char) -> *mut std::os::raw::c_char {
+    use panther_validation::proof::sign;
+    let proof_json = unsafe { CStr::from_ptr(proof_json_c).to_string_lossy().into_owned() };
+    let trusted_json = unsafe { if trusted_json_c.is_null() { None } else { Some(CStr::from_ptr(trusted_json_c).to_string_lossy().into_owned()) } };
+    let res = (|| {
+        let proof: panther_validation::proof::Proof = serde_json::from_str(&proof_json)?;
+        match trusted_json {
+            Some(t) => sign::TrustedKeys::from_json_str(&t)?.check(&proof),
+            None if sign::verify_signature(&proof) => Ok(proof.signature.map(|s| s.key_id).unwrap_or_default()),
+            None => Err(anyhow::anyhow!(if proof.signature.is_some() { "invalid signature" } else { "proof is not signed" })),
+        }
+    })();
+    let out = match res {
+        Ok(key_id) => serde_json::json!({ "valid": true, "key_id": key_id }),
+        Err(e) => serde_json::json!({ "valid": false, "error": e.to_string() }),
+    };
+    rust_string_to_c(out.to_string())
+}
+
+/// Stamps a proof with an RFC 3161 token from the TSA at `tsa_url`; returns the proof with `timestamp_token` set.
+#[cfg(feature = "validation")]
+#[no_mangle]
+pub extern "C" fn panther_proof_timestamp(proof_json_c: *const c_char, tsa_url_c: *const c_char) -> *mut std::os::raw::c_char {
+    use panther_validation::proof::timestamp;
+    let proof_json = unsafe { CStr::from_ptr(proof_json_c).to_string_lossy().into_owned() };
+    let tsa_url = unsafe { CStr::from_ptr(tsa_url_c).to_string_lossy().into_owned() };
+    let rt = match tokio::runtime::Builder::new_multi_thread().enable_all().build() {
+        Ok(r) => r,
+        Err(_) => return rust_string_to_c("{\"error\":\"runtime init failed\"}".to_string()),
+    };
+    let res = rt.block_on(async move {
+        let mut proof: panther_validation::proof::Proof = serde_json::from_str(&proof_json)?;
+        timestamp::stamp(&mut proof, &timestamp::HttpTsa::new(tsa_url)).await?;
+        Ok::<String, anyhow::Error>(serde_json::to_string(&proof)?)
+    });
+    match res {
+        Ok(s) => rust_string_to_c(s),
+        Err(e) => rust_string_to_c(serde_json::json!({ "error": e.to_string() }).to_string()),
+    }
+}
+
+/// `{"valid": bool, "report"?, "error"?}` for a proof's timestamp token; with `roots_pem` the report's
+/// `chain` is `trusted` or `untrusted` (and `valid` false when untrusted), otherwise `unchecked`.
+#[cfg(feature = "validation")]
+#[no_mangle]
+pub extern "C" fn panther_proof_verify_timestamp(proof_json_c: *const c_char, roots_pem_c: *const c_char) -> *mut std::os::raw::c_char {
+    use panther_validation::proof::timestamp::{self, ChainStatus, TsaRoots};
+    let proof_json = unsafe { CStr::from_ptr(proof_json_c).to_string_lossy().into_owned() };
+    let roots_pem = unsafe { if roots_pem_c.is_null() { None } else { Some(CStr::from_ptr(roots_pem_c).to_string_lossy().into_owned()) } };
+    let res = (|| {
+        let proof: panther_validation::proof::Proof = serde_json::from_str(&proof_json)?;
+        let roots = roots_pem.as_deref().map(TsaRoots::from_pem_str).transpose()?;
+        timestamp::verify_timestamp(&proof, roots.as_ref())
+    })();
+    let out = match res {
+        Ok(r) => serde_json::json!({ "valid": r.chain != ChainStatus::Untrusted, "report": r }),
+        Err(e) => serde_json::json!({ "valid": false, "error": e.to_string() }),
+    };
+    rust_string_to_c(out.to_string())
+}
+
+/// Anchors `proof_hash_hex` with the backend in `config_json` (`{"backend": "file", "ledger_path": ...}`,
+/// `{"backend": "devchain", "rpc_url": ...}` or the Ethereum fields); returns the receipt.
+#[cfg(feature = "validation")]
+#[no_mangle]
+pub extern "C" fn panther_proof_anchor(proof_hash_hex_c: *const c_char, config_json_c: *const c_char) -> *mut std::os::raw::c_char {
+    let proof_hash_hex = unsafe { CStr::from_ptr(proof_hash_hex_c).to_string_lossy().into_owned() };
+    let config_json = unsafe { CStr::from_ptr(config_json_c).to_string_lossy().into_owned() };
+    let rt = match tokio::runtime::Builder::new_multi_thread().enable_all().build() {
+        Ok(r) => r,
+        Err(_) => return rust_string_to_c("{\"error\":\"runtime init failed\"}".to_string()),
+    };
+    let res = rt.block_on(async move {
+        let cfg: panther_validation::anchor::AnchorConfig = serde_json::from_str(&config_json)?;
+        let receipt = cfg.backend()?.anchor(&proof_hash_hex).await?;
+        Ok::<String, anyhow::Error>(serde_json::to_string(&receipt)?)
+    });
+    match res {
+        Ok(s) => rust_string_to_c(s),
+        Err(e) => rust_string_to_c(serde_json::json!({ "error": e.to_string() }).to_string()),
+    }
+}
+
+/// `{"anchored", "confirmations", "receipt"}` for `proof_hash_hex` on the backend in `config_json`.
+#[cfg(feature = "validation")]
+#[no_mangle]
+pub extern "C" fn panther_proof_anchor_status(proof_hash_hex_c: *const c_char, config_json_c: *const c_char) -> *mut std::os::raw::c_char {
+    let proof_hash_hex = unsafe { CStr::from_ptr(proof_hash_hex_c).to_string_lossy().into_owned() };
+    let config_json = unsafe { CStr::from_ptr(config_json_c).to_string_lossy().into_owned() };
+    let rt = match tokio::runtime::Builder::new_multi_thread().enable_all().build() {
+        Ok(r) => r,
+        Err(_) => return rust_string_to_c("{\"error\":\"runtime init failed\"}".to_string()),
+    };
+    let res = rt.block_on(async move {
+        let cfg: panther_validation::anchor::AnchorConfig = serde_json::from_str(&config_json)?;
+        let backend = cfg.backend()?;
+        let status = backend.status(&proof_hash_hex).await?;
+        let receipt = backend.receipt(&proof_hash_hex).await?;
+        Ok::<String, anyhow::Error>(serde_json::json!({ "anchored": status.anchored, "confirmations": status.confirmations, "receipt": receipt }).to_string())
+    });
+    match res {
+        Ok(s) => rust_string_to_c(s),
+        Err(e) => rust_string_to_c(serde_json::json!({ "error": e.to_string() }).to_string()),
+    }
+}
+
 // ---------- Blockchain (optional) ----------
 #[cfg(feature = "blockchain-eth")]
 #[no_mangle]
@@ -937,19 +1585,11 @@ pub extern "C" fn panther_validation_run_multi(
     prompt_c: *const c_char,
     providers_json_c: *const c_char,
 ) -> *mut std::os::raw::c_char {
-    #[derive(serde::Deserialize)]
-    struct Cfg {
-        #[serde(rename = "type")] ty: String,
-        #[serde(default)] api_key: Option<String>,
-        base_url: Option<String>,
-        model: Option<String>,
-    }
-
     let prompt = unsafe { CStr::from_ptr(prompt_c).to_string_lossy().into_owned() };
     let cfg_json = unsafe { CStr::from_ptr(providers_json_c).to_string_lossy().into_owned() };
     let guidelines_json: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/../panther-validation/guidelines/anvisa.json"));
 
-    let cfgs: Result<Vec<Cfg>, _> = serde_json::from_str(&cfg_json);
+    let cfgs: Result<Vec<ProviderCfg>, _> = serde_json::from_str(&cfg_json);
     if let Err(e) = cfgs {
         return rust_string_to_c(format!("{{\"error\":\"providers json invalid: {}\"}}", e));
     }
@@ -960,7 +1600,7 @@ pub extern "C" fn panther_validation_run_multi(
         match c.ty.as_str() {
             #[cfg(feature = "validation-openai")]
             "openai" => {
-                if let (Some(key), Some(model)) = (c.api_key.clone(), c.model.clone()) {
+                if let (Some(key), Some(model)) = (c.api_key(), c.model.clone()) {
                     let base = c.base_url.unwrap_or_else(|| "https://api.openai.com".to_string());
                     let p = panther_providers::openai::OpenAiProvider { api_key: key, model: model.clone(), base_url: base };
                     providers.push((format!("openai:{}", model), Arc::new(p)));
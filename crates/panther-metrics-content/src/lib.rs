@@ -54,12 +54,12 @@ pub fn evaluate_bleu(reference: &str, candidate: &str) -> f64 {
     for w in c.iter() { *cand_counts.entry(*w).or_insert(0u32) += 1; }
     for (w, cc) in cand_counts { let rc = *ref_counts.get(w).unwrap_or(&0); match_count += cc.min(rc); }
     let precision = (match_count as f64) / (c.len() as f64);
-    let bp = if c.len() > 0 && reference.len() > 0 {
+    let bp = if !c.is_empty() && !reference.is_empty() {
         let r_len = tokenize(reference).len() as f64;
         let c_len = c.len() as f64;
         if c_len > r_len { 1.0 } else { (-((r_len / c_len) - 1.0)).exp() }
     } else { 0.0 };
-    (precision.max(0.0).min(1.0) * bp).max(0.0).min(1.0)
+    (precision.clamp(0.0, 1.0) * bp).clamp(0.0, 1.0)
 }
 
 pub fn evaluate_coherence(text: &str) -> f64 {
@@ -148,8 +148,7 @@ pub fn evaluate_factcheck_adv_score(facts: &[String], candidate: &str) -> f64 {
     }
     let rate = (contradictions as f64) / (facts.len() as f64);
     let beta = 0.7; // weight of contradiction penalty
-    let score = (coverage * (1.0 - beta * rate)).clamp(0.0, 1.0);
-    score
+    (coverage * (1.0 - beta * rate)).clamp(0.0, 1.0)
 }
 
 // Plagiarism (MVP): Jaccard similarity of word 3-grams against a corpus; returns best match score.
@@ -235,7 +234,7 @@ mod tests {
     #[test]
     fn accuracy_and_bleu_basics() {
         let acc = evaluate_accuracy("a b c", "a b d");
-        assert!(acc >= 0.0 && acc <= 1.0);
+        assert!((0.0..=1.0).contains(&acc));
         let bleu = evaluate_bleu("a b c", "a b c");
         assert!(bleu > 0.0);
     }
@@ -249,7 +248,7 @@ mod tests {
         let cand = "insulin regulates glucose in blood";
         let s3 = evaluate_plagiarism_ngram(&corpus, cand, 3);
         let s2 = evaluate_plagiarism_ngram(&corpus, cand, 2);
-        assert!(s3 >= 0.0 && s3 <= 1.0);
+        assert!((0.0..=1.0).contains(&s3));
         assert!(s2 >= s3 || (s3 - s2).abs() < 1e-6); // bigram usually >= trigram
     }
 }
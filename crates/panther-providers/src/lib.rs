@@ -1,10 +1,22 @@
 use panther_domain::entities::{Completion, Prompt};
+#[cfg(any(feature = "openai", feature = "ollama", feature = "anthropic", feature = "openai-async", feature = "ollama-async", feature = "anthropic-async"))]
+use panther_domain::entities::SamplingParams;
 use panther_domain::ports::LlmProvider;
 #[cfg(any(feature = "openai-async", feature = "ollama-async"))]
 use panther_domain::ports::LlmProviderAsync;
 
 pub struct NullProvider;
 
+/// Ollama takes sampling overrides under `options`.
+#[cfg(any(feature = "ollama", feature = "ollama-async"))]
+fn ollama_options(params: &SamplingParams) -> Option<serde_json::Value> {
+    if *params == SamplingParams::default() { return None; }
+    let mut o = serde_json::Map::new();
+    if let Some(t) = params.temperature { o.insert("temperature".into(), t.into()); }
+    if let Some(seed) = params.seed { o.insert("seed".into(), seed.into()); }
+    Some(o.into())
+}
+
 impl LlmProvider for NullProvider {
     fn generate(&self, prompt: &Prompt) -> anyhow::Result<Completion> {
         Ok(Completion { text: format!("echo: {}", prompt.text), model: Some(self.name().into()) })
@@ -30,14 +42,19 @@ pub mod openai_async {
     #[async_trait]
     impl LlmProviderAsync for OpenAiProviderAsync {
         async fn generate(&self, prompt: &Prompt) -> anyhow::Result<Completion> {
+            self.generate_sampled(prompt, &SamplingParams::default()).await
+        }
+
+        async fn generate_sampled(&self, prompt: &Prompt, params: &SamplingParams) -> anyhow::Result<Completion> {
             let url = format!("{}/v1/chat/completions", self.base_url.trim_end_matches('/'));
-            let body = serde_json::json!({
+            let mut body = serde_json::json!({
                 "model": self.model,
                 "messages": [
                     {"role": "user", "content": prompt.text}
                 ],
-                "temperature": 0.2
+                "temperature": params.temperature.unwrap_or(0.2)
             });
+            if let Some(seed) = params.seed { body["seed"] = seed.into(); }
             let client = reqwest::Client::builder()
                 .timeout(std::time::Duration::from_secs(self.timeout_secs.max(1)))
                 .build()?;
@@ -84,6 +101,7 @@ pub mod openai_async {
             Err(last_err.unwrap_or_else(|| anyhow::anyhow!("openai unknown error")))
         }
         fn name(&self) -> &'static str { "openai" }
+        fn model(&self) -> Option<&str> { Some(&self.model) }
     }
 }
 
@@ -104,12 +122,17 @@ pub mod ollama_async {
     #[async_trait]
     impl LlmProviderAsync for OllamaProviderAsync {
         async fn generate(&self, prompt: &Prompt) -> anyhow::Result<Completion> {
+            self.generate_sampled(prompt, &SamplingParams::default()).await
+        }
+
+        async fn generate_sampled(&self, prompt: &Prompt, params: &SamplingParams) -> anyhow::Result<Completion> {
             let url = format!("{}/api/generate", self.base_url.trim_end_matches('/'));
-            let body = serde_json::json!({
+            let mut body = serde_json::json!({
                 "model": self.model,
                 "prompt": prompt.text,
                 "stream": false
             });
+            if let Some(o) = ollama_options(params) { body["options"] = o; }
             let client = reqwest::Client::builder()
                 .timeout(std::time::Duration::from_secs(self.timeout_secs.max(1)))
                 .build()?;
@@ -148,6 +171,7 @@ pub mod ollama_async {
             Err(last_err.unwrap_or_else(|| anyhow::anyhow!("ollama unknown error")))
         }
         fn name(&self) -> &'static str { "ollama" }
+        fn model(&self) -> Option<&str> { Some(&self.model) }
     }
 }
 
@@ -164,14 +188,19 @@ pub mod openai {
 
     impl LlmProvider for OpenAiProvider {
         fn generate(&self, prompt: &Prompt) -> anyhow::Result<Completion> {
+            self.generate_sampled(prompt, &SamplingParams::default())
+        }
+
+        fn generate_sampled(&self, prompt: &Prompt, params: &SamplingParams) -> anyhow::Result<Completion> {
             let url = format!("{}/v1/chat/completions", self.base_url.trim_end_matches('/'));
-            let body = serde_json::json!({
+            let mut body = serde_json::json!({
                 "model": self.model,
                 "messages": [
                     {"role": "user", "content": prompt.text}
                 ],
-                "temperature": 0.2
+                "temperature": params.temperature.unwrap_or(0.2)
             });
+            if let Some(seed) = params.seed { body["seed"] = seed.into(); }
             let client = reqwest::blocking::Client::new();
             let res = client
                 .post(url)
@@ -187,6 +216,7 @@ pub mod openai {
             Ok(Completion { text, model: Some(self.model.clone()) })
         }
         fn name(&self) -> &'static str { "openai" }
+        fn model(&self) -> Option<&str> { Some(&self.model) }
     }
 }
 
@@ -202,12 +232,17 @@ pub mod ollama {
 
     impl LlmProvider for OllamaProvider {
         fn generate(&self, prompt: &Prompt) -> anyhow::Result<Completion> {
+            self.generate_sampled(prompt, &SamplingParams::default())
+        }
+
+        fn generate_sampled(&self, prompt: &Prompt, params: &SamplingParams) -> anyhow::Result<Completion> {
             let url = format!("{}/api/generate", self.base_url.trim_end_matches('/'));
-            let body = serde_json::json!({
+            let mut body = serde_json::json!({
                 "model": self.model,
                 "prompt": prompt.text,
                 "stream": false
             });
+            if let Some(o) = ollama_options(params) { body["options"] = o; }
             let client = reqwest::blocking::Client::new();
             let res = client.post(url).json(&body).send()?;
             let status = res.status();
@@ -219,6 +254,7 @@ pub mod ollama {
             Ok(Completion { text, model: Some(self.model.clone()) })
         }
         fn name(&self) -> &'static str { "ollama" }
+        fn model(&self) -> Option<&str> { Some(&self.model) }
     }
 }
 
@@ -236,11 +272,15 @@ pub mod anthropic {
 
     impl LlmProvider for AnthropicProvider {
         fn generate(&self, prompt: &Prompt) -> anyhow::Result<Completion> {
+            self.generate_sampled(prompt, &SamplingParams::default())
+        }
+
+        fn generate_sampled(&self, prompt: &Prompt, params: &SamplingParams) -> anyhow::Result<Completion> {
             let url = format!("{}/v1/messages", self.base_url.trim_end_matches('/'));
             let body = serde_json::json!({
                 "model": self.model,
                 "max_tokens": 512,
-                "temperature": 0.2,
+                "temperature": params.temperature.unwrap_or(0.2),
                 "messages": [
                     {"role": "user", "content": [{"type":"text","text": prompt.text}]}
                 ]
@@ -261,6 +301,7 @@ pub mod anthropic {
             Ok(Completion { text, model: Some(self.model.clone()) })
         }
         fn name(&self) -> &'static str { "anthropic" }
+        fn model(&self) -> Option<&str> { Some(&self.model) }
     }
 }
 
@@ -283,11 +324,15 @@ pub mod anthropic_async {
     #[async_trait]
     impl LlmProviderAsync for AnthropicProviderAsync {
         async fn generate(&self, prompt: &Prompt) -> anyhow::Result<Completion> {
+            self.generate_sampled(prompt, &SamplingParams::default()).await
+        }
+
+        async fn generate_sampled(&self, prompt: &Prompt, params: &SamplingParams) -> anyhow::Result<Completion> {
             let url = format!("{}/v1/messages", self.base_url.trim_end_matches('/'));
             let body = serde_json::json!({
                 "model": self.model,
                 "max_tokens": 512,
-                "temperature": 0.2,
+                "temperature": params.temperature.unwrap_or(0.2),
                 "messages": [
                     {"role": "user", "content": [{"type":"text","text": prompt.text}]}
                 ]
@@ -336,6 +381,7 @@ pub mod anthropic_async {
             Err(last_err.unwrap_or_else(|| anyhow::anyhow!("anthropic unknown error")))
         }
         fn name(&self) -> &'static str { "anthropic" }
+        fn model(&self) -> Option<&str> { Some(&self.model) }
     }
 }
 
@@ -1,4 +1,7 @@
-use panther_domain::ports::KeyValueStore;
+use panther_domain::entities::{RunQuery, ValidationRun};
+use panther_domain::ports::{KeyValueStore, RunRepository};
+
+const RUNS_TREE: &str = "validation_runs";
 
 pub struct SledStore {
     db: sled::Db,
@@ -9,6 +12,50 @@ impl SledStore {
         let db = sled::open(path)?;
         Ok(Self { db })
     }
+
+    /// Run history kept in the same database (sled allows one open handle per path).
+    pub fn runs(&self) -> anyhow::Result<SledRunRepository> {
+        Ok(SledRunRepository { tree: self.db.open_tree(RUNS_TREE)? })
+    }
+}
+
+/// Runs keyed by id; ids sort by time, so iteration order is chronological.
+pub struct SledRunRepository {
+    tree: sled::Tree,
+}
+
+impl SledRunRepository {
+    pub fn open(path: &str) -> anyhow::Result<Self> {
+        SledStore::open(path)?.runs()
+    }
+}
+
+impl RunRepository for SledRunRepository {
+    fn save(&self, run: &ValidationRun) -> anyhow::Result<()> {
+        self.tree.insert(run.id.as_bytes(), serde_json::to_vec(run)?)?;
+        self.tree.flush()?;
+        Ok(())
+    }
+
+    fn get(&self, id: &str) -> anyhow::Result<Option<ValidationRun>> {
+        match self.tree.get(id.as_bytes())? {
+            Some(v) => Ok(Some(serde_json::from_slice(&v)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn list(&self, query: &RunQuery) -> anyhow::Result<Vec<ValidationRun>> {
+        let mut out = Vec::new();
+        for item in self.tree.iter().rev() {
+            let (_, v) = item?;
+            let run: ValidationRun = serde_json::from_slice(&v)?;
+            if query.since_ms.is_some_and(|t| run.timestamp_ms < t) { break; }
+            if !query.matches(&run) { continue; }
+            out.push(run);
+            if query.limit.is_some_and(|l| out.len() >= l) { break; }
+        }
+        Ok(out)
+    }
 }
 
 impl KeyValueStore for SledStore {
@@ -30,6 +77,16 @@ impl KeyValueStore for SledStore {
         self.db.flush()?;
         Ok(())
     }
+
+    fn set_many(&self, entries: Vec<(String, String)>) -> anyhow::Result<()> {
+        let mut batch = sled::Batch::default();
+        for (key, value) in entries {
+            batch.insert(key.as_bytes(), value.as_bytes());
+        }
+        self.db.apply_batch(batch)?;
+        self.db.flush()?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -46,6 +103,37 @@ mod tests {
         assert_eq!(store.get("k").unwrap(), Some("v".into()));
         store.delete("k").unwrap();
         assert_eq!(store.get("k").unwrap(), None);
+        store.set_many(vec![("a".into(), "1".into()), ("b".into(), "2".into()), ("a".into(), "3".into())]).unwrap();
+        assert_eq!((store.get("a").unwrap(), store.get("b").unwrap()), (Some("3".into()), Some("2".into())));
+    }
+
+    #[test]
+    fn runs_list_filter_and_diff() {
+        let dir = tempdir().unwrap();
+        let repo = SledStore::open(dir.path().join("db").to_str().unwrap()).unwrap().runs().unwrap();
+        let run = |id: &str, ts: i64, scores: &[(&str, f64)]| ValidationRun {
+            id: id.into(),
+            timestamp_ms: ts,
+            prompt: "p".into(),
+            providers: serde_json::json!([]),
+            guidelines_hash: Some("abc123".into()),
+            scores: scores.iter().map(|(p, s)| (p.to_string(), *s)).collect(),
+            results: serde_json::json!([]),
+            proof: None,
+        };
+        repo.save(&run("run-0001", 1, &[("openai:a", 70.0)])).unwrap();
+        repo.save(&run("run-0002", 2, &[("openai:a", 80.0), ("ollama:b", 50.0)])).unwrap();
+
+        let all = repo.list(&RunQuery::default()).unwrap();
+        assert_eq!(all.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), vec!["run-0002", "run-0001"]);
+        let q = RunQuery { provider: Some("ollama:b".into()), guidelines_hash: Some("abc".into()), ..Default::default() };
+        assert_eq!(repo.list(&q).unwrap().len(), 1);
+        assert_eq!(repo.list(&RunQuery { since_ms: Some(2), ..Default::default() }).unwrap().len(), 1);
+
+        let d = repo.diff("run-0001", "run-0002").unwrap();
+        assert_eq!(d.scores[1].delta, Some(10.0));
+        assert_eq!((d.scores[0].before, d.scores[0].after), (None, Some(50.0)));
+        assert!(repo.diff("run-0001", "missing").is_err());
     }
 }
 
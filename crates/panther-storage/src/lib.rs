@@ -1,4 +1,5 @@
-use panther_domain::ports::KeyValueStore;
+use panther_domain::entities::{RunQuery, ValidationRun};
+use panther_domain::ports::{KeyValueStore, RunRepository};
 use std::collections::HashMap;
 use std::sync::Mutex;
 
@@ -21,6 +22,29 @@ impl KeyValueStore for InMemoryStore {
     }
 }
 
+#[derive(Default)]
+pub struct InMemoryRunRepository {
+    runs: Mutex<Vec<ValidationRun>>,
+}
+
+impl RunRepository for InMemoryRunRepository {
+    fn save(&self, run: &ValidationRun) -> anyhow::Result<()> {
+        let mut runs = self.runs.lock().unwrap();
+        runs.retain(|r| r.id != run.id);
+        runs.push(run.clone());
+        runs.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(())
+    }
+    fn get(&self, id: &str) -> anyhow::Result<Option<ValidationRun>> {
+        Ok(self.runs.lock().unwrap().iter().find(|r| r.id == id).cloned())
+    }
+    fn list(&self, query: &RunQuery) -> anyhow::Result<Vec<ValidationRun>> {
+        let runs = self.runs.lock().unwrap();
+        let matching = runs.iter().rev().filter(|r| query.matches(r)).cloned();
+        Ok(matching.take(query.limit.unwrap_or(usize::MAX)).collect())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,646 @@
+//! Anchoring backends behind [`AnchorBackend`].
+//!
+//! [`FileLedger`] appends to a local hash-chained JSONL file and needs nothing else, so the
+//! seal/anchor flow runs offline and on premises. [`DevChain`] writes the hash as calldata of
+//! a self-transfer on a local node with unlocked accounts (anvil, hardhat). With
+//! `blockchain-eth`, [`EthContract`] calls the `anchor(bytes32)` contract.
+//! [`AnchorConfig::backend`] picks one. Chain backends wait for the configured confirmation
+//! depth before returning a receipt; [`queue::AnchorQueue`] batches many hashes under one
+//! Merkle root and survives restarts.
+
+use anyhow::Context;
+use async_trait::async_trait;
+use panther_domain::entities::{AnchorReceipt, AnchorStatus};
+pub use panther_domain::ports::AnchorBackend;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha3::{Digest, Sha3_512};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::secrets::Secret;
+
+pub mod queue;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    /// `anchor(bytes32)` contract on an EVM chain (needs `blockchain-eth`).
+    #[default]
+    Eth,
+    File,
+    DevChain,
+}
+
+impl BackendKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Eth => "eth",
+            Self::File => "file",
+            Self::DevChain => "devchain",
+        }
+    }
+}
+
+/// `anchor` section of an agent plan; which fields matter depends on `backend`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AnchorConfig {
+    #[serde(default)]
+    pub backend: BackendKind,
+    #[serde(default)]
+    pub rpc_url: Option<String>,
+    #[serde(default)]
+    pub contract_addr: Option<String>,
+    /// Redacted whenever the config is serialized.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priv_key: Option<Secret>,
+    #[serde(default)]
+    pub ledger_path: Option<String>,
+    /// Dev chain sender; defaults to the node's first unlocked account.
+    #[serde(default)]
+    pub from: Option<String>,
+    /// Blocks to wait for before `anchor` returns (default 1; 0 returns once the transaction is accepted).
+    #[serde(default)]
+    pub confirmations: Option<u64>,
+    /// How long `anchor` waits for those confirmations (default 120 s).
+    #[serde(default)]
+    pub receipt_timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub gas: GasStrategy,
+}
+
+/// Gas settings for chain backends; with nothing set the node picks both limit and price.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GasStrategy {
+    /// Fixed gas limit instead of the node's estimate.
+    #[serde(default)]
+    pub limit: Option<u64>,
+    /// Fixed gas price in wei; takes precedence over `bump_percent`.
+    #[serde(default)]
+    pub price_wei: Option<u64>,
+    /// Percent added to the node's suggested price, to get ahead in a busy mempool.
+    #[serde(default)]
+    pub bump_percent: u64,
+}
+
+impl GasStrategy {
+    pub fn needs_node_price(&self) -> bool { self.price_wei.is_none() && self.bump_percent > 0 }
+
+    /// Price to set on the transaction given the node's suggestion; `None` leaves it to the node.
+    pub fn price(&self, suggested: Option<u64>) -> Option<u64> {
+        match (self.price_wei, suggested) {
+            (Some(p), _) => Some(p),
+            (None, Some(s)) if self.bump_percent > 0 => Some(s.saturating_add(s.saturating_mul(self.bump_percent) / 100)),
+            _ => None,
+        }
+    }
+}
+
+impl AnchorConfig {
+    pub fn file(path: impl Into<String>) -> Self { Self { backend: BackendKind::File, ledger_path: Some(path.into()), ..Self::default() } }
+
+    pub fn dev_chain(rpc_url: impl Into<String>) -> Self { Self { backend: BackendKind::DevChain, rpc_url: Some(rpc_url.into()), ..Self::default() } }
+
+    /// Confirmation depth and timeout from `confirmations` / `receipt_timeout_ms`.
+    pub fn wait(&self) -> Wait {
+        Wait { confirmations: self.confirmations.unwrap_or(1), timeout: Duration::from_millis(self.receipt_timeout_ms.unwrap_or(120_000)) }
+    }
+
+    pub fn backend(&self) -> anyhow::Result<Arc<dyn AnchorBackend>> {
+        let need = |v: &Option<String>, field: &str| v.clone().ok_or_else(|| anyhow::anyhow!("{} anchoring needs `{}`", self.backend.as_str(), field));
+        match self.backend {
+            BackendKind::File => Ok(Arc::new(FileLedger::new(need(&self.ledger_path, "ledger_path")?))),
+            BackendKind::DevChain => {
+                let mut chain = DevChain::new(need(&self.rpc_url, "rpc_url")?);
+                chain.from = self.from.clone();
+                chain.gas = self.gas.clone();
+                chain.wait = self.wait();
+                Ok(Arc::new(chain))
+            }
+            #[cfg(feature = "blockchain-eth")]
+            BackendKind::Eth => {
+                let priv_key = self.priv_key.clone().ok_or_else(|| anyhow::anyhow!("eth anchoring needs `priv_key`"))?;
+                let mut eth = EthContract::new(need(&self.rpc_url, "rpc_url")?, need(&self.contract_addr, "contract_addr")?, priv_key);
+                eth.gas = self.gas.clone();
+                eth.wait = self.wait();
+                Ok(Arc::new(eth))
+            }
+            #[cfg(not(feature = "blockchain-eth"))]
+            BackendKind::Eth => anyhow::bail!("blockchain feature disabled (eth anchoring needs `blockchain-eth`)"),
+        }
+    }
+}
+
+/// Confirmation depth a chain backend's `anchor` waits for.
+#[derive(Debug, Clone, Copy)]
+pub struct Wait {
+    pub confirmations: u64,
+    pub timeout: Duration,
+}
+
+impl Default for Wait {
+    fn default() -> Self { Self { confirmations: 1, timeout: Duration::from_secs(120) } }
+}
+
+/// Polls `status` until `hash_hex` has `confirmations` blocks; errors once `timeout` passes.
+pub async fn wait_for_confirmations(backend: &dyn AnchorBackend, hash_hex: &str, confirmations: u64, timeout: Duration) -> anyhow::Result<AnchorStatus> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut poll = Duration::from_millis(100);
+    loop {
+        let status = backend.status(hash_hex).await?;
+        // Backends that cannot count blocks only say whether the hash is on-chain
+        if status.anchored && status.confirmations.unwrap_or(u64::MAX) >= confirmations {
+            return Ok(status);
+        }
+        if tokio::time::Instant::now() + poll > deadline {
+            anyhow::bail!("{} not confirmed after {} ms ({} of {} confirmations)", hash_hex, timeout.as_millis(), status.confirmations.unwrap_or(0), confirmations);
+        }
+        tokio::time::sleep(poll).await;
+        poll = (poll * 2).min(Duration::from_secs(5));
+    }
+}
+
+fn normalize(hash_hex: &str) -> anyhow::Result<String> {
+    let h = hash_hex.trim();
+    let h = h.strip_prefix("0x").unwrap_or(h).to_ascii_lowercase();
+    anyhow::ensure!(!h.is_empty() && h.len().is_multiple_of(2) && h.bytes().all(|b| b.is_ascii_hexdigit()), "not a hex hash: '{}'", hash_hex);
+    Ok(h)
+}
+
+fn now_ms() -> i64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as i64
+}
+
+// ---- File ledger ----
+
+/// One line of the ledger; `entry_hash` covers the other fields, `prev` links to the entry before.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub index: u64,
+    pub hash: String,
+    pub prev: String,
+    pub timestamp_ms: i64,
+    pub entry_hash: String,
+}
+
+impl LedgerEntry {
+    fn digest(&self) -> String {
+        let body = json!({ "index": self.index, "hash": self.hash, "prev": self.prev, "timestamp_ms": self.timestamp_ms });
+        hex::encode(Sha3_512::digest(crate::proof::jcs::to_vec(&body)))
+    }
+}
+
+// Appends within one process are serialized; separate processes should not share a ledger
+static LEDGER_LOCK: Mutex<()> = Mutex::new(());
+
+/// Append-only JSONL ledger at `path`; editing or dropping a line breaks [`FileLedger::verify_chain`].
+#[derive(Debug, Clone)]
+pub struct FileLedger {
+    path: PathBuf,
+}
+
+impl FileLedger {
+    pub fn new(path: impl Into<PathBuf>) -> Self { Self { path: path.into() } }
+
+    pub fn path(&self) -> &Path { &self.path }
+
+    /// A missing file is an empty ledger.
+    pub fn entries(&self) -> anyhow::Result<Vec<LedgerEntry>> {
+        let text = match std::fs::read_to_string(&self.path) {
+            Ok(t) => t,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e).with_context(|| format!("reading {}", self.path.display())),
+        };
+        text.lines()
+            .filter(|l| !l.trim().is_empty())
+            .enumerate()
+            .map(|(i, l)| serde_json::from_str(l).with_context(|| format!("{} line {}", self.path.display(), i + 1)))
+            .collect()
+    }
+
+    /// Number of entries, or the first one whose index, link or hash is off.
+    pub fn verify_chain(&self) -> anyhow::Result<usize> {
+        let entries = self.entries()?;
+        check_chain(&entries)?;
+        Ok(entries.len())
+    }
+
+    /// Appends `hash_hex` unless it is already in the ledger; returns its entry either way.
+    pub fn append(&self, hash_hex: &str) -> anyhow::Result<LedgerEntry> {
+        let hash = normalize(hash_hex)?;
+        let _guard = LEDGER_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let entries = self.entries()?;
+        check_chain(&entries)?;
+        if let Some(e) = entries.iter().find(|e| e.hash == hash) {
+            return Ok(e.clone());
+        }
+        let prev = entries.last().map(|e| e.entry_hash.clone()).unwrap_or_else(genesis);
+        let mut entry = LedgerEntry { index: entries.len() as u64, hash, prev, timestamp_ms: now_ms(), entry_hash: String::new() };
+        entry.entry_hash = entry.digest();
+        if let Some(dir) = self.path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(&self.path).with_context(|| format!("opening {}", self.path.display()))?;
+        writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+        file.sync_data()?;
+        Ok(entry)
+    }
+
+    fn receipt_for(&self, e: &LedgerEntry) -> AnchorReceipt {
+        AnchorReceipt {
+            backend: "file".into(),
+            hash: e.hash.clone(),
+            tx_id: e.entry_hash.clone(),
+            location: Some(self.path.display().to_string()),
+            block: Some(e.index),
+            digest: None,
+            timestamp_ms: e.timestamp_ms,
+        }
+    }
+
+    fn find(&self, hash_hex: &str) -> anyhow::Result<(Option<LedgerEntry>, usize)> {
+        let hash = normalize(hash_hex)?;
+        let entries = self.entries()?;
+        check_chain(&entries)?;
+        let len = entries.len();
+        Ok((entries.into_iter().find(|e| e.hash == hash), len))
+    }
+}
+
+fn genesis() -> String { "0".repeat(128) }
+
+fn check_chain(entries: &[LedgerEntry]) -> anyhow::Result<()> {
+    let mut prev = genesis();
+    for (i, e) in entries.iter().enumerate() {
+        anyhow::ensure!(e.index == i as u64, "ledger entry {} has index {}", i, e.index);
+        anyhow::ensure!(e.prev == prev, "ledger entry {} does not link to the entry before it", i);
+        anyhow::ensure!(e.entry_hash == e.digest(), "ledger entry {} was modified", i);
+        prev = e.entry_hash.clone();
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl AnchorBackend for FileLedger {
+    fn name(&self) -> &'static str { "file" }
+
+    async fn anchor(&self, hash_hex: &str) -> anyhow::Result<AnchorReceipt> { self.append(hash_hex).map(|e| self.receipt_for(&e)) }
+
+    async fn status(&self, hash_hex: &str) -> anyhow::Result<AnchorStatus> {
+        let (entry, len) = self.find(hash_hex)?;
+        Ok(AnchorStatus { anchored: entry.is_some(), confirmations: entry.map(|e| len as u64 - e.index) })
+    }
+
+    async fn receipt(&self, hash_hex: &str) -> anyhow::Result<Option<AnchorReceipt>> { Ok(self.find(hash_hex)?.0.map(|e| self.receipt_for(&e))) }
+}
+
+// ---- Local dev chain ----
+
+/// JSON-RPC node with unlocked accounts (anvil, hardhat, geth --dev). No contract needed: the
+/// hash is the calldata of a zero-value transaction from the sender to itself.
+pub struct DevChain {
+    rpc_url: String,
+    /// Sender; the node's first account when unset.
+    pub from: Option<String>,
+    /// How many recent blocks [`AnchorBackend::status`] scans for hashes anchored elsewhere.
+    pub scan_blocks: u64,
+    pub gas: GasStrategy,
+    pub wait: Wait,
+    client: reqwest::Client,
+    sent: Mutex<HashMap<String, String>>,
+    next_nonce: Mutex<Option<u64>>,
+}
+
+struct Located {
+    tx_id: String,
+    block: Option<u64>,
+    timestamp_ms: Option<i64>,
+}
+
+fn quantity(v: &Value) -> Option<u64> { v.as_str().and_then(|s| u64::from_str_radix(s.trim_start_matches("0x"), 16).ok()) }
+
+impl DevChain {
+    pub fn new(rpc_url: impl Into<String>) -> Self {
+        Self {
+            rpc_url: rpc_url.into(),
+            from: None,
+            scan_blocks: 256,
+            gas: GasStrategy::default(),
+            wait: Wait::default(),
+            client: reqwest::Client::new(),
+            sent: Mutex::new(HashMap::new()),
+            next_nonce: Mutex::new(None),
+        }
+    }
+
+    async fn rpc(&self, method: &str, params: Value) -> anyhow::Result<Value> {
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let res: Value = self.client.post(&self.rpc_url).json(&body).send().await?.error_for_status()?.json().await?;
+        if let Some(err) = res.get("error") {
+            anyhow::bail!("{} failed: {}", method, err.get("message").and_then(|m| m.as_str()).unwrap_or("unknown error"));
+        }
+        Ok(res.get("result").cloned().unwrap_or(Value::Null))
+    }
+
+    async fn sender(&self) -> anyhow::Result<String> {
+        if let Some(from) = &self.from {
+            return Ok(from.clone());
+        }
+        let accounts = self.rpc("eth_accounts", json!([])).await?;
+        accounts.get(0).and_then(|a| a.as_str()).map(str::to_string).ok_or_else(|| anyhow::anyhow!("dev chain has no unlocked accounts"))
+    }
+
+    // Hands out consecutive nonces so concurrent anchors from one instance never collide;
+    // the sequence starts from the node's pending count and restarts from it after a failed send
+    async fn nonce(&self, from: &str) -> anyhow::Result<u64> {
+        if let Some(n) = self.take_nonce(None) {
+            return Ok(n);
+        }
+        let pending = quantity(&self.rpc("eth_getTransactionCount", json!([from, "pending"])).await?).ok_or_else(|| anyhow::anyhow!("eth_getTransactionCount returned no number"))?;
+        Ok(self.take_nonce(Some(pending)).unwrap_or(pending))
+    }
+
+    fn take_nonce(&self, start: Option<u64>) -> Option<u64> {
+        let mut next = self.next_nonce.lock().unwrap_or_else(|e| e.into_inner());
+        let n = next.or(start)?;
+        *next = Some(n + 1);
+        Some(n)
+    }
+
+    async fn head(&self) -> anyhow::Result<u64> {
+        quantity(&self.rpc("eth_blockNumber", json!([])).await?).ok_or_else(|| anyhow::anyhow!("eth_blockNumber returned no number"))
+    }
+
+    async fn block_time_ms(&self, block: u64) -> anyhow::Result<Option<i64>> {
+        let b = self.rpc("eth_getBlockByNumber", json!([format!("0x{:x}", block), false])).await?;
+        Ok(quantity(&b["timestamp"]).map(|s| s as i64 * 1000))
+    }
+
+    async fn locate(&self, hash: &str) -> anyhow::Result<Option<Located>> {
+        let known = self.sent.lock().unwrap_or_else(|e| e.into_inner()).get(hash).cloned();
+        if let Some(tx_id) = known {
+            let r = self.rpc("eth_getTransactionReceipt", json!([tx_id])).await?;
+            let block = quantity(&r["blockNumber"]);
+            let timestamp_ms = match block { Some(b) => self.block_time_ms(b).await?, None => None };
+            return Ok(Some(Located { tx_id, block, timestamp_ms }));
+        }
+        let data = format!("0x{}", hash);
+        let head = self.head().await?;
+        for n in (head.saturating_sub(self.scan_blocks.saturating_sub(1))..=head).rev() {
+            let b = self.rpc("eth_getBlockByNumber", json!([format!("0x{:x}", n), true])).await?;
+            let found = b["transactions"].as_array().into_iter().flatten().find(|tx| tx["input"].as_str().is_some_and(|i| i.eq_ignore_ascii_case(&data)));
+            if let Some(tx) = found {
+                let tx_id = tx["hash"].as_str().unwrap_or_default().to_string();
+                return Ok(Some(Located { tx_id, block: Some(n), timestamp_ms: quantity(&b["timestamp"]).map(|s| s as i64 * 1000) }));
+            }
+        }
+        Ok(None)
+    }
+
+    async fn send(&self, hash: &str) -> anyhow::Result<String> {
+        let from = self.sender().await?;
+        let mut tx = json!({ "from": from, "to": from, "value": "0x0", "data": format!("0x{}", hash) });
+        tx["nonce"] = json!(format!("0x{:x}", self.nonce(&from).await?));
+        if let Some(limit) = self.gas.limit {
+            tx["gas"] = json!(format!("0x{:x}", limit));
+        }
+        let suggested = if self.gas.needs_node_price() { quantity(&self.rpc("eth_gasPrice", json!([])).await?) } else { None };
+        if let Some(price) = self.gas.price(suggested) {
+            tx["gasPrice"] = json!(format!("0x{:x}", price));
+        }
+        match self.rpc("eth_sendTransaction", json!([tx])).await {
+            Ok(id) => id.as_str().map(str::to_string).ok_or_else(|| anyhow::anyhow!("eth_sendTransaction returned no hash")),
+            Err(e) => {
+                *self.next_nonce.lock().unwrap_or_else(|e| e.into_inner()) = None;
+                Err(e)
+            }
+        }
+    }
+
+    fn receipt_for(&self, hash: String, l: Located) -> AnchorReceipt {
+        AnchorReceipt {
+            backend: "devchain".into(),
+            hash,
+            tx_id: l.tx_id,
+            location: Some(self.rpc_url.clone()),
+            block: l.block,
+            digest: None,
+            timestamp_ms: l.timestamp_ms.unwrap_or_else(now_ms),
+        }
+    }
+}
+
+#[async_trait]
+impl AnchorBackend for DevChain {
+    fn name(&self) -> &'static str { "devchain" }
+
+    async fn anchor(&self, hash_hex: &str) -> anyhow::Result<AnchorReceipt> {
+        let hash = normalize(hash_hex)?;
+        let located = match self.locate(&hash).await? {
+            Some(l) => l,
+            None => {
+                let tx_id = self.send(&hash).await?;
+                self.sent.lock().unwrap_or_else(|e| e.into_inner()).insert(hash.clone(), tx_id.clone());
+                Located { tx_id, block: None, timestamp_ms: None }
+            }
+        };
+        if self.wait.confirmations == 0 {
+            return Ok(self.receipt_for(hash, located));
+        }
+        wait_for_confirmations(self, &hash, self.wait.confirmations, self.wait.timeout).await?;
+        let located = self.locate(&hash).await?.unwrap_or(located);
+        Ok(self.receipt_for(hash, located))
+    }
+
+    async fn status(&self, hash_hex: &str) -> anyhow::Result<AnchorStatus> {
+        let hash = normalize(hash_hex)?;
+        match self.locate(&hash).await?.and_then(|l| l.block) {
+            Some(block) => Ok(AnchorStatus { anchored: true, confirmations: Some(self.head().await?.saturating_sub(block) + 1) }),
+            None => Ok(AnchorStatus { anchored: false, confirmations: None }),
+        }
+    }
+
+    async fn receipt(&self, hash_hex: &str) -> anyhow::Result<Option<AnchorReceipt>> {
+        let hash = normalize(hash_hex)?;
+        Ok(self.locate(&hash).await?.map(|l| self.receipt_for(hash, l)))
+    }
+}
+
+// ---- Ethereum contract ----
+
+/// The `anchor(bytes32)` / `isAnchored(bytes32)` contract used by [`crate::anchor_eth`].
+/// Receipts are only known for hashes anchored through this instance.
+#[cfg(feature = "blockchain-eth")]
+pub struct EthContract {
+    rpc_url: String,
+    contract_addr: String,
+    priv_key: Secret,
+    pub gas: GasStrategy,
+    pub wait: Wait,
+    anchorer: Mutex<Option<Arc<crate::anchor_eth::EthAnchorer>>>,
+    receipts: Mutex<HashMap<String, AnchorReceipt>>,
+}
+
+#[cfg(feature = "blockchain-eth")]
+impl EthContract {
+    pub fn new(rpc_url: String, contract_addr: String, priv_key: Secret) -> Self {
+        Self {
+            rpc_url,
+            contract_addr,
+            priv_key,
+            gas: GasStrategy::default(),
+            wait: Wait::default(),
+            anchorer: Mutex::new(None),
+            receipts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // One connection (and so one nonce sequence) per backend
+    async fn anchorer(&self) -> anyhow::Result<Arc<crate::anchor_eth::EthAnchorer>> {
+        if let Some(a) = self.anchorer.lock().unwrap_or_else(|e| e.into_inner()).clone() {
+            return Ok(a);
+        }
+        let a = Arc::new(crate::anchor_eth::EthAnchorer::connect(&self.rpc_url, &self.contract_addr, self.priv_key.expose(), self.gas.clone()).await?);
+        Ok(self.anchorer.lock().unwrap_or_else(|e| e.into_inner()).get_or_insert(a).clone())
+    }
+}
+
+#[cfg(feature = "blockchain-eth")]
+#[async_trait]
+impl AnchorBackend for EthContract {
+    fn name(&self) -> &'static str { "eth" }
+
+    async fn anchor(&self, hash_hex: &str) -> anyhow::Result<AnchorReceipt> {
+        let hash = normalize(hash_hex)?;
+        let anchorer = self.anchorer().await?;
+        let res = tokio::time::timeout(self.wait.timeout, anchorer.anchor(&hash, self.wait.confirmations as usize))
+            .await
+            .map_err(|_| anyhow::anyhow!("anchor of {} not confirmed after {} ms", hash, self.wait.timeout.as_millis()))??;
+        let receipt = AnchorReceipt {
+            backend: "eth".into(),
+            digest: Some(crate::anchor_eth::onchain_digest(&hash)?),
+            hash: hash.clone(),
+            tx_id: res.tx_hash,
+            location: Some(self.contract_addr.clone()),
+            block: res.block,
+            timestamp_ms: now_ms(),
+        };
+        self.receipts.lock().unwrap_or_else(|e| e.into_inner()).insert(hash, receipt.clone());
+        Ok(receipt)
+    }
+
+    async fn status(&self, hash_hex: &str) -> anyhow::Result<AnchorStatus> {
+        let hash = normalize(hash_hex)?;
+        let anchorer = self.anchorer().await?;
+        let anchored = anchorer.is_anchored(&hash).await?;
+        let tx = self.receipts.lock().unwrap_or_else(|e| e.into_inner()).get(&hash).map(|r| r.tx_id.clone());
+        let confirmations = match tx {
+            Some(tx) if anchored => anchorer.confirmations(&tx).await?,
+            _ => None,
+        };
+        Ok(AnchorStatus { anchored, confirmations })
+    }
+
+    async fn receipt(&self, hash_hex: &str) -> anyhow::Result<Option<AnchorReceipt>> {
+        Ok(self.receipts.lock().unwrap_or_else(|e| e.into_inner()).get(&normalize(hash_hex)?).cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn file_ledger_chains_entries_and_detects_edits() {
+        let path = std::env::temp_dir().join(format!("panther-ledger-{}-{}.jsonl", std::process::id(), now_ms()));
+        let backend = AnchorConfig::file(path.display().to_string()).backend().unwrap();
+        let a = backend.anchor(&"ab".repeat(64)).await.unwrap();
+        let b = backend.anchor(&format!("0x{}", "CD".repeat(64))).await.unwrap();
+        assert_eq!((a.block, b.block), (Some(0), Some(1)));
+        assert_eq!(backend.anchor(&"ab".repeat(64)).await.unwrap(), a);
+        assert_eq!(backend.status(&"ab".repeat(64)).await.unwrap(), AnchorStatus { anchored: true, confirmations: Some(2) });
+        assert!(!backend.status(&"ef".repeat(64)).await.unwrap().anchored);
+        assert_eq!(backend.receipt(&"cd".repeat(64)).await.unwrap(), Some(b));
+
+        let ledger = FileLedger::new(&path);
+        assert_eq!(ledger.verify_chain().unwrap(), 2);
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, text.replacen(&"ab".repeat(64), &"ee".repeat(64), 1)).unwrap();
+        assert!(ledger.verify_chain().unwrap_err().to_string().contains("entry 0"));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    // Just enough of anvil's JSON-RPC for one account that mines every transaction at once
+    async fn fake_node() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let chain = Arc::new(Mutex::new(Vec::<Value>::new()));
+        tokio::spawn(async move {
+            while let Ok((mut sock, _)) = listener.accept().await {
+                let chain = chain.clone();
+                tokio::spawn(async move {
+                    let mut buf = Vec::new();
+                    let mut chunk = [0u8; 4096];
+                    let body = loop {
+                        let n = sock.read(&mut chunk).await.unwrap_or(0);
+                        if n == 0 { return; }
+                        buf.extend_from_slice(&chunk[..n]);
+                        let text = String::from_utf8_lossy(&buf).to_string();
+                        let Some((head, body)) = text.split_once("\r\n\r\n") else { continue };
+                        let len = head.lines().find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap_or(0))).unwrap_or(0);
+                        if body.len() >= len { break body.to_string(); }
+                    };
+                    let req: Value = serde_json::from_str(&body).unwrap();
+                    let result = {
+                        let mut blocks = chain.lock().unwrap();
+                        match req["method"].as_str().unwrap() {
+                            "eth_accounts" => json!(["0x00000000000000000000000000000000000000aa"]),
+                            "eth_blockNumber" => json!(format!("0x{:x}", blocks.len().saturating_sub(1))),
+                            "eth_getTransactionCount" => json!(format!("0x{:x}", blocks.len())),
+                            "eth_sendTransaction" => {
+                                let n = blocks.len();
+                                assert_eq!(quantity(&req["params"][0]["nonce"]), Some(n as u64));
+                                let tx_id = format!("0x{:064x}", n + 1);
+                                let tx = json!({ "hash": tx_id, "input": req["params"][0]["data"] });
+                                blocks.push(json!({ "number": format!("0x{:x}", n), "timestamp": "0x10", "transactions": [tx] }));
+                                json!(tx_id)
+                            }
+                            "eth_getTransactionReceipt" => {
+                                let id = req["params"][0].clone();
+                                blocks.iter().find(|b| b["transactions"][0]["hash"] == id).map_or(Value::Null, |b| json!({ "blockNumber": b["number"] }))
+                            }
+                            "eth_getBlockByNumber" => {
+                                let n = quantity(&req["params"][0]).unwrap() as usize;
+                                blocks.get(n).cloned().unwrap_or(Value::Null)
+                            }
+                            m => panic!("unexpected {m}"),
+                        }
+                    };
+                    let body = json!({ "jsonrpc": "2.0", "id": req["id"], "result": result }).to_string();
+                    let res = format!("HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}", body.len(), body);
+                    let _ = sock.write_all(res.as_bytes()).await;
+                });
+            }
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn dev_chain_finds_hashes_anchored_by_other_instances() {
+        let url = fake_node().await;
+        let chain = AnchorConfig::dev_chain(url.clone()).backend().unwrap();
+        let receipt = chain.anchor(&"ab".repeat(64)).await.unwrap();
+        assert_eq!((receipt.block, receipt.timestamp_ms), (Some(0), 16_000));
+        chain.anchor(&"cd".repeat(64)).await.unwrap();
+
+        let other = DevChain::new(url);
+        assert_eq!(other.status(&"ab".repeat(64)).await.unwrap(), AnchorStatus { anchored: true, confirmations: Some(2) });
+        assert_eq!(other.receipt(&"ab".repeat(64)).await.unwrap().map(|r| r.tx_id), Some(receipt.tx_id));
+        assert!(!other.status(&"ef".repeat(64)).await.unwrap().anchored);
+    }
+}
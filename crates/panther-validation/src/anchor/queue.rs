@@ -0,0 +1,241 @@
+//! Pending anchors persisted in a [`KeyValueStore`].
+//!
+//! Hashes queued with [`AnchorQueue::enqueue`] are anchored together by [`AnchorQueue::flush`]:
+//! one Merkle root per batch (the same tree as [`crate::proof::batch`]), so a single transaction
+//! covers them all and each hash gets an inclusion proof. The batch is written to the store
+//! before it is sent and updated after every step, so after a crash [`AnchorQueue::resume`]
+//! finishes it instead of losing it or anchoring it twice.
+
+use super::{normalize, wait_for_confirmations, AnchorBackend, Wait};
+use crate::proof::merkle::InclusionProof;
+use crate::proof::{batch_hashes, BatchProof};
+use panther_domain::entities::AnchorReceipt;
+use panther_domain::ports::KeyValueStore;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+const PENDING_KEY: &str = "anchor:pending";
+const IN_FLIGHT_KEY: &str = "anchor:in_flight";
+
+fn done_key(hash: &str) -> String { format!("anchor:done:{}", hash) }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchState {
+    /// Recorded, transaction not known to have gone out.
+    Sending,
+    /// Transaction sent; waiting for confirmations.
+    Submitted,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingBatch {
+    pub batch: BatchProof,
+    pub hashes: Vec<String>,
+    pub state: BatchState,
+    #[serde(default)]
+    pub receipt: Option<AnchorReceipt>,
+}
+
+/// A hash covered by an anchored batch root.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchAnchor {
+    pub hash: String,
+    pub root: String,
+    pub inclusion: InclusionProof,
+    /// Missing when a restarted backend could confirm the root but no longer had its receipt.
+    #[serde(default)]
+    pub receipt: Option<AnchorReceipt>,
+    #[serde(default)]
+    pub confirmations: Option<u64>,
+}
+
+impl BatchAnchor {
+    /// The inclusion path leads from `hash` to `root`, and the receipt is for `root`.
+    pub fn verify(&self) -> bool {
+        self.inclusion.root == self.root && self.inclusion.verify_leaf(self.hash.as_bytes()) && self.receipt.as_ref().is_none_or(|r| r.hash == self.root)
+    }
+
+    /// Receipt for a `.pantherproof` bundle: the root's receipt plus this hash's `inclusion` path.
+    pub fn bundle_receipt(&self) -> serde_json::Value {
+        let mut v = match &self.receipt {
+            Some(r) => serde_json::to_value(r).unwrap_or_default(),
+            None => serde_json::json!({ "hash": self.root }),
+        };
+        v["inclusion"] = serde_json::to_value(&self.inclusion).unwrap_or_default();
+        v
+    }
+}
+
+pub struct AnchorQueue {
+    store: Arc<dyn KeyValueStore>,
+    backend: Arc<dyn AnchorBackend>,
+    wait: Wait,
+    sdk_version: String,
+    // Guards the pending list's read-modify-write; `flushing` serializes whole batches
+    pending_lock: Mutex<()>,
+    flushing: futures::lock::Mutex<()>,
+}
+
+impl AnchorQueue {
+    pub fn new(store: Arc<dyn KeyValueStore>, backend: Arc<dyn AnchorBackend>) -> Self {
+        Self {
+            store,
+            backend,
+            wait: Wait::default(),
+            sdk_version: env!("CARGO_PKG_VERSION").to_string(),
+            pending_lock: Mutex::new(()),
+            flushing: futures::lock::Mutex::new(()),
+        }
+    }
+
+    /// Confirmation depth a batch root must reach before its hashes count as anchored.
+    pub fn with_wait(mut self, wait: Wait) -> Self {
+        self.wait = wait;
+        self
+    }
+
+    fn load<T: serde::de::DeserializeOwned>(&self, key: &str) -> anyhow::Result<Option<T>> {
+        self.store.get(key)?.map(|s| serde_json::from_str(&s)).transpose().map_err(Into::into)
+    }
+
+    fn save<T: Serialize>(&self, key: &str, value: &T) -> anyhow::Result<()> { self.store.set(key, serde_json::to_string(value)?) }
+
+    pub fn pending(&self) -> anyhow::Result<Vec<String>> { Ok(self.load(PENDING_KEY)?.unwrap_or_default()) }
+
+    pub fn in_flight(&self) -> anyhow::Result<Option<PendingBatch>> { self.load(IN_FLIGHT_KEY) }
+
+    pub fn get(&self, hash_hex: &str) -> anyhow::Result<Option<BatchAnchor>> { self.load(&done_key(&normalize(hash_hex)?)) }
+
+    /// Queues `hash_hex` for the next batch; `false` when it is already queued, in flight or anchored.
+    pub fn enqueue(&self, hash_hex: &str) -> anyhow::Result<bool> {
+        let hash = normalize(hash_hex)?;
+        let _guard = self.pending_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut pending = self.pending()?;
+        let in_flight = self.in_flight()?.is_some_and(|b| b.hashes.contains(&hash));
+        if pending.contains(&hash) || in_flight || self.store.get(&done_key(&hash))?.is_some() {
+            return Ok(false);
+        }
+        pending.push(hash);
+        self.save(PENDING_KEY, &pending)?;
+        Ok(true)
+    }
+
+    /// Finishes a batch left in flight by an earlier process.
+    pub async fn resume(&self) -> anyhow::Result<Vec<BatchAnchor>> {
+        let _flushing = self.flushing.lock().await;
+        match self.in_flight()? {
+            Some(batch) => self.finish(batch).await,
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Resumes any batch in flight, then anchors everything queued under one root.
+    pub async fn flush(&self) -> anyhow::Result<Vec<BatchAnchor>> {
+        let _flushing = self.flushing.lock().await;
+        let mut anchored = match self.in_flight()? {
+            Some(batch) => self.finish(batch).await?,
+            None => Vec::new(),
+        };
+        let batch = {
+            let _guard = self.pending_lock.lock().unwrap_or_else(|e| e.into_inner());
+            // A crash between recording a batch and clearing the queue leaves its hashes here too
+            let mut hashes = Vec::new();
+            for h in self.pending()? {
+                if self.store.get(&done_key(&h))?.is_none() {
+                    hashes.push(h);
+                }
+            }
+            if hashes.is_empty() {
+                self.store.delete(PENDING_KEY)?;
+                return Ok(anchored);
+            }
+            let refs: Vec<&str> = hashes.iter().map(String::as_str).collect();
+            let (batch, _) = batch_hashes(&refs, &self.sdk_version);
+            let pending = PendingBatch { batch, hashes, state: BatchState::Sending, receipt: None };
+            self.save(IN_FLIGHT_KEY, &pending)?;
+            self.store.delete(PENDING_KEY)?;
+            pending
+        };
+        anchored.extend(self.finish(batch).await?);
+        Ok(anchored)
+    }
+
+    async fn finish(&self, mut batch: PendingBatch) -> anyhow::Result<Vec<BatchAnchor>> {
+        let root = batch.batch.root.clone();
+        if batch.state == BatchState::Sending {
+            // The transaction may have gone out just before a crash; only send it if the root is unknown
+            batch.receipt = match self.backend.receipt(&root).await? {
+                Some(r) => Some(r),
+                None if self.backend.status(&root).await?.anchored => None,
+                None => Some(self.backend.anchor(&root).await?),
+            };
+            batch.state = BatchState::Submitted;
+            self.save(IN_FLIGHT_KEY, &batch)?;
+        }
+        let status = wait_for_confirmations(self.backend.as_ref(), &root, self.wait.confirmations, self.wait.timeout).await?;
+
+        let refs: Vec<&str> = batch.hashes.iter().map(String::as_str).collect();
+        let (_, inclusions) = batch_hashes(&refs, &self.sdk_version);
+        let anchored: Vec<BatchAnchor> = batch
+            .hashes
+            .iter()
+            .zip(inclusions)
+            .map(|(hash, inclusion)| BatchAnchor { hash: hash.clone(), root: root.clone(), inclusion, receipt: batch.receipt.clone(), confirmations: status.confirmations })
+            .collect();
+        for a in &anchored {
+            self.save(&done_key(&a.hash), a)?;
+        }
+        self.store.delete(IN_FLIGHT_KEY)?;
+        Ok(anchored)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anchor::FileLedger;
+    use async_trait::async_trait;
+    use panther_domain::entities::AnchorStatus;
+
+    // Writes to the ledger, then fails as if the process died before hearing back
+    struct CrashAfterSend(FileLedger);
+
+    #[async_trait]
+    impl AnchorBackend for CrashAfterSend {
+        fn name(&self) -> &'static str { "crash" }
+        async fn anchor(&self, hash_hex: &str) -> anyhow::Result<AnchorReceipt> {
+            self.0.append(hash_hex)?;
+            anyhow::bail!("connection reset")
+        }
+        async fn status(&self, hash_hex: &str) -> anyhow::Result<AnchorStatus> { self.0.status(hash_hex).await }
+        async fn receipt(&self, _hash_hex: &str) -> anyhow::Result<Option<AnchorReceipt>> { Ok(None) }
+    }
+
+    #[tokio::test]
+    async fn resumes_a_batch_interrupted_after_sending() {
+        let path = std::env::temp_dir().join(format!("panther-queue-{}-{}.jsonl", std::process::id(), super::super::now_ms()));
+        let store: Arc<dyn KeyValueStore> = Arc::new(panther_storage::InMemoryStore::default());
+        let hashes: Vec<String> = ["aa", "bb", "cc"].iter().map(|b| b.repeat(64)).collect();
+
+        let crashing = AnchorQueue::new(store.clone(), Arc::new(CrashAfterSend(FileLedger::new(&path))));
+        for h in &hashes {
+            assert!(crashing.enqueue(h).unwrap());
+        }
+        assert!(!crashing.enqueue(&hashes[0]).unwrap());
+        assert!(crashing.flush().await.is_err());
+        assert_eq!(crashing.in_flight().unwrap().map(|b| b.state), Some(BatchState::Sending));
+        assert!(crashing.pending().unwrap().is_empty());
+
+        let queue = AnchorQueue::new(store, Arc::new(FileLedger::new(&path)));
+        let anchored = queue.resume().await.unwrap();
+        assert_eq!(anchored.len(), 3);
+        assert!(anchored.iter().all(BatchAnchor::verify));
+        assert_eq!(FileLedger::new(&path).verify_chain().unwrap(), 1);
+        assert!(queue.in_flight().unwrap().is_none());
+        assert_eq!(queue.get(&hashes[1]).unwrap().unwrap().root, anchored[0].root);
+        assert!(!queue.enqueue(&hashes[2]).unwrap());
+        assert!(queue.flush().await.unwrap().is_empty());
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,73 @@
+//! Records for the validation run history.
+//!
+//! [`new_run`] turns a finished validation into a [`ValidationRun`] that any
+//! [`RunRepository`](panther_domain::ports::RunRepository) can store. Provider
+//! configs are stored without credentials.
+
+use crate::proof::{guidelines_hash, Proof};
+use crate::ValidationResult;
+use panther_domain::entities::ValidationRun;
+use serde_json::Value;
+use std::hash::{BuildHasher, RandomState};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+pub use crate::secrets::scrub_secrets;
+
+/// `run-<13-digit ms>-<8 hex>`: sorts by time, unique within and across processes.
+pub fn new_run_id(timestamp_ms: i64) -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let suffix = RandomState::new().hash_one((timestamp_ms, n, std::process::id())) as u32;
+    format!("run-{:013}-{:08x}", timestamp_ms.max(0), suffix)
+}
+
+/// Provider configs for runs where only the `type:model` labels are known.
+pub fn providers_from_labels<S: AsRef<str>>(labels: &[S]) -> Value {
+    Value::Array(labels.iter().map(|l| serde_json::json!({ "name": l.as_ref() })).collect())
+}
+
+pub fn new_run(
+    prompt: &str,
+    providers: &Value,
+    guidelines_json: &str,
+    results: &[ValidationResult],
+    proof: Option<&Proof>,
+) -> anyhow::Result<ValidationRun> {
+    let timestamp_ms = crate::now_ms();
+    Ok(ValidationRun {
+        id: new_run_id(timestamp_ms),
+        timestamp_ms,
+        prompt: prompt.to_string(),
+        providers: scrub_secrets(providers),
+        guidelines_hash: Some(guidelines_hash(guidelines_json)),
+        scores: results.iter().map(|r| (r.provider_name.clone(), r.adherence_score)).collect(),
+        results: serde_json::to_value(results)?,
+        proof: proof.map(serde_json::to_value).transpose()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scrubs_credentials_and_orders_ids() {
+        let providers = serde_json::json!([
+            { "type": "openai", "model": "gpt-4o-mini", "api_key": "sk-live", "headers": { "Authorization": "Bearer x" } },
+            { "type": "ollama", "base_url": "http://localhost:11434", "api_key": null }
+        ]);
+        let clean = scrub_secrets(&providers);
+        let text = clean.to_string();
+        assert!(!text.contains("sk-live") && !text.contains("Bearer"));
+        assert_eq!(clean[0]["model"], "gpt-4o-mini");
+        assert_eq!(clean[1]["api_key"], Value::Null);
+
+        let (a, b) = (new_run_id(1_700_000_000_000), new_run_id(1_700_000_000_000));
+        assert_ne!(a, b);
+        assert!(new_run_id(999) < a);
+
+        let run = new_run("q", &providers, "[]", &[], None).unwrap();
+        assert_eq!(run.guidelines_hash.as_deref(), Some(guidelines_hash("[]").as_str()));
+        assert!(!run.providers.to_string().contains("sk-live"));
+    }
+}
@@ -0,0 +1,480 @@
+//! Draft guideline packs from regulatory documents.
+//!
+//! Local documents (PDF text extracts, DOCX converted to text, Markdown, HTML)
+//! are split into sections; each section becomes a proposed topic whose
+//! candidate expected terms are the section's most distinctive n-grams
+//! (TF-IDF across sections) and whose candidate forbidden terms come from
+//! prohibition cues ("é vedado", "must not", ...). An optional provider can
+//! suggest extra terms, which are kept only if they occur in the section. The
+//! result is a draft pack plus review notes citing the evidence for each term;
+//! it is meant to be edited by a human before use.
+
+use crate::matching::{fold, MatchOptions, Matcher};
+use crate::pack::{GuidelinePack, PackMetadata, PACK_FORMAT};
+use crate::Guideline;
+use anyhow::{bail, Result};
+use panther_domain::entities::Prompt;
+use panther_domain::ports::LlmProvider;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const STOPWORDS: &[&str] = &[
+    // Portuguese
+    "a", "ao", "aos", "as", "à", "às", "até", "com", "como", "da", "das", "de", "dela", "dele", "do", "dos", "e", "é", "ela",
+    "ele", "em", "entre", "era", "essa", "esse", "esta", "está", "este", "foi", "for", "há", "isso", "já", "lhe", "mais",
+    "mas", "mesmo", "na", "nas", "não", "nem", "no", "nos", "num", "numa", "o", "os", "ou", "para", "pela", "pelas", "pelo",
+    "pelos", "por", "qual", "quando", "que", "se", "sem", "ser", "seu", "seus", "sua", "suas", "são", "também", "tem",
+    "um", "uma", "umas", "uns", "deve", "devem", "pode", "podem", "caso", "casos", "sobre", "cada", "outro", "outros",
+    // English
+    "an", "and", "are", "be", "by", "for", "from", "has", "have", "in", "is", "it", "its", "of", "on", "or", "that",
+    "the", "their", "this", "to", "was", "were", "will", "with", "should", "must", "may", "can", "not", "any", "all",
+    "such", "other", "which", "these", "those", "when", "than",
+];
+
+/// Phrases introducing something the text prohibits; the words that follow become forbidden-term candidates.
+const PROHIBITION_CUES: &[&str] = &[
+    "é vedado", "é vedada", "é proibido", "é proibida", "fica proibido", "fica vedado", "não é permitido",
+    "não é permitida", "não deve", "não devem", "não se deve", "não pode", "não podem", "must not", "shall not",
+    "is prohibited", "are prohibited", "do not", "never",
+];
+
+const MAX_NGRAM: usize = 3;
+const FORBIDDEN_PHRASE_WORDS: usize = 6;
+const MIN_SECTION_WORDS: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceFormat {
+    /// Plain text: PDF text extracts, DOCX converted to text.
+    Text,
+    Markdown,
+    Html,
+}
+
+impl SourceFormat {
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_ascii_lowercase();
+        Ok(match ext.as_str() {
+            "md" | "markdown" => Self::Markdown,
+            "html" | "htm" => Self::Html,
+            "pdf" | "docx" | "doc" => bail!("{}: extract text first (e.g. `pdftotext`, `pandoc -t plain`) and ingest the .txt", path.display()),
+            _ => Self::Text,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Section {
+    pub title: String,
+    pub body: String,
+    pub source: String,
+}
+
+#[derive(Clone)]
+pub struct IngestOptions {
+    pub metadata: PackMetadata,
+    /// Candidate expected terms per topic.
+    pub max_terms: usize,
+    /// Minimum occurrences in a section for a term to be proposed.
+    pub min_count: usize,
+    /// Optional provider asked to suggest terms per section.
+    pub provider: Option<Arc<dyn LlmProvider>>,
+}
+
+impl Default for IngestOptions {
+    fn default() -> Self {
+        Self {
+            metadata: PackMetadata {
+                id: "draft".into(),
+                version: "0.1.0-draft".into(),
+                name: None,
+                description: None,
+                jurisdiction: None,
+                locale: None,
+                authors: vec![],
+                effective_date: None,
+            },
+            max_terms: 8,
+            min_count: 2,
+            provider: None,
+        }
+    }
+}
+
+/// Why a term was proposed, for the reviewer.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReviewNote {
+    pub topic: String,
+    /// expected_term | forbidden_term
+    pub kind: String,
+    pub term: String,
+    /// tf-idf | prohibition_cue | provider
+    pub origin: String,
+    pub score: f64,
+    /// Sentence the term was taken from.
+    pub evidence: String,
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Draft {
+    pub pack: GuidelinePack,
+    pub review: Vec<ReviewNote>,
+}
+
+impl Draft {
+    /// Writes the pack (YAML for `.yaml`/`.yml`, JSON otherwise) and the review notes next to it
+    /// as `<out>.review.json`; returns the review path.
+    pub fn write(&self, out: &Path) -> Result<PathBuf> {
+        let yaml = matches!(out.extension().and_then(|e| e.to_str()), Some("yaml" | "yml"));
+        let body = if yaml { serde_yaml::to_string(&self.pack)? } else { serde_json::to_string_pretty(&self.pack)? };
+        std::fs::write(out, body)?;
+        let mut review = out.as_os_str().to_owned();
+        review.push(".review.json");
+        let review = PathBuf::from(review);
+        std::fs::write(&review, serde_json::to_string_pretty(&self.review)?)?;
+        Ok(review)
+    }
+}
+
+/// Reads and segments a local document.
+pub fn load_sections(path: &Path) -> Result<Vec<Section>> {
+    let format = SourceFormat::from_path(path)?;
+    let text = std::fs::read_to_string(path)?;
+    let source = path.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string();
+    Ok(segment(&text, format, &source))
+}
+
+pub fn segment(text: &str, format: SourceFormat, source: &str) -> Vec<Section> {
+    let text = match format {
+        SourceFormat::Html => html_to_markdown(text),
+        _ => join_wrapped(text),
+    };
+    let mut sections: Vec<Section> = Vec::new();
+    let mut title = String::new();
+    let mut body = String::new();
+    let mut flush = |title: &str, body: &mut String| {
+        if !body.trim().is_empty() {
+            sections.push(Section { title: title.trim().to_string(), body: body.trim().to_string(), source: source.to_string() });
+        }
+        body.clear();
+    };
+    for line in text.lines() {
+        let heading = match format {
+            SourceFormat::Text => text_heading(line),
+            _ => line.trim_start().strip_prefix('#').map(|h| h.trim_start_matches('#').trim().to_string()),
+        };
+        match heading {
+            Some(h) if !h.is_empty() => {
+                flush(&title, &mut body);
+                title = h;
+            }
+            _ => {
+                body.push_str(line.trim());
+                body.push('\n');
+            }
+        }
+    }
+    flush(&title, &mut body);
+    sections
+}
+
+/// Undoes line-break hyphenation and form feeds left by PDF extraction.
+fn join_wrapped(text: &str) -> String {
+    let text = text.replace('\u{c}', "\n").replace("\r\n", "\n");
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '-' && chars.peek() == Some(&'\n') {
+            let mut ahead = chars.clone();
+            ahead.next();
+            if ahead.peek().is_some_and(|n| n.is_lowercase()) {
+                chars.next();
+                continue;
+            }
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Heading heuristics for plain text: numbered headings, articles/chapters, short all-caps lines.
+fn text_heading(line: &str) -> Option<String> {
+    let t = line.trim();
+    if t.is_empty() || t.len() > 90 || t.ends_with('.') || t.ends_with(',') || t.ends_with(';') { return None; }
+    let lower = t.to_lowercase();
+    let keyword = ["capítulo", "capitulo", "seção", "secao", "título", "chapter", "section", "art.", "artigo"]
+        .iter()
+        .any(|k| lower.starts_with(k));
+    let numbered = {
+        let rest = t.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+        rest.len() < t.len() && t.chars().next().is_some_and(|c| c.is_ascii_digit()) && rest.starts_with([' ', ')'])
+            && rest.trim_start_matches([')', ' ']).chars().next().is_some_and(char::is_alphabetic)
+    };
+    let letters: Vec<char> = t.chars().filter(|c| c.is_alphabetic()).collect();
+    let all_caps = letters.len() >= 4 && letters.iter().all(|c| c.is_uppercase());
+    (keyword || numbered || all_caps).then(|| t.trim_end_matches(':').to_string())
+}
+
+fn html_to_markdown(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(open) = rest.find('<') {
+        out.push_str(&rest[..open]);
+        let Some(close) = rest[open..].find('>') else { break };
+        let tag = rest[open + 1..open + close].trim().to_ascii_lowercase();
+        rest = &rest[open + close + 1..];
+        let name: String = tag.trim_start_matches('/').chars().take_while(|c| c.is_ascii_alphanumeric()).collect();
+        if !tag.starts_with('/') && (name == "script" || name == "style") {
+            // Drop the element's content
+            let end = format!("</{}", name);
+            rest = rest.to_ascii_lowercase().find(&end).map(|i| &rest[i..]).unwrap_or("");
+            continue;
+        }
+        match name.as_str() {
+            "h1" | "h2" | "h3" | "h4" if !tag.starts_with('/') => out.push_str("\n# "),
+            "h1" | "h2" | "h3" | "h4" | "p" | "br" | "li" | "div" | "tr" | "section" => out.push('\n'),
+            _ => out.push(' '),
+        }
+    }
+    out.push_str(rest);
+    let decoded = out
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    decoded.lines().map(|l| l.split_whitespace().collect::<Vec<_>>().join(" ")).collect::<Vec<_>>().join("\n")
+}
+
+fn is_stopword(w: &str) -> bool { STOPWORDS.contains(&w) || w.chars().all(|c| c.is_ascii_digit()) || w.chars().count() < 3 }
+
+fn sentences(text: &str) -> impl Iterator<Item = &str> {
+    text.split(['.', ';', '!', '?', '\n']).map(str::trim).filter(|s| !s.is_empty())
+}
+
+/// Word n-grams (not starting or ending with a stopword) keyed by folded form, with the first surface form seen.
+fn ngrams(text: &str) -> HashMap<String, (String, usize)> {
+    let mut out: HashMap<String, (String, usize)> = HashMap::new();
+    for sentence in sentences(text) {
+        let words: Vec<&str> = sentence
+            .split(|c: char| !(c.is_alphanumeric() || c == '-'))
+            .map(|w| w.trim_matches('-'))
+            .filter(|w| !w.is_empty())
+            .collect();
+        for n in 1..=MAX_NGRAM {
+            for win in words.windows(n) {
+                let first = win[0].to_lowercase();
+                let last = win[n - 1].to_lowercase();
+                if is_stopword(&first) || is_stopword(&last) { continue; }
+                let surface = win.join(" ").to_lowercase();
+                let e = out.entry(fold(&surface)).or_insert((surface, 0));
+                e.1 += 1;
+            }
+        }
+    }
+    out
+}
+
+/// First sentence of `text` containing `term`.
+fn evidence(text: &str, term: &str) -> String {
+    let opts = MatchOptions::default();
+    sentences(text).find(|s| Matcher::new(s, &opts).find(term, &[]).is_some()).unwrap_or_default().to_string()
+}
+
+fn forbidden_candidates(text: &str) -> Vec<(String, String)> {
+    let mut out = Vec::new();
+    for sentence in sentences(text) {
+        let lower = sentence.to_lowercase();
+        for cue in PROHIBITION_CUES {
+            let Some(i) = lower.find(cue) else { continue };
+            let phrase: Vec<&str> = lower[i + cue.len()..]
+                .split(|c: char| !(c.is_alphanumeric() || c == '-'))
+                .filter(|w| !w.is_empty())
+                .skip_while(|w| is_stopword(w))
+                .take(FORBIDDEN_PHRASE_WORDS)
+                .collect();
+            let mut phrase = phrase;
+            while phrase.last().is_some_and(|w| is_stopword(w)) { phrase.pop(); }
+            if !phrase.is_empty() {
+                out.push((phrase.join(" "), sentence.to_string()));
+                break;
+            }
+        }
+    }
+    out
+}
+
+#[derive(Deserialize)]
+struct Suggestion {
+    #[serde(default)]
+    expected_terms: Vec<String>,
+    #[serde(default)]
+    forbidden_terms: Vec<String>,
+}
+
+fn suggest(provider: &dyn LlmProvider, section: &Section, max_terms: usize) -> Option<Suggestion> {
+    let prompt = Prompt {
+        text: format!(
+            "From the regulatory text below, list up to {} key terms an answer on this topic must mention, and terms or claims it must not contain. \
+             Use the text's own wording and language. Reply with JSON only: {{\"expected_terms\": [...], \"forbidden_terms\": [...]}}\n\nTopic: {}\n\n{}",
+            max_terms, section.title, section.body
+        ),
+    };
+    let reply = provider.generate(&prompt).ok()?.text;
+    let (start, end) = (reply.find('{')?, reply.rfind('}')?);
+    serde_json::from_str(&reply[start..=end]).ok()
+}
+
+/// Builds a draft pack from segmented sections.
+pub fn draft(sections: &[Section], options: &IngestOptions) -> Draft {
+    let sections: Vec<&Section> = sections.iter().filter(|s| s.body.split_whitespace().count() >= MIN_SECTION_WORDS).collect();
+    let grams: Vec<HashMap<String, (String, usize)>> = sections.iter().map(|s| ngrams(&s.body)).collect();
+    let mut df: HashMap<&str, usize> = HashMap::new();
+    for g in &grams {
+        for k in g.keys() { *df.entry(k.as_str()).or_default() += 1; }
+    }
+    let n = sections.len() as f64;
+
+    let mut topics: Vec<Guideline> = Vec::new();
+    let mut review = Vec::new();
+    for (section, grams) in sections.iter().zip(&grams) {
+        let mut scored: Vec<(&String, &String, f64)> = grams
+            .iter()
+            .filter(|(_, (_, c))| *c >= options.min_count)
+            .map(|(k, (surface, c))| {
+                let idf = ((n + 1.0) / (df[k.as_str()] as f64 + 1.0)).ln() + 1.0;
+                // Longer phrases are more specific; favour them over their parts
+                let len = surface.split_whitespace().count() as f64;
+                (k, surface, *c as f64 * idf * (1.0 + 0.5 * (len - 1.0)))
+            })
+            .collect();
+        scored.sort_by(|a, b| b.2.total_cmp(&a.2).then_with(|| a.1.cmp(b.1)));
+
+        let mut expected: Vec<String> = Vec::new();
+        for (key, surface, score) in scored {
+            if expected.len() >= options.max_terms { break; }
+            // Skip parts of phrases already chosen, and phrases containing a chosen term
+            if expected.iter().any(|e| {
+                let f = fold(e);
+                f.contains(key.as_str()) || key.contains(f.as_str())
+            }) {
+                continue;
+            }
+            review.push(ReviewNote {
+                topic: String::new(),
+                kind: "expected_term".into(),
+                term: surface.clone(),
+                origin: "tf-idf".into(),
+                score: (score * 100.0).round() / 100.0,
+                evidence: evidence(&section.body, surface),
+                source: section.source.clone(),
+            });
+            expected.push(surface.clone());
+        }
+
+        let mut forbidden: Vec<String> = Vec::new();
+        for (phrase, sentence) in forbidden_candidates(&section.body) {
+            if forbidden.contains(&phrase) { continue; }
+            review.push(ReviewNote {
+                topic: String::new(),
+                kind: "forbidden_term".into(),
+                term: phrase.clone(),
+                origin: "prohibition_cue".into(),
+                score: 1.0,
+                evidence: sentence,
+                source: section.source.clone(),
+            });
+            forbidden.push(phrase);
+        }
+
+        if let Some(s) = options.provider.as_ref().and_then(|p| suggest(p.as_ref(), section, options.max_terms)) {
+            let matcher = Matcher::new(&section.body, &MatchOptions::default());
+            let lists = [(s.expected_terms, "expected_term"), (s.forbidden_terms, "forbidden_term")];
+            for (terms, kind) in lists {
+                let target = if kind == "expected_term" { &mut expected } else { &mut forbidden };
+                // Only keep suggestions grounded in the source text
+                for t in terms.into_iter().filter(|t| matcher.find(t, &[]).is_some()) {
+                    if target.iter().any(|e| fold(e) == fold(&t)) { continue; }
+                    review.push(ReviewNote {
+                        topic: String::new(),
+                        kind: kind.into(),
+                        term: t.clone(),
+                        origin: "provider".into(),
+                        score: 0.0,
+                        evidence: evidence(&section.body, &t),
+                        source: section.source.clone(),
+                    });
+                    target.push(t);
+                }
+            }
+        }
+
+        if expected.is_empty() && forbidden.is_empty() { continue; }
+        let mut topic = if section.title.is_empty() { expected.first().cloned().unwrap_or_else(|| "Sem título".into()) } else { section.title.clone() };
+        if topics.iter().any(|g| g.topic == topic) { topic = format!("{} ({})", topic, topics.len() + 1); }
+        for note in review.iter_mut().filter(|r| r.topic.is_empty()) { note.topic = topic.clone(); }
+        topics.push(Guideline {
+            topic,
+            expected_terms: expected,
+            required_terms: vec![],
+            term_weights: BTreeMap::new(),
+            weight: None,
+            forbidden_terms: forbidden,
+            forbidden_patterns: vec![],
+            must_not_claim: vec![],
+            synonyms: BTreeMap::new(),
+        });
+    }
+
+    let mut metadata = options.metadata.clone();
+    if metadata.description.is_none() {
+        let mut sources: Vec<&str> = sections.iter().map(|s| s.source.as_str()).collect();
+        sources.dedup();
+        metadata.description = Some(format!("Draft generated from {}; review before use.", sources.join(", ")));
+    }
+    Draft { pack: GuidelinePack { format: PACK_FORMAT.into(), metadata, topics, rules: vec![] }, review }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = "CAPÍTULO I\nUSO NA GRAVIDEZ\n\
+        A prescrição na gravidez exige consulta médica prévia. A categoria de risco deve constar na bula e a \
+        categoria de risco orienta a consulta médica. É vedado afirmar que o medicamento é seguro na gravidez.\n\
+        2. Dosagem pediátrica\n\
+        A dosagem pediátrica depende do peso corporal. Ajustes de dosagem pediátrica exigem peso corporal atualizado \
+        e acompanhamento do pediatra responsável.\n";
+
+    #[test]
+    fn segments_text_and_proposes_terms() {
+        let sections = segment(DOC, SourceFormat::Text, "rdc.txt");
+        assert_eq!(sections.iter().map(|s| s.title.as_str()).collect::<Vec<_>>(), vec!["USO NA GRAVIDEZ", "2. Dosagem pediátrica"]);
+
+        let d = draft(&sections, &IngestOptions::default());
+        assert_eq!(d.pack.topics.len(), 2);
+        let gravidez = &d.pack.topics[0];
+        assert!(gravidez.expected_terms.contains(&"consulta médica".to_string()), "{:?}", gravidez.expected_terms);
+        assert!(gravidez.expected_terms.contains(&"categoria de risco".to_string()));
+        assert_eq!(gravidez.forbidden_terms, vec!["afirmar que o medicamento é seguro".to_string()]);
+        assert!(d.pack.topics[1].expected_terms.contains(&"dosagem pediátrica".to_string()));
+        assert!(d.review.iter().all(|r| !r.topic.is_empty() && !r.evidence.is_empty()));
+        // The draft is a loadable pack
+        GuidelinePack::parse(&serde_json::to_string(&d.pack).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn html_and_markdown_headings() {
+        let html = "<html><style>h1{}</style><h2>Advertências</h2><p>Texto &amp; mais</p><script>x()</script><h2>Dose</h2><p>Uma</p></html>";
+        let s = segment(html, SourceFormat::Html, "a.html");
+        assert_eq!(s.len(), 2);
+        assert_eq!((s[0].title.as_str(), s[0].body.as_str()), ("Advertências", "Texto & mais"));
+        let md = segment("# A\nx\n## B\ny", SourceFormat::Markdown, "a.md");
+        assert_eq!(md[1].title, "B");
+        assert_eq!(join_wrapped("contra-\nindicado"), "contraindicado");
+    }
+}
@@ -1,19 +1,62 @@
 use anyhow::Result;
-use panther_domain::entities::Prompt;
-use panther_domain::ports::LlmProvider;
+use async_trait::async_trait;
+use futures::stream::{FuturesUnordered, Stream, StreamExt};
+use panther_core::budget::{estimate_tokens, BudgetCtl, BudgetManager, Reservation, SpendContext};
+use panther_core::context::ContextPolicy;
+use panther_domain::entities::{Completion, Prompt, SamplingParams};
+use panther_domain::errors::PantherError;
+use panther_domain::ports::{LlmProvider, LlmProviderAsync};
 use serde::{Deserialize, Serialize};
-use std::fs;
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::task;
 
-// Explicit result alias to help type inference in tooling (rust-analyzer)
-type VRes = anyhow::Result<ValidationResult>;
+pub use tokio_util::sync::CancellationToken;
+
+pub mod anchor;
+pub mod history;
+pub mod ingest;
+pub mod judge;
+pub mod matching;
+pub mod pack;
+pub mod proof;
+pub mod regression;
+pub mod scoring;
+pub mod secrets;
+pub mod stats;
+
+use judge::{JudgeConfig, JudgeSummary};
+use pack::GuidelinePack;
+use scoring::{GuidelineScore, ScoringConfig, TopicScore, Violation};
+use stats::{SamplingConfig, ScoreStats};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Guideline {
     pub topic: String,
     pub expected_terms: Vec<String>,
+    /// Terms that must appear; a missing one caps the score (see `ScoringConfig::required_cap`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required_terms: Vec<String>,
+    /// Per-term weights (default 1.0).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub term_weights: BTreeMap<String, f64>,
+    /// Weight in the overall score (default: the guideline's total term weight).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight: Option<f64>,
+    /// Terms that must not appear; each match is a penalty (see `ScoringConfig::violation_penalty`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub forbidden_terms: Vec<String>,
+    /// Case-insensitive regexes that must not match.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub forbidden_patterns: Vec<String>,
+    /// Statements the response must not make; negated mentions ("not safe ...") are allowed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub must_not_claim: Vec<String>,
+    /// Alternatives accepted for a term, e.g. `{"médico": ["obstetra"]}`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub synonyms: BTreeMap<String, Vec<String>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,102 +67,422 @@ pub struct ValidationResult {
     pub latency_ms: i64,
     pub cost: Option<f64>,
     pub raw_text: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub guideline_scores: Vec<GuidelineScore>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub topic_scores: Vec<TopicScore>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub violations: Vec<Violation>,
+    /// Judge verdicts; when present `adherence_score` blends keyword and judge scores.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub judge: Option<JudgeSummary>,
+    /// Present when the provider was sampled more than once; `adherence_score` is then the mean.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<ScoreStats>,
+    /// Why the provider produced no answer; `raw_text` then holds the error message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorCategory>,
 }
 
+/// Failure class of a provider call, as reported in `ValidationResult.error`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    BudgetExceeded,
+    Cancelled,
+    ContextLengthExceeded,
+    Timeout,
+    RateLimit,
+    InvalidRequest,
+    UpstreamError,
+    NetworkError,
+    Unknown,
+}
+
+/// Validates with blocking providers; they run on tokio's blocking pool under the shared async core.
 pub struct LLMValidator {
-    guidelines: Vec<Guideline>,
-    providers: Vec<(String, Arc<dyn LlmProvider>)>,
+    core: Core,
 }
 
-impl LLMValidator {
-    pub fn from_path<P: AsRef<Path>>(path: P, providers: Vec<(String, Arc<dyn LlmProvider>)>) -> Result<Self> {
-        let text = fs::read_to_string(path)?;
-        let guidelines: Vec<Guideline> = serde_json::from_str(&text)?;
-        Ok(Self { guidelines, providers })
+/// Validates with async providers (blocking ones can be mixed in via [`blocking_provider`]).
+pub struct LLMValidatorAsync {
+    core: Core,
+}
+
+type AsyncProviders = Vec<(String, Arc<dyn LlmProviderAsync>)>;
+
+/// Runs a blocking provider on tokio's blocking pool.
+struct Blocking(Arc<dyn LlmProvider>);
+
+tokio::task_local! {
+    // Budget hold of the provider call being awaited, shared with the thread that serves it
+    static IN_FLIGHT: Option<Arc<Reservation>>;
+}
+
+/// Awaits a provider call with `reservation` held for it, and hands the reservation back once the
+/// call returns. A blocking provider cannot be cancelled: when a timeout or cancel drops the call,
+/// its thread keeps running and the hold is only released when that thread returns.
+pub(crate) async fn holding<T>(reservation: Option<Reservation>, call: impl std::future::Future<Output = T>) -> (T, Option<Reservation>) {
+    let held = reservation.map(Arc::new);
+    let out = IN_FLIGHT.scope(held.clone(), call).await;
+    // The blocking thread has dropped its share by the time its result arrives
+    (out, held.and_then(Arc::into_inner))
+}
+
+fn in_flight() -> Option<Arc<Reservation>> { IN_FLIGHT.try_with(Clone::clone).ok().flatten() }
+
+#[async_trait]
+impl LlmProviderAsync for Blocking {
+    async fn generate(&self, prompt: &Prompt) -> Result<Completion> {
+        let (p, prompt, held) = (self.0.clone(), prompt.clone(), in_flight());
+        task::spawn_blocking(move || {
+            let _held = held;
+            p.generate(&prompt)
+        })
+        .await?
     }
 
-    pub fn from_json_str(json: &str, providers: Vec<(String, Arc<dyn LlmProvider>)>) -> Result<Self> {
-        let guidelines: Vec<Guideline> = serde_json::from_str(json)?;
-        Ok(Self { guidelines, providers })
+    async fn generate_sampled(&self, prompt: &Prompt, params: &SamplingParams) -> Result<Completion> {
+        let (p, prompt, params, held) = (self.0.clone(), prompt.clone(), *params, in_flight());
+        task::spawn_blocking(move || {
+            let _held = held;
+            p.generate_sampled(&prompt, &params)
+        })
+        .await?
     }
 
-    pub async fn validate(&self, input_prompt: &str) -> Result<Vec<ValidationResult>> {
-        let expected: Vec<String> = self
-            .guidelines
-            .iter()
-            .flat_map(|g| g.expected_terms.iter().cloned())
-            .collect();
+    fn name(&self) -> &'static str { self.0.name() }
+    fn model(&self) -> Option<&str> { self.0.model() }
+}
+
+/// Adapts a blocking provider for [`LLMValidatorAsync`]. Its calls cannot be cancelled: a timeout
+/// or cancel stops waiting for them, but their budget stays reserved until they return.
+pub fn blocking_provider(provider: Arc<dyn LlmProvider>) -> Arc<dyn LlmProviderAsync> { Arc::new(Blocking(provider)) }
+
+fn wrap_blocking(providers: Vec<(String, Arc<dyn LlmProvider>)>) -> AsyncProviders {
+    providers.into_iter().map(|(label, p)| (label, blocking_provider(p))).collect()
+}
 
-        let mut tasks: Vec<tokio::task::JoinHandle<VRes>> = Vec::new();
-        for (label, prov) in &self.providers {
-            let label = label.clone();
-            let prov = prov.clone();
-            let prompt = Prompt { text: input_prompt.to_string() };
-            let expected_terms = expected.clone();
-            tasks.push(task::spawn_blocking(move || -> VRes {
-                let start = now_ms();
-                let res = prov.generate(&prompt);
-                let end = now_ms();
-                match res {
-                    Ok(c) => {
-                        let (score, missing) = score_text(&c.text, &expected_terms);
-                        Ok::<ValidationResult, anyhow::Error>(ValidationResult {
-                            provider_name: label,
-                            adherence_score: score,
-                            missing_terms: missing,
-                            latency_ms: end - start,
-                            cost: None,
-                            raw_text: c.text,
-                        })
-                    }
-                    Err(e) => {
-                        let msg = e.to_string();
-                        let cat = classify_error(&msg);
-                        let raw = serde_json::json!({"error": {"category": cat, "message": msg}}).to_string();
-                        Ok::<ValidationResult, anyhow::Error>(ValidationResult {
-                            provider_name: label,
-                            adherence_score: 0.0,
-                            missing_terms: expected_terms,
-                            latency_ms: end - start,
-                            cost: None,
-                            raw_text: raw,
-                        })
-                    }
-                }
-            }));
+/// Configuration and execution shared by both validators.
+struct Core {
+    guidelines: Vec<Guideline>,
+    providers: AsyncProviders,
+    budget: Option<BudgetCtl>,
+    context: Option<ContextPolicy>,
+    scoring: Arc<ScoringConfig>,
+    judges: AsyncProviders,
+    judge: Arc<JudgeConfig>,
+    sampling: Arc<SamplingConfig>,
+    timeout: Option<Duration>,
+    provider_timeouts: HashMap<String, Duration>,
+}
+
+fn error_result(label: String, msg: String, expected_terms: Vec<String>, latency_ms: i64) -> ValidationResult {
+    let cat = classify_error(&msg);
+    let raw = serde_json::json!({"error": {"category": cat, "message": msg}}).to_string();
+    ValidationResult {
+        provider_name: label,
+        adherence_score: 0.0,
+        missing_terms: expected_terms,
+        latency_ms,
+        cost: None,
+        raw_text: raw,
+        guideline_scores: Vec::new(),
+        topic_scores: Vec::new(),
+        violations: Vec::new(),
+        judge: None,
+        stats: None,
+        error: Some(cat),
+    }
+}
+
+/// Settings shared by every provider task of one `validate` call.
+#[derive(Clone)]
+struct RunCtx {
+    question: String,
+    expected: Vec<String>,
+    budget: Option<BudgetCtl>,
+    context: Option<ContextPolicy>,
+    guidelines: Arc<Vec<Guideline>>,
+    scoring: Arc<ScoringConfig>,
+    judge: Arc<JudgeConfig>,
+    sampling: Arc<SamplingConfig>,
+}
+
+/// A failed sample: error message and latency.
+type SampleErr = (String, i64);
+
+impl RunCtx {
+    fn new(
+        question: &str,
+        guidelines: &[Guideline],
+        budget: &Option<BudgetCtl>,
+        context: &Option<ContextPolicy>,
+        scoring: &Arc<ScoringConfig>,
+        judge: &Arc<JudgeConfig>,
+        sampling: &Arc<SamplingConfig>,
+    ) -> Self {
+        Self {
+            question: question.to_string(),
+            expected: scoring::all_terms(guidelines),
+            budget: budget.clone(),
+            context: context.clone(),
+            guidelines: Arc::new(guidelines.to_vec()),
+            scoring: scoring.clone(),
+            judge: judge.clone(),
+            sampling: sampling.clone(),
+        }
+    }
+
+    fn score(&self, text: &str) -> scoring::Scored { scoring::score_guidelines(text, &self.guidelines, &self.scoring) }
+
+    #[allow(clippy::too_many_arguments)]
+    fn build(
+        &self,
+        label: &str,
+        prompt: &Prompt,
+        reservation: Option<Reservation>,
+        text: String,
+        latency_ms: i64,
+        scored: scoring::Scored,
+        judged: Option<JudgeSummary>,
+    ) -> ValidationResult {
+        ValidationResult {
+            cost: reservation.and_then(|r| r.settle(estimate_tokens(&prompt.text), estimate_tokens(&text)).ok()),
+            provider_name: label.to_string(),
+            adherence_score: judged.as_ref().map_or(scored.overall, |j| judge::combine(j, &self.judge)),
+            missing_terms: scored.missing_terms,
+            latency_ms,
+            raw_text: text,
+            guideline_scores: scored.guidelines,
+            topic_scores: scored.topics,
+            violations: scored.violations,
+            judge: judged,
+            stats: None,
+            error: None,
         }
+    }
 
-        let mut results = Vec::new();
-        for t in tasks {
-            match t.await {
-                Ok(Ok(vr)) => results.push(vr),
-                Ok(Err(e)) => eprintln!("task error: {}", e),
-                Err(e) => eprintln!("join error: {}", e),
+    /// Folds repeated samples into one result: mean score, the median sample's details, summed cost.
+    fn aggregate(&self, label: String, samples: Vec<std::result::Result<ValidationResult, SampleErr>>) -> ValidationResult {
+        let total = samples.len();
+        let mut last_err = None;
+        let mut ok = Vec::new();
+        for s in samples {
+            match s {
+                Ok(r) => ok.push(r),
+                Err(e) => last_err = Some(e),
             }
         }
+        if ok.is_empty() {
+            let (msg, latency) = last_err.unwrap_or_else(|| ("no samples".into(), 0));
+            return error_result(label, msg, self.expected.clone(), latency);
+        }
+        if total == 1 { return ok.remove(0); }
+        let scores: Vec<f64> = ok.iter().map(|r| r.adherence_score).collect();
+        let stats = stats::summarize(&scores, total - ok.len());
+        let cost = ok.iter().filter_map(|r| r.cost).reduce(|a, b| a + b);
+        let latency_ms = ok.iter().map(|r| r.latency_ms).sum::<i64>() / ok.len() as i64;
+        ok.sort_by(|a, b| a.adherence_score.total_cmp(&b.adherence_score));
+        let mut rep = ok.swap_remove(ok.len() / 2);
+        rep.adherence_score = stats.mean;
+        rep.cost = cost;
+        rep.latency_ms = latency_ms;
+        rep.stats = Some(stats);
+        rep
+    }
+}
+
+impl Core {
+    fn new(pack: &GuidelinePack, providers: AsyncProviders) -> Self {
+        Self {
+            guidelines: pack.guidelines(),
+            providers,
+            budget: None,
+            context: None,
+            scoring: Arc::default(),
+            judges: Vec::new(),
+            judge: Arc::default(),
+            sampling: Arc::default(),
+            timeout: None,
+            provider_timeouts: HashMap::new(),
+        }
+    }
+
+    /// One result per provider in completion order; ends early once `cancel` fires.
+    fn stream<'a>(&'a self, input_prompt: &str, cancel: CancellationToken) -> impl Stream<Item = ValidationResult> + Send + 'a {
+        let run = RunCtx::new(input_prompt, &self.guidelines, &self.budget, &self.context, &self.scoring, &self.judge, &self.sampling);
+        let tasks: FuturesUnordered<_> = self
+            .providers
+            .iter()
+            .map(|(label, prov)| {
+                let timeout = self.provider_timeouts.get(label).copied().or(self.timeout);
+                validate_provider(run.clone(), label.clone(), prov.clone(), &self.judges, timeout)
+            })
+            .collect();
+        tasks.take_until(cancel.cancelled_owned())
+    }
+
+    async fn validate(&self, input_prompt: &str, cancel: &CancellationToken) -> Result<Vec<ValidationResult>> {
+        let mut results: Vec<ValidationResult> = self.stream(input_prompt, cancel.clone()).collect().await;
+        if results.len() < self.providers.len() {
+            return Err(PantherError::Cancelled(format!("{} of {} providers finished", results.len(), self.providers.len())).into());
+        }
         results.sort_by(|a, b| b.adherence_score.partial_cmp(&a.adherence_score).unwrap_or(std::cmp::Ordering::Equal));
         Ok(results)
     }
 }
 
+async fn validate_provider(
+    run: RunCtx,
+    label: String,
+    prov: Arc<dyn LlmProviderAsync>,
+    judges: &[(String, Arc<dyn LlmProviderAsync>)],
+    timeout: Option<Duration>,
+) -> ValidationResult {
+    let mut samples = Vec::new();
+    for i in 0..run.sampling.samples() {
+        let start = now_ms();
+        let call = async {
+            let prompt = panther_core::fit_prompt_async(&run.context, &run.budget, prov.as_ref(), Prompt { text: run.question.clone() }).await?;
+            let params = run.sampling.params(i);
+            let reservation = BudgetCtl::admit_opt(&run.budget, &label, &prompt.text, None)?;
+            let (c, reservation) = holding(reservation, prov.generate_sampled(&prompt, &params)).await;
+            Ok::<_, anyhow::Error>((prompt, reservation, c?))
+        };
+        let res = match timeout {
+            Some(d) => tokio::time::timeout(d, call)
+                .await
+                .unwrap_or_else(|_| Err(anyhow::anyhow!("timeout: no response within {} ms", d.as_millis()))),
+            None => call.await,
+        };
+        let latency = now_ms() - start;
+        samples.push(match res {
+            Ok((prompt, reservation, c)) => {
+                let scored = run.score(&c.text);
+                let judged = if judges.is_empty() {
+                    None
+                } else {
+                    Some(judge::run_async(judges, &run.question, &c.text, &run.guidelines, &run.judge, scored.overall, &run.budget, &run.context).await)
+                };
+                Ok(run.build(&label, &prompt, reservation, c.text, latency, scored, judged))
+            }
+            Err(e) => Err((e.to_string(), latency)),
+        });
+    }
+    run.aggregate(label, samples)
+}
+
+impl LLMValidator {
+    pub fn from_path<P: AsRef<Path>>(path: P, providers: Vec<(String, Arc<dyn LlmProvider>)>) -> Result<Self> {
+        Ok(Self::from_pack(&GuidelinePack::load(path)?, providers))
+    }
+
+    /// Accepts a guideline pack or a legacy guideline array, as JSON or YAML.
+    pub fn from_json_str(json: &str, providers: Vec<(String, Arc<dyn LlmProvider>)>) -> Result<Self> {
+        Ok(Self::from_pack(&GuidelinePack::parse(json)?, providers))
+    }
+
+    pub fn from_pack(pack: &GuidelinePack, providers: Vec<(String, Arc<dyn LlmProvider>)>) -> Self {
+        Self { core: Core::new(pack, wrap_blocking(providers)) }
+    }
+
+    /// Grades each response with the given judge providers and blends their score in (see `JudgeConfig::weight`).
+    pub fn with_judges(mut self, judges: Vec<(String, Arc<dyn LlmProvider>)>, config: JudgeConfig) -> Self {
+        self.core.judges = wrap_blocking(judges);
+        self.core.judge = Arc::new(config);
+        self
+    }
+}
+
+impl LLMValidatorAsync {
+    pub fn from_path<P: AsRef<Path>>(path: P, providers: AsyncProviders) -> Result<Self> {
+        Ok(Self::from_pack(&GuidelinePack::load(path)?, providers))
+    }
+
+    /// Accepts a guideline pack or a legacy guideline array, as JSON or YAML.
+    pub fn from_json_str(json: &str, providers: AsyncProviders) -> Result<Self> {
+        Ok(Self::from_pack(&GuidelinePack::parse(json)?, providers))
+    }
+
+    pub fn from_pack(pack: &GuidelinePack, providers: AsyncProviders) -> Self {
+        Self { core: Core::new(pack, providers) }
+    }
+
+    /// Grades each response with the given judge providers and blends their score in (see `JudgeConfig::weight`).
+    pub fn with_judges(mut self, judges: AsyncProviders, config: JudgeConfig) -> Self {
+        self.core.judges = judges;
+        self.core.judge = Arc::new(config);
+        self
+    }
+}
+
+// Builders and entry points shared by both validators; they only differ in how providers are given
+macro_rules! validator_api {
+    ($($validator:ty),+) => {$(
+        impl $validator {
+            /// Rejects providers over a hard budget limit and fills `ValidationResult.cost`.
+            pub fn with_budget(mut self, manager: Arc<BudgetManager>, ctx: SpendContext) -> Self {
+                self.core.budget = Some(BudgetCtl::new(manager, ctx));
+                self
+            }
+
+            /// Fits the prompt to each provider's context window before sending.
+            pub fn with_context_policy(mut self, policy: ContextPolicy) -> Self {
+                self.core.context = Some(policy);
+                self
+            }
+
+            /// Topic weights and required-term cap used to aggregate guideline scores.
+            pub fn with_scoring(mut self, config: ScoringConfig) -> Self {
+                self.core.scoring = Arc::new(config);
+                self
+            }
+
+            /// Calls each provider `n_samples` times and reports score statistics (see `stats`).
+            pub fn with_sampling(mut self, config: SamplingConfig) -> Self {
+                self.core.sampling = Arc::new(config);
+                self
+            }
+
+            /// Time limit for each provider call; a call that runs over yields an error result with category `timeout`.
+            /// Blocking providers are not interrupted: their budget stays reserved until the call returns.
+            pub fn with_timeout(mut self, timeout: Duration) -> Self {
+                self.core.timeout = Some(timeout);
+                self
+            }
+
+            /// Overrides [`Self::with_timeout`] for one provider label.
+            pub fn with_provider_timeout(mut self, label: &str, timeout: Duration) -> Self {
+                self.core.provider_timeouts.insert(label.to_string(), timeout);
+                self
+            }
+
+            /// Waits for every provider and returns the results sorted by score.
+            pub async fn validate(&self, input_prompt: &str) -> Result<Vec<ValidationResult>> {
+                self.core.validate(input_prompt, &CancellationToken::new()).await
+            }
+
+            /// Like [`Self::validate`], but fails with `PantherError::Cancelled` when `cancel` fires first.
+            pub async fn validate_with_cancel(&self, input_prompt: &str, cancel: &CancellationToken) -> Result<Vec<ValidationResult>> {
+                self.core.validate(input_prompt, cancel).await
+            }
+
+            /// Yields each provider's result as soon as it completes; providers still running when `cancel` fires are dropped.
+            pub fn validate_stream<'a>(&'a self, input_prompt: &str, cancel: CancellationToken) -> impl Stream<Item = ValidationResult> + Send + 'a {
+                self.core.stream(input_prompt, cancel)
+            }
+        }
+    )+};
+}
+
+validator_api!(LLMValidator, LLMValidatorAsync);
+
 fn now_ms() -> i64 {
     use std::time::{SystemTime, UNIX_EPOCH};
     SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as i64
 }
 
-fn score_text(text: &str, expected_terms: &[String]) -> (f64, Vec<String>) {
-    if expected_terms.is_empty() { return (100.0, vec![]); }
-    let lower = text.to_lowercase();
-    let mut missing = Vec::new();
-    for term in expected_terms {
-        if !lower.contains(&term.to_lowercase()) { missing.push(term.clone()); }
-    }
-    let total = expected_terms.len() as f64;
-    let penalty = (missing.len() as f64) * (100.0 / total);
-    let score = (100.0 - penalty).clamp(0.0, 100.0);
-    (score, missing)
-}
-
 pub struct ProviderFactory;
 
 impl ProviderFactory {
@@ -172,7 +535,7 @@ impl ProviderFactory {
 pub struct ProviderFactoryAsync;
 
 impl ProviderFactoryAsync {
-    pub fn openai_from_env() -> Result<(String, Arc<dyn panther_domain::ports::LlmProviderAsync>)> {
+    pub fn openai_from_env() -> Result<(String, Arc<dyn LlmProviderAsync>)> {
         #[cfg(feature = "openai-async")]
         {
             let api_key = std::env::var("PANTHER_OPENAI_API_KEY")?;
@@ -187,7 +550,7 @@ impl ProviderFactoryAsync {
         }
     }
 
-    pub fn ollama_from_env() -> Result<(String, Arc<dyn panther_domain::ports::LlmProviderAsync>)> {
+    pub fn ollama_from_env() -> Result<(String, Arc<dyn LlmProviderAsync>)> {
         #[cfg(feature = "ollama-async")]
         {
             let base = std::env::var("PANTHER_OLLAMA_BASE").unwrap_or_else(|_| "http://localhost:11434".to_string());
@@ -201,7 +564,7 @@ impl ProviderFactoryAsync {
         }
     }
 
-    pub fn anthropic_from_env() -> Result<(String, Arc<dyn panther_domain::ports::LlmProviderAsync>)> {
+    pub fn anthropic_from_env() -> Result<(String, Arc<dyn LlmProviderAsync>)> {
         #[cfg(feature = "anthropic-async")]
         {
             let api_key = std::env::var("PANTHER_ANTHROPIC_API_KEY")?;
@@ -218,220 +581,253 @@ impl ProviderFactoryAsync {
     }
 }
 
-pub struct LLMValidatorAsync {
-    guidelines: Vec<Guideline>,
-    providers: Vec<(String, Arc<dyn panther_domain::ports::LlmProviderAsync>)>,
-}
-
-impl LLMValidatorAsync {
-    pub fn from_json_str(json: &str, providers: Vec<(String, Arc<dyn panther_domain::ports::LlmProviderAsync>)>) -> Result<Self> {
-        let guidelines: Vec<Guideline> = serde_json::from_str(json)?;
-        Ok(Self { guidelines, providers })
-    }
-
-    pub async fn validate(&self, input_prompt: &str) -> Result<Vec<ValidationResult>> {
-        use futures::future::join_all;
-        let expected: Vec<String> = self
-            .guidelines
-            .iter()
-            .flat_map(|g| g.expected_terms.iter().cloned())
-            .collect();
-        let prompt = Prompt { text: input_prompt.to_string() };
-        let mut futs = Vec::new();
-        for (label, prov) in &self.providers {
-            let label = label.clone();
-            let prov = prov.clone();
-            let expected_terms = expected.clone();
-            let prompt = prompt.clone();
-            futs.push(async move {
-                let start = now_ms();
-                let res = prov.generate(&prompt).await;
-                let end = now_ms();
-                match res {
-                    Ok(c) => {
-                        let (score, missing) = score_text(&c.text, &expected_terms);
-                        Ok::<ValidationResult, anyhow::Error>(ValidationResult {
-                            provider_name: label,
-                            adherence_score: score,
-                            missing_terms: missing,
-                            latency_ms: end - start,
-                            cost: None,
-                            raw_text: c.text,
-                        })
-                    }
-                    Err(e) => {
-                        let msg = e.to_string();
-                        let cat = classify_error(&msg);
-                        let raw = serde_json::json!({"error": {"category": cat, "message": msg}}).to_string();
-                        Ok::<ValidationResult, anyhow::Error>(ValidationResult {
-                            provider_name: label,
-                            adherence_score: 0.0,
-                            missing_terms: expected_terms,
-                            latency_ms: end - start,
-                            cost: None,
-                            raw_text: raw,
-                        })
-                    }
-                }
-            });
-        }
-        let mut results = Vec::new();
-        for r in join_all(futs).await { if let Ok(v) = r { results.push(v) } }
-        results.sort_by(|a, b| b.adherence_score.partial_cmp(&a.adherence_score).unwrap_or(std::cmp::Ordering::Equal));
-        Ok(results)
-    }
-}
-
-fn classify_error(msg: &str) -> &'static str {
+fn classify_error(msg: &str) -> ErrorCategory {
     let m = msg.to_ascii_lowercase();
-    if m.starts_with("timeout:") || m.contains("timeout") {
-        "timeout"
+    if m.starts_with("budget_exceeded:") {
+        ErrorCategory::BudgetExceeded
+    } else if m.starts_with("cancelled:") {
+        ErrorCategory::Cancelled
+    } else if m.starts_with("context_length_exceeded:") {
+        ErrorCategory::ContextLengthExceeded
+    } else if m.starts_with("timeout:") || m.contains("timeout") {
+        ErrorCategory::Timeout
     } else if m.starts_with("rate_limit:") || m.contains("429") {
-        "rate_limit"
+        ErrorCategory::RateLimit
     } else if m.starts_with("invalid_request:") || m.contains("400") || m.contains("422") {
-        "invalid_request"
+        ErrorCategory::InvalidRequest
     } else if m.starts_with("upstream_error:") || m.contains("5xx") || m.contains("500") {
-        "upstream_error"
+        ErrorCategory::UpstreamError
     } else if m.contains("network_error") {
-        "network_error"
+        ErrorCategory::NetworkError
     } else {
-        "unknown"
+        ErrorCategory::Unknown
     }
 }
 
-// ---- Proofs (Stage 1: offline) ----
-pub mod proof {
+#[cfg(test)]
+mod tests {
     use super::*;
-    use sha3::{Digest, Sha3_512};
+    use panther_domain::entities::Completion;
 
-    #[derive(Debug, Clone, Serialize, Deserialize)]
-    pub struct ProofContext {
-        pub sdk_version: String,
-        pub salt: Option<String>,
+    struct EchoProvider;
+
+    impl LlmProvider for EchoProvider {
+        fn generate(&self, prompt: &Prompt) -> Result<Completion> {
+            Ok(Completion { text: format!("{} gestante", prompt.text), model: None })
+        }
     }
 
-    #[derive(Debug, Clone, Serialize, Deserialize)]
-    pub struct Proof {
-        pub scheme: String,          // e.g., "panther-proof-v1"
-        pub input_hash: String,      // hex(sha3_512(canonical(prompt, providers, guidelines, salt?)))
-        pub results_hash: String,    // hex(sha3_512(canonical(results)))
-        pub combined_hash: String,   // hex(sha3_512(input_hash || results_hash))
-        pub guidelines_hash: String, // hex(sha3_512(canonical(guidelines)))
-        pub providers_hash: String,  // hex(sha3_512(canonical(providers)))
-        pub timestamp_ms: i64,
-        pub sdk_version: String,
-        pub salt_present: bool,
-    }
-
-    fn canonicalize(value: &serde_json::Value) -> serde_json::Value {
-        match value {
-            serde_json::Value::Object(map) => {
-                let mut entries: Vec<_> = map.iter().collect();
-                entries.sort_by(|a, b| a.0.cmp(b.0));
-                let mut new = serde_json::Map::new();
-                for (k, v) in entries {
-                    new.insert(k.clone(), canonicalize(v));
-                }
-                serde_json::Value::Object(new)
-            }
-            serde_json::Value::Array(arr) => {
-                let v = arr.iter().map(canonicalize).collect::<Vec<_>>();
-                serde_json::Value::Array(v)
-            }
-            _ => value.clone(),
+    #[tokio::test]
+    async fn budget_fills_cost_then_rejects() {
+        let manager = BudgetManager::from_json_str(
+            Arc::new(panther_storage::InMemoryStore::default()),
+            r#"{"budgets":[{"scope":"tenant","key":"acme","max_usd":0.003}],
+                "pricing":[{"provider":"echo","usd_per_1k_in":1.0,"usd_per_1k_out":1.0}],
+                "output_tokens":2}"#,
+        )
+        .unwrap();
+        let ctx = SpendContext { tenant: Some("acme".into()), run: None };
+        let guidelines = r#"[{"topic":"t","expected_terms":["gestante"]}]"#;
+        let providers: Vec<(String, Arc<dyn LlmProvider>)> = vec![("echo".into(), Arc::new(EchoProvider))];
+        let v = LLMValidator::from_json_str(guidelines, providers).unwrap().with_budget(Arc::new(manager), ctx);
+
+        let first = v.validate("hi").await.unwrap();
+        assert!((first[0].cost.unwrap() - 0.003).abs() < 1e-9);
+        assert_eq!(first[0].adherence_score, 100.0);
+
+        let second = v.validate("hi").await.unwrap();
+        assert_eq!(second[0].adherence_score, 0.0);
+        assert_eq!(second[0].error, Some(ErrorCategory::BudgetExceeded));
+        assert!(second[0].raw_text.contains("\"budget_exceeded\""));
+    }
+
+    struct FixedJudge(&'static str);
+
+    impl LlmProvider for FixedJudge {
+        fn generate(&self, _prompt: &Prompt) -> Result<Completion> { Ok(Completion { text: self.0.into(), model: None }) }
+    }
+
+    #[tokio::test]
+    async fn judge_score_blends_with_keywords() {
+        let guidelines = r#"[{"topic":"t","expected_terms":["gestante"]}]"#;
+        let providers: Vec<(String, Arc<dyn LlmProvider>)> = vec![("echo".into(), Arc::new(EchoProvider))];
+        let judges: Vec<(String, Arc<dyn LlmProvider>)> = vec![
+            ("j1".into(), Arc::new(FixedJudge(r#"{"score":20,"criteria":[{"criterion":"t","pass":false}]}"#))),
+            ("j2".into(), Arc::new(FixedJudge("not json"))),
+        ];
+        let v = LLMValidator::from_json_str(guidelines, providers)
+            .unwrap()
+            .with_judges(judges, JudgeConfig { rubric: None, weight: Some(0.25) });
+        let r = &v.validate("hi").await.unwrap()[0];
+        let j = r.judge.as_ref().unwrap();
+        assert_eq!((j.keyword_score, j.score), (100.0, Some(20.0)));
+        assert!(j.verdicts[1].error.is_some());
+        assert_eq!(j.prompt_version, judge::JUDGE_PROMPT_VERSION);
+        assert!((r.adherence_score - 80.0).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn judges_are_charged_to_the_budget() {
+        let store = Arc::new(panther_storage::InMemoryStore::default());
+        let manager = BudgetManager::from_json_str(
+            store,
+            r#"{"budgets":[{"scope":"provider","key":"j2","max_tokens":10}],"output_tokens":5}"#,
+        )
+        .unwrap();
+        let guidelines = r#"[{"topic":"t","expected_terms":["gestante"]}]"#;
+        let providers: Vec<(String, Arc<dyn LlmProvider>)> = vec![("echo".into(), Arc::new(EchoProvider))];
+        let verdict = r#"{"score":60,"criteria":[{"criterion":"t","pass":true}]}"#;
+        let judges: Vec<(String, Arc<dyn LlmProvider>)> = vec![("j1".into(), Arc::new(FixedJudge(verdict))), ("j2".into(), Arc::new(FixedJudge(verdict)))];
+        let manager = Arc::new(manager);
+        let v = LLMValidator::from_json_str(guidelines, providers)
+            .unwrap()
+            .with_judges(judges, JudgeConfig::default())
+            .with_budget(manager.clone(), SpendContext::default());
+        let r = &v.validate("hi").await.unwrap()[0];
+        let j = r.judge.as_ref().unwrap();
+        // the judge prompt alone is over j2's cap; j1 has no budget
+        assert_eq!(j.verdicts[0].score, Some(60.0));
+        assert!(j.verdicts[1].error.as_deref().unwrap().starts_with("budget_exceeded:"));
+        assert_eq!(manager.remaining("j2", &SpendContext::default()).unwrap()[0].remaining_tokens, Some(10));
+    }
+
+    /// Answers correctly only for even seeds.
+    struct SeededProvider;
+
+    impl LlmProvider for SeededProvider {
+        fn generate(&self, prompt: &Prompt) -> Result<Completion> { self.generate_sampled(prompt, &Default::default()) }
+        fn generate_sampled(&self, _prompt: &Prompt, params: &panther_domain::entities::SamplingParams) -> Result<Completion> {
+            let text = if params.seed.unwrap_or(0).is_multiple_of(2) { "gestante" } else { "nada" };
+            Ok(Completion { text: text.into(), model: None })
         }
     }
 
-    fn hash_json(value: &serde_json::Value) -> String {
-        let canon = canonicalize(value);
-        let bytes = serde_json::to_vec(&canon).unwrap_or_default();
-        let mut hasher = Sha3_512::new();
-        hasher.update(&bytes);
-        let out = hasher.finalize();
-        hex::encode(out)
-    }
-
-    fn hash_concat_hex(a_hex: &str, b_hex: &str) -> String {
-        let mut hasher = Sha3_512::new();
-        hasher.update(a_hex.as_bytes());
-        hasher.update(b_hex.as_bytes());
-        let out = hasher.finalize();
-        hex::encode(out)
-    }
-
-    pub fn compute_proof(
-        prompt: &str,
-        providers_json: &str,
-        guidelines_json: &str,
-        results_json: &str,
-        ctx: &ProofContext,
-    ) -> anyhow::Result<Proof> {
-        let providers_val: serde_json::Value = serde_json::from_str(providers_json).unwrap_or(serde_json::Value::Null);
-        let guidelines_val: serde_json::Value = serde_json::from_str(guidelines_json).unwrap_or(serde_json::Value::Null);
-        let results_val: serde_json::Value = serde_json::from_str(results_json).unwrap_or(serde_json::Value::Null);
-
-        let providers_hash = hash_json(&providers_val);
-        let guidelines_hash = hash_json(&guidelines_val);
-        let results_hash = hash_json(&results_val);
-
-        // input bundle: prompt + providers + guidelines + optional salt
-        let input_bundle = serde_json::json!({
-            "prompt": prompt,
-            "providers": providers_val,
-            "guidelines": guidelines_val,
-            "salt": ctx.salt,
-        });
-        let input_hash = hash_json(&input_bundle);
-        let combined_hash = hash_concat_hex(&input_hash, &results_hash);
-        let proof = Proof {
-            scheme: "panther-proof-v1".to_string(),
-            input_hash,
-            results_hash,
-            combined_hash,
-            guidelines_hash,
-            providers_hash,
-            timestamp_ms: now_ms(),
-            sdk_version: ctx.sdk_version.clone(),
-            salt_present: ctx.salt.is_some(),
-        };
-        Ok(proof)
-    }
-
-    pub fn verify_proof_local(
-        expected: &Proof,
-        prompt: &str,
-        providers_json: &str,
-        guidelines_json: &str,
-        results_json: &str,
-        salt: Option<String>,
-    ) -> bool {
-        let ctx = ProofContext { sdk_version: expected.sdk_version.clone(), salt };
-        if let Ok(p) = compute_proof(prompt, providers_json, guidelines_json, results_json, &ctx) {
-            p.combined_hash == expected.combined_hash
-        } else {
-            false
+    #[tokio::test]
+    async fn samples_report_mean_and_interval() {
+        let guidelines = r#"[{"topic":"t","expected_terms":["gestante"]}]"#;
+        let providers: Vec<(String, Arc<dyn LlmProvider>)> =
+            vec![("seeded".into(), Arc::new(SeededProvider)), ("echo".into(), Arc::new(EchoProvider))];
+        let v = LLMValidator::from_json_str(guidelines, providers)
+            .unwrap()
+            .with_sampling(SamplingConfig { n_samples: 6, temperatures: vec![], seed: Some(0) });
+        let results = v.validate("hi").await.unwrap();
+        let seeded = results.iter().find(|r| r.provider_name == "seeded").unwrap();
+        let st = seeded.stats.as_ref().unwrap();
+        assert_eq!((st.n, st.errors), (6, 0));
+        assert!((seeded.adherence_score - 50.0).abs() < 1e-9);
+        assert!(st.ci_low < 50.0 && st.ci_high > 50.0);
+
+        let cmp = stats::pairwise(&results, stats::DEFAULT_ALPHA);
+        assert_eq!(cmp.len(), 1);
+        assert!((cmp[0].mean_diff.abs() - 50.0).abs() < 1e-9);
+    }
+
+    struct SlowProvider(u64);
+
+    #[async_trait]
+    impl LlmProviderAsync for SlowProvider {
+        async fn generate(&self, _prompt: &Prompt) -> Result<Completion> {
+            tokio::time::sleep(Duration::from_millis(self.0)).await;
+            Ok(Completion { text: "gestante".into(), model: None })
         }
     }
 
-    #[cfg(test)]
-    mod tests {
-        use super::*;
+    #[tokio::test]
+    async fn streams_in_completion_order_with_timeout_and_cancel() {
+        let guidelines = r#"[{"topic":"t","expected_terms":["gestante"]}]"#;
+        let providers: AsyncProviders = vec![
+            ("slow".into(), Arc::new(SlowProvider(200))),
+            ("stuck".into(), Arc::new(SlowProvider(60_000))),
+            ("echo".into(), blocking_provider(Arc::new(EchoProvider))),
+        ];
+        let v = LLMValidatorAsync::from_json_str(guidelines, providers)
+            .unwrap()
+            .with_timeout(Duration::from_secs(5))
+            .with_provider_timeout("stuck", Duration::from_millis(50));
+        let order: Vec<String> = v.validate_stream("hi", CancellationToken::new()).map(|r| r.provider_name).collect().await;
+        assert_eq!(order, ["echo", "stuck", "slow"]);
+
+        let results = v.validate("hi").await.unwrap();
+        let stuck = results.iter().find(|r| r.provider_name == "stuck").unwrap();
+        assert!(stuck.raw_text.contains("\"timeout\""));
+
+        let cancel = CancellationToken::new();
+        let trigger = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            trigger.cancel();
+        });
+        let err = v.validate_with_cancel("hi", &cancel).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<PantherError>(), Some(PantherError::Cancelled(_))));
+    }
 
-        #[test]
-        fn proof_determinism_and_verify() {
-            let prompt = "hello";
-            let providers = "[{\"type\":\"ollama\",\"base_url\":\"http://localhost:11434\",\"model\":\"llama3\"}]";
-            let guidelines = "[]";
-            let results = "[]";
-            let ctx = ProofContext { sdk_version: "test".into(), salt: Some("s1".into()) };
+    struct SleepyProvider(u64);
+
+    impl LlmProvider for SleepyProvider {
+        fn generate(&self, _prompt: &Prompt) -> Result<Completion> {
+            std::thread::sleep(Duration::from_millis(self.0));
+            Ok(Completion { text: "gestante".into(), model: None })
+        }
+    }
 
-            let p1 = compute_proof(prompt, providers, guidelines, results, &ctx).unwrap();
-            let p2 = compute_proof(prompt, providers, guidelines, results, &ctx).unwrap();
-            assert_eq!(p1.combined_hash, p2.combined_hash);
+    #[tokio::test]
+    async fn blocking_validator_shares_timeout_cancel_and_stream_order() {
+        let guidelines = r#"[{"topic":"t","expected_terms":["gestante"]}]"#;
+        let providers: Vec<(String, Arc<dyn LlmProvider>)> = vec![
+            ("slow".into(), Arc::new(SleepyProvider(150))),
+            ("stuck".into(), Arc::new(SleepyProvider(400))),
+            ("echo".into(), Arc::new(EchoProvider)),
+        ];
+        let v = LLMValidator::from_json_str(guidelines, providers)
+            .unwrap()
+            .with_timeout(Duration::from_secs(5))
+            .with_provider_timeout("stuck", Duration::from_millis(50));
+        let order: Vec<String> = v.validate_stream("hi", CancellationToken::new()).map(|r| r.provider_name).collect().await;
+        assert_eq!(order, ["echo", "stuck", "slow"]);
+
+        let results = v.validate("hi").await.unwrap();
+        let stuck = results.iter().find(|r| r.provider_name == "stuck").unwrap();
+        assert_eq!((stuck.error, stuck.adherence_score), (Some(ErrorCategory::Timeout), 0.0));
+        assert!(results.iter().filter(|r| r.provider_name != "stuck").all(|r| r.error.is_none()));
+
+        let cancel = CancellationToken::new();
+        let trigger = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            trigger.cancel();
+        });
+        let err = v.validate_with_cancel("hi", &cancel).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<PantherError>(), Some(PantherError::Cancelled(_))));
+    }
 
-            assert!(verify_proof_local(&p1, prompt, providers, guidelines, results, Some("s1".into())));
-            assert!(!verify_proof_local(&p1, prompt, providers, guidelines, results, Some("different".into())));
+    #[tokio::test]
+    async fn a_timed_out_blocking_call_holds_its_budget_until_it_returns() {
+        let manager = Arc::new(
+            BudgetManager::from_json_str(
+                Arc::new(panther_storage::InMemoryStore::default()),
+                r#"{"budgets":[{"scope":"provider","key":"sleepy","max_tokens":1000}],"output_tokens":5}"#,
+            )
+            .unwrap(),
+        );
+        let guidelines = r#"[{"topic":"t","expected_terms":["gestante"]}]"#;
+        let providers: Vec<(String, Arc<dyn LlmProvider>)> = vec![("sleepy".into(), Arc::new(SleepyProvider(300)))];
+        let v = LLMValidator::from_json_str(guidelines, providers)
+            .unwrap()
+            .with_budget(manager.clone(), SpendContext::default())
+            .with_timeout(Duration::from_millis(50));
+        let remaining = || manager.remaining("sleepy", &SpendContext::default()).unwrap()[0].remaining_tokens.unwrap();
+
+        let r = &v.validate("hi").await.unwrap()[0];
+        assert_eq!(r.error, Some(ErrorCategory::Timeout));
+        // The thread is still generating, so its tokens are still spoken for
+        assert!(remaining() < 1000);
+        for _ in 0..100 {
+            if remaining() == 1000 {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
         }
+        panic!("the hold outlived the call: {} tokens left", remaining());
     }
 }
 
@@ -439,31 +835,35 @@ pub mod proof {
 #[cfg(feature = "blockchain-eth")]
 pub mod anchor_eth {
     use super::*;
+    use crate::anchor::GasStrategy;
     use ethers::abi::Abi;
     use ethers::contract::Contract;
-    use ethers::middleware::SignerMiddleware;
-    use ethers::prelude::k256::ecdsa::SigningKey;
-    use ethers::prelude::Wallet;
-    use ethers::providers::{Http, Provider};
+    use ethers::middleware::{NonceManagerMiddleware, SignerMiddleware};
+    use ethers::providers::{Http, Middleware, Provider};
     use ethers::signers::{LocalWallet, Signer};
-    use ethers::types::{Address, H256};
+    use ethers::types::{Address, H256, U256};
     use std::str::FromStr;
-    use std::sync::Arc;
-    use std::time::Duration;
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
-    pub struct AnchorResult { pub tx_hash: String }
+    pub struct AnchorResult {
+        pub tx_hash: String,
+        /// Block the transaction was mined in, when the call waited for it.
+        #[serde(default)]
+        pub block: Option<u64>,
+    }
 
     const ABI_JSON: &str = r#"[
       {"inputs":[{"internalType":"bytes32","name":"h","type":"bytes32"}],"name":"anchor","outputs":[],"stateMutability":"nonpayable","type":"function"},
       {"inputs":[{"internalType":"bytes32","name":"h","type":"bytes32"}],"name":"isAnchored","outputs":[{"internalType":"bool","name":"","type":"bool"}],"stateMutability":"view","type":"function"}
     ]"#;
 
+    /// Hashes longer than the contract's `bytes32` (SHA3-512 proof hashes) go on-chain as their SHA3-256.
     fn h256_from_hex(s: &str) -> anyhow::Result<H256> {
+        use sha3::{Digest, Sha3_256};
         let s = s.trim();
         let s = s.strip_prefix("0x").unwrap_or(s);
         let mut bytes = hex::decode(s)?;
-        if bytes.len() > 32 { anyhow::bail!("hash too long"); }
+        if bytes.len() > 32 { bytes = Sha3_256::digest(&bytes).to_vec(); }
         if bytes.len() < 32 {
             let mut padded = vec![0u8; 32 - bytes.len()];
             padded.extend(bytes);
@@ -472,27 +872,73 @@ pub mod anchor_eth {
         Ok(H256::from_slice(&bytes))
     }
 
+    /// The `bytes32` the contract stores for `proof_hash_hex`, as hex.
+    pub fn onchain_digest(proof_hash_hex: &str) -> anyhow::Result<String> { Ok(hex::encode(h256_from_hex(proof_hash_hex)?)) }
+
+    type Client = NonceManagerMiddleware<SignerMiddleware<Provider<Http>, LocalWallet>>;
+
+    /// Signing connection to the registry contract. Concurrent anchors through one instance
+    /// share a local nonce sequence instead of racing on the node's pending count.
+    pub struct EthAnchorer {
+        client: Arc<Client>,
+        contract: Contract<Client>,
+        gas: GasStrategy,
+    }
+
+    impl EthAnchorer {
+        pub async fn connect(rpc_url: &str, contract_addr: &str, priv_key_hex: &str, gas: GasStrategy) -> anyhow::Result<Self> {
+            let provider = Provider::<Http>::try_from(rpc_url)?.interval(Duration::from_millis(1000));
+            let chain_id = provider.get_chainid().await?.as_u64();
+            let wallet: LocalWallet = LocalWallet::from_str(priv_key_hex)?.with_chain_id(chain_id);
+            let address = wallet.address();
+            let client = Arc::new(NonceManagerMiddleware::new(SignerMiddleware::new(provider, wallet), address));
+            let abi: Abi = serde_json::from_str(ABI_JSON)?;
+            let contract = Contract::new(Address::from_str(contract_addr)?, abi, client.clone());
+            Ok(Self { client, contract, gas })
+        }
+
+        /// Sends `anchor(bytes32)` and waits until it has `confirmations` blocks (0 returns once the node accepted it).
+        pub async fn anchor(&self, proof_hash_hex: &str, confirmations: usize) -> anyhow::Result<AnchorResult> {
+            let h = h256_from_hex(proof_hash_hex)?;
+            let mut call = self.contract.method::<_, ()>("anchor", h)?;
+            if let Some(limit) = self.gas.limit {
+                call = call.gas(limit);
+            }
+            let suggested = if self.gas.needs_node_price() { Some(self.client.get_gas_price().await?.low_u64()) } else { None };
+            if let Some(price) = self.gas.price(suggested) {
+                call = call.gas_price(U256::from(price));
+            }
+            let pending = call.send().await?;
+            let tx_hash = format!("{:#x}", pending.tx_hash());
+            let block = match confirmations {
+                0 => None,
+                n => pending.confirmations(n).await?.and_then(|r| r.block_number).map(|b| b.as_u64()),
+            };
+            Ok(AnchorResult { tx_hash, block })
+        }
+
+        pub async fn is_anchored(&self, proof_hash_hex: &str) -> anyhow::Result<bool> {
+            let h = h256_from_hex(proof_hash_hex)?;
+            Ok(self.contract.method::<_, bool>("isAnchored", h)?.call().await?)
+        }
+
+        /// Blocks since `tx_hash` was mined (itself included), `None` while it is pending.
+        pub async fn confirmations(&self, tx_hash: &str) -> anyhow::Result<Option<u64>> {
+            let Some(receipt) = self.client.get_transaction_receipt(H256::from_str(tx_hash)?).await? else { return Ok(None) };
+            let Some(block) = receipt.block_number else { return Ok(None) };
+            let head = self.client.get_block_number().await?;
+            Ok(Some(head.as_u64().saturating_sub(block.as_u64()) + 1))
+        }
+    }
+
+    /// One-off anchor with the node's gas price; waits for the transaction to be mined.
     pub async fn anchor_proof(
         proof_hash_hex: &str,
         rpc_url: &str,
         contract_addr: &str,
         priv_key_hex: &str,
     ) -> anyhow::Result<AnchorResult> {
-        let provider = Provider::<Http>::try_from(rpc_url)?.interval(Duration::from_millis(1000));
-        let chain_id = provider.get_chainid().await?.as_u64();
-        let wallet: LocalWallet = LocalWallet::from_str(priv_key_hex)?.with_chain_id(chain_id);
-        let client = Arc::new(SignerMiddleware::new(provider, wallet));
-
-        let addr: Address = Address::from_str(contract_addr)?;
-        let abi: Abi = serde_json::from_str(ABI_JSON)?;
-        let contract = Contract::new(addr, abi, client);
-        let h = h256_from_hex(proof_hash_hex)?;
-
-        // anchor(bytes32)
-        let pending = contract.method::<_, ()>("anchor", h)?.send().await?;
-        let tx_hash = format!("{:#x}", pending.tx_hash());
-        // optional wait: let _receipt = pending.await?;
-        Ok(AnchorResult { tx_hash })
+        EthAnchorer::connect(rpc_url, contract_addr, priv_key_hex, GasStrategy::default()).await?.anchor(proof_hash_hex, 1).await
     }
 
     pub async fn is_anchored(
@@ -0,0 +1,233 @@
+//! Term matching for guideline scoring.
+//!
+//! Text and terms are split into words, lowercased and accent-folded
+//! ("Contra-indicação" → "contraindicacao"), then compared word by word:
+//! exact form first, then a light Portuguese/English stem, then synonyms, then
+//! (optionally) edit distance. Matches report the byte span in the original text.
+
+use serde::{Deserialize, Serialize};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+// Words shorter than this are never fuzzy-matched ("dose" vs "nose")
+const FUZZY_MIN_LEN: usize = 5;
+// Stems shorter than this are discarded in favour of the folded word
+const MIN_STEM: usize = 3;
+
+const PT_SUFFIXES: &[&str] = &[
+    "amentos", "imentos", "amento", "imento", "adoras", "adores", "adora", "ador", "acoes", "icoes", "mente",
+    "idades", "idade", "ancias", "ancia", "encias", "encia", "acao", "icao", "ados", "adas", "idos", "idas",
+    "ivos", "ivas", "ado", "ada", "ido", "ida", "ivo", "iva", "oes", "aes", "ais", "eis", "ar", "er", "ir",
+    "os", "as", "es", "o", "a", "e", "s",
+];
+const EN_SUFFIXES: &[&str] = &[
+    "ational", "ations", "ation", "ments", "ment", "ness", "ities", "ity", "ingly", "ing", "edly", "ies", "ied",
+    "ed", "ly", "es", "s", "e",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Stemmer {
+    /// Try both Portuguese and English stems.
+    #[default]
+    Auto,
+    Portuguese,
+    English,
+    Off,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct MatchOptions {
+    #[serde(default)]
+    pub stemmer: Stemmer,
+    /// Maximum edit distance per word; 0 disables fuzzy matching.
+    #[serde(default)]
+    pub fuzzy_distance: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKind {
+    Exact,
+    Stem,
+    Synonym,
+    Fuzzy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TermMatch {
+    /// Guideline term this match counts for.
+    pub term: String,
+    /// The text as written in the response.
+    pub matched: String,
+    pub start: usize,
+    pub end: usize,
+    pub kind: MatchKind,
+}
+
+/// Lowercases, strips diacritics and drops in-word hyphens and apostrophes.
+pub fn fold(s: &str) -> String {
+    s.nfd()
+        .filter(|c| !is_combining_mark(*c) && *c != '-' && *c != '\'' && *c != '’')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Light suffix-stripping stem of an already folded word.
+pub fn stem(word: &str, suffixes: &[&str]) -> String {
+    for suf in suffixes {
+        if let Some(base) = word.strip_suffix(suf) {
+            if base.chars().count() >= MIN_STEM {
+                return base.to_string();
+            }
+        }
+    }
+    word.to_string()
+}
+
+#[derive(Debug, Clone)]
+struct Word {
+    start: usize,
+    end: usize,
+    folded: String,
+    stems: [Option<String>; 2],
+}
+
+fn words(text: &str, stemmer: Stemmer) -> Vec<Word> {
+    let mut out = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if !c.is_alphanumeric() { continue; }
+        let mut end = i + c.len_utf8();
+        while let Some(&(j, d)) = chars.peek() {
+            let joiner = matches!(d, '-' | '\'' | '’')
+                && text[j + d.len_utf8()..].chars().next().is_some_and(char::is_alphanumeric);
+            if !(d.is_alphanumeric() || is_combining_mark(d) || joiner) { break; }
+            end = j + d.len_utf8();
+            chars.next();
+        }
+        let folded = fold(&text[i..end]);
+        let stems = stems_of(&folded, stemmer);
+        out.push(Word { start: i, end, folded, stems });
+    }
+    out
+}
+
+fn stems_of(folded: &str, stemmer: Stemmer) -> [Option<String>; 2] {
+    // Short words ("dos", "as") only ever match exactly
+    if folded.chars().count() <= MIN_STEM { return [None, None]; }
+    let pt = matches!(stemmer, Stemmer::Auto | Stemmer::Portuguese).then(|| stem(folded, PT_SUFFIXES));
+    let en = matches!(stemmer, Stemmer::Auto | Stemmer::English).then(|| stem(folded, EN_SUFFIXES));
+    [pt, en]
+}
+
+/// Levenshtein distance over chars.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+/// A response prepared once for matching many terms.
+pub struct Matcher<'a> {
+    text: &'a str,
+    words: Vec<Word>,
+    options: MatchOptions,
+}
+
+impl<'a> Matcher<'a> {
+    pub fn new(text: &'a str, options: &MatchOptions) -> Self {
+        Self { text, words: words(text, options.stemmer), options: options.clone() }
+    }
+
+    fn word_kind(&self, w: &Word, t: &Word) -> Option<MatchKind> {
+        if w.folded == t.folded { return Some(MatchKind::Exact); }
+        if w.stems.iter().zip(&t.stems).any(|(a, b)| a.is_some() && a == b) { return Some(MatchKind::Stem); }
+        let d = self.options.fuzzy_distance;
+        let long_enough = w.folded.chars().count() >= FUZZY_MIN_LEN && t.folded.chars().count() >= FUZZY_MIN_LEN;
+        if d > 0 && long_enough && edit_distance(&w.folded, &t.folded) <= d { return Some(MatchKind::Fuzzy); }
+        None
+    }
+
+    /// Every non-overlapping occurrence of `phrase`, counted for `term`.
+    fn occurrences(&self, term: &str, phrase: &str, synonym: bool) -> Vec<TermMatch> {
+        let needle = words(phrase, self.options.stemmer);
+        let mut out = Vec::new();
+        if needle.is_empty() { return out; }
+        let mut i = 0;
+        while i + needle.len() <= self.words.len() {
+            let window = &self.words[i..i + needle.len()];
+            let kinds: Option<Vec<MatchKind>> = window.iter().zip(&needle).map(|(w, t)| self.word_kind(w, t)).collect();
+            match kinds {
+                Some(kinds) => {
+                    let mut kind = kinds.into_iter().max().unwrap_or(MatchKind::Exact);
+                    if synonym { kind = kind.max(MatchKind::Synonym); }
+                    let (start, end) = (window[0].start, window[needle.len() - 1].end);
+                    out.push(TermMatch { term: term.to_string(), matched: self.text[start..end].to_string(), start, end, kind });
+                    i += needle.len();
+                }
+                None => i += 1,
+            }
+        }
+        out
+    }
+
+    /// All occurrences of `term` or any of its synonyms, in text order.
+    pub fn find_all(&self, term: &str, synonyms: &[String]) -> Vec<TermMatch> {
+        let mut out = self.occurrences(term, term, false);
+        for s in synonyms {
+            for m in self.occurrences(term, s, true) {
+                if !out.iter().any(|o| o.start < m.end && m.start < o.end) { out.push(m); }
+            }
+        }
+        out.sort_by_key(|m| m.start);
+        out
+    }
+
+    /// Best occurrence of `term`: the closest kind of match, earliest first.
+    pub fn find(&self, term: &str, synonyms: &[String]) -> Option<TermMatch> {
+        self.find_all(term, synonyms).into_iter().min_by_key(|m| (m.kind, m.start))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folds_stems_and_respects_word_boundaries() {
+        let text = "É Contra-indicação; consulte a Anvisa. Anvisaria não conta.";
+        let m = Matcher::new(text, &MatchOptions::default());
+        let hit = m.find("contraindicado", &[]).unwrap();
+        assert_eq!(hit.matched, "Contra-indicação");
+        assert_eq!(hit.kind, MatchKind::Stem);
+        assert_eq!(&text[hit.start..hit.end], "Contra-indicação");
+        let anvisa = m.find_all("ANVISA", &[]);
+        assert_eq!(anvisa.len(), 1);
+        assert_eq!(anvisa[0].kind, MatchKind::Exact);
+        assert!(m.find("dose", &[]).is_none());
+        assert!(Matcher::new("a dosagem", &MatchOptions::default()).find("dos", &[]).is_none());
+    }
+
+    #[test]
+    fn synonyms_and_fuzzy() {
+        let text = "Procure orientação do obstetra sobre a posologia recomendda.";
+        let m = Matcher::new(text, &MatchOptions::default());
+        let syn = m.find("médico", &["obstetra".into()]).unwrap();
+        assert_eq!((syn.matched.as_str(), syn.kind), ("obstetra", MatchKind::Synonym));
+        assert!(m.find("recomendada", &[]).is_none());
+
+        let fuzzy = MatchOptions { stemmer: Stemmer::Off, fuzzy_distance: 1 };
+        let hit = Matcher::new(text, &fuzzy).find("recomendada", &[]).unwrap();
+        assert_eq!((hit.matched.as_str(), hit.kind), ("recomendda", MatchKind::Fuzzy));
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+}
@@ -0,0 +1,309 @@
+//! Versioned guideline packs.
+//!
+//! A pack wraps the guideline topics with metadata (id, version, jurisdiction,
+//! locale, authors, effective date) and pack-wide rules that are merged into the
+//! topics they target. Packs load from JSON or YAML; the legacy bare array of
+//! guidelines is migrated into a pack with placeholder metadata. The canonical
+//! JSON form (sorted keys, migrated) is what proofs hash.
+
+use crate::matching::fold;
+use crate::scoring;
+use crate::Guideline;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+pub const PACK_FORMAT: &str = "panther-guidelines/v1";
+/// JSON Schema for the pack format.
+pub const PACK_SCHEMA: &str = include_str!("../schema/guideline-pack.schema.json");
+const LEGACY_ID: &str = "legacy";
+const LEGACY_VERSION: &str = "0.0.0";
+
+fn default_format() -> String { PACK_FORMAT.to_string() }
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PackMetadata {
+    pub id: String,
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Regulatory scope, e.g. "BR-ANVISA".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jurisdiction: Option<String>,
+    /// BCP 47 tag, e.g. "pt-BR".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub authors: Vec<String>,
+    /// ISO date (YYYY-MM-DD) from which the pack applies.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effective_date: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleKind {
+    RequiredTerm,
+    ForbiddenTerm,
+    ForbiddenPattern,
+    MustNotClaim,
+}
+
+/// A rule applied to several topics at once.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PackRule {
+    pub id: String,
+    pub kind: RuleKind,
+    pub value: String,
+    /// Topics the rule applies to; empty means every topic.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub topics: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuidelinePack {
+    #[serde(default = "default_format")]
+    pub format: String,
+    pub metadata: PackMetadata,
+    pub topics: Vec<Guideline>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<PackRule>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LintLevel {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LintIssue {
+    pub level: LintLevel,
+    /// duplicate_topic | duplicate_term | empty_topic | overlapping_synonyms | unreachable_rule | invalid_pattern | metadata
+    pub code: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub topic: Option<String>,
+    pub message: String,
+}
+
+impl GuidelinePack {
+    /// Wraps a legacy guideline array.
+    pub fn from_legacy(topics: Vec<Guideline>) -> Self {
+        Self {
+            format: default_format(),
+            metadata: PackMetadata {
+                id: LEGACY_ID.into(),
+                version: LEGACY_VERSION.into(),
+                name: None,
+                description: None,
+                jurisdiction: None,
+                locale: None,
+                authors: vec![],
+                effective_date: None,
+            },
+            topics,
+            rules: vec![],
+        }
+    }
+
+    /// Parses a pack or a legacy array, as JSON or YAML.
+    pub fn parse(text: &str) -> Result<Self> {
+        let t = text.trim_start();
+        let pack = if t.starts_with('[') {
+            Self::from_legacy(serde_json::from_str(text).context("invalid legacy guidelines JSON")?)
+        } else if t.starts_with('{') {
+            serde_json::from_str(text).context("invalid guideline pack JSON")?
+        } else {
+            let v: serde_yaml::Value = serde_yaml::from_str(text).context("invalid guideline pack YAML")?;
+            if v.is_sequence() { Self::from_legacy(serde_yaml::from_value(v)?) } else { serde_yaml::from_value(v)? }
+        };
+        pack.check()?;
+        Ok(pack)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("loading {}", path.display()))
+    }
+
+    /// Hard errors that make the pack unusable (lint reports the softer problems).
+    fn check(&self) -> Result<()> {
+        let major = self.format.strip_prefix("panther-guidelines/").unwrap_or_default();
+        if major != "v1" { bail!("unsupported guideline pack format '{}' (expected {})", self.format, PACK_FORMAT); }
+        scoring::check_guidelines(&self.guidelines())
+    }
+
+    /// Topics with the pack-wide rules merged in; this is what the validators score against.
+    pub fn guidelines(&self) -> Vec<Guideline> {
+        let mut out = self.topics.clone();
+        for rule in &self.rules {
+            for g in out.iter_mut().filter(|g| rule.topics.is_empty() || rule.topics.contains(&g.topic)) {
+                let list = match rule.kind {
+                    RuleKind::RequiredTerm => &mut g.required_terms,
+                    RuleKind::ForbiddenTerm => &mut g.forbidden_terms,
+                    RuleKind::ForbiddenPattern => &mut g.forbidden_patterns,
+                    RuleKind::MustNotClaim => &mut g.must_not_claim,
+                };
+                if !list.contains(&rule.value) { list.push(rule.value.clone()); }
+            }
+        }
+        out
+    }
+
+    /// Canonical JSON value (sorted keys) used for hashing.
+    pub fn canonical_value(&self) -> serde_json::Value {
+        crate::proof::canonicalize(&serde_json::to_value(self).unwrap_or(serde_json::Value::Null))
+    }
+
+    pub fn lint(&self) -> Vec<LintIssue> {
+        let mut issues = Vec::new();
+        let mut push = |level, code: &str, topic: Option<&str>, message: String| {
+            issues.push(LintIssue { level, code: code.into(), topic: topic.map(str::to_string), message });
+        };
+        let m = &self.metadata;
+        if m.id.trim().is_empty() || m.version.trim().is_empty() {
+            push(LintLevel::Error, "metadata", None, "metadata.id and metadata.version are required".into());
+        }
+        if m.id == LEGACY_ID {
+            push(LintLevel::Warning, "metadata", None, "legacy guideline array; add metadata to make it a versioned pack".into());
+        }
+        if let Some(d) = &m.effective_date {
+            if !is_iso_date(d) { push(LintLevel::Error, "metadata", None, format!("effective_date '{}' is not YYYY-MM-DD", d)); }
+        }
+
+        let mut seen_topics: Vec<String> = Vec::new();
+        let mut seen_terms: Vec<(String, String)> = Vec::new();
+        for g in &self.topics {
+            let topic = Some(g.topic.as_str());
+            if seen_topics.contains(&fold(&g.topic)) {
+                push(LintLevel::Error, "duplicate_topic", topic, format!("topic '{}' is defined more than once", g.topic));
+            }
+            seen_topics.push(fold(&g.topic));
+            if g.expected_terms.is_empty() && g.required_terms.is_empty() {
+                push(LintLevel::Warning, "empty_topic", topic, "topic has no expected or required terms and always scores 100".into());
+            }
+
+            let mut terms: Vec<String> = Vec::new();
+            for t in g.expected_terms.iter().chain(&g.required_terms) {
+                let f = fold(t);
+                if f.trim().is_empty() {
+                    push(LintLevel::Error, "empty_topic", topic, "empty term".into());
+                    continue;
+                }
+                if terms.contains(&f) && !g.required_terms.contains(t) {
+                    push(LintLevel::Warning, "duplicate_term", topic, format!("'{}' is listed more than once", t));
+                }
+                if let Some((other, _)) = seen_terms.iter().find(|(o, tf)| *tf == f && o != &g.topic) {
+                    push(LintLevel::Warning, "duplicate_term", topic, format!("'{}' is also expected by topic '{}'", t, other));
+                }
+                terms.push(f.clone());
+                seen_terms.push((g.topic.clone(), f));
+            }
+
+            let mut synonym_owner: Vec<(String, String)> = Vec::new();
+            for (term, syns) in &g.synonyms {
+                if !terms.contains(&fold(term)) {
+                    push(LintLevel::Warning, "unreachable_rule", topic, format!("synonyms for '{}', which is not a term of this topic", term));
+                }
+                for s in syns {
+                    let f = fold(s);
+                    if f != fold(term) && terms.contains(&f) {
+                        push(LintLevel::Warning, "overlapping_synonyms", topic, format!("synonym '{}' of '{}' is itself a term", s, term));
+                    }
+                    if let Some((owner, _)) = synonym_owner.iter().find(|(o, sf)| *sf == f && fold(o) != fold(term)) {
+                        push(LintLevel::Warning, "overlapping_synonyms", topic, format!("synonym '{}' is shared by '{}' and '{}'", s, owner, term));
+                    }
+                    synonym_owner.push((term.clone(), f));
+                }
+            }
+            for key in g.term_weights.keys() {
+                if !terms.contains(&fold(key)) {
+                    push(LintLevel::Warning, "unreachable_rule", topic, format!("weight for '{}', which is not a term of this topic", key));
+                }
+            }
+        }
+
+        for (g, merged) in self.topics.iter().zip(self.guidelines()) {
+            let topic = Some(g.topic.as_str());
+            let expected: Vec<String> = merged.expected_terms.iter().chain(&merged.required_terms).map(|t| fold(t)).collect();
+            for bad in merged.forbidden_terms.iter().chain(&merged.must_not_claim) {
+                if expected.contains(&fold(bad)) {
+                    push(LintLevel::Error, "unreachable_rule", topic, format!("'{}' is both expected and forbidden; the topic can never pass", bad));
+                }
+            }
+            for p in &merged.forbidden_patterns {
+                if let Err(e) = regex::RegexBuilder::new(p).case_insensitive(true).build() {
+                    push(LintLevel::Error, "invalid_pattern", topic, format!("'{}': {}", p, e));
+                }
+            }
+        }
+
+        for rule in &self.rules {
+            if rule.value.trim().is_empty() {
+                push(LintLevel::Error, "unreachable_rule", None, format!("rule '{}' has an empty value", rule.id));
+            }
+            for t in &rule.topics {
+                if !self.topics.iter().any(|g| &g.topic == t) {
+                    push(LintLevel::Error, "unreachable_rule", None, format!("rule '{}' targets unknown topic '{}'", rule.id, t));
+                }
+            }
+        }
+        issues
+    }
+}
+
+fn is_iso_date(s: &str) -> bool {
+    let parts: Vec<&str> = s.split('-').collect();
+    let ok = |p: &str, len: usize, max: u32| p.len() == len && p.parse::<u32>().is_ok_and(|n| (1..=max).contains(&n));
+    parts.len() == 3 && ok(parts[0], 4, 9999) && ok(parts[1], 2, 12) && ok(parts[2], 2, 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_migrates_and_hashes_like_equivalent_pack() {
+        let legacy = GuidelinePack::parse(include_str!("../guidelines/anvisa.json")).unwrap();
+        assert_eq!(legacy.metadata.id, LEGACY_ID);
+        assert_eq!(legacy.guidelines().len(), 1);
+        let yaml = serde_yaml::to_string(&legacy).unwrap();
+        let again = GuidelinePack::parse(&yaml).unwrap();
+        assert_eq!(legacy.canonical_value(), again.canonical_value());
+        let schema: serde_json::Value = serde_json::from_str(PACK_SCHEMA).unwrap();
+        assert_eq!(schema["properties"]["format"]["const"], PACK_FORMAT);
+    }
+
+    #[test]
+    fn lint_reports_problems_and_rules_merge() {
+        let pack = GuidelinePack::parse(
+            r#"
+format: panther-guidelines/v1
+metadata: { id: demo, version: 1.0.0, locale: pt-BR, effective_date: 2024-13-01 }
+topics:
+  - topic: Gravidez
+    expected_terms: [médico, Medico, dosagem]
+    synonyms: { médico: [obstetra], dosagem: [obstetra, posologia] }
+  - topic: Vazio
+    expected_terms: []
+rules:
+  - { id: r1, kind: must_not_claim, value: seguro na gravidez }
+  - { id: r2, kind: forbidden_term, value: dosagem, topics: [Gravidez] }
+  - { id: r3, kind: forbidden_term, value: x, topics: [Nope] }
+"#,
+        )
+        .unwrap();
+        assert_eq!(pack.guidelines()[1].must_not_claim, vec!["seguro na gravidez".to_string()]);
+        let issues = pack.lint();
+        let codes: Vec<(&str, LintLevel)> = issues.iter().map(|i| (i.code.as_str(), i.level)).collect();
+        for expected in ["metadata", "duplicate_term", "overlapping_synonyms", "empty_topic", "unreachable_rule"] {
+            assert!(codes.iter().any(|(c, _)| *c == expected), "missing {} in {:?}", expected, codes);
+        }
+        assert_eq!(codes.iter().filter(|(c, l)| *c == "unreachable_rule" && *l == LintLevel::Error).count(), 2);
+        assert!(GuidelinePack::parse("{\"format\":\"panther-guidelines/v9\",\"metadata\":{\"id\":\"a\",\"version\":\"1\"},\"topics\":[]}").is_err());
+    }
+}
@@ -0,0 +1,482 @@
+//! Offline proofs: hashes over the inputs and results of a validation run.
+//!
+//! `panther-proof-v3` (what [`compute_proof`] produces) hashes RFC 8785 canonical JSON
+//! (see [`jcs`]) and makes each result a Merkle leaf bound to the input hash, so one
+//! provider's answer can be proven without revealing the others, and proofs of many items
+//! can be rolled into a single batch root (see [`batch`]). Its input hash also covers
+//! `timestamp_ms`, which an RFC 3161 token (see [`timestamp`]) can then vouch for.
+//!
+//! Older schemes are still verified with the encoding they were produced with:
+//! `panther-proof-v2-redacted` is v3 without the timestamp, `panther-proof-v2-jcs` is that with the
+//! providers' credentials still hashed, `panther-proof-v2` is the same Merkle tree over sorted-key
+//! `serde_json` output, and `panther-proof-v1` hashed that output and the whole results array at once.
+
+use super::*;
+use sha3::{Digest, Sha3_512};
+
+pub mod bundle;
+pub mod jcs;
+pub mod merkle;
+pub mod sign;
+pub mod timestamp;
+
+use merkle::{InclusionProof, MerkleTree};
+use sign::ProofSignature;
+use timestamp::TimestampToken;
+
+pub const SCHEME_V1: &str = "panther-proof-v1";
+pub const SCHEME_V2: &str = "panther-proof-v2";
+pub const SCHEME_V2_JCS: &str = "panther-proof-v2-jcs";
+pub const SCHEME_V2_REDACTED: &str = "panther-proof-v2-redacted";
+pub const SCHEME_V3: &str = "panther-proof-v3";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProofContext {
+    pub sdk_version: String,
+    pub salt: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Proof {
+    pub scheme: String,          // SCHEME_V3 (SCHEME_V1/SCHEME_V2/SCHEME_V2_JCS/SCHEME_V2_REDACTED for older proofs)
+    pub input_hash: String,      // hex(sha3_512(canonical(prompt, providers, guidelines, salt?, timestamp_ms from v3)))
+    pub results_hash: String,    // hex(sha3_512(canonical(results)))
+    pub combined_hash: String,   // v1: hex(sha3_512(input_hash || results_hash)); v2+: Merkle root over result leaves
+    pub guidelines_hash: String, // hex(sha3_512(canonical(guidelines)))
+    pub providers_hash: String,  // hex(sha3_512(canonical(providers)))
+    pub timestamp_ms: i64,
+    pub sdk_version: String,
+    pub salt_present: bool,
+    /// Number of Merkle leaves (absent in v1).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub leaf_count: Option<usize>,
+    /// [`crate::secrets::config_fingerprint`] of the providers (from `panther-proof-v2-redacted` on).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_fingerprint: Option<String>,
+    /// Set by [`sign::ProofSigner::sign`]; not part of any hash.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<ProofSignature>,
+    /// Set by [`timestamp::stamp`]; not part of any hash.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp_token: Option<TimestampToken>,
+}
+
+/// A Merkle root over the `combined_hash` of many proofs; anchoring it covers every item.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BatchProof {
+    pub scheme: String,
+    pub root: String,
+    pub leaf_count: usize,
+    pub timestamp_ms: i64,
+    pub sdk_version: String,
+}
+
+pub(crate) fn canonicalize(value: &serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            let mut new = serde_json::Map::new();
+            for (k, v) in entries {
+                new.insert(k.clone(), canonicalize(v));
+            }
+            serde_json::Value::Object(new)
+        }
+        serde_json::Value::Array(arr) => {
+            let v = arr.iter().map(canonicalize).collect::<Vec<_>>();
+            serde_json::Value::Array(v)
+        }
+        _ => value.clone(),
+    }
+}
+
+/// How a scheme turns its inputs into hashed bytes. A scheme string never changes meaning: a
+/// different encoding gets a new scheme, and every scheme ever emitted keeps its entry here.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Encoding {
+    /// RFC 8785 bytes, and a root that commits to the input when there are no results;
+    /// otherwise sorted-key `serde_json` and the plain tree root.
+    jcs: bool,
+    /// Guidelines hash by their pack canonical form rather than the JSON as given.
+    pack_guidelines: bool,
+    /// `combined_hash` is a Merkle root over one leaf per result.
+    result_tree: bool,
+    /// Credentials are removed from the providers before hashing, and the proof carries a fingerprint.
+    strip_secrets: bool,
+    /// `timestamp_ms` is part of the input bundle.
+    hashed_timestamp: bool,
+}
+
+impl Encoding {
+    const V1: Self = Self { jcs: false, pack_guidelines: false, result_tree: false, strip_secrets: false, hashed_timestamp: false };
+    const V2: Self = Self { pack_guidelines: true, result_tree: true, ..Self::V1 };
+    const V2_JCS: Self = Self { jcs: true, ..Self::V2 };
+    const V2_REDACTED: Self = Self { strip_secrets: true, ..Self::V2_JCS };
+    const V3: Self = Self { hashed_timestamp: true, ..Self::V2_REDACTED };
+
+    fn of(scheme: &str) -> Option<Self> {
+        match scheme {
+            SCHEME_V1 => Some(Self::V1),
+            SCHEME_V2 => Some(Self::V2),
+            SCHEME_V2_JCS => Some(Self::V2_JCS),
+            SCHEME_V2_REDACTED => Some(Self::V2_REDACTED),
+            SCHEME_V3 => Some(Self::V3),
+            _ => None,
+        }
+    }
+
+    // Unknown schemes get the current encoding, so new callers never fall back to a legacy one
+    fn or_current(scheme: &str) -> Self { Self::of(scheme).unwrap_or(Self::V3) }
+}
+
+/// Whether proofs of `scheme` hash providers without their credentials.
+pub(crate) fn strips_secrets(scheme: &str) -> bool { Encoding::or_current(scheme).strip_secrets }
+
+// From v2 on, packs (and legacy arrays, once migrated) hash by their canonical form, whatever the
+// file syntax; v1 hashed the guidelines JSON exactly as given
+fn guidelines_value(scheme: &str, guidelines_json: &str) -> serde_json::Value {
+    let raw = || serde_json::from_str(guidelines_json).unwrap_or(serde_json::Value::Null);
+    if !Encoding::or_current(scheme).pack_guidelines {
+        return raw();
+    }
+    match crate::pack::GuidelinePack::parse(guidelines_json) {
+        Ok(pack) => pack.canonical_value(),
+        Err(_) => raw(),
+    }
+}
+
+/// Same value as `Proof::guidelines_hash` of a new proof, without computing a whole proof.
+pub fn guidelines_hash(guidelines_json: &str) -> String { hash_json(SCHEME_V3, &guidelines_value(SCHEME_V3, guidelines_json)) }
+
+/// Bytes hashed for a JSON value under `scheme`: RFC 8785 from `panther-proof-v2-jcs` on,
+/// sorted-key `serde_json` for v1 and v2.
+pub fn canonical_bytes(scheme: &str, value: &serde_json::Value) -> Vec<u8> {
+    if Encoding::or_current(scheme).jcs {
+        jcs::to_vec(value)
+    } else {
+        serde_json::to_vec(&canonicalize(value)).unwrap_or_default()
+    }
+}
+
+fn hash_json(scheme: &str, value: &serde_json::Value) -> String {
+    let bytes = canonical_bytes(scheme, value);
+    let mut hasher = Sha3_512::new();
+    hasher.update(&bytes);
+    let out = hasher.finalize();
+    hex::encode(out)
+}
+
+fn hash_concat_hex(a_hex: &str, b_hex: &str) -> String {
+    let mut hasher = Sha3_512::new();
+    hasher.update(a_hex.as_bytes());
+    hasher.update(b_hex.as_bytes());
+    let out = hasher.finalize();
+    hex::encode(out)
+}
+
+pub fn compute_proof(
+    prompt: &str,
+    providers_json: &str,
+    guidelines_json: &str,
+    results_json: &str,
+    ctx: &ProofContext,
+) -> anyhow::Result<Proof> {
+    compute(SCHEME_V3, prompt, providers_json, guidelines_json, results_json, ctx, now_ms())
+}
+
+/// Like [`compute_proof`], with the original `panther-proof-v2` encoding (sorted-key `serde_json`,
+/// credentials hashed, no timestamp).
+#[deprecated(note = "emits the legacy panther-proof-v2 encoding; use compute_proof")]
+pub fn compute_proof_v2(
+    prompt: &str,
+    providers_json: &str,
+    guidelines_json: &str,
+    results_json: &str,
+    ctx: &ProofContext,
+) -> anyhow::Result<Proof> {
+    compute(SCHEME_V2, prompt, providers_json, guidelines_json, results_json, ctx, now_ms())
+}
+
+// `timestamp_ms` is only hashed from v3 on, but every scheme records it
+fn compute(
+    scheme: &str,
+    prompt: &str,
+    providers_json: &str,
+    guidelines_json: &str,
+    results_json: &str,
+    ctx: &ProofContext,
+    timestamp_ms: i64,
+) -> anyhow::Result<Proof> {
+    let enc = Encoding::of(scheme).ok_or_else(|| anyhow::anyhow!("unknown proof scheme: {}", scheme))?;
+    let mut providers_val: serde_json::Value = serde_json::from_str(providers_json).unwrap_or(serde_json::Value::Null);
+    // Older schemes hashed credentials along with the config; later ones never see them
+    if enc.strip_secrets {
+        providers_val = crate::secrets::strip_secrets(&providers_val);
+    }
+    let guidelines_val = guidelines_value(scheme, guidelines_json);
+    let results_val: serde_json::Value = serde_json::from_str(results_json).unwrap_or(serde_json::Value::Null);
+
+    let providers_hash = hash_json(scheme, &providers_val);
+    let guidelines_hash = hash_json(scheme, &guidelines_val);
+    let results_hash = hash_json(scheme, &results_val);
+
+    // input bundle: prompt + providers + guidelines + optional salt (+ timestamp from v3)
+    let mut input_bundle = serde_json::json!({
+        "prompt": prompt,
+        "providers": providers_val,
+        "guidelines": guidelines_val,
+        "salt": ctx.salt,
+    });
+    if enc.hashed_timestamp {
+        input_bundle["timestamp_ms"] = serde_json::json!(timestamp_ms);
+    }
+    let input_hash = hash_json(scheme, &input_bundle);
+    let (combined_hash, leaf_count) = if enc.result_tree {
+        let tree = results_tree(enc, &input_hash, &results_val);
+        (results_root(enc, &input_hash, &tree), Some(tree.len()))
+    } else {
+        (hash_concat_hex(&input_hash, &results_hash), None)
+    };
+    let proof = Proof {
+        scheme: scheme.to_string(),
+        input_hash,
+        results_hash,
+        combined_hash,
+        guidelines_hash,
+        providers_hash,
+        timestamp_ms,
+        sdk_version: ctx.sdk_version.clone(),
+        salt_present: ctx.salt.is_some(),
+        leaf_count,
+        config_fingerprint: enc.strip_secrets.then(|| crate::secrets::config_fingerprint(&providers_val)),
+        signature: None,
+        timestamp_token: None,
+    };
+    Ok(proof)
+}
+
+/// Recomputes the proof from the inputs; a signed proof must also carry a valid signature.
+pub fn verify_proof_local(
+    expected: &Proof,
+    prompt: &str,
+    providers_json: &str,
+    guidelines_json: &str,
+    results_json: &str,
+    salt: Option<String>,
+) -> bool {
+    if expected.signature.is_some() && !sign::verify_signature(expected) {
+        return false;
+    }
+    let ctx = ProofContext { sdk_version: expected.sdk_version.clone(), salt };
+    if let Ok(p) = compute(&expected.scheme, prompt, providers_json, guidelines_json, results_json, &ctx, expected.timestamp_ms) {
+        p.combined_hash == expected.combined_hash
+    } else {
+        false
+    }
+}
+
+// A leaf commits to the run's input as well, so a revealed result cannot be replayed under another prompt
+fn result_leaf(enc: Encoding, input_hash: &str, result: &serde_json::Value) -> Vec<u8> {
+    let leaf = serde_json::json!({ "input_hash": input_hash, "result": result });
+    if enc.jcs {
+        jcs::to_vec(&leaf)
+    } else {
+        serde_json::to_vec(&canonicalize(&leaf)).unwrap_or_default()
+    }
+}
+
+fn results_tree(enc: Encoding, input_hash: &str, results: &serde_json::Value) -> MerkleTree {
+    let items = match results {
+        serde_json::Value::Array(items) => items.iter().collect(),
+        other => vec![other],
+    };
+    MerkleTree::from_leaves(items.into_iter().map(|r| merkle::leaf_hash(&result_leaf(enc, input_hash, r))).collect())
+}
+
+// With no results the root still has to commit to the input (the original v2 used the empty tree's root)
+fn results_root(enc: Encoding, input_hash: &str, tree: &MerkleTree) -> String {
+    if enc.jcs && tree.is_empty() {
+        hex::encode(merkle::leaf_hash(&result_leaf(enc, input_hash, &serde_json::Value::Null)))
+    } else {
+        tree.root_hex()
+    }
+}
+
+fn result_tree_encoding(proof: &Proof) -> Option<Encoding> { Encoding::of(&proof.scheme).filter(|e| e.result_tree) }
+
+/// Inclusion proof for `results[index]` of a v2 or v3 proof.
+pub fn prove_result(proof: &Proof, results_json: &str, index: usize) -> anyhow::Result<InclusionProof> {
+    let enc = result_tree_encoding(proof)
+        .ok_or_else(|| anyhow::anyhow!("inclusion proofs need {} or later, got {}", SCHEME_V2, proof.scheme))?;
+    let results: serde_json::Value = serde_json::from_str(results_json)?;
+    let tree = results_tree(enc, &proof.input_hash, &results);
+    anyhow::ensure!(results_root(enc, &proof.input_hash, &tree) == proof.combined_hash, "results do not match the proof");
+    tree.prove(index).ok_or_else(|| anyhow::anyhow!("no result at index {} ({} leaves)", index, tree.len()))
+}
+
+/// Checks a single revealed result against a v2 or v3 proof, without the other results.
+pub fn verify_result(proof: &Proof, result: &serde_json::Value, inclusion: &InclusionProof) -> bool {
+    result_tree_encoding(proof)
+        .is_some_and(|enc| inclusion.root == proof.combined_hash && inclusion.verify_leaf(&result_leaf(enc, &proof.input_hash, result)))
+}
+
+/// Rolls many proofs into one root; returns the batch and one inclusion proof per input, in order.
+pub fn batch(proofs: &[Proof], sdk_version: &str) -> (BatchProof, Vec<InclusionProof>) {
+    let hashes: Vec<&str> = proofs.iter().map(|p| p.combined_hash.as_str()).collect();
+    batch_hashes(&hashes, sdk_version)
+}
+
+/// [`batch`] over bare `combined_hash` values (hex, as in the proof).
+pub fn batch_hashes(hashes: &[&str], sdk_version: &str) -> (BatchProof, Vec<InclusionProof>) {
+    let tree = MerkleTree::from_leaves(hashes.iter().map(|h| merkle::leaf_hash(h.as_bytes())).collect());
+    let inclusions = (0..tree.len()).filter_map(|i| tree.prove(i)).collect();
+    let batch = BatchProof {
+        scheme: SCHEME_V2.to_string(),
+        root: tree.root_hex(),
+        leaf_count: tree.len(),
+        timestamp_ms: now_ms(),
+        sdk_version: sdk_version.to_string(),
+    };
+    (batch, inclusions)
+}
+
+/// Checks that `proof` is part of the batch with the given root.
+pub fn verify_in_batch(proof: &Proof, inclusion: &InclusionProof, batch_root: &str) -> bool {
+    inclusion.root == batch_root && inclusion.verify_leaf(proof.combined_hash.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proof_determinism_and_verify() {
+        let prompt = "hello";
+        let providers = "[{\"type\":\"ollama\",\"base_url\":\"http://localhost:11434\",\"model\":\"llama3\"}]";
+        let guidelines = "[]";
+        let results = "[]";
+        let ctx = ProofContext { sdk_version: "test".into(), salt: Some("s1".into()) };
+
+        let p1 = compute_proof(prompt, providers, guidelines, results, &ctx).unwrap();
+        let p2 = compute(SCHEME_V3, prompt, providers, guidelines, results, &ctx, p1.timestamp_ms).unwrap();
+        assert_eq!(p1.combined_hash, p2.combined_hash);
+        // v3 hashes the timestamp
+        let later = compute(SCHEME_V3, prompt, providers, guidelines, results, &ctx, p1.timestamp_ms + 1).unwrap();
+        assert_ne!(p1.combined_hash, later.combined_hash);
+        let mut backdated = p1.clone();
+        backdated.timestamp_ms -= 1000;
+        assert!(!verify_proof_local(&backdated, prompt, providers, guidelines, results, Some("s1".into())));
+        // Credentials are not part of the proof
+        let keyed = providers.replace("\"model\"", "\"api_key\":\"sk-live\",\"model\"");
+        let p3 = compute_proof(prompt, &keyed, guidelines, results, &ctx).unwrap();
+        assert_eq!((&p3.providers_hash, &p3.config_fingerprint), (&p1.providers_hash, &p1.config_fingerprint));
+
+        assert!(verify_proof_local(&p1, prompt, providers, guidelines, results, Some("s1".into())));
+        assert!(!verify_proof_local(&p1, prompt, providers, guidelines, results, Some("different".into())));
+    }
+
+    #[derive(Deserialize)]
+    struct FrozenVector {
+        prompt: String,
+        providers: String,
+        guidelines: String,
+        results: String,
+        salt: Option<String>,
+        proof: Proof,
+    }
+
+    #[test]
+    fn legacy_v1_proofs_still_verify() {
+        let v: FrozenVector = serde_json::from_str(include_str!("../testdata/proof-v1-baseline.json")).unwrap();
+        let (prompt, providers, guidelines, results) = (v.prompt.as_str(), v.providers.as_str(), v.guidelines.as_str(), v.results.as_str());
+        let legacy = v.proof;
+        assert_eq!(legacy.scheme, SCHEME_V1);
+        assert!(verify_proof_local(&legacy, prompt, providers, guidelines, results, v.salt.clone()));
+        // every component, not just the combined hash, must come out as the baseline computed it
+        let ctx = ProofContext { sdk_version: legacy.sdk_version.clone(), salt: v.salt.clone() };
+        let recomputed = compute(SCHEME_V1, prompt, providers, guidelines, results, &ctx, legacy.timestamp_ms).unwrap();
+        assert_eq!(
+            (&recomputed.input_hash, &recomputed.results_hash, &recomputed.guidelines_hash, &recomputed.providers_hash),
+            (&legacy.input_hash, &legacy.results_hash, &legacy.guidelines_hash, &legacy.providers_hash)
+        );
+
+        let current = compute_proof(prompt, providers, guidelines, results, &ctx).unwrap();
+        assert_ne!(current.combined_hash, legacy.combined_hash);
+        // Number formatting no longer depends on how the results were written
+        assert!(verify_proof_local(&current, prompt, providers, guidelines, &results.replace("87.5", "87.50"), v.salt));
+    }
+
+    #[test]
+    fn legacy_v2_proofs_still_verify() {
+        let v: FrozenVector = serde_json::from_str(include_str!("../testdata/proof-v2-baseline.json")).unwrap();
+        let (prompt, providers, guidelines, results) = (v.prompt.as_str(), v.providers.as_str(), v.guidelines.as_str(), v.results.as_str());
+        let legacy = v.proof;
+        assert_eq!(legacy.scheme, SCHEME_V2);
+        assert!(verify_proof_local(&legacy, prompt, providers, guidelines, results, v.salt.clone()));
+        let ctx = ProofContext { sdk_version: legacy.sdk_version.clone(), salt: v.salt.clone() };
+        let recomputed = compute(SCHEME_V2, prompt, providers, guidelines, results, &ctx, legacy.timestamp_ms).unwrap();
+        assert_eq!(
+            (&recomputed.input_hash, &recomputed.results_hash, &recomputed.guidelines_hash, &recomputed.providers_hash, recomputed.leaf_count),
+            (&legacy.input_hash, &legacy.results_hash, &legacy.guidelines_hash, &legacy.providers_hash, legacy.leaf_count)
+        );
+        let inc = prove_result(&legacy, results, 0).unwrap();
+        let first: serde_json::Value = serde_json::from_str::<Vec<serde_json::Value>>(results).unwrap().remove(0);
+        assert!(verify_result(&legacy, &first, &inc));
+
+        #[allow(deprecated)]
+        let again = compute_proof_v2(prompt, providers, guidelines, results, &ctx).unwrap();
+        assert_eq!((again.scheme.as_str(), &again.combined_hash), (SCHEME_V2, &legacy.combined_hash));
+        // The JCS encoding is a different scheme, not a different reading of this one
+        let jcs = compute(SCHEME_V2_JCS, prompt, providers, guidelines, results, &ctx, legacy.timestamp_ms).unwrap();
+        assert_ne!(jcs.combined_hash, legacy.combined_hash);
+        assert!(!verify_proof_local(&Proof { scheme: SCHEME_V2_JCS.into(), ..legacy }, prompt, providers, guidelines, results, v.salt));
+    }
+
+    #[test]
+    fn credentials_stay_hashed_in_schemes_that_hashed_them() {
+        let v: FrozenVector = serde_json::from_str(include_str!("../testdata/proof-v2-credentials.json")).unwrap();
+        let (prompt, providers, guidelines, results) = (v.prompt.as_str(), v.providers.as_str(), v.guidelines.as_str(), v.results.as_str());
+        let legacy = v.proof;
+        assert!(providers.contains("api_key"));
+        assert!(verify_proof_local(&legacy, prompt, providers, guidelines, results, v.salt.clone()));
+        let ctx = ProofContext { sdk_version: legacy.sdk_version.clone(), salt: v.salt.clone() };
+        let recomputed = compute(SCHEME_V2, prompt, providers, guidelines, results, &ctx, legacy.timestamp_ms).unwrap();
+        assert_eq!((&recomputed.providers_hash, &recomputed.input_hash), (&legacy.providers_hash, &legacy.input_hash));
+        assert_eq!(recomputed.config_fingerprint, None);
+        let rotated = providers.replace("sk-test-0123456789", "sk-test-rotated");
+        assert!(!verify_proof_local(&legacy, prompt, &rotated, guidelines, results, v.salt.clone()));
+
+        // v2-jcs hashed them too; only the redacted schemes drop them and add a fingerprint
+        for (scheme, covers_key) in [(SCHEME_V2_JCS, true), (SCHEME_V2_REDACTED, false), (SCHEME_V3, false)] {
+            let p = compute(scheme, prompt, providers, guidelines, results, &ctx, legacy.timestamp_ms).unwrap();
+            assert_eq!(verify_proof_local(&p, prompt, &rotated, guidelines, results, v.salt.clone()), !covers_key, "{}", scheme);
+            assert_eq!(p.config_fingerprint.is_some(), !covers_key, "{}", scheme);
+        }
+    }
+
+    #[test]
+    fn v2_proves_single_results_and_batches() {
+        let results = r#"[{"provider_name":"a","adherence_score":90.0},{"provider_name":"b","adherence_score":40.0},{"provider_name":"c","adherence_score":70.0}]"#;
+        let ctx = ProofContext { sdk_version: "test".into(), salt: None };
+        let p = compute_proof("hello", "[]", "[]", results, &ctx).unwrap();
+        assert_eq!((p.scheme.as_str(), p.leaf_count), (SCHEME_V3, Some(3)));
+        assert!(verify_proof_local(&p, "hello", "[]", "[]", results, None));
+        assert!(!verify_proof_local(&p, "bye", "[]", "[]", results, None));
+
+        let inc = prove_result(&p, results, 1).unwrap();
+        let b: serde_json::Value = serde_json::json!({"adherence_score": 40.0, "provider_name": "b"});
+        assert!(verify_result(&p, &b, &inc));
+        assert!(!verify_result(&p, &serde_json::json!({"provider_name":"b","adherence_score":99.0}), &inc));
+
+        // v2-jcs proofs keep verifying without a hashed timestamp
+        let v2 = compute(SCHEME_V2_JCS, "hello", "[]", "[]", results, &ctx, 0).unwrap();
+        assert!(verify_proof_local(&Proof { timestamp_ms: 42, ..v2.clone() }, "hello", "[]", "[]", results, None));
+        assert!(verify_result(&v2, &b, &prove_result(&v2, results, 1).unwrap()));
+
+        let other = compute_proof("other", "[]", "[]", "[]", &ctx).unwrap();
+        let (batch, incs) = batch(&[p.clone(), other.clone()], "test");
+        assert_eq!(batch.leaf_count, 2);
+        assert!(verify_in_batch(&other, &incs[1], &batch.root));
+        assert!(!verify_in_batch(&p, &incs[1], &batch.root));
+    }
+}
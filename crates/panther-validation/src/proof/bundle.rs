@@ -0,0 +1,227 @@
+//! Self-contained `.pantherproof` bundles.
+//!
+//! A bundle carries the proof together with every input it commits to (prompt, providers,
+//! guidelines, results and salt), so [`ProofBundle::verify`] can recompute each hash offline
+//! and say which component no longer matches. Signatures and timestamp tokens travel inside
+//! the proof; an anchor receipt, when present, is checked against the proof's hash.
+
+use super::merkle::InclusionProof;
+use super::{compute, guidelines_value, sign, strips_secrets, timestamp, Proof, ProofContext};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+pub const BUNDLE_FORMAT: &str = "panther-proof-bundle/v1";
+pub const BUNDLE_EXTENSION: &str = "pantherproof";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProofBundle {
+    pub format: String,
+    pub proof: Proof,
+    pub prompt: String,
+    /// Without credentials, which v2 proofs never hash.
+    pub providers: serde_json::Value,
+    /// Canonical pack form, so YAML and legacy arrays round-trip to the same hash.
+    pub guidelines: serde_json::Value,
+    pub results: serde_json::Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub salt: Option<String>,
+    /// Receipt from the anchoring backend; its `hash` must be the proof's `combined_hash`, or a
+    /// batch root the receipt's `inclusion` path leads to from it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anchor: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Mismatch,
+    /// Nothing to check (e.g. unsigned proof, no anchor receipt, no timestamp token).
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComponentCheck {
+    pub component: String,
+    pub status: CheckStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleReport {
+    pub checks: Vec<ComponentCheck>,
+}
+
+impl BundleReport {
+    pub fn ok(&self) -> bool { self.checks.iter().all(|c| c.status != CheckStatus::Mismatch) }
+
+    pub fn mismatches(&self) -> impl Iterator<Item = &ComponentCheck> { self.checks.iter().filter(|c| c.status == CheckStatus::Mismatch) }
+
+    fn push(&mut self, component: &str, status: CheckStatus, detail: Option<String>) {
+        self.checks.push(ComponentCheck { component: component.to_string(), status, detail });
+    }
+
+    fn compare(&mut self, component: &str, expected: &str, actual: &str) {
+        if expected == actual {
+            self.push(component, CheckStatus::Ok, None);
+        } else {
+            self.push(component, CheckStatus::Mismatch, Some(format!("proof has {}, inputs give {}", short(expected), short(actual))));
+        }
+    }
+}
+
+fn short(h: &str) -> &str { &h[..h.len().min(16)] }
+
+impl ProofBundle {
+    /// Bundles `proof` with the inputs it was computed from; the JSON arguments are the same strings
+    /// given to [`super::compute_proof`].
+    pub fn seal(proof: Proof, prompt: &str, providers_json: &str, guidelines_json: &str, results_json: &str, salt: Option<String>) -> anyhow::Result<Self> {
+        let mut providers = serde_json::from_str(providers_json).context("providers are not JSON")?;
+        // Legacy proofs hashed the credentials, so only their bundles keep them
+        if strips_secrets(&proof.scheme) {
+            providers = crate::secrets::strip_secrets(&providers);
+        }
+        let guidelines = guidelines_value(&proof.scheme, guidelines_json);
+        Ok(Self {
+            format: BUNDLE_FORMAT.to_string(),
+            proof,
+            prompt: prompt.to_string(),
+            providers,
+            guidelines,
+            results: serde_json::from_str(results_json).context("results are not JSON")?,
+            salt,
+            anchor: None,
+        })
+    }
+
+    pub fn with_anchor(mut self, receipt: serde_json::Value) -> Self {
+        self.anchor = Some(receipt);
+        self
+    }
+
+    pub fn from_json_str(s: &str) -> anyhow::Result<Self> {
+        let bundle: Self = serde_json::from_str(s).context("invalid proof bundle")?;
+        anyhow::ensure!(bundle.format == BUNDLE_FORMAT, "unsupported bundle format '{}' (expected {})", bundle.format, BUNDLE_FORMAT);
+        Ok(bundle)
+    }
+
+    pub fn to_json_string(&self) -> anyhow::Result<String> { Ok(serde_json::to_string_pretty(self)?) }
+
+    pub fn read(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        Self::from_json_str(&text).with_context(|| format!("loading {}", path.display()))
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_json_string()?).with_context(|| format!("writing {}", path.display()))
+    }
+
+    /// Recomputes every hash from the bundled inputs; with `trusted`, the signer must be pinned there.
+    pub fn verify(&self, trusted: Option<&sign::TrustedKeys>) -> BundleReport { self.verify_with_roots(trusted, None) }
+
+    /// [`Self::verify`], and with `tsa_roots` the timestamp token's TSA must chain to one of them.
+    pub fn verify_with_roots(&self, trusted: Option<&sign::TrustedKeys>, tsa_roots: Option<&timestamp::TsaRoots>) -> BundleReport {
+        let mut report = BundleReport { checks: Vec::new() };
+        let p = &self.proof;
+        let ctx = ProofContext { sdk_version: p.sdk_version.clone(), salt: self.salt.clone() };
+        let (providers, guidelines, results) = (self.providers.to_string(), self.guidelines.to_string(), self.results.to_string());
+        match compute(&p.scheme, &self.prompt, &providers, &guidelines, &results, &ctx, p.timestamp_ms) {
+            Ok(actual) => {
+                report.compare("providers", &p.providers_hash, &actual.providers_hash);
+                report.compare("guidelines", &p.guidelines_hash, &actual.guidelines_hash);
+                report.compare("results", &p.results_hash, &actual.results_hash);
+                // The input hash also covers the prompt, the salt and (from v3) the timestamp
+                report.compare("input", &p.input_hash, &actual.input_hash);
+                report.compare("combined", &p.combined_hash, &actual.combined_hash);
+                if let (Some(expected), Some(actual)) = (&p.config_fingerprint, &actual.config_fingerprint) {
+                    report.compare("config", expected, actual);
+                }
+                if p.salt_present != self.salt.is_some() {
+                    report.push("salt", CheckStatus::Mismatch, Some(format!("proof salt_present={}, bundle salt_present={}", p.salt_present, self.salt.is_some())));
+                }
+            }
+            Err(e) => report.push("scheme", CheckStatus::Mismatch, Some(e.to_string())),
+        }
+
+        match (&p.signature, trusted) {
+            (None, Some(_)) => report.push("signature", CheckStatus::Mismatch, Some("proof is not signed".into())),
+            (None, None) => report.push("signature", CheckStatus::Skipped, Some("unsigned".into())),
+            (Some(_), Some(keys)) => match keys.check(p) {
+                Ok(key_id) => report.push("signature", CheckStatus::Ok, Some(format!("trusted key {}", key_id))),
+                Err(e) => report.push("signature", CheckStatus::Mismatch, Some(e.to_string())),
+            },
+            (Some(sig), None) if sign::verify_signature(p) => report.push("signature", CheckStatus::Ok, Some(format!("key {} (not pinned)", sig.key_id))),
+            (Some(sig), None) => report.push("signature", CheckStatus::Mismatch, Some(format!("invalid signature by {}", sig.key_id))),
+        }
+
+        let inclusion = self.anchor.as_ref().and_then(|a| a.get("inclusion")).map(|i| serde_json::from_value::<InclusionProof>(i.clone()));
+        match (self.anchor.as_ref().map(|a| a.get("hash").and_then(|h| h.as_str())), inclusion) {
+            (None, _) => report.push("anchor", CheckStatus::Skipped, Some("no receipt".into())),
+            (Some(None), _) => report.push("anchor", CheckStatus::Skipped, Some("receipt has no hash".into())),
+            (Some(Some(h)), None) => report.compare("anchor", &p.combined_hash, h.trim_start_matches("0x")),
+            // Batch receipts anchor a root; the inclusion path ties the proof to it
+            (Some(Some(h)), Some(Ok(inc))) if super::verify_in_batch(p, &inc, h.trim_start_matches("0x")) => {
+                report.push("anchor", CheckStatus::Ok, Some(format!("leaf {} of batch {}", inc.leaf_index, short(&inc.root))))
+            }
+            (Some(Some(h)), Some(_)) => report.push("anchor", CheckStatus::Mismatch, Some(format!("proof is not in batch {}", short(h.trim_start_matches("0x"))))),
+        }
+
+        match (&p.timestamp_token, tsa_roots) {
+            (None, Some(_)) => report.push("timestamp", CheckStatus::Mismatch, Some("proof has no timestamp token".into())),
+            (None, None) => report.push("timestamp", CheckStatus::Skipped, Some("no token".into())),
+            (Some(_), roots) => match timestamp::verify_timestamp(p, roots) {
+                Ok(t) if t.chain == timestamp::ChainStatus::Untrusted => {
+                    report.push("timestamp", CheckStatus::Mismatch, Some(format!("untrusted TSA {}: {}", t.tsa, t.chain_detail.unwrap_or_default())))
+                }
+                Ok(t) => {
+                    let chain = if t.chain == timestamp::ChainStatus::Trusted { "trusted" } else { "chain not checked" };
+                    report.push("timestamp", CheckStatus::Ok, Some(format!("genTime {} ms by {} ({})", t.gen_time_ms, t.tsa, chain)))
+                }
+                Err(e) => report.push("timestamp", CheckStatus::Mismatch, Some(e.to_string())),
+            },
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof::compute_proof;
+
+    #[test]
+    fn round_trips_and_names_the_tampered_component() {
+        let prompt = "Posso tomar ibuprofeno na gravidez?";
+        let providers = r#"[{"type":"ollama","model":"llama3"}]"#;
+        let guidelines = "- topic: Gravidez\n  expected_terms: [médico]\n";
+        let results = r#"[{"provider_name":"ollama:llama3","adherence_score":87.5}]"#;
+        let ctx = ProofContext { sdk_version: "test".into(), salt: Some("s1".into()) };
+        let proof = compute_proof(prompt, providers, guidelines, results, &ctx).unwrap();
+        let bundle = ProofBundle::seal(proof, prompt, providers, guidelines, results, ctx.salt.clone())
+            .unwrap()
+            .with_anchor(serde_json::json!({ "backend": "file", "hash": "0x00" }));
+
+        let read = ProofBundle::from_json_str(&bundle.to_json_string().unwrap()).unwrap();
+        let report = read.verify(None);
+        let bad: Vec<&str> = report.mismatches().map(|c| c.component.as_str()).collect();
+        assert_eq!(bad, ["anchor"]);
+
+        let other = compute_proof("outra", providers, guidelines, results, &ctx).unwrap();
+        let (batch, inclusions) = crate::proof::batch(&[other, read.proof.clone()], "test");
+        let batched = read.clone().with_anchor(serde_json::json!({ "hash": batch.root, "inclusion": inclusions[1] }));
+        assert!(batched.verify(None).ok());
+        let wrong_leaf = read.clone().with_anchor(serde_json::json!({ "hash": batch.root, "inclusion": inclusions[0] }));
+        assert!(!wrong_leaf.verify(None).ok());
+
+        let mut tampered = read.clone().with_anchor(serde_json::json!({ "hash": read.proof.combined_hash }));
+        assert!(tampered.verify(None).ok());
+        tampered.results[0]["adherence_score"] = serde_json::json!(99.0);
+        let bad: Vec<String> = tampered.verify(None).mismatches().map(|c| c.component.clone()).collect();
+        assert_eq!(bad, ["results", "combined"]);
+        assert!(tampered.verify(Some(&sign::TrustedKeys::default())).mismatches().any(|c| c.component == "signature"));
+    }
+}
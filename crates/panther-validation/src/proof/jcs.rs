@@ -0,0 +1,138 @@
+//! JSON Canonicalization Scheme (RFC 8785).
+//!
+//! Object members are sorted by their UTF-16 code units, strings use the minimal
+//! ECMAScript escaping and numbers are printed like JavaScript's `Number.prototype.toString`,
+//! so any conforming implementation (Python, Swift, JS) produces the same bytes.
+//! Integers beyond 2^53 are rounded to the nearest double, as JavaScript would.
+
+use serde_json::Value;
+use std::fmt::Write;
+
+pub fn to_string(value: &Value) -> String {
+    let mut out = String::new();
+    write_value(&mut out, value);
+    out
+}
+
+pub fn to_vec(value: &Value) -> Vec<u8> { to_string(value).into_bytes() }
+
+fn write_value(out: &mut String, value: &Value) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => out.push_str(&number(n.as_f64().unwrap_or(0.0))),
+        Value::String(s) => write_string(out, s),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, v) in items.iter().enumerate() {
+                if i > 0 { out.push(','); }
+                write_value(out, v);
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.encode_utf16().cmp(b.0.encode_utf16()));
+            out.push('{');
+            for (i, (k, v)) in entries.into_iter().enumerate() {
+                if i > 0 { out.push(','); }
+                write_string(out, k);
+                out.push(':');
+                write_value(out, v);
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{08}' => out.push_str("\\b"),
+            '\u{0C}' => out.push_str("\\f"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => { let _ = write!(out, "\\u{:04x}", c as u32); }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// ECMAScript `Number.prototype.toString` for finite doubles.
+pub fn number(f: f64) -> String {
+    if f == 0.0 || !f.is_finite() { return "0".to_string(); }
+    // Rust's `{:e}` yields the shortest round-tripping digits, e.g. "1.2345e-7"
+    let sci = format!("{:e}", f.abs());
+    let (mantissa, exp) = sci.split_once('e').unwrap_or((&sci, "0"));
+    let mut digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let n = exp.parse::<i32>().unwrap_or(0) + 1;
+    round_half_even(&mut digits, f.abs(), n);
+    let k = digits.len() as i32;
+    let mut s = String::new();
+    if f < 0.0 { s.push('-'); }
+    if k <= n && n <= 21 {
+        s.push_str(&digits);
+        s.extend(std::iter::repeat_n('0', (n - k) as usize));
+    } else if 0 < n && n <= 21 {
+        s.push_str(&digits[..n as usize]);
+        s.push('.');
+        s.push_str(&digits[n as usize..]);
+    } else if -6 < n && n <= 0 {
+        s.push_str("0.");
+        s.extend(std::iter::repeat_n('0', (-n) as usize));
+        s.push_str(&digits);
+    } else {
+        s.push_str(&digits[..1]);
+        if k > 1 {
+            s.push('.');
+            s.push_str(&digits[1..]);
+        }
+        let _ = write!(s, "e{}{}", if n - 1 < 0 { '-' } else { '+' }, (n - 1).abs());
+    }
+    s
+}
+
+/// When the double sits exactly halfway between two shortest candidates Rust rounds
+/// up, while ECMAScript picks the even one.
+fn round_half_even(digits: &mut String, f: f64, n: i32) {
+    let last = digits.as_bytes()[digits.len() - 1] - b'0';
+    if last.is_multiple_of(2) { return; }
+    let mut lower = digits[..digits.len() - 1].to_string();
+    lower.push((b'0' + last - 1) as char);
+    // `{:.N$e}` prints the exact binary value once N is large enough
+    let exact = format!("{:.800e}", f);
+    let exact: String = exact.split_once('e').map_or(&exact[..], |(m, _)| m).chars().filter(|c| *c != '.').collect();
+    let tie = exact.strip_prefix(lower.as_str()).and_then(|rest| rest.strip_prefix('5')).is_some_and(|rest| rest.bytes().all(|b| b == b'0'));
+    if tie && format!("{}e{}", lower, n - lower.len() as i32).parse::<f64>() == Ok(f) {
+        *digits = lower.trim_end_matches('0').to_string();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(serde::Deserialize)]
+    struct Vectors {
+        numbers: Vec<(String, String)>,
+        documents: Vec<(String, String)>,
+    }
+
+    #[test]
+    fn matches_shared_vectors() {
+        let v: Vectors = serde_json::from_str(include_str!("../../testdata/jcs-vectors.json")).unwrap();
+        for (bits, expected) in &v.numbers {
+            let f = f64::from_bits(u64::from_str_radix(bits.trim_start_matches("0x"), 16).unwrap());
+            assert_eq!(&number(f), expected, "{bits}");
+        }
+        for (input, expected) in &v.documents {
+            let value: Value = serde_json::from_str(input).unwrap();
+            assert_eq!(&to_string(&value), expected, "{input}");
+        }
+    }
+}
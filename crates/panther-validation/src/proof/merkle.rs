@@ -0,0 +1,136 @@
+//! Merkle trees for `panther-proof-v2`.
+//!
+//! Leaves and inner nodes are SHA3-512 with distinct prefixes (`0x00` / `0x01`), so a
+//! leaf can never be passed off as an inner node. An odd node at the end of a level is
+//! promoted to the next level unchanged rather than paired with itself.
+
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_512};
+
+pub type Hash = [u8; 64];
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+pub fn leaf_hash(data: &[u8]) -> Hash {
+    let mut h = Sha3_512::new();
+    h.update([LEAF_PREFIX]);
+    h.update(data);
+    h.finalize().into()
+}
+
+fn node_hash(left: &Hash, right: &Hash) -> Hash {
+    let mut h = Sha3_512::new();
+    h.update([NODE_PREFIX]);
+    h.update(left);
+    h.update(right);
+    h.finalize().into()
+}
+
+fn from_hex(s: &str) -> Option<Hash> { hex::decode(s).ok()?.try_into().ok() }
+
+#[derive(Debug, Clone)]
+pub struct MerkleTree {
+    /// `levels[0]` are the leaves, the last level holds the root.
+    levels: Vec<Vec<Hash>>,
+}
+
+impl MerkleTree {
+    /// An empty tree has the root `leaf_hash(b"")`.
+    pub fn from_leaves(leaves: Vec<Hash>) -> Self {
+        let mut levels = vec![leaves];
+        while levels.last().is_some_and(|l| l.len() > 1) {
+            let next = levels.last().unwrap().chunks(2).map(|c| if c.len() == 2 { node_hash(&c[0], &c[1]) } else { c[0] }).collect();
+            levels.push(next);
+        }
+        Self { levels }
+    }
+
+    pub fn len(&self) -> usize { self.levels[0].len() }
+
+    pub fn is_empty(&self) -> bool { self.levels[0].is_empty() }
+
+    pub fn root(&self) -> Hash { self.levels.last().and_then(|l| l.first()).copied().unwrap_or_else(|| leaf_hash(b"")) }
+
+    pub fn root_hex(&self) -> String { hex::encode(self.root()) }
+
+    /// Sibling path from leaf `index` up to the root.
+    pub fn prove(&self, index: usize) -> Option<InclusionProof> {
+        let leaf = *self.levels[0].get(index)?;
+        let mut path = Vec::new();
+        let mut i = index;
+        for level in &self.levels[..self.levels.len() - 1] {
+            let sibling = i ^ 1;
+            if let Some(h) = level.get(sibling) {
+                let side = if sibling < i { Side::Left } else { Side::Right };
+                path.push(ProofStep { side, hash: hex::encode(h) });
+            }
+            i /= 2;
+        }
+        Some(InclusionProof { leaf_index: index, leaf_count: self.len(), leaf_hash: hex::encode(leaf), path, root: self.root_hex() })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofStep {
+    /// Which side of the running hash the sibling goes on.
+    pub side: Side,
+    pub hash: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InclusionProof {
+    pub leaf_index: usize,
+    pub leaf_count: usize,
+    pub leaf_hash: String,
+    pub path: Vec<ProofStep>,
+    pub root: String,
+}
+
+impl InclusionProof {
+    /// Recomputes the root from `leaf_hash` and the path.
+    pub fn verify(&self) -> bool {
+        let Some(mut acc) = from_hex(&self.leaf_hash) else { return false };
+        for step in &self.path {
+            let Some(sib) = from_hex(&step.hash) else { return false };
+            acc = match step.side {
+                Side::Left => node_hash(&sib, &acc),
+                Side::Right => node_hash(&acc, &sib),
+            };
+        }
+        hex::encode(acc) == self.root
+    }
+
+    /// Like [`Self::verify`], also checking that `data` is the leaf.
+    pub fn verify_leaf(&self, data: &[u8]) -> bool { hex::encode(leaf_hash(data)) == self.leaf_hash && self.verify() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_leaf_proves_against_the_root() {
+        for n in 1..=7usize {
+            let data: Vec<Vec<u8>> = (0..n).map(|i| format!("item-{i}").into_bytes()).collect();
+            let tree = MerkleTree::from_leaves(data.iter().map(|d| leaf_hash(d)).collect());
+            for (i, d) in data.iter().enumerate() {
+                let p = tree.prove(i).unwrap();
+                assert!(p.verify_leaf(d), "n={n} i={i}");
+                assert!(!p.verify_leaf(b"other"));
+            }
+        }
+        let tree = MerkleTree::from_leaves(vec![leaf_hash(b"a"), leaf_hash(b"b"), leaf_hash(b"c")]);
+        let mut p = tree.prove(0).unwrap();
+        p.path[0].side = Side::Left;
+        assert!(!p.verify());
+        assert_eq!(MerkleTree::from_leaves(vec![]).root(), leaf_hash(b""));
+    }
+}
@@ -0,0 +1,303 @@
+//! Signatures over proofs, so a proof also says which deployment produced it.
+//!
+//! A signer holds an Ed25519 key (or a secp256k1 key with the `secp256k1` feature) and a
+//! key id. It signs the RFC 8785 form of `{format, scheme, combined_hash, key_id,
+//! signed_at_ms}` and stores the result in `Proof::signature` together with the public
+//! key, so a signed proof is self-contained. [`TrustedKeys`] pins key ids to public keys
+//! for auditors who must reject proofs signed by anyone else.
+
+use super::{jcs, Proof};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_512};
+use std::collections::HashMap;
+use std::path::Path;
+
+pub const SIGNATURE_FORMAT: &str = "panther-proof-sig-v1";
+
+/// Hex secret key; takes precedence over `PANTHER_PROOF_KEY_FILE`.
+pub const ENV_KEY: &str = "PANTHER_PROOF_KEY";
+/// Path to a key file (see [`ProofSigner::load`]).
+pub const ENV_KEY_FILE: &str = "PANTHER_PROOF_KEY_FILE";
+pub const ENV_KEY_ID: &str = "PANTHER_PROOF_KEY_ID";
+/// `ed25519` (default) or `secp256k1`.
+pub const ENV_KEY_ALG: &str = "PANTHER_PROOF_KEY_ALG";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureAlg {
+    Ed25519,
+    Secp256k1,
+}
+
+impl std::str::FromStr for SignatureAlg {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "ed25519" => Ok(Self::Ed25519),
+            "secp256k1" => Ok(Self::Secp256k1),
+            other => anyhow::bail!("unknown signature algorithm: {}", other),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofSignature {
+    pub format: String,
+    pub alg: SignatureAlg,
+    pub key_id: String,
+    /// Hex; Ed25519 raw key or SEC1 compressed secp256k1 point.
+    pub public_key: String,
+    pub signed_at_ms: i64,
+    /// Hex; Ed25519 signature or secp256k1 ECDSA (SHA-256, r || s).
+    pub signature: String,
+}
+
+enum Key {
+    Ed25519(ed25519_dalek::SigningKey),
+    #[cfg(feature = "secp256k1")]
+    Secp256k1(k256::ecdsa::SigningKey),
+}
+
+/// On-disk key file: `{"alg": "ed25519", "key_id": "prod-eu-1", "secret_key": "<hex>"}`.
+#[derive(Deserialize)]
+struct KeyFile {
+    #[serde(default)]
+    alg: Option<SignatureAlg>,
+    #[serde(default)]
+    key_id: Option<String>,
+    secret_key: String,
+}
+
+pub struct ProofSigner {
+    key: Key,
+    key_id: String,
+}
+
+impl std::fmt::Debug for ProofSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProofSigner").field("alg", &self.alg()).field("key_id", &self.key_id).finish_non_exhaustive()
+    }
+}
+
+impl ProofSigner {
+    /// `secret_hex` is a 32-byte key for either algorithm; `key_id` defaults to [`default_key_id`].
+    pub fn from_secret_hex(alg: SignatureAlg, secret_hex: &str, key_id: Option<String>) -> anyhow::Result<Self> {
+        let bytes: [u8; 32] = hex::decode(secret_hex.trim().trim_start_matches("0x"))
+            .context("secret key is not hex")?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("secret key must be 32 bytes"))?;
+        let key = match alg {
+            SignatureAlg::Ed25519 => Key::Ed25519(ed25519_dalek::SigningKey::from_bytes(&bytes)),
+            #[cfg(feature = "secp256k1")]
+            SignatureAlg::Secp256k1 => Key::Secp256k1(k256::ecdsa::SigningKey::from_bytes(&bytes.into()).context("invalid secp256k1 key")?),
+            #[cfg(not(feature = "secp256k1"))]
+            SignatureAlg::Secp256k1 => anyhow::bail!("secp256k1 signing needs the `secp256k1` feature"),
+        };
+        let mut signer = Self { key, key_id: String::new() };
+        signer.key_id = key_id.filter(|k| !k.is_empty()).unwrap_or_else(|| default_key_id(signer.alg(), &signer.public_key_hex()));
+        Ok(signer)
+    }
+
+    /// Reads a JSON key file (see [`KeyFile`] above); `alg` defaults to Ed25519.
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).with_context(|| format!("reading key file {}", path.display()))?;
+        let file: KeyFile = serde_json::from_str(&text).with_context(|| format!("parsing key file {}", path.display()))?;
+        Self::from_secret_hex(file.alg.unwrap_or(SignatureAlg::Ed25519), &file.secret_key, file.key_id)
+    }
+
+    /// Signer configured through `PANTHER_PROOF_KEY*`, or `None` when no key is set.
+    pub fn from_env() -> anyhow::Result<Option<Self>> {
+        let key_id = std::env::var(ENV_KEY_ID).ok();
+        if let Ok(secret) = std::env::var(ENV_KEY) {
+            let alg = match std::env::var(ENV_KEY_ALG) {
+                Ok(a) => a.parse()?,
+                Err(_) => SignatureAlg::Ed25519,
+            };
+            return Self::from_secret_hex(alg, &secret, key_id).map(Some);
+        }
+        match std::env::var(ENV_KEY_FILE) {
+            Ok(path) => {
+                let mut signer = Self::load(path)?;
+                if let Some(id) = key_id.filter(|k| !k.is_empty()) {
+                    signer.key_id = id;
+                }
+                Ok(Some(signer))
+            }
+            Err(_) => Ok(None),
+        }
+    }
+
+    pub fn alg(&self) -> SignatureAlg {
+        match &self.key {
+            Key::Ed25519(_) => SignatureAlg::Ed25519,
+            #[cfg(feature = "secp256k1")]
+            Key::Secp256k1(_) => SignatureAlg::Secp256k1,
+        }
+    }
+
+    pub fn key_id(&self) -> &str { &self.key_id }
+
+    pub fn public_key_hex(&self) -> String {
+        match &self.key {
+            Key::Ed25519(k) => hex::encode(k.verifying_key().to_bytes()),
+            #[cfg(feature = "secp256k1")]
+            Key::Secp256k1(k) => hex::encode(k.verifying_key().to_encoded_point(true).as_bytes()),
+        }
+    }
+
+    /// Signs `proof` in place, replacing any previous signature.
+    pub fn sign(&self, proof: &mut Proof) {
+        let mut sig = ProofSignature {
+            format: SIGNATURE_FORMAT.to_string(),
+            alg: self.alg(),
+            key_id: self.key_id.clone(),
+            public_key: self.public_key_hex(),
+            signed_at_ms: super::now_ms(),
+            signature: String::new(),
+        };
+        let msg = message(proof, &sig);
+        sig.signature = match &self.key {
+            Key::Ed25519(k) => {
+                use ed25519_dalek::Signer;
+                hex::encode(k.sign(&msg).to_bytes())
+            }
+            #[cfg(feature = "secp256k1")]
+            Key::Secp256k1(k) => {
+                use k256::ecdsa::signature::Signer;
+                let s: k256::ecdsa::Signature = k.sign(&msg);
+                hex::encode(s.to_bytes())
+            }
+        };
+        proof.signature = Some(sig);
+    }
+}
+
+/// `<alg>:` plus the first 16 hex digits of SHA3-512 over the public key.
+pub fn default_key_id(alg: SignatureAlg, public_key_hex: &str) -> String {
+    let digest = Sha3_512::digest(public_key_hex.as_bytes());
+    let alg = serde_json::to_value(alg).ok().and_then(|v| v.as_str().map(str::to_string)).unwrap_or_default();
+    format!("{}:{}", alg, &hex::encode(digest)[..16])
+}
+
+fn message(proof: &Proof, sig: &ProofSignature) -> Vec<u8> {
+    jcs::to_vec(&serde_json::json!({
+        "format": sig.format,
+        "scheme": proof.scheme,
+        "combined_hash": proof.combined_hash,
+        "key_id": sig.key_id,
+        "signed_at_ms": sig.signed_at_ms,
+    }))
+}
+
+fn verify_with(alg: SignatureAlg, public_key: &[u8], msg: &[u8], signature: &[u8]) -> bool {
+    match alg {
+        SignatureAlg::Ed25519 => {
+            use ed25519_dalek::Verifier;
+            let (Ok(pk), Ok(sig)) = (<[u8; 32]>::try_from(public_key), ed25519_dalek::Signature::from_slice(signature)) else { return false };
+            ed25519_dalek::VerifyingKey::from_bytes(&pk).is_ok_and(|vk| vk.verify(msg, &sig).is_ok())
+        }
+        #[cfg(feature = "secp256k1")]
+        SignatureAlg::Secp256k1 => {
+            use k256::ecdsa::signature::Verifier;
+            let (Ok(vk), Ok(sig)) = (k256::ecdsa::VerifyingKey::from_sec1_bytes(public_key), k256::ecdsa::Signature::from_slice(signature)) else { return false };
+            vk.verify(msg, &sig).is_ok()
+        }
+        #[cfg(not(feature = "secp256k1"))]
+        SignatureAlg::Secp256k1 => false,
+    }
+}
+
+/// Checks the embedded signature against the embedded public key; `false` when unsigned.
+pub fn verify_signature(proof: &Proof) -> bool {
+    let Some(sig) = &proof.signature else { return false };
+    if sig.format != SIGNATURE_FORMAT { return false; }
+    let (Ok(pk), Ok(bytes)) = (hex::decode(&sig.public_key), hex::decode(&sig.signature)) else { return false };
+    verify_with(sig.alg, &pk, &message(proof, sig), &bytes)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrustedKey {
+    pub alg: SignatureAlg,
+    pub public_key: String,
+}
+
+/// Key ids an auditor accepts, e.g. `{"prod-eu-1": {"alg": "ed25519", "public_key": "<hex>"}}`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TrustedKeys(pub HashMap<String, TrustedKey>);
+
+impl TrustedKeys {
+    pub fn from_json_str(s: &str) -> anyhow::Result<Self> { Ok(serde_json::from_str(s)?) }
+
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        Self::from_json_str(&std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?)
+    }
+
+    pub fn insert(&mut self, key_id: impl Into<String>, alg: SignatureAlg, public_key: impl Into<String>) {
+        self.0.insert(key_id.into(), TrustedKey { alg, public_key: public_key.into() });
+    }
+
+    /// Key id of a valid signature by a trusted key; errors say why the proof is rejected.
+    pub fn check(&self, proof: &Proof) -> anyhow::Result<String> {
+        let sig = proof.signature.as_ref().ok_or_else(|| anyhow::anyhow!("proof is not signed"))?;
+        let trusted = self.0.get(&sig.key_id).ok_or_else(|| anyhow::anyhow!("unknown key id: {}", sig.key_id))?;
+        anyhow::ensure!(
+            trusted.alg == sig.alg && trusted.public_key.eq_ignore_ascii_case(&sig.public_key),
+            "public key does not match trusted key {}",
+            sig.key_id
+        );
+        anyhow::ensure!(verify_signature(proof), "invalid signature by {}", sig.key_id);
+        Ok(sig.key_id.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof::{compute_proof, verify_proof_local, ProofContext};
+
+    #[test]
+    fn signed_proofs_verify_and_name_their_key() {
+        let ctx = ProofContext { sdk_version: "test".into(), salt: None };
+        let results = r#"[{"provider_name":"ollama:llama3","adherence_score":90.0}]"#;
+        let mut proof = compute_proof("oi", "[]", "[]", results, &ctx).unwrap();
+        let signer = ProofSigner::from_secret_hex(SignatureAlg::Ed25519, &"07".repeat(32), Some("prod-eu-1".into())).unwrap();
+        signer.sign(&mut proof);
+        assert!(verify_signature(&proof));
+        assert!(verify_proof_local(&proof, "oi", "[]", "[]", results, None));
+
+        let mut trusted = TrustedKeys::default();
+        trusted.insert("prod-eu-1", SignatureAlg::Ed25519, signer.public_key_hex());
+        assert_eq!(trusted.check(&proof).unwrap(), "prod-eu-1");
+
+        // A different key claiming the same id is rejected by the auditor
+        let impostor = ProofSigner::from_secret_hex(SignatureAlg::Ed25519, &"08".repeat(32), Some("prod-eu-1".into())).unwrap();
+        let mut forged = proof.clone();
+        impostor.sign(&mut forged);
+        assert!(verify_signature(&forged));
+        assert!(trusted.check(&forged).is_err());
+
+        let mut tampered = proof.clone();
+        tampered.combined_hash = "00".repeat(64);
+        assert!(!verify_signature(&tampered));
+        let mut relabelled = proof;
+        relabelled.signature.as_mut().unwrap().key_id = "other".into();
+        assert!(!verify_signature(&relabelled));
+        assert!(!verify_proof_local(&relabelled, "oi", "[]", "[]", results, None));
+    }
+
+    #[cfg(feature = "secp256k1")]
+    #[test]
+    fn secp256k1_round_trip() {
+        let ctx = ProofContext { sdk_version: "test".into(), salt: None };
+        let mut proof = compute_proof("oi", "[]", "[]", "[]", &ctx).unwrap();
+        let signer = ProofSigner::from_secret_hex(SignatureAlg::Secp256k1, &"07".repeat(32), None).unwrap();
+        signer.sign(&mut proof);
+        assert!(signer.key_id().starts_with("secp256k1:"));
+        assert!(verify_signature(&proof));
+    }
+}
@@ -0,0 +1,673 @@
+//! RFC 3161 trusted timestamps for proofs.
+//!
+//! `Proof::timestamp_ms` comes from the machine that computed the proof. [`stamp`] asks a
+//! timestamping authority to countersign the proof's `combined_hash` (as the SHA-512 imprint
+//! of its raw bytes) and keeps the returned token in `Proof::timestamp_token`. [`verify_timestamp`]
+//! checks the token offline: imprint, CMS signature, and — given [`TsaRoots`] — the TSA's
+//! certificate chain at the time it signed. From `panther-proof-v3` on `timestamp_ms` is hashed
+//! too, so a stamped v3 proof cannot claim to be younger than its token.
+//!
+//! [`HttpTsa`] talks to a real TSA; [`LocalTsa`] is an in-process one with a throwaway CA for tests.
+
+pub mod der;
+
+use super::{Proof, SCHEME_V3};
+use crate::now_ms;
+use anyhow::Context;
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD as B64;
+use base64::Engine;
+use der::Tlv;
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::signature::{self, EcdsaKeyPair, KeyPair};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+const OID_SIGNED_DATA: &str = "1.2.840.113549.1.7.2";
+const OID_TST_INFO: &str = "1.2.840.113549.1.9.16.1.4";
+const OID_CONTENT_TYPE: &str = "1.2.840.113549.1.9.3";
+const OID_MESSAGE_DIGEST: &str = "1.2.840.113549.1.9.4";
+const OID_SIGNING_CERT: &str = "1.2.840.113549.1.9.16.2.12";
+const OID_SIGNING_CERT_V2: &str = "1.2.840.113549.1.9.16.2.47";
+const OID_SHA1: &str = "1.3.14.3.2.26";
+const OID_SHA256: &str = "2.16.840.1.101.3.4.2.1";
+const OID_SHA384: &str = "2.16.840.1.101.3.4.2.2";
+const OID_SHA512: &str = "2.16.840.1.101.3.4.2.3";
+const OID_EC_PUBLIC_KEY: &str = "1.2.840.10045.2.1";
+const OID_P256: &str = "1.2.840.10045.3.1.7";
+const OID_P384: &str = "1.3.132.0.34";
+const OID_ECDSA_SHA256: &str = "1.2.840.10045.4.3.2";
+const OID_ECDSA_SHA384: &str = "1.2.840.10045.4.3.3";
+const OID_RSA: &str = "1.2.840.113549.1.1.1";
+const OID_RSA_SHA256: &str = "1.2.840.113549.1.1.11";
+const OID_RSA_SHA384: &str = "1.2.840.113549.1.1.12";
+const OID_RSA_SHA512: &str = "1.2.840.113549.1.1.13";
+const OID_COMMON_NAME: &str = "2.5.4.3";
+const OID_BASIC_CONSTRAINTS: &str = "2.5.29.19";
+const OID_KEY_USAGE: &str = "2.5.29.15";
+const OID_EXT_KEY_USAGE: &str = "2.5.29.37";
+const OID_TIME_STAMPING: &str = "1.3.6.1.5.5.7.3.8";
+// id-kp-timeStamping, encoded, for webpki's EKU check
+const TIME_STAMPING_EKU: &[u8] = &[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x03, 0x08];
+
+/// Policy [`LocalTsa`] stamps under (an arc reserved for examples).
+pub const LOCAL_TSA_POLICY: &str = "2.999.3161.1";
+/// How far a v3 proof's own `timestamp_ms` may run ahead of the TSA's clock.
+pub const MAX_SKEW_MS: i64 = 5 * 60 * 1000;
+
+const CHAIN_ALGS: &[&dyn rustls_pki_types::SignatureVerificationAlgorithm] = &[
+    webpki::ring::ECDSA_P256_SHA256,
+    webpki::ring::ECDSA_P256_SHA384,
+    webpki::ring::ECDSA_P384_SHA256,
+    webpki::ring::ECDSA_P384_SHA384,
+    webpki::ring::RSA_PKCS1_2048_8192_SHA256,
+    webpki::ring::RSA_PKCS1_2048_8192_SHA384,
+    webpki::ring::RSA_PKCS1_2048_8192_SHA512,
+    webpki::ring::RSA_PSS_2048_8192_SHA256_LEGACY_KEY,
+    webpki::ring::RSA_PSS_2048_8192_SHA384_LEGACY_KEY,
+    webpki::ring::RSA_PSS_2048_8192_SHA512_LEGACY_KEY,
+];
+
+/// A TSA's countersignature of `combined_hash`; not part of any hash.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimestampToken {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tsa: Option<String>,
+    /// The token's `genTime`, copied out for display.
+    pub gen_time_ms: i64,
+    /// DER `TimeStampToken` (CMS SignedData), base64.
+    pub token: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChainStatus {
+    /// The TSA certificate chains to one of the given roots and may stamp.
+    Trusted,
+    Untrusted,
+    /// No roots given; only the token's own signature was checked.
+    Unchecked,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimestampReport {
+    pub gen_time_ms: i64,
+    /// The proof's `timestamp_ms` minus `gen_time_ms`.
+    pub skew_ms: i64,
+    /// Subject of the certificate that signed the token.
+    pub tsa: String,
+    pub policy: String,
+    pub serial: String,
+    pub chain: ChainStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_detail: Option<String>,
+}
+
+#[async_trait]
+pub trait TimestampAuthority: Send + Sync {
+    /// DER `TimeStampResp` for a DER `TimeStampReq`.
+    async fn respond(&self, request: &[u8]) -> anyhow::Result<Vec<u8>>;
+
+    /// Recorded in the token so a reader knows where it came from.
+    fn url(&self) -> Option<&str> { None }
+}
+
+/// A TSA reached over HTTP (`application/timestamp-query`).
+pub struct HttpTsa {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl HttpTsa {
+    pub fn new(url: impl Into<String>) -> Self { Self { url: url.into(), client: reqwest::Client::new() } }
+}
+
+#[async_trait]
+impl TimestampAuthority for HttpTsa {
+    async fn respond(&self, request: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let res = self
+            .client
+            .post(&self.url)
+            .header("Content-Type", "application/timestamp-query")
+            .header("Accept", "application/timestamp-reply")
+            .body(request.to_vec())
+            .send()
+            .await
+            .with_context(|| format!("contacting TSA {}", self.url))?
+            .error_for_status()?;
+        Ok(res.bytes().await?.to_vec())
+    }
+
+    fn url(&self) -> Option<&str> { Some(&self.url) }
+}
+
+/// Trust anchors for TSA certificates.
+#[derive(Debug, Clone, Default)]
+pub struct TsaRoots {
+    certs: Vec<Vec<u8>>,
+}
+
+impl TsaRoots {
+    /// Every `CERTIFICATE` block of a PEM file.
+    pub fn from_pem_str(pem: &str) -> anyhow::Result<Self> {
+        let mut certs = Vec::new();
+        let mut body: Option<String> = None;
+        for line in pem.lines().map(str::trim) {
+            match (line, body.as_mut()) {
+                ("-----BEGIN CERTIFICATE-----", _) => body = Some(String::new()),
+                ("-----END CERTIFICATE-----", Some(b)) => {
+                    certs.push(B64.decode(b.as_bytes()).context("bad base64 in PEM certificate")?);
+                    body = None;
+                }
+                (l, Some(b)) => b.push_str(l),
+                _ => {}
+            }
+        }
+        anyhow::ensure!(!certs.is_empty(), "no certificates in PEM");
+        Ok(Self { certs })
+    }
+
+    pub fn from_der(der: Vec<u8>) -> Self { Self { certs: vec![der] } }
+
+    /// PEM (one or more certificates) or a single DER certificate.
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+        match std::str::from_utf8(&bytes) {
+            Ok(text) if text.contains("-----BEGIN") => Self::from_pem_str(text),
+            _ => Ok(Self::from_der(bytes)),
+        }
+    }
+}
+
+// ---- Parsing and checking tokens ----
+
+struct TstInfo<'a> {
+    policy: String,
+    imprint_alg: String,
+    imprint: &'a [u8],
+    serial: &'a [u8],
+    gen_time_ms: i64,
+    nonce: Option<&'a [u8]>,
+}
+
+struct Cert<'a> {
+    raw: &'a [u8],
+    serial: &'a [u8],
+    issuer: &'a [u8],
+    subject: Tlv<'a>,
+    spki: Tlv<'a>,
+}
+
+struct Checked<'a> {
+    tst: TstInfo<'a>,
+    signer: Cert<'a>,
+    certs: Vec<&'a [u8]>,
+}
+
+fn alg_oid(alg: &Tlv<'_>) -> anyhow::Result<String> { Ok(der::oid_to_string(alg.reader().expect(der::OID)?.value)) }
+
+fn digest(alg_oid: &str, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let alg = match alg_oid {
+        OID_SHA1 => &ring::digest::SHA1_FOR_LEGACY_USE_ONLY,
+        OID_SHA256 => &ring::digest::SHA256,
+        OID_SHA384 => &ring::digest::SHA384,
+        OID_SHA512 => &ring::digest::SHA512,
+        other => anyhow::bail!("unsupported digest algorithm {}", other),
+    };
+    Ok(ring::digest::digest(alg, data).as_ref().to_vec())
+}
+
+fn digest_algorithm(oid: &str) -> Vec<u8> { der::sequence(&[&der::oid(oid), &der::null()]) }
+
+// The datum a TSA stamps: SHA-512 over the raw bytes of `combined_hash`
+fn imprint(proof: &Proof) -> anyhow::Result<Vec<u8>> {
+    let hash = hex::decode(proof.combined_hash.trim_start_matches("0x")).context("combined_hash is not hex")?;
+    digest(OID_SHA512, &hash)
+}
+
+fn parse_cert(raw: &[u8]) -> anyhow::Result<Cert<'_>> {
+    let cert = der::parse(raw)?;
+    let tbs = cert.reader().expect(der::SEQUENCE)?;
+    let mut r = tbs.reader();
+    r.optional(der::context(0, true))?;
+    let serial = r.expect(der::INTEGER)?.value;
+    r.expect(der::SEQUENCE)?;
+    let issuer = r.expect(der::SEQUENCE)?.raw;
+    r.expect(der::SEQUENCE)?;
+    let subject = r.expect(der::SEQUENCE)?;
+    let spki = r.expect(der::SEQUENCE)?;
+    Ok(Cert { raw, serial, issuer, subject, spki })
+}
+
+/// `CN=..., O=...` rendering of a DER `Name`.
+fn name_string(name: &Tlv<'_>) -> String {
+    let mut parts = Vec::new();
+    let mut rdns = name.reader();
+    while let Ok(rdn) = rdns.read() {
+        let mut atvs = rdn.reader();
+        while let Ok(atv) = atvs.read() {
+            let mut r = atv.reader();
+            let (Ok(oid), Ok(value)) = (r.read(), r.read()) else { continue };
+            let key = match der::oid_to_string(oid.value).as_str() {
+                OID_COMMON_NAME => "CN".to_string(),
+                "2.5.4.6" => "C".to_string(),
+                "2.5.4.10" => "O".to_string(),
+                "2.5.4.11" => "OU".to_string(),
+                other => other.to_string(),
+            };
+            parts.push(format!("{}={}", key, String::from_utf8_lossy(value.value)));
+        }
+    }
+    parts.join(", ")
+}
+
+fn verify_signature(spki: &Tlv<'_>, sig_alg: &str, digest_alg: &str, message: &[u8], sig: &[u8]) -> anyhow::Result<()> {
+    let mut r = spki.reader();
+    let alg = r.expect(der::SEQUENCE)?;
+    let key = r.expect(der::BIT_STRING)?.value.get(1..).unwrap_or_default();
+    let mut a = alg.reader();
+    let key_alg = der::oid_to_string(a.expect(der::OID)?.value);
+    let hash = match sig_alg {
+        OID_ECDSA_SHA256 | OID_RSA_SHA256 => OID_SHA256,
+        OID_ECDSA_SHA384 | OID_RSA_SHA384 => OID_SHA384,
+        OID_RSA_SHA512 => OID_SHA512,
+        // Plain rsaEncryption: the digest algorithm decides
+        OID_RSA => digest_alg,
+        other => anyhow::bail!("unsupported signature algorithm {}", other),
+    };
+    let verify_alg: &dyn signature::VerificationAlgorithm = match (key_alg.as_str(), hash) {
+        (OID_EC_PUBLIC_KEY, _) => {
+            let curve = a.optional(der::OID)?.map(|c| der::oid_to_string(c.value)).unwrap_or_default();
+            match (curve.as_str(), hash) {
+                (OID_P256, OID_SHA256) => &signature::ECDSA_P256_SHA256_ASN1,
+                (OID_P256, OID_SHA384) => &signature::ECDSA_P256_SHA384_ASN1,
+                (OID_P384, OID_SHA256) => &signature::ECDSA_P384_SHA256_ASN1,
+                (OID_P384, OID_SHA384) => &signature::ECDSA_P384_SHA384_ASN1,
+                _ => anyhow::bail!("unsupported ECDSA curve {} with {}", curve, hash),
+            }
+        }
+        (OID_RSA, OID_SHA256) => &signature::RSA_PKCS1_2048_8192_SHA256,
+        (OID_RSA, OID_SHA384) => &signature::RSA_PKCS1_2048_8192_SHA384,
+        (OID_RSA, OID_SHA512) => &signature::RSA_PKCS1_2048_8192_SHA512,
+        _ => anyhow::bail!("unsupported key {} with {}", key_alg, hash),
+    };
+    signature::UnparsedPublicKey::new(verify_alg, key).verify(message, sig).map_err(|_| anyhow::anyhow!("token signature does not verify"))
+}
+
+fn parse_tst_info(econtent: &[u8]) -> anyhow::Result<TstInfo<'_>> {
+    let tst = der::parse(econtent)?;
+    let mut r = tst.reader();
+    r.expect(der::INTEGER)?;
+    let policy = der::oid_to_string(r.expect(der::OID)?.value);
+    let mi = r.expect(der::SEQUENCE)?;
+    let mut m = mi.reader();
+    let imprint_alg = alg_oid(&m.expect(der::SEQUENCE)?)?;
+    let imprint = m.expect(der::OCTET_STRING)?.value;
+    let serial = r.expect(der::INTEGER)?.value;
+    let gen_time_ms = der::parse_time(&r.expect(der::GENERALIZED_TIME)?)?;
+    r.optional(der::SEQUENCE)?; // accuracy
+    r.optional(der::BOOLEAN)?; // ordering
+    let nonce = r.optional(der::INTEGER)?.map(|n| n.raw);
+    Ok(TstInfo { policy, imprint_alg, imprint, serial, gen_time_ms, nonce })
+}
+
+// Hash algorithm and hash of the first ESSCertID(v2) in a signing certificate attribute
+fn signing_cert_hash(value: &Tlv<'_>, v2: bool) -> anyhow::Result<(String, Vec<u8>)> {
+    let certs = value.reader().expect(der::SEQUENCE)?;
+    let id = certs.reader().expect(der::SEQUENCE)?;
+    let mut r = id.reader();
+    let alg = match (v2, r.peek_tag()) {
+        (true, Some(der::SEQUENCE)) => alg_oid(&r.read()?)?,
+        (true, _) => OID_SHA256.to_string(),
+        (false, _) => OID_SHA1.to_string(),
+    };
+    Ok((alg, r.expect(der::OCTET_STRING)?.value.to_vec()))
+}
+
+/// Parses a DER `TimeStampToken` and checks that it is a valid TSA signature over `expected_imprint`.
+fn check_token<'a>(token: &'a [u8], expected_imprint: &[u8]) -> anyhow::Result<Checked<'a>> {
+    let content_info = der::parse(token).context("timestamp token is not DER")?;
+    let mut r = content_info.reader();
+    anyhow::ensure!(der::oid_to_string(r.expect(der::OID)?.value) == OID_SIGNED_DATA, "timestamp token is not CMS SignedData");
+    let signed_data = r.expect(der::context(0, true))?.reader().expect(der::SEQUENCE)?;
+    let mut sd = signed_data.reader();
+    sd.expect(der::INTEGER)?;
+    sd.expect(der::SET)?;
+    let encap = sd.expect(der::SEQUENCE)?;
+    let mut e = encap.reader();
+    anyhow::ensure!(der::oid_to_string(e.expect(der::OID)?.value) == OID_TST_INFO, "token does not carry a TSTInfo");
+    let econtent = e.expect(der::context(0, true))?.reader().expect(der::OCTET_STRING)?.value;
+    let mut certs = Vec::new();
+    if let Some(set) = sd.optional(der::context(0, true))? {
+        let mut c = set.reader();
+        while !c.is_empty() {
+            certs.push(c.read()?.raw);
+        }
+    }
+    sd.optional(der::context(1, true))?;
+    let signer_info = sd.expect(der::SET)?.reader().expect(der::SEQUENCE)?;
+
+    let mut si = signer_info.reader();
+    si.expect(der::INTEGER)?;
+    let sid = si.read()?;
+    let digest_alg = alg_oid(&si.expect(der::SEQUENCE)?)?;
+    let signed_attrs = si.expect(der::context(0, true)).context("token has no signed attributes")?;
+    let sig_alg = alg_oid(&si.expect(der::SEQUENCE)?)?;
+    let sig = si.expect(der::OCTET_STRING)?.value;
+
+    let (mut content_type, mut message_digest, mut signing_cert) = (None, None, None);
+    let mut attrs = signed_attrs.reader();
+    while !attrs.is_empty() {
+        let attr = attrs.expect(der::SEQUENCE)?;
+        let mut a = attr.reader();
+        let oid = der::oid_to_string(a.expect(der::OID)?.value);
+        let value = a.expect(der::SET)?.reader().read()?;
+        match oid.as_str() {
+            OID_CONTENT_TYPE => content_type = Some(der::oid_to_string(value.value)),
+            OID_MESSAGE_DIGEST => message_digest = Some(value.value),
+            OID_SIGNING_CERT_V2 => signing_cert = Some(signing_cert_hash(&value, true)?),
+            OID_SIGNING_CERT if signing_cert.is_none() => signing_cert = Some(signing_cert_hash(&value, false)?),
+            _ => {}
+        }
+    }
+    anyhow::ensure!(content_type.as_deref() == Some(OID_TST_INFO), "signed content type is not TSTInfo");
+    anyhow::ensure!(message_digest == Some(digest(&digest_alg, econtent)?.as_slice()), "TSTInfo does not match the signed digest");
+
+    // RFC 3161 names the signer through the signing certificate attribute
+    let (cert_alg, cert_hash) = signing_cert.ok_or_else(|| anyhow::anyhow!("token has no signing certificate attribute"))?;
+    let mut signer = None;
+    for raw in &certs {
+        if digest(&cert_alg, raw)? == cert_hash {
+            signer = Some(parse_cert(raw)?);
+        }
+    }
+    let signer = signer.ok_or_else(|| anyhow::anyhow!("token does not include the TSA certificate"))?;
+    if sid.tag == der::SEQUENCE {
+        let mut s = sid.reader();
+        anyhow::ensure!(s.read()?.raw == signer.issuer && s.read()?.value == signer.serial, "signer id does not match the TSA certificate");
+    }
+    verify_signature(&signer.spki, &sig_alg, &digest_alg, &der::tlv(der::SET, signed_attrs.value), sig)?;
+
+    let tst = parse_tst_info(econtent)?;
+    anyhow::ensure!(tst.imprint_alg == OID_SHA512 && tst.imprint == expected_imprint, "token is for a different hash");
+    Ok(Checked { tst, signer, certs })
+}
+
+// The TSA certificate must chain to a root and be valid for timestamping when it signed
+fn check_chain(checked: &Checked<'_>, roots: &TsaRoots) -> Result<String, String> {
+    use rustls_pki_types::{CertificateDer, UnixTime};
+    let ee_der = CertificateDer::from(checked.signer.raw);
+    let ee = webpki::EndEntityCert::try_from(&ee_der).map_err(|e| format!("TSA certificate: {:?}", e))?;
+    let root_ders: Vec<CertificateDer<'_>> = roots.certs.iter().map(|c| CertificateDer::from(c.as_slice())).collect();
+    let anchors = root_ders.iter().map(|c| webpki::anchor_from_trusted_cert(c).map_err(|e| format!("root certificate: {:?}", e))).collect::<Result<Vec<_>, _>>()?;
+    let intermediates: Vec<CertificateDer<'_>> = checked.certs.iter().filter(|c| **c != checked.signer.raw).map(|c| CertificateDer::from(*c)).collect();
+    let at = UnixTime::since_unix_epoch(std::time::Duration::from_millis(checked.tst.gen_time_ms.max(0) as u64));
+    let path = ee
+        .verify_for_usage(CHAIN_ALGS, &anchors, &intermediates, at, webpki::KeyUsage::required(TIME_STAMPING_EKU), None, None)
+        .map_err(|e| format!("{:?}", e))?;
+    let root = der::tlv(der::SEQUENCE, path.anchor().subject.as_ref());
+    Ok(format!("chains to {}", der::parse(&root).map(|n| name_string(&n)).unwrap_or_default()))
+}
+
+/// Checks `proof.timestamp_token` against the proof; with `roots`, also the TSA's certificate chain.
+/// A token that does not cover the proof is an error; an untrusted chain is reported, not an error.
+pub fn verify_timestamp(proof: &Proof, roots: Option<&TsaRoots>) -> anyhow::Result<TimestampReport> {
+    let t = proof.timestamp_token.as_ref().ok_or_else(|| anyhow::anyhow!("proof has no timestamp token"))?;
+    let token = B64.decode(t.token.as_bytes()).context("timestamp token is not base64")?;
+    let checked = check_token(&token, &imprint(proof)?)?;
+    let gen_time_ms = checked.tst.gen_time_ms;
+    anyhow::ensure!(gen_time_ms == t.gen_time_ms, "token genTime {} does not match gen_time_ms {}", gen_time_ms, t.gen_time_ms);
+    let skew_ms = proof.timestamp_ms - gen_time_ms;
+    // Only v3 hashes its timestamp, so only there is a late one a contradiction
+    if proof.scheme == SCHEME_V3 {
+        anyhow::ensure!(skew_ms <= MAX_SKEW_MS, "proof claims to be from {} ms after the TSA stamped it", skew_ms);
+    }
+    let (chain, chain_detail) = match roots.map(|r| check_chain(&checked, r)) {
+        None => (ChainStatus::Unchecked, None),
+        Some(Ok(detail)) => (ChainStatus::Trusted, Some(detail)),
+        Some(Err(detail)) => (ChainStatus::Untrusted, Some(detail)),
+    };
+    Ok(TimestampReport {
+        gen_time_ms,
+        skew_ms,
+        tsa: name_string(&checked.signer.subject),
+        policy: checked.tst.policy.clone(),
+        serial: hex::encode(checked.tst.serial),
+        chain,
+        chain_detail,
+    })
+}
+
+fn token_from_response(response: &[u8]) -> anyhow::Result<&[u8]> {
+    let resp = der::parse(response).context("TSA response is not DER")?;
+    let mut r = resp.reader();
+    let status = r.expect(der::SEQUENCE)?;
+    let mut s = status.reader();
+    // granted (0) and grantedWithMods (1) carry a token
+    let code = s.read()?.uint()?;
+    if code > 1 {
+        let mut text = Vec::new();
+        if let Some(free) = s.optional(der::SEQUENCE)? {
+            let mut f = free.reader();
+            while let Ok(line) = f.read() {
+                text.push(String::from_utf8_lossy(line.value).into_owned());
+            }
+        }
+        anyhow::bail!("TSA refused the request (status {}{})", code, if text.is_empty() { String::new() } else { format!(": {}", text.join("; ")) });
+    }
+    Ok(r.expect(der::SEQUENCE).context("TSA response has no token")?.raw)
+}
+
+/// Gets `proof.combined_hash` timestamped by `tsa` and stores the token in the proof.
+pub async fn stamp(proof: &mut Proof, tsa: &dyn TimestampAuthority) -> anyhow::Result<TimestampReport> {
+    let imprint = imprint(proof)?;
+    let mut nonce = [0u8; 16];
+    SystemRandom::new().fill(&mut nonce).map_err(|_| anyhow::anyhow!("no randomness for the request nonce"))?;
+    let nonce = der::uint_bytes(&nonce);
+    let message_imprint = der::sequence(&[&digest_algorithm(OID_SHA512), &der::octets(&imprint)]);
+    let request = der::sequence(&[&der::uint(1), &message_imprint, &nonce, &der::boolean(true)]);
+
+    let response = tsa.respond(&request).await?;
+    let token = token_from_response(&response)?;
+    let checked = check_token(token, &imprint)?;
+    anyhow::ensure!(checked.tst.nonce == Some(nonce.as_slice()), "TSA response does not echo the request nonce");
+    let stamped = TimestampToken { tsa: tsa.url().map(str::to_string), gen_time_ms: checked.tst.gen_time_ms, token: B64.encode(token) };
+    let previous = proof.timestamp_token.replace(stamped);
+    verify_timestamp(proof, None).inspect_err(|_| proof.timestamp_token = previous)
+}
+
+// ---- Local TSA ----
+
+const DAY_MS: i64 = 86_400_000;
+
+/// In-process TSA with its own throwaway root CA, for tests and offline demos.
+pub struct LocalTsa {
+    key: EcdsaKeyPair,
+    cert: Vec<u8>,
+    cert_serial: Vec<u8>,
+    root: Vec<u8>,
+    issuer: Vec<u8>,
+    rng: SystemRandom,
+    serial: AtomicU64,
+    /// Added to the local clock when stamping.
+    pub offset_ms: i64,
+}
+
+fn name(cn: &str) -> Vec<u8> {
+    let atv = der::sequence(&[&der::oid(OID_COMMON_NAME), &der::tlv(der::UTF8_STRING, cn.as_bytes())]);
+    der::sequence(&[&der::set_of(vec![atv])])
+}
+
+fn extension(oid: &str, value: &[u8]) -> Vec<u8> { der::sequence(&[&der::oid(oid), &der::boolean(true), &der::octets(value)]) }
+
+fn p256_key(rng: &SystemRandom) -> anyhow::Result<EcdsaKeyPair> {
+    let alg = &signature::ECDSA_P256_SHA256_ASN1_SIGNING;
+    let pkcs8 = EcdsaKeyPair::generate_pkcs8(alg, rng).map_err(|_| anyhow::anyhow!("generating TSA key"))?;
+    EcdsaKeyPair::from_pkcs8(alg, pkcs8.as_ref(), rng).map_err(|e| anyhow::anyhow!("loading TSA key: {}", e))
+}
+
+fn random_serial(rng: &SystemRandom) -> anyhow::Result<Vec<u8>> {
+    let mut serial = [0u8; 16];
+    rng.fill(&mut serial).map_err(|_| anyhow::anyhow!("no randomness for a serial number"))?;
+    serial[0] &= 0x7f;
+    Ok(serial.to_vec())
+}
+
+impl LocalTsa {
+    pub fn new() -> anyhow::Result<Self> {
+        let rng = SystemRandom::new();
+        let (root_key, key) = (p256_key(&rng)?, p256_key(&rng)?);
+        let issuer = name("Panther Local TSA Root");
+        let now = now_ms();
+        let certificate = |subject: &[u8], public_key: &[u8], serial: &[u8], extensions: &[Vec<u8>]| -> anyhow::Result<Vec<u8>> {
+            let spki = der::sequence(&[&der::sequence(&[&der::oid(OID_EC_PUBLIC_KEY), &der::oid(OID_P256)]), &der::bits(public_key)]);
+            let tbs = der::sequence(&[
+                &der::constructed(der::context(0, true), &[&der::uint(2)]),
+                &der::uint_bytes(serial),
+                &der::algorithm(OID_ECDSA_SHA256),
+                &issuer,
+                &der::sequence(&[&der::utc_time(now - DAY_MS), &der::utc_time(now + 3650 * DAY_MS)]),
+                subject,
+                &spki,
+                &der::constructed(der::context(3, true), &[&der::sequence(&[&extensions.concat()])]),
+            ]);
+            let sig = root_key.sign(&rng, &tbs).map_err(|_| anyhow::anyhow!("signing certificate"))?;
+            Ok(der::sequence(&[&tbs, &der::algorithm(OID_ECDSA_SHA256), &der::bits(sig.as_ref())]))
+        };
+        let root = certificate(
+            &issuer,
+            root_key.public_key().as_ref(),
+            &random_serial(&rng)?,
+            &[
+                extension(OID_BASIC_CONSTRAINTS, &der::sequence(&[&der::boolean(true)])),
+                // keyCertSign, cRLSign
+                extension(OID_KEY_USAGE, &der::tlv(der::BIT_STRING, &[1, 0x06])),
+            ],
+        )?;
+        let cert_serial = random_serial(&rng)?;
+        let cert = certificate(
+            &name("Panther Local TSA"),
+            key.public_key().as_ref(),
+            &cert_serial,
+            &[
+                // digitalSignature
+                extension(OID_KEY_USAGE, &der::tlv(der::BIT_STRING, &[7, 0x80])),
+                extension(OID_EXT_KEY_USAGE, &der::sequence(&[&der::oid(OID_TIME_STAMPING)])),
+            ],
+        )?;
+        Ok(Self { key, cert, cert_serial, root, issuer, rng, serial: AtomicU64::new(1), offset_ms: 0 })
+    }
+
+    /// The root certificate, DER.
+    pub fn root_der(&self) -> &[u8] { &self.root }
+
+    pub fn root_pem(&self) -> String {
+        let b64 = B64.encode(&self.root);
+        let lines: Vec<&str> = b64.as_bytes().chunks(64).map(|c| std::str::from_utf8(c).unwrap_or_default()).collect();
+        format!("-----BEGIN CERTIFICATE-----\n{}\n-----END CERTIFICATE-----\n", lines.join("\n"))
+    }
+
+    pub fn roots(&self) -> TsaRoots { TsaRoots::from_der(self.root.clone()) }
+
+    fn token(&self, message_imprint: &[u8], nonce: Option<&[u8]>, include_cert: bool) -> anyhow::Result<Vec<u8>> {
+        let serial = self.serial.fetch_add(1, Ordering::SeqCst);
+        let tst = der::sequence(&[
+            &der::uint(1),
+            &der::oid(LOCAL_TSA_POLICY),
+            message_imprint,
+            &der::uint(serial),
+            &der::generalized_time(now_ms() + self.offset_ms),
+            nonce.unwrap_or_default(),
+        ]);
+        let attribute = |oid: &str, value: &[u8]| der::sequence(&[&der::oid(oid), &der::set_of(vec![value.to_vec()])]);
+        let ess_cert_id = der::sequence(&[&der::octets(&digest(OID_SHA256, &self.cert)?)]);
+        let attrs = der::set_of(vec![
+            attribute(OID_CONTENT_TYPE, &der::oid(OID_TST_INFO)),
+            attribute(OID_MESSAGE_DIGEST, &der::octets(&digest(OID_SHA256, &tst)?)),
+            attribute(OID_SIGNING_CERT_V2, &der::sequence(&[&der::sequence(&[&ess_cert_id])])),
+        ]);
+        // Signed as a SET, stored as [0] IMPLICIT
+        let sig = self.key.sign(&self.rng, &attrs).map_err(|_| anyhow::anyhow!("signing token"))?;
+        let signer_info = der::sequence(&[
+            &der::uint(1),
+            &der::sequence(&[&self.issuer, &der::uint_bytes(&self.cert_serial)]),
+            &digest_algorithm(OID_SHA256),
+            &der::tlv(der::context(0, true), der::parse(&attrs)?.value),
+            &der::algorithm(OID_ECDSA_SHA256),
+            &der::octets(sig.as_ref()),
+        ]);
+        let certs = if include_cert { der::constructed(der::context(0, true), &[&self.cert]) } else { Vec::new() };
+        let signed_data = der::sequence(&[
+            &der::uint(3),
+            &der::set_of(vec![digest_algorithm(OID_SHA256)]),
+            &der::sequence(&[&der::oid(OID_TST_INFO), &der::constructed(der::context(0, true), &[&der::octets(&tst)])]),
+            &certs,
+            &der::set_of(vec![signer_info]),
+        ]);
+        Ok(der::sequence(&[&der::oid(OID_SIGNED_DATA), &der::constructed(der::context(0, true), &[&signed_data])]))
+    }
+}
+
+#[async_trait]
+impl TimestampAuthority for LocalTsa {
+    async fn respond(&self, request: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let req = der::parse(request)?;
+        let mut r = req.reader();
+        r.expect(der::INTEGER)?;
+        let message_imprint = r.expect(der::SEQUENCE)?.raw;
+        r.optional(der::OID)?;
+        let nonce = r.optional(der::INTEGER)?.map(|n| n.raw);
+        let cert_req = r.optional(der::BOOLEAN)?.is_some_and(|b| b.value != [0]);
+        let token = self.token(message_imprint, nonce, cert_req)?;
+        Ok(der::sequence(&[&der::sequence(&[&der::uint(0)]), &token]))
+    }
+
+    fn url(&self) -> Option<&str> { Some("local") }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof::bundle::ProofBundle;
+    use crate::proof::{compute_proof, ProofContext};
+
+    #[tokio::test]
+    async fn stamps_proofs_and_checks_the_tsa_chain() {
+        let ctx = ProofContext { sdk_version: "test".into(), salt: None };
+        let results = r#"[{"provider_name":"a","adherence_score":90.0}]"#;
+        let mut proof = compute_proof("hello", "[]", "[]", results, &ctx).unwrap();
+        let tsa = LocalTsa::new().unwrap();
+
+        let report = stamp(&mut proof, &tsa).await.unwrap();
+        assert_eq!((report.chain, report.policy.as_str(), report.tsa.as_str()), (ChainStatus::Unchecked, LOCAL_TSA_POLICY, "CN=Panther Local TSA"));
+        assert!(report.skew_ms <= 0);
+
+        // Survives a JSON round trip and checks against the root, as PEM too
+        let read: Proof = serde_json::from_str(&serde_json::to_string(&proof).unwrap()).unwrap();
+        let trusted = verify_timestamp(&read, Some(&TsaRoots::from_pem_str(&tsa.root_pem()).unwrap())).unwrap();
+        assert_eq!(trusted.chain, ChainStatus::Trusted, "{:?}", trusted.chain_detail);
+        assert_eq!(trusted.chain_detail.as_deref(), Some("chains to CN=Panther Local TSA Root"));
+        let other = LocalTsa::new().unwrap();
+        assert_eq!(verify_timestamp(&read, Some(&other.roots())).unwrap().chain, ChainStatus::Untrusted);
+        let bundle = ProofBundle::seal(read.clone(), "hello", "[]", "[]", results, None).unwrap();
+        assert!(bundle.verify_with_roots(None, Some(&tsa.roots())).ok());
+        assert!(!bundle.verify_with_roots(None, Some(&other.roots())).ok());
+
+        // The token only covers the hash it was issued for
+        let mut tampered = read.clone();
+        let flipped = if tampered.combined_hash.starts_with('0') { "1" } else { "0" };
+        tampered.combined_hash.replace_range(..1, flipped);
+        assert!(verify_timestamp(&tampered, None).is_err());
+        let mut swapped = read.clone();
+        swapped.timestamp_token.as_mut().unwrap().gen_time_ms += 1;
+        assert!(verify_timestamp(&swapped, None).is_err());
+
+        // A v3 proof cannot be younger than its token
+        let mut late = LocalTsa::new().unwrap();
+        late.offset_ms = -2 * MAX_SKEW_MS;
+        assert!(stamp(&mut proof, &late).await.is_err());
+        assert_eq!(proof.timestamp_token, read.timestamp_token);
+    }
+}
@@ -0,0 +1,256 @@
+//! Just enough DER for RFC 3161 and the certificates around it: definite-length TLVs read
+//! in place (so signed bytes can be checked exactly as received) and written from parts.
+
+pub const BOOLEAN: u8 = 0x01;
+pub const INTEGER: u8 = 0x02;
+pub const BIT_STRING: u8 = 0x03;
+pub const OCTET_STRING: u8 = 0x04;
+pub const NULL: u8 = 0x05;
+pub const OID: u8 = 0x06;
+pub const UTF8_STRING: u8 = 0x0c;
+pub const UTC_TIME: u8 = 0x17;
+pub const GENERALIZED_TIME: u8 = 0x18;
+pub const SEQUENCE: u8 = 0x30;
+pub const SET: u8 = 0x31;
+
+/// Context-specific tag `[n]`.
+pub const fn context(n: u8, constructed: bool) -> u8 { 0x80 | if constructed { 0x20 } else { 0 } | n }
+
+#[derive(Debug, Clone, Copy)]
+pub struct Tlv<'a> {
+    pub tag: u8,
+    pub value: &'a [u8],
+    /// Tag, length and value as they appeared in the input.
+    pub raw: &'a [u8],
+}
+
+impl<'a> Tlv<'a> {
+    pub fn reader(&self) -> Reader<'a> { Reader::new(self.value) }
+
+    pub fn uint(&self) -> anyhow::Result<u64> {
+        anyhow::ensure!(self.tag == INTEGER, "expected INTEGER, got tag 0x{:02x}", self.tag);
+        let bytes = self.value.strip_prefix(&[0]).unwrap_or(self.value);
+        anyhow::ensure!(bytes.len() <= 8, "integer too large");
+        Ok(bytes.iter().fold(0u64, |acc, b| (acc << 8) | *b as u64))
+    }
+}
+
+pub struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Self { Self { data } }
+
+    pub fn is_empty(&self) -> bool { self.data.is_empty() }
+
+    pub fn peek_tag(&self) -> Option<u8> { self.data.first().copied() }
+
+    pub fn read(&mut self) -> anyhow::Result<Tlv<'a>> {
+        let data = self.data;
+        anyhow::ensure!(data.len() >= 2, "truncated DER");
+        let tag = data[0];
+        anyhow::ensure!(tag & 0x1f != 0x1f, "multi-byte tags are not supported");
+        let (len, header) = match data[1] {
+            n if n < 0x80 => (n as usize, 2),
+            0x80 => anyhow::bail!("indefinite length is not DER"),
+            n => {
+                let count = (n & 0x7f) as usize;
+                anyhow::ensure!(count <= 4 && data.len() >= 2 + count, "bad DER length");
+                (data[2..2 + count].iter().fold(0usize, |acc, b| (acc << 8) | *b as usize), 2 + count)
+            }
+        };
+        anyhow::ensure!(data.len() >= header + len, "truncated DER");
+        self.data = &data[header + len..];
+        Ok(Tlv { tag, value: &data[header..header + len], raw: &data[..header + len] })
+    }
+
+    pub fn expect(&mut self, tag: u8) -> anyhow::Result<Tlv<'a>> {
+        let tlv = self.read()?;
+        anyhow::ensure!(tlv.tag == tag, "expected tag 0x{:02x}, got 0x{:02x}", tag, tlv.tag);
+        Ok(tlv)
+    }
+
+    pub fn optional(&mut self, tag: u8) -> anyhow::Result<Option<Tlv<'a>>> {
+        if self.peek_tag() == Some(tag) { self.read().map(Some) } else { Ok(None) }
+    }
+}
+
+/// Parses a single TLV that must span all of `data`.
+pub fn parse(data: &[u8]) -> anyhow::Result<Tlv<'_>> {
+    let mut r = Reader::new(data);
+    let tlv = r.read()?;
+    anyhow::ensure!(r.is_empty(), "trailing bytes after DER value");
+    Ok(tlv)
+}
+
+pub fn tlv(tag: u8, value: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    match value.len() {
+        n if n < 0x80 => out.push(n as u8),
+        n => {
+            let bytes: Vec<u8> = n.to_be_bytes().into_iter().skip_while(|b| *b == 0).collect();
+            out.push(0x80 | bytes.len() as u8);
+            out.extend(bytes);
+        }
+    }
+    out.extend_from_slice(value);
+    out
+}
+
+pub fn constructed(tag: u8, parts: &[&[u8]]) -> Vec<u8> { tlv(tag, &parts.concat()) }
+
+pub fn sequence(parts: &[&[u8]]) -> Vec<u8> { constructed(SEQUENCE, parts) }
+
+/// `SET OF`, with its elements in the sorted order DER requires.
+pub fn set_of(mut items: Vec<Vec<u8>>) -> Vec<u8> {
+    items.sort();
+    tlv(SET, &items.concat())
+}
+
+/// Unsigned big-endian integer, with the leading zero DER needs when the high bit is set.
+pub fn uint_bytes(bytes: &[u8]) -> Vec<u8> {
+    let trimmed: Vec<u8> = bytes.iter().copied().skip_while(|b| *b == 0).collect();
+    let mut value = if trimmed.is_empty() { vec![0] } else { trimmed };
+    if value[0] & 0x80 != 0 {
+        value.insert(0, 0);
+    }
+    tlv(INTEGER, &value)
+}
+
+pub fn uint(n: u64) -> Vec<u8> { uint_bytes(&n.to_be_bytes()) }
+
+pub fn boolean(b: bool) -> Vec<u8> { tlv(BOOLEAN, &[if b { 0xff } else { 0 }]) }
+
+pub fn octets(b: &[u8]) -> Vec<u8> { tlv(OCTET_STRING, b) }
+
+pub fn bits(b: &[u8]) -> Vec<u8> { tlv(BIT_STRING, &[&[0u8][..], b].concat()) }
+
+pub fn null() -> Vec<u8> { tlv(NULL, &[]) }
+
+/// Dotted OID (`"1.2.840.113549.1.7.2"`) to its encoded content bytes.
+pub fn oid_bytes(dotted: &str) -> Vec<u8> {
+    let arcs: Vec<u64> = dotted.split('.').map(|a| a.parse().unwrap_or(0)).collect();
+    let mut out = Vec::new();
+    let first = arcs.first().copied().unwrap_or(0) * 40 + arcs.get(1).copied().unwrap_or(0);
+    for arc in std::iter::once(first).chain(arcs.into_iter().skip(2)) {
+        let mut chunk = vec![(arc & 0x7f) as u8];
+        let mut rest = arc >> 7;
+        while rest > 0 {
+            chunk.push(0x80 | (rest & 0x7f) as u8);
+            rest >>= 7;
+        }
+        out.extend(chunk.into_iter().rev());
+    }
+    out
+}
+
+pub fn oid(dotted: &str) -> Vec<u8> { tlv(OID, &oid_bytes(dotted)) }
+
+pub fn oid_to_string(bytes: &[u8]) -> String {
+    let mut arcs = Vec::new();
+    let mut acc = 0u64;
+    for b in bytes {
+        acc = (acc << 7) | (b & 0x7f) as u64;
+        if b & 0x80 == 0 {
+            if arcs.is_empty() {
+                let first = (acc / 40).min(2);
+                arcs.push(first);
+                arcs.push(acc - first * 40);
+            } else {
+                arcs.push(acc);
+            }
+            acc = 0;
+        }
+    }
+    arcs.iter().map(u64::to_string).collect::<Vec<_>>().join(".")
+}
+
+/// `AlgorithmIdentifier` without parameters.
+pub fn algorithm(dotted: &str) -> Vec<u8> { sequence(&[&oid(dotted)]) }
+
+// Days since 1970-01-01 from a proleptic Gregorian date, and back
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((m + 9) % 12) + 2) / 5 + d - 1;
+    era * 146_097 + yoe * 365 + yoe / 4 - yoe / 100 + doy - 719_468
+}
+
+fn civil_from_days(z: i64) -> (i64, i64, i64) {
+    let z = z + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    (yoe + era * 400 + i64::from(m <= 2), m, d)
+}
+
+fn civil(ms: i64) -> String {
+    let secs = ms.div_euclid(1000);
+    let (y, m, d) = civil_from_days(secs.div_euclid(86_400));
+    let t = secs.rem_euclid(86_400);
+    format!("{:04}{:02}{:02}{:02}{:02}{:02}", y, m, d, t / 3600, t / 60 % 60, t % 60)
+}
+
+/// `YYYYMMDDHHMMSS[.fff]Z`, dropping trailing zeros of the fraction as DER requires.
+pub fn generalized_time(ms: i64) -> Vec<u8> {
+    let mut s = civil(ms);
+    let frac = ms.rem_euclid(1000);
+    if frac != 0 {
+        s.push_str(format!(".{:03}", frac).trim_end_matches('0'));
+    }
+    s.push('Z');
+    tlv(GENERALIZED_TIME, s.as_bytes())
+}
+
+/// `YYMMDDHHMMSSZ`, for certificate validity before 2050.
+pub fn utc_time(ms: i64) -> Vec<u8> { tlv(UTC_TIME, format!("{}Z", &civil(ms)[2..]).as_bytes()) }
+
+/// Milliseconds since the epoch from a GeneralizedTime or UTCTime value (UTC only).
+pub fn parse_time(tlv: &Tlv<'_>) -> anyhow::Result<i64> {
+    let s = std::str::from_utf8(tlv.value)?;
+    let s = s.strip_suffix('Z').ok_or_else(|| anyhow::anyhow!("time '{}' is not UTC", s))?;
+    let (full, frac) = s.split_once('.').unwrap_or((s, ""));
+    let full = match tlv.tag {
+        UTC_TIME => format!("{}{}", if full[..2].parse::<u32>()? >= 50 { "19" } else { "20" }, full),
+        GENERALIZED_TIME => full.to_string(),
+        t => anyhow::bail!("expected a time, got tag 0x{:02x}", t),
+    };
+    anyhow::ensure!(full.len() == 14 && full.bytes().all(|b| b.is_ascii_digit()), "bad time '{}'", s);
+    let n = |r: std::ops::Range<usize>| full[r].parse::<i64>().unwrap_or(0);
+    let days = days_from_civil(n(0..4), n(4..6), n(6..8));
+    let secs = days * 86_400 + n(8..10) * 3600 + n(10..12) * 60 + n(12..14);
+    let millis = format!("{:0<3}", &frac[..frac.len().min(3)]).parse::<i64>().unwrap_or(0);
+    Ok(secs * 1000 + millis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_oids_times_and_lengths() {
+        for o in ["1.2.840.113549.1.7.2", "2.16.840.1.101.3.4.2.3", "1.3.6.1.5.5.7.3.8", "2.999.3161.1"] {
+            assert_eq!(oid_to_string(&oid_bytes(o)), o);
+        }
+        assert_eq!(oid("1.2.840.113549"), [0x06, 0x06, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d]);
+        for ms in [0, 951_782_400_123, 1_700_000_000_500, 4_102_444_799_000] {
+            assert_eq!(parse_time(&parse(&generalized_time(ms)).unwrap()).unwrap(), ms);
+        }
+        assert_eq!(generalized_time(1_700_000_000_500), tlv(GENERALIZED_TIME, b"20231114221320.5Z"));
+        assert_eq!(parse_time(&parse(&utc_time(1_700_000_000_000)).unwrap()).unwrap(), 1_700_000_000_000);
+
+        let long = vec![7u8; 300];
+        let enc = sequence(&[&octets(&long), &uint(0x80)]);
+        let top = parse(&enc).unwrap();
+        let mut r = top.reader();
+        assert_eq!(r.expect(OCTET_STRING).unwrap().value, &long[..]);
+        assert_eq!(r.read().unwrap().uint().unwrap(), 0x80);
+        assert!(r.is_empty());
+    }
+}
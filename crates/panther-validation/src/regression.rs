@@ -0,0 +1,415 @@
+//! Regression detection between two evaluation runs.
+//!
+//! Runs are read from `panther-ai-eval` output directories (`results.jsonl`,
+//! one sub-directory per scenario, or a single-run `validation_results.json`).
+//! Items are matched by prompt; each provider's score changes are compared
+//! against absolute, relative and significance thresholds, and the outcome is a
+//! machine-readable verdict plus Markdown/HTML reports. A current run that lost
+//! providers, or more than a tolerated share of the baseline items, also regresses.
+
+use crate::stats::paired_permutation_test;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+const DEFAULT_MAX_DROP: f64 = 5.0;
+const DEFAULT_MAX_MISSING_PCT: f64 = 10.0;
+
+/// One prompt's adherence score per provider.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EvalItem {
+    /// Prompt, prefixed with the scenario directory and suffixed `#n` for repeated prompts.
+    pub key: String,
+    pub scores: BTreeMap<String, f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegressionThresholds {
+    /// Largest tolerated drop of a provider's mean score, in points (default 5).
+    #[serde(default)]
+    pub max_drop: Option<f64>,
+    /// Largest tolerated drop relative to the baseline mean, in percent.
+    #[serde(default)]
+    pub max_drop_pct: Option<f64>,
+    /// Also flag any drop a paired permutation test finds significant at this level.
+    #[serde(default)]
+    pub alpha: Option<f64>,
+    /// Largest tolerated share of baseline items absent from the current run, in percent (default 10).
+    #[serde(default)]
+    pub max_missing_pct: Option<f64>,
+    /// Do not regress when a baseline provider is absent from the current run.
+    #[serde(default)]
+    pub allow_missing_providers: bool,
+}
+
+impl Default for RegressionThresholds {
+    fn default() -> Self {
+        Self {
+            max_drop: Some(DEFAULT_MAX_DROP),
+            max_drop_pct: None,
+            alpha: None,
+            max_missing_pct: Some(DEFAULT_MAX_MISSING_PCT),
+            allow_missing_providers: false,
+        }
+    }
+}
+
+impl RegressionThresholds {
+    /// Reasons a drop from `before` to `after` breaks the absolute/relative thresholds.
+    fn breaches(&self, before: f64, after: f64) -> Vec<String> {
+        let drop = before - after;
+        let mut out = Vec::new();
+        if let Some(max) = self.max_drop {
+            if drop > max { out.push(format!("dropped {:.1} points (max {:.1})", drop, max)); }
+        }
+        if let (Some(max), true) = (self.max_drop_pct, before > 0.0) {
+            let pct = drop / before * 100.0;
+            if pct > max { out.push(format!("dropped {:.1}% (max {:.1}%)", pct, max)); }
+        }
+        out
+    }
+
+    /// Reasons the current run's coverage of the baseline is a regression on its own.
+    fn coverage_breaches(&self, baseline_items: usize, missing_items: &[String], missing_providers: &[String]) -> Vec<String> {
+        let mut out = Vec::new();
+        if !missing_providers.is_empty() && !self.allow_missing_providers {
+            out.push(format!("provider(s) missing from current run: {}", missing_providers.join(", ")));
+        }
+        if let (Some(max), true) = (self.max_missing_pct, baseline_items > 0) {
+            let pct = missing_items.len() as f64 / baseline_items as f64 * 100.0;
+            if pct > max {
+                out.push(format!("{} of {} baseline item(s) missing ({:.1}%, max {:.1}%)", missing_items.len(), baseline_items, pct, max));
+            }
+        }
+        out
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    Pass,
+    Regression,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ItemDelta {
+    pub key: String,
+    pub provider: String,
+    pub baseline: f64,
+    pub current: f64,
+    pub delta: f64,
+    pub regressed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProviderDelta {
+    pub provider: String,
+    /// Items scored for this provider in both runs.
+    pub items: usize,
+    pub baseline_mean: f64,
+    pub current_mean: f64,
+    pub delta: f64,
+    pub delta_pct: Option<f64>,
+    /// Paired sign-flip permutation test over per-item deltas.
+    pub p_value: f64,
+    pub regressed_items: usize,
+    pub regressed: bool,
+    pub reasons: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompareReport {
+    pub verdict: Verdict,
+    pub thresholds: RegressionThresholds,
+    /// Run-level regressions: missing providers, too many missing items.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reasons: Vec<String>,
+    pub providers: Vec<ProviderDelta>,
+    pub items: Vec<ItemDelta>,
+    /// Items of the baseline absent from the current run.
+    pub missing_items: Vec<String>,
+    pub new_items: Vec<String>,
+    pub missing_providers: Vec<String>,
+    pub new_providers: Vec<String>,
+}
+
+fn scores_of(results: &serde_json::Value) -> BTreeMap<String, f64> {
+    results
+        .as_array()
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|r| Some((r.get("provider_name")?.as_str()?.to_string(), r.get("adherence_score")?.as_f64()?)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn load_into(path: &Path, prefix: &str, out: &mut Vec<EvalItem>) -> Result<bool> {
+    let jsonl = if path.is_file() { Some(path.to_path_buf()) } else { Some(path.join("results.jsonl")).filter(|p| p.is_file()) };
+    if let Some(file) = jsonl {
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        for line in fs::read_to_string(file)?.lines().filter(|l| !l.trim().is_empty()) {
+            let Ok(v) = serde_json::from_str::<serde_json::Value>(line) else { continue };
+            let Some(results) = v.get("results") else { continue };
+            let prompt = v.get("prompt").and_then(|p| p.as_str()).unwrap_or_default();
+            let n = seen.entry(prompt.to_string()).or_default();
+            *n += 1;
+            let key = if *n == 1 { format!("{}{}", prefix, prompt) } else { format!("{}{}#{}", prefix, prompt, n) };
+            out.push(EvalItem { key, scores: scores_of(results) });
+        }
+        return Ok(true);
+    }
+    let single = path.join("validation_results.json");
+    if single.is_file() {
+        let results: serde_json::Value = serde_json::from_str(&fs::read_to_string(single)?)?;
+        out.push(EvalItem { key: format!("{}validation_results", prefix), scores: scores_of(&results) });
+        return Ok(true);
+    }
+    let mut dirs: Vec<_> = fs::read_dir(path)?.filter_map(|e| e.ok().map(|e| e.path())).filter(|p| p.is_dir()).collect();
+    dirs.sort();
+    let mut found = false;
+    for d in dirs {
+        let name = d.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string();
+        found |= load_into(&d, &format!("{}{}/", prefix, name), out)?;
+    }
+    Ok(found)
+}
+
+/// Loads an evaluation output directory (or a `results.jsonl` file).
+pub fn load_run(path: &Path) -> Result<Vec<EvalItem>> {
+    let mut items = Vec::new();
+    if !path.exists() || !load_into(path, "", &mut items)? {
+        bail!("no results.jsonl or validation_results.json under {}", path.display());
+    }
+    Ok(items)
+}
+
+fn mean(xs: &[f64]) -> f64 { if xs.is_empty() { 0.0 } else { xs.iter().sum::<f64>() / xs.len() as f64 } }
+
+pub fn compare(baseline: &[EvalItem], current: &[EvalItem], thresholds: &RegressionThresholds) -> CompareReport {
+    let cur: HashMap<&str, &EvalItem> = current.iter().map(|i| (i.key.as_str(), i)).collect();
+    let base_keys: Vec<&str> = baseline.iter().map(|i| i.key.as_str()).collect();
+    let missing_items: Vec<String> = base_keys.iter().filter(|k| !cur.contains_key(*k)).map(|k| k.to_string()).collect();
+    let new_items = current.iter().filter(|i| !base_keys.contains(&i.key.as_str())).map(|i| i.key.clone()).collect();
+
+    let mut items = Vec::new();
+    let mut paired: BTreeMap<&str, Vec<(f64, f64)>> = BTreeMap::new();
+    for b in baseline {
+        let Some(c) = cur.get(b.key.as_str()) else { continue };
+        for (provider, before) in &b.scores {
+            let Some(after) = c.scores.get(provider) else { continue };
+            paired.entry(provider).or_default().push((*before, *after));
+            items.push(ItemDelta {
+                key: b.key.clone(),
+                provider: provider.clone(),
+                baseline: *before,
+                current: *after,
+                delta: after - before,
+                regressed: !thresholds.breaches(*before, *after).is_empty(),
+            });
+        }
+    }
+
+    let providers_in = |run: &[EvalItem]| -> Vec<String> {
+        let mut v: Vec<String> = run.iter().flat_map(|i| i.scores.keys().cloned()).collect();
+        v.sort();
+        v.dedup();
+        v
+    };
+    let (base_providers, cur_providers) = (providers_in(baseline), providers_in(current));
+    let missing_providers: Vec<String> = base_providers.iter().filter(|p| !cur_providers.contains(p)).cloned().collect();
+    let new_providers = cur_providers.iter().filter(|p| !base_providers.contains(p)).cloned().collect();
+
+    let providers: Vec<ProviderDelta> = paired
+        .into_iter()
+        .map(|(provider, pairs)| {
+            let before: Vec<f64> = pairs.iter().map(|p| p.0).collect();
+            let after: Vec<f64> = pairs.iter().map(|p| p.1).collect();
+            let diffs: Vec<f64> = pairs.iter().map(|(b, a)| a - b).collect();
+            let (baseline_mean, current_mean) = (mean(&before), mean(&after));
+            let p_value = paired_permutation_test(&diffs);
+            let mut reasons = thresholds.breaches(baseline_mean, current_mean);
+            if let Some(alpha) = thresholds.alpha {
+                if current_mean < baseline_mean && p_value < alpha {
+                    reasons.push(format!("significant drop (p={:.3} < {})", p_value, alpha));
+                }
+            }
+            ProviderDelta {
+                provider: provider.to_string(),
+                items: pairs.len(),
+                baseline_mean,
+                current_mean,
+                delta: current_mean - baseline_mean,
+                delta_pct: (baseline_mean > 0.0).then(|| (current_mean - baseline_mean) / baseline_mean * 100.0),
+                p_value,
+                regressed_items: items.iter().filter(|i| i.provider == provider && i.regressed).count(),
+                regressed: !reasons.is_empty(),
+                reasons,
+            }
+        })
+        .collect();
+
+    let reasons = thresholds.coverage_breaches(baseline.len(), &missing_items, &missing_providers);
+    let verdict = if !reasons.is_empty() || providers.iter().any(|p| p.regressed) { Verdict::Regression } else { Verdict::Pass };
+    CompareReport { verdict, thresholds: thresholds.clone(), reasons, providers, items, missing_items, new_items, missing_providers, new_providers }
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+impl CompareReport {
+    pub fn regressed(&self) -> bool { self.verdict == Verdict::Regression }
+
+    fn thresholds_line(&self) -> String {
+        let t = &self.thresholds;
+        let mut parts = Vec::new();
+        if let Some(v) = t.max_drop { parts.push(format!("max drop {:.1} points", v)); }
+        if let Some(v) = t.max_drop_pct { parts.push(format!("max drop {:.1}%", v)); }
+        if let Some(v) = t.alpha { parts.push(format!("significance α={}", v)); }
+        if let Some(v) = t.max_missing_pct { parts.push(format!("max missing items {:.1}%", v)); }
+        if t.allow_missing_providers { parts.push("missing providers allowed".into()); }
+        if parts.is_empty() { "none".into() } else { parts.join(", ") }
+    }
+
+    fn worst_items(&self) -> Vec<&ItemDelta> {
+        let mut v: Vec<&ItemDelta> = self.items.iter().filter(|i| i.regressed).collect();
+        v.sort_by(|a, b| a.delta.total_cmp(&b.delta));
+        v
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let verdict = if self.regressed() { "REGRESSION" } else { "PASS" };
+        let mut s = format!("# Regression report: {}\n\nThresholds: {}\n\n", verdict, self.thresholds_line());
+        for r in &self.reasons { s.push_str(&format!("- ❌ {}\n", r)); }
+        if !self.reasons.is_empty() { s.push('\n'); }
+        s.push_str("| Provider | Items | Baseline | Current | Δ | Δ% | p | Status |\n|---|---:|---:|---:|---:|---:|---:|---|\n");
+        for p in &self.providers {
+            let status = if p.regressed { format!("❌ {}", p.reasons.join("; ")) } else { "✅".into() };
+            let pct = p.delta_pct.map(|v| format!("{:+.1}%", v)).unwrap_or_else(|| "-".into());
+            s.push_str(&format!(
+                "| {} | {} | {:.1} | {:.1} | {:+.1} | {} | {:.3} | {} |\n",
+                p.provider, p.items, p.baseline_mean, p.current_mean, p.delta, pct, p.p_value, status
+            ));
+        }
+        let worst = self.worst_items();
+        if !worst.is_empty() {
+            s.push_str("\n## Regressed items\n\n| Item | Provider | Baseline | Current | Δ |\n|---|---|---:|---:|---:|\n");
+            for i in worst {
+                s.push_str(&format!("| {} | {} | {:.1} | {:.1} | {:+.1} |\n", i.key.replace('|', "\\|"), i.provider, i.baseline, i.current, i.delta));
+            }
+        }
+        for (title, list) in [
+            ("Missing from current run", &self.missing_items),
+            ("New in current run", &self.new_items),
+            ("Providers missing from current run", &self.missing_providers),
+            ("Providers new in current run", &self.new_providers),
+        ] {
+            if !list.is_empty() {
+                s.push_str(&format!("\n## {}\n\n", title));
+                for k in list { s.push_str(&format!("- {}\n", k)); }
+            }
+        }
+        s
+    }
+
+    pub fn to_html(&self) -> String {
+        let verdict = if self.regressed() { "REGRESSION" } else { "PASS" };
+        let mut s = String::from("<!doctype html><html><head><meta charset='utf-8'><title>Regression Report</title>");
+        s.push_str("<style>body{font-family:Arial,sans-serif;margin:20px} table{border-collapse:collapse} td,th{border:1px solid #ccc;padding:4px 8px} .bad{background:#fde2e2} .good{background:#e2f5e2}</style></head><body>");
+        s.push_str(&format!("<h2>Regression report: {}</h2><p>Thresholds: {}</p>", verdict, escape_html(&self.thresholds_line())));
+        for r in &self.reasons { s.push_str(&format!("<p class='bad'>{}</p>", escape_html(r))); }
+        s.push_str("<table><tr><th>Provider</th><th>Items</th><th>Baseline</th><th>Current</th><th>Δ</th><th>Δ%</th><th>p</th><th>Status</th></tr>");
+        for p in &self.providers {
+            let (class, status) = if p.regressed { ("bad", p.reasons.join("; ")) } else { ("good", "ok".to_string()) };
+            let pct = p.delta_pct.map(|v| format!("{:+.1}%", v)).unwrap_or_else(|| "-".into());
+            s.push_str(&format!(
+                "<tr class='{}'><td>{}</td><td>{}</td><td>{:.1}</td><td>{:.1}</td><td>{:+.1}</td><td>{}</td><td>{:.3}</td><td>{}</td></tr>",
+                class, escape_html(&p.provider), p.items, p.baseline_mean, p.current_mean, p.delta, pct, p.p_value, escape_html(&status)
+            ));
+        }
+        s.push_str("</table>");
+        let worst = self.worst_items();
+        if !worst.is_empty() {
+            s.push_str("<h3>Regressed items</h3><table><tr><th>Item</th><th>Provider</th><th>Baseline</th><th>Current</th><th>Δ</th></tr>");
+            for i in worst {
+                s.push_str(&format!(
+                    "<tr class='bad'><td>{}</td><td>{}</td><td>{:.1}</td><td>{:.1}</td><td>{:+.1}</td></tr>",
+                    escape_html(&i.key), escape_html(&i.provider), i.baseline, i.current, i.delta
+                ));
+            }
+            s.push_str("</table>");
+        }
+        if !self.missing_items.is_empty() || !self.new_items.is_empty() {
+            s.push_str(&format!("<p>{} item(s) missing from the current run, {} new.</p>", self.missing_items.len(), self.new_items.len()));
+        }
+        s.push_str("</body></html>");
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(key: &str, scores: &[(&str, f64)]) -> EvalItem {
+        EvalItem { key: key.into(), scores: scores.iter().map(|(p, s)| (p.to_string(), *s)).collect() }
+    }
+
+    #[test]
+    fn flags_drops_beyond_thresholds() {
+        let baseline: Vec<EvalItem> = (0..8).map(|i| item(&format!("q{}", i), &[("a", 80.0), ("b", 70.0)])).collect();
+        let mut current: Vec<EvalItem> = (0..8).map(|i| item(&format!("q{}", i), &[("a", 79.0), ("b", 60.0)])).collect();
+        current.push(item("extra", &[("a", 50.0)]));
+
+        let r = compare(&baseline, &current, &RegressionThresholds::default());
+        assert_eq!(r.verdict, Verdict::Regression);
+        let (a, b) = (&r.providers[0], &r.providers[1]);
+        assert!(!a.regressed && (a.delta + 1.0).abs() < 1e-9);
+        assert!(b.regressed && b.regressed_items == 8);
+        assert_eq!(r.new_items, vec!["extra".to_string()]);
+        assert!(r.to_markdown().contains("REGRESSION"));
+
+        // A consistent one-point drop is within 5 points but significant
+        let strict = RegressionThresholds { max_drop: None, max_drop_pct: None, alpha: Some(0.05), ..Default::default() };
+        let r = compare(&baseline, &current, &strict);
+        assert!(r.providers[0].regressed, "{:?}", r.providers[0]);
+        let lenient = RegressionThresholds { max_drop: Some(20.0), ..Default::default() };
+        assert_eq!(compare(&baseline, &current, &lenient).verdict, Verdict::Pass);
+    }
+
+    #[test]
+    fn missing_providers_and_items_regress() {
+        let baseline: Vec<EvalItem> = (0..10).map(|i| item(&format!("q{}", i), &[("a", 80.0), ("b", 70.0)])).collect();
+        let r = compare(&baseline, &[], &RegressionThresholds::default());
+        assert_eq!(r.verdict, Verdict::Regression);
+        assert!(r.providers.is_empty());
+        assert_eq!((r.missing_items.len(), r.missing_providers.len(), r.reasons.len()), (10, 2, 2));
+        assert!(r.to_markdown().contains("missing"));
+
+        // one item of ten lost and provider b gone
+        let current: Vec<EvalItem> = (0..9).map(|i| item(&format!("q{}", i), &[("a", 80.0)])).collect();
+        assert_eq!(compare(&baseline, &current, &RegressionThresholds::default()).verdict, Verdict::Regression);
+        let tolerant = RegressionThresholds { allow_missing_providers: true, ..Default::default() };
+        assert_eq!(compare(&baseline, &current, &tolerant).verdict, Verdict::Pass);
+        let strict = RegressionThresholds { max_missing_pct: Some(5.0), ..tolerant };
+        assert_eq!(compare(&baseline, &current, &strict).verdict, Verdict::Regression);
+    }
+
+    #[test]
+    fn loads_scenario_directories() {
+        let dir = std::env::temp_dir().join(format!("panther-regression-{}", std::process::id()));
+        let scen = dir.join("dosage");
+        fs::create_dir_all(&scen).unwrap();
+        let line = |p: &str, s: f64| format!("{{\"index\":0,\"prompt\":\"{}\",\"results\":[{{\"provider_name\":\"a\",\"adherence_score\":{}}}]}}\n", p, s);
+        fs::write(scen.join("results.jsonl"), line("q", 50.0) + &line("q", 60.0) + "{\"prompt\":\"err\",\"error\":\"x\"}\n").unwrap();
+        let items = load_run(&dir).unwrap();
+        assert_eq!(items.iter().map(|i| i.key.as_str()).collect::<Vec<_>>(), vec!["dosage/q", "dosage/q#2"]);
+        assert_eq!(items[1].scores["a"], 60.0);
+        assert!(load_run(&dir.join("missing")).is_err());
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,386 @@
+//! Weighted guideline scoring.
+//!
+//! Each guideline is scored on its own (weighted share of expected terms found),
+//! guidelines sharing a topic are combined into a topic score, and the overall
+//! adherence is the weighted mean of guideline scores. Missing a required term
+//! caps both the guideline score and the overall score. Forbidden terms,
+//! forbidden patterns and "must not claim" statements are penalties. Terms are
+//! matched with [`crate::matching`] (accent folding, stems, synonyms).
+
+use crate::matching::{fold, MatchOptions, Matcher, TermMatch};
+use crate::Guideline;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const DEFAULT_REQUIRED_CAP: f64 = 50.0;
+const DEFAULT_VIOLATION_PENALTY: f64 = 100.0;
+const SNIPPET_CONTEXT: usize = 40;
+// Words that, shortly before a "must not claim" statement in the same clause, turn it into a denial;
+// compared folded like matched terms, so "NÃO" and "isn’t" are cues too
+const NEGATIONS: &[&str] = &["not", "no", "never", "nor", "isnt", "arent", "nao", "nunca", "nem", "jamais"];
+const NEGATION_WINDOW: usize = 3;
+const CLAUSE_BREAKS: &[char] = &['.', ',', ';', ':', '!', '?'];
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ScoringConfig {
+    /// Multiplier applied to every guideline of a topic in the overall score.
+    #[serde(default)]
+    pub topic_weights: BTreeMap<String, f64>,
+    /// Highest score allowed when a required term is missing (default 50).
+    #[serde(default)]
+    pub required_cap: Option<f64>,
+    /// Points removed per violation from the guideline score; the overall score is
+    /// capped at `100 - penalty * violations` (default 100).
+    #[serde(default)]
+    pub violation_penalty: Option<f64>,
+    /// Stemming and fuzzy matching used for terms and claims.
+    #[serde(default)]
+    pub matching: MatchOptions,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ViolationKind {
+    ForbiddenTerm,
+    ForbiddenPattern,
+    MustNotClaim,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Violation {
+    pub topic: String,
+    pub kind: ViolationKind,
+    /// The term, pattern or statement that matched.
+    pub rule: String,
+    /// Text around the match.
+    pub snippet: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GuidelineScore {
+    pub topic: String,
+    pub score: f64,
+    pub weight: f64,
+    pub missing_terms: Vec<String>,
+    #[serde(default)]
+    pub missing_required: Vec<String>,
+    #[serde(default)]
+    pub violations: usize,
+    /// Where each found term matched in the response.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub matches: Vec<TermMatch>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TopicScore {
+    pub topic: String,
+    pub score: f64,
+    pub weight: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Scored {
+    pub overall: f64,
+    pub missing_terms: Vec<String>,
+    pub guidelines: Vec<GuidelineScore>,
+    pub topics: Vec<TopicScore>,
+    pub violations: Vec<Violation>,
+}
+
+/// Rejects guidelines whose forbidden patterns are not valid regexes.
+pub fn check_guidelines(guidelines: &[Guideline]) -> anyhow::Result<()> {
+    for g in guidelines {
+        for p in &g.forbidden_patterns {
+            RegexBuilder::new(p)
+                .case_insensitive(true)
+                .build()
+                .map_err(|e| anyhow::anyhow!("invalid forbidden pattern in '{}': {}", g.topic, e))?;
+        }
+    }
+    Ok(())
+}
+
+/// All expected and required terms, deduplicated in order of appearance.
+pub fn all_terms(guidelines: &[Guideline]) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for g in guidelines {
+        for t in guideline_terms(g) {
+            if !out.contains(&t) { out.push(t); }
+        }
+    }
+    out
+}
+
+fn guideline_terms(g: &Guideline) -> Vec<String> {
+    let mut terms = g.expected_terms.clone();
+    for r in &g.required_terms {
+        if !terms.iter().any(|t| t.eq_ignore_ascii_case(r)) { terms.push(r.clone()); }
+    }
+    terms
+}
+
+fn term_weight(g: &Guideline, term: &str) -> f64 {
+    g.term_weights
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(term))
+        .map(|(_, w)| w.max(0.0))
+        .unwrap_or(1.0)
+}
+
+fn synonyms<'g>(g: &'g Guideline, term: &str) -> &'g [String] {
+    g.synonyms
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(term))
+        .map(|(_, v)| v.as_slice())
+        .unwrap_or(&[])
+}
+
+fn ci_regex(pattern: &str) -> Option<Regex> { RegexBuilder::new(pattern).case_insensitive(true).build().ok() }
+
+/// Text around `start..end`, widened by a few characters on each side.
+fn snippet(text: &str, start: usize, end: usize) -> String {
+    let mut s = start.saturating_sub(SNIPPET_CONTEXT);
+    while !text.is_char_boundary(s) { s -= 1; }
+    let mut e = (end + SNIPPET_CONTEXT).min(text.len());
+    while !text.is_char_boundary(e) { e += 1; }
+    let mut out = text[s..e].split_whitespace().collect::<Vec<_>>().join(" ");
+    if s > 0 { out.insert(0, '…'); }
+    if e < text.len() { out.push('…'); }
+    out
+}
+
+fn negated(before: &str) -> bool {
+    // A cue in an earlier clause ("Consulte, não exceda. Seguro ...") negates nothing here
+    let clause = before.rsplit(CLAUSE_BREAKS).next().unwrap_or(before);
+    clause
+        .split(|c: char| !c.is_alphanumeric() && c != '\'' && c != '’')
+        .filter(|w| !w.is_empty())
+        .rev()
+        .take(NEGATION_WINDOW)
+        .any(|w| NEGATIONS.contains(&fold(w).as_str()))
+}
+
+fn find_violations(text: &str, matcher: &Matcher, g: &Guideline) -> Vec<Violation> {
+    let mut out = Vec::new();
+    let mut report = |kind, rule: &str, start: usize, end: usize| {
+        out.push(Violation { topic: g.topic.clone(), kind, rule: rule.to_string(), snippet: snippet(text, start, end) });
+    };
+    for t in &g.forbidden_terms {
+        if let Some(m) = matcher.find(t, synonyms(g, t)) {
+            report(ViolationKind::ForbiddenTerm, t, m.start, m.end);
+        }
+    }
+    // Invalid patterns are rejected up front by `check_guidelines`
+    for p in &g.forbidden_patterns {
+        if let Some(m) = ci_regex(p).and_then(|re| re.find(text)) {
+            report(ViolationKind::ForbiddenPattern, p, m.start(), m.end());
+        }
+    }
+    for c in &g.must_not_claim {
+        // "not safe during pregnancy" is the desired answer, not a claim
+        let claim = matcher.find_all(c, synonyms(g, c)).into_iter().find(|m| !negated(&text[..m.start]));
+        if let Some(m) = claim {
+            report(ViolationKind::MustNotClaim, c, m.start, m.end);
+        }
+    }
+    out
+}
+
+pub fn score_guidelines(text: &str, guidelines: &[Guideline], config: &ScoringConfig) -> Scored {
+    let cap = config.required_cap.unwrap_or(DEFAULT_REQUIRED_CAP);
+    let penalty = config.violation_penalty.unwrap_or(DEFAULT_VIOLATION_PENALTY).max(0.0);
+    let matcher = Matcher::new(text, &config.matching);
+    let mut scored = Scored::default();
+    let mut any_required_missing = false;
+
+    for g in guidelines {
+        let terms = guideline_terms(g);
+        let total: f64 = terms.iter().map(|t| term_weight(g, t)).sum();
+        let mut found = 0.0;
+        let mut missing = Vec::new();
+        let mut matches = Vec::new();
+        for t in &terms {
+            match matcher.find(t, synonyms(g, t)) {
+                Some(m) => {
+                    found += term_weight(g, t);
+                    matches.push(m);
+                }
+                None => missing.push(t.clone()),
+            }
+        }
+        let missing_required: Vec<String> =
+            g.required_terms.iter().filter(|r| missing.iter().any(|m| m.eq_ignore_ascii_case(r))).cloned().collect();
+        let mut score = if total > 0.0 { (found / total * 100.0).clamp(0.0, 100.0) } else { 100.0 };
+        if !missing_required.is_empty() {
+            any_required_missing = true;
+            score = score.min(cap);
+        }
+        let violations = find_violations(text, &matcher, g);
+        score = (score - penalty * violations.len() as f64).max(0.0);
+        // Default weight = total term weight, which reproduces the flat per-term score
+        let topic_w = config.topic_weights.get(&g.topic).copied().unwrap_or(1.0).max(0.0);
+        let weight = g.weight.unwrap_or(total).max(0.0) * topic_w;
+        for m in &missing {
+            if !scored.missing_terms.contains(m) { scored.missing_terms.push(m.clone()); }
+        }
+        scored.guidelines.push(GuidelineScore {
+            topic: g.topic.clone(),
+            score,
+            weight,
+            missing_terms: missing,
+            missing_required,
+            violations: violations.len(),
+            matches,
+        });
+        scored.violations.extend(violations);
+    }
+
+    scored.overall = weighted_mean(scored.guidelines.iter().map(|g| (g.score, g.weight)));
+    if any_required_missing { scored.overall = scored.overall.min(cap); }
+    // A violation anywhere bounds the whole response, not just its guideline's share
+    let bound = (100.0 - penalty * scored.violations.len() as f64).max(0.0);
+    scored.overall = scored.overall.min(bound);
+
+    let mut topics: Vec<String> = Vec::new();
+    for g in &scored.guidelines {
+        if !topics.contains(&g.topic) { topics.push(g.topic.clone()); }
+    }
+    scored.topics = topics
+        .into_iter()
+        .map(|topic| {
+            let items = scored.guidelines.iter().filter(|g| g.topic == topic);
+            let weight: f64 = items.clone().map(|g| g.weight).sum();
+            let score = weighted_mean(items.map(|g| (g.score, g.weight)));
+            TopicScore { topic, score, weight }
+        })
+        .collect();
+    scored
+}
+
+fn weighted_mean(items: impl Iterator<Item = (f64, f64)>) -> f64 {
+    let (mut sum, mut wsum, mut n, mut plain) = (0.0, 0.0, 0usize, 0.0);
+    for (score, w) in items {
+        sum += score * w;
+        wsum += w;
+        plain += score;
+        n += 1;
+    }
+    if n == 0 { return 100.0; }
+    // All weights zero: fall back to an unweighted mean
+    if wsum <= 0.0 { return plain / n as f64; }
+    sum / wsum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn g(topic: &str, terms: &[&str]) -> Guideline {
+        Guideline {
+            topic: topic.into(),
+            expected_terms: terms.iter().map(|s| s.to_string()).collect(),
+            required_terms: vec![],
+            term_weights: BTreeMap::new(),
+            weight: None,
+            forbidden_terms: vec![],
+            forbidden_patterns: vec![],
+            must_not_claim: vec![],
+            synonyms: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn default_weights_match_flat_score() {
+        let gs = vec![g("a", &["x", "y", "z"]), g("b", &["w"])];
+        let s = score_guidelines("x y", &gs, &ScoringConfig::default());
+        assert!((s.overall - 50.0).abs() < 1e-9);
+        assert_eq!(s.missing_terms, vec!["z".to_string(), "w".to_string()]);
+        assert_eq!(s.topics.len(), 2);
+        assert!((s.topics[1].score - 0.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn term_weights_and_required_cap() {
+        let mut a = g("dosage", &["dose", "doctor"]);
+        a.term_weights.insert("dose".into(), 3.0);
+        a.required_terms = vec!["pregnancy".into()];
+        let s = score_guidelines("ask a doctor about the dose", &[a.clone()], &ScoringConfig::default());
+        // 4 of 5 weight found, but the required term is missing
+        assert!((s.guidelines[0].score - 50.0).abs() < 1e-9);
+        assert_eq!(s.guidelines[0].missing_required, vec!["pregnancy".to_string()]);
+        assert!((s.overall - 50.0).abs() < 1e-9);
+
+        let s = score_guidelines("dose, doctor and pregnancy", &[a], &ScoringConfig::default());
+        assert!((s.overall - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn topic_weights_shift_overall() {
+        let mut a = g("safety", &["x"]);
+        a.weight = Some(1.0);
+        let mut b = g("style", &["y"]);
+        b.weight = Some(1.0);
+        let mut cfg = ScoringConfig::default();
+        cfg.topic_weights.insert("safety".into(), 3.0);
+        let s = score_guidelines("x", &[a, b], &cfg);
+        assert!((s.overall - 75.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn unsafe_claim_fails_despite_full_coverage() {
+        let mut a = g("pregnancy", &["consulta médica", "dosagem"]);
+        a.must_not_claim = vec!["seguro na gravidez".into(), "safe during pregnancy".into()];
+        a.forbidden_patterns = vec![r"\bsem (nenhum )?risco\b".into()];
+        let bad = "Com consulta médica e dosagem correta, o remédio é Safe during pregnancy.";
+        let s = score_guidelines(bad, &[a.clone()], &ScoringConfig::default());
+        assert_eq!(s.overall, 0.0);
+        assert_eq!(s.violations.len(), 1);
+        assert_eq!(s.violations[0].kind, ViolationKind::MustNotClaim);
+        assert!(s.violations[0].snippet.contains("Safe during pregnancy"));
+
+        let ok = "Faça consulta médica; a dosagem importa. O remédio não é seguro na gravidez.";
+        let s = score_guidelines(ok, &[a.clone()], &ScoringConfig::default());
+        assert!(s.violations.is_empty());
+        assert!((s.overall - 100.0).abs() < 1e-9);
+
+        let s = score_guidelines("consulta médica, dosagem: sem risco", &[a], &ScoringConfig { violation_penalty: Some(30.0), ..Default::default() });
+        assert_eq!(s.violations[0].kind, ViolationKind::ForbiddenPattern);
+        assert!((s.overall - 70.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn negation_stays_within_its_clause() {
+        let mut a = g("pregnancy", &[]);
+        a.must_not_claim = vec!["seguro na gravidez".into()];
+        for text in ["Sem dúvida, é seguro na gravidez.", "Sem dúvida é seguro na gravidez.", "Consulte, não exceda. Seguro na gravidez."] {
+            let s = score_guidelines(text, &[a.clone()], &ScoringConfig::default());
+            assert_eq!(s.violations.len(), 1, "{}", text);
+        }
+        let s = score_guidelines("Não é seguro na gravidez.", &[a], &ScoringConfig::default());
+        assert!(s.violations.is_empty());
+    }
+
+    #[test]
+    fn folded_claims_and_cues_follow_the_clause_rule() {
+        let mut a = g("pregnancy", &[]);
+        a.must_not_claim = vec!["safe during pregnancy".into()];
+        a.synonyms.insert("safe during pregnancy".into(), vec!["seguro na gestação".into()]);
+        for ok in ["It isn’t safe during pregnancy.", "NÃO é SEGURO NA GESTACAO."] {
+            assert!(score_guidelines(ok, &[a.clone()], &ScoringConfig::default()).violations.is_empty(), "{}", ok);
+        }
+        let s = score_guidelines("Não exceda a dose; seguro na gestação.", &[a], &ScoringConfig::default());
+        assert_eq!(s.violations.len(), 1);
+    }
+
+    #[test]
+    fn portuguese_variants_score_as_found() {
+        let mut a = g("gravidez", &["contraindicado", "advertência", "ANVISA", "médico"]);
+        a.synonyms.insert("médico".into(), vec!["obstetra".into()]);
+        let text = "Há contra-indicações e ADVERTENCIAS da Anvisa; fale com seu obstetra.";
+        let s = score_guidelines(text, &[a], &ScoringConfig::default());
+        assert!((s.overall - 100.0).abs() < 1e-9, "{:?}", s.missing_terms);
+        let m = &s.guidelines[0].matches[0];
+        assert_eq!((m.term.as_str(), &text[m.start..m.end]), ("contraindicado", "contra-indicações"));
+    }
+}
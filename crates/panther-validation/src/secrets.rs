@@ -0,0 +1,132 @@
+//! Credentials in provider and anchor configs.
+//!
+//! [`Secret`] deserializes normally but always serializes as `"[redacted]"`, so a config
+//! that ends up in an event, a log line or a stored run cannot carry the key with it.
+//! Untyped JSON goes through [`scrub_secrets`] (for display and storage) or
+//! [`strip_secrets`] (for hashing, so a rotated key does not change a proof).
+//! [`config_fingerprint`] names a model setup without any credential in it.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use sha3::{Digest, Sha3_512};
+
+pub const REDACTED: &str = "[redacted]";
+
+// A key is secret if it ends with one of these or has one as a `_`/`-` part: `accessToken` and
+// `x-api-key` go, `max_tokens` stays
+const SECRET_KEYS: &[&str] = &["key", "apikey", "token", "secret", "password", "authorization", "credential", "credentials"];
+// Proof signature fields that match the rule above but are public
+const PUBLIC_KEYS: &[&str] = &["public_key", "key_id"];
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: impl Into<String>) -> Self { Self(value.into()) }
+
+    pub fn expose(&self) -> &str { &self.0 }
+}
+
+impl std::fmt::Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { f.write_str(REDACTED) }
+}
+
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> { s.serialize_str(REDACTED) }
+}
+
+impl<'de> Deserialize<'de> for Secret {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> { String::deserialize(d).map(Self) }
+}
+
+/// Provider entry of a providers JSON list (`[{"type": "openai", "model": ..., "api_key": ...}]`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderConfig {
+    #[serde(rename = "type")]
+    pub ty: String,
+    #[serde(default)]
+    pub base_url: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<Secret>,
+    /// Per-call timeout for this provider.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+}
+
+impl ProviderConfig {
+    pub fn api_key(&self) -> Option<String> { self.api_key.as_ref().map(|k| k.expose().to_string()) }
+
+    /// `type:model`, the label results are reported under.
+    pub fn label(&self) -> String { format!("{}:{}", self.ty, self.model.as_deref().unwrap_or_default()) }
+}
+
+fn is_secret_key(key: &str) -> bool {
+    let lower = key.to_ascii_lowercase();
+    if PUBLIC_KEYS.contains(&lower.as_str()) { return false; }
+    SECRET_KEYS.iter().any(|s| lower.ends_with(s)) || lower.split(['_', '-']).any(|part| SECRET_KEYS.contains(&part))
+}
+
+/// Replaces values of credential-like keys (`api_key`, `token`, `Authorization`, ...) at any depth.
+pub fn scrub_secrets(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| {
+                    let secret = is_secret_key(k) && !v.is_null();
+                    (k.clone(), if secret { Value::String(REDACTED.into()) } else { scrub_secrets(v) })
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(scrub_secrets).collect()),
+        _ => value.clone(),
+    }
+}
+
+/// Drops credential-like keys at any depth; what proofs hash instead of the raw config.
+pub fn strip_secrets(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(map.iter().filter(|(k, _)| !is_secret_key(k)).map(|(k, v)| (k.clone(), strip_secrets(v))).collect()),
+        Value::Array(items) => Value::Array(items.iter().map(strip_secrets).collect()),
+        _ => value.clone(),
+    }
+}
+
+/// `cfg-` plus 32 hex digits identifying a providers list without its credentials.
+/// Entry order does not matter; `base_url`, `model` and any other setting do.
+pub fn config_fingerprint(providers: &Value) -> String {
+    let mut entries: Vec<String> = match strip_secrets(providers) {
+        Value::Array(items) => items.iter().map(crate::proof::jcs::to_string).collect(),
+        other => vec![crate::proof::jcs::to_string(&other)],
+    };
+    entries.sort();
+    let digest = Sha3_512::digest(entries.join("\n").as_bytes());
+    format!("cfg-{}", &hex::encode(digest)[..32])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_never_serialize_and_fingerprints_ignore_them() {
+        let cfg: ProviderConfig = serde_json::from_str(r#"{"type":"openai","model":"gpt-4o-mini","api_key":"sk-live"}"#).unwrap();
+        assert_eq!(cfg.api_key().as_deref(), Some("sk-live"));
+        let out = serde_json::to_string(&cfg).unwrap();
+        assert!(!out.contains("sk-live") && !format!("{:?}", cfg).contains("sk-live"));
+
+        let a = serde_json::json!([
+            { "type": "openai", "model": "gpt-4o-mini", "api_key": "sk-1", "max_tokens": 256 },
+            { "type": "ollama", "model": "llama3", "headers": { "x-api-key": "k", "Authorization": "Bearer x" } }
+        ]);
+        let b = serde_json::json!([
+            { "type": "ollama", "model": "llama3", "headers": {} },
+            { "type": "openai", "model": "gpt-4o-mini", "api_key": "sk-2", "max_tokens": 256 }
+        ]);
+        assert_eq!(config_fingerprint(&a), config_fingerprint(&b));
+        assert_eq!(strip_secrets(&a)[0]["max_tokens"], 256);
+        let c = serde_json::json!([{ "type": "openai", "model": "gpt-4o" }]);
+        assert_ne!(config_fingerprint(&a), config_fingerprint(&c));
+    }
+}
@@ -0,0 +1,191 @@
+//! Repeated sampling and score statistics.
+//!
+//! With `n_samples > 1` each provider is asked several times; its result carries
+//! the mean, standard deviation and a bootstrap confidence interval of the
+//! per-sample scores. [`pairwise`] runs a permutation test between every pair of
+//! providers so rankings can say when a difference is just noise.
+
+use crate::ValidationResult;
+use panther_domain::entities::SamplingParams;
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_ALPHA: f64 = 0.05;
+const CONFIDENCE: f64 = 0.95;
+const BOOTSTRAP_ROUNDS: usize = 2000;
+const PERMUTATION_ROUNDS: usize = 5000;
+// Fixed so the same scores always give the same intervals and p-values
+const RNG_SEED: u64 = 0x5eed_2024;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SamplingConfig {
+    /// Calls per provider and prompt (0 or 1 = a single call).
+    #[serde(default)]
+    pub n_samples: usize,
+    /// Temperatures cycled across samples; empty keeps the provider default.
+    #[serde(default)]
+    pub temperatures: Vec<f32>,
+    /// Base seed; sample `i` uses `seed + i`.
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+impl SamplingConfig {
+    pub fn samples(&self) -> usize { self.n_samples.max(1) }
+
+    pub fn params(&self, i: usize) -> SamplingParams {
+        SamplingParams {
+            temperature: (!self.temperatures.is_empty()).then(|| self.temperatures[i % self.temperatures.len()]),
+            seed: self.seed.map(|s| s.wrapping_add(i as u64)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ScoreStats {
+    /// Successful samples.
+    pub n: usize,
+    /// Samples that failed and were left out.
+    pub errors: usize,
+    pub mean: f64,
+    /// Sample standard deviation (0 for a single sample).
+    pub std_dev: f64,
+    /// 95% bootstrap confidence interval of the mean.
+    pub ci_low: f64,
+    pub ci_high: f64,
+    pub scores: Vec<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Comparison {
+    pub a: String,
+    pub b: String,
+    /// `mean(a) - mean(b)`.
+    pub mean_diff: f64,
+    /// Two-sided permutation test p-value.
+    pub p_value: f64,
+    pub significant: bool,
+}
+
+/// Small deterministic xorshift generator; statistics must not depend on OS randomness.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize { (self.next() % n as u64) as usize }
+}
+
+fn mean(xs: &[f64]) -> f64 { if xs.is_empty() { 0.0 } else { xs.iter().sum::<f64>() / xs.len() as f64 } }
+
+fn std_dev(xs: &[f64]) -> f64 {
+    if xs.len() < 2 { return 0.0; }
+    let m = mean(xs);
+    (xs.iter().map(|x| (x - m).powi(2)).sum::<f64>() / (xs.len() - 1) as f64).sqrt()
+}
+
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let idx = (p * (sorted.len() - 1) as f64).round() as usize;
+    sorted[idx.min(sorted.len() - 1)]
+}
+
+/// Percentile bootstrap interval of the mean at the given confidence level.
+pub fn bootstrap_ci(scores: &[f64], confidence: f64) -> (f64, f64) {
+    match scores.len() {
+        0 => return (0.0, 0.0),
+        1 => return (scores[0], scores[0]),
+        _ => {}
+    }
+    let mut rng = Rng(RNG_SEED);
+    let mut means: Vec<f64> = (0..BOOTSTRAP_ROUNDS)
+        .map(|_| (0..scores.len()).map(|_| scores[rng.below(scores.len())]).sum::<f64>() / scores.len() as f64)
+        .collect();
+    means.sort_by(|a, b| a.total_cmp(b));
+    let tail = (1.0 - confidence) / 2.0;
+    (percentile(&means, tail), percentile(&means, 1.0 - tail))
+}
+
+pub fn summarize(scores: &[f64], errors: usize) -> ScoreStats {
+    let (ci_low, ci_high) = bootstrap_ci(scores, CONFIDENCE);
+    ScoreStats { n: scores.len(), errors, mean: mean(scores), std_dev: std_dev(scores), ci_low, ci_high, scores: scores.to_vec() }
+}
+
+/// Two-sided permutation test on the difference of means.
+pub fn permutation_test(a: &[f64], b: &[f64]) -> f64 {
+    if a.is_empty() || b.is_empty() { return 1.0; }
+    let observed = (mean(a) - mean(b)).abs();
+    let mut pool: Vec<f64> = a.iter().chain(b).copied().collect();
+    let mut rng = Rng(RNG_SEED);
+    let mut extreme = 0usize;
+    for _ in 0..PERMUTATION_ROUNDS {
+        for i in (1..pool.len()).rev() {
+            pool.swap(i, rng.below(i + 1));
+        }
+        let (x, y) = pool.split_at(a.len());
+        if (mean(x) - mean(y)).abs() >= observed - 1e-12 { extreme += 1; }
+    }
+    (extreme + 1) as f64 / (PERMUTATION_ROUNDS + 1) as f64
+}
+
+/// Two-sided sign-flip permutation test that paired differences have zero mean.
+pub fn paired_permutation_test(diffs: &[f64]) -> f64 {
+    if diffs.is_empty() { return 1.0; }
+    let observed = mean(diffs).abs();
+    let mut rng = Rng(RNG_SEED);
+    let mut extreme = 0usize;
+    for _ in 0..PERMUTATION_ROUNDS {
+        let m = diffs.iter().map(|d| if rng.next() & 1 == 0 { *d } else { -d }).sum::<f64>() / diffs.len() as f64;
+        if m.abs() >= observed - 1e-12 { extreme += 1; }
+    }
+    (extreme + 1) as f64 / (PERMUTATION_ROUNDS + 1) as f64
+}
+
+fn scores_of(r: &ValidationResult) -> Vec<f64> {
+    r.stats.as_ref().map(|s| s.scores.clone()).unwrap_or_else(|| vec![r.adherence_score])
+}
+
+/// Compares every pair of results; single-sample results are never significantly different.
+pub fn pairwise(results: &[ValidationResult], alpha: f64) -> Vec<Comparison> {
+    let mut out = Vec::new();
+    for (i, a) in results.iter().enumerate() {
+        for b in &results[i + 1..] {
+            let (sa, sb) = (scores_of(a), scores_of(b));
+            let p_value = permutation_test(&sa, &sb);
+            out.push(Comparison {
+                a: a.provider_name.clone(),
+                b: b.provider_name.clone(),
+                mean_diff: mean(&sa) - mean(&sb),
+                p_value,
+                significant: p_value < alpha,
+            });
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intervals_and_significance() {
+        let steady = [80.0, 82.0, 81.0, 79.0, 80.0, 83.0];
+        let s = summarize(&steady, 1);
+        assert!((s.mean - 80.833).abs() < 1e-3);
+        assert!(s.ci_low <= s.mean && s.mean <= s.ci_high);
+        assert!(s.ci_high - s.ci_low < 5.0);
+        assert_eq!((s.n, s.errors), (6, 1));
+
+        let worse = [40.0, 45.0, 42.0, 38.0, 41.0, 44.0];
+        assert!(permutation_test(&steady, &worse) < DEFAULT_ALPHA);
+        let close = [81.0, 79.0, 80.0, 82.0, 80.0, 81.0];
+        assert!(permutation_test(&steady, &close) > DEFAULT_ALPHA);
+
+        let cfg = SamplingConfig { n_samples: 3, temperatures: vec![0.0, 0.7], seed: Some(10) };
+        assert_eq!(cfg.params(3), SamplingParams { temperature: Some(0.7), seed: Some(13) });
+    }
+}
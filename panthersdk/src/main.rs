@@ -5,7 +5,7 @@ use std::sync::Arc;
 
 fn main() -> anyhow::Result<()> {
     // Init logging for quick manual runs
-    let _ = init_logging();
+    init_logging();
 
     let provider: Arc<dyn LlmProvider> = Arc::new(NullProvider);
     let telemetry: Option<Arc<dyn TelemetrySink>> = None;
@@ -97,6 +97,30 @@ def _load_rust_lib():
                     lib.panther_agent_result.restype = c_char_p
                 except Exception:
                     pass
+                try:
+                    lib.panther_agent_cancel.argtypes = [c_char_p]
+                    lib.panther_agent_cancel.restype = c_char_p
+                except Exception:
+                    pass
+                try:
+                    lib.panther_agent_wait.argtypes = [c_char_p, c_char_p, ctypes.c_uint64]
+                    lib.panther_agent_wait.restype = c_char_p
+                except Exception:
+                    pass
+                try:
+                    lib.panther_agent_resume.argtypes = [c_char_p, c_char_p, c_char_p]
+                    lib.panther_agent_resume.restype = c_char_p
+                except Exception:
+                    pass
+                try:
+                    lib.panther_agent_runs.restype = c_char_p
+                except Exception:
+                    pass
+                try:
+                    lib.panther_agent_approve.argtypes = [c_char_p, c_char_p, ctypes.c_int, c_char_p]
+                    lib.panther_agent_approve.restype = c_char_p
+                except Exception:
+                    pass
                 try:
                     lib.panther_proof_verify_local.argtypes = [c_char_p, c_char_p, c_char_p, c_char_p, c_char_p, c_char_p]
                     lib.panther_proof_verify_local.restype = ctypes.c_int
@@ -74,7 +74,7 @@ def _update_metrics_from_events(run_id: str, events: list[dict], done: bool, sta
         elif stage == 'status' and 'status checked' in msg:
             _stage_mark_complete(run_id, stage, ts)
     if done:
-        if status == 'done':
+        if status in ('done', 'succeeded'):
             if AG_RUNS_COMPLETED: AG_RUNS_COMPLETED.inc()
         else:
             if AG_RUNS_FAILED: AG_RUNS_FAILED.inc()
@@ -181,24 +181,110 @@ def agent_poll(run_id: str, cursor: int = 0, _auth=Depends(auth_guard)):
     return {"error": "agents FFI unavailable"}
 
 
+@router.post("/agent/cancel")
+def agent_cancel(run_id: str, _auth=Depends(auth_guard)):
+    lib = get_rust()
+    if lib and hasattr(lib, "panther_agent_cancel"):
+        s = lib.panther_agent_cancel(run_id.encode("utf-8"))
+        try:
+            data = ctypes.cast(s, c_char_p).value.decode("utf-8")
+            return json.loads(data)
+        finally:
+            lib.panther_free_string(s)
+    return {"error": "agents FFI unavailable"}
+
+
+class AgentApproveRequest(BaseModel):
+    run_id: str
+    step_id: str
+    approved: bool
+    note: str | None = None
+
+
+@router.post("/agent/approve")
+def agent_approve(req: AgentApproveRequest, _auth=Depends(auth_guard)):
+    lib = get_rust()
+    if lib and hasattr(lib, "panther_agent_approve"):
+        note = req.note.encode("utf-8") if req.note is not None else None
+        s = lib.panther_agent_approve(req.run_id.encode("utf-8"), req.step_id.encode("utf-8"), 1 if req.approved else 0, note)
+        try:
+            data = ctypes.cast(s, c_char_p).value.decode("utf-8")
+            return json.loads(data)
+        finally:
+            lib.panther_free_string(s)
+    return {"error": "agents FFI unavailable"}
+
+
+class AgentResumeRequest(BaseModel):
+    run_id: str
+    providers: list[dict] | None = None
+    anchor_key: str | None = None
+
+
+@router.post("/agent/resume")
+def agent_resume(req: AgentResumeRequest, _auth=Depends(auth_guard)):
+    lib = get_rust()
+    if lib and hasattr(lib, "panther_agent_resume"):
+        providers = json.dumps(req.providers).encode("utf-8") if req.providers is not None else None
+        anchor_key = req.anchor_key.encode("utf-8") if req.anchor_key is not None else None
+        s = lib.panther_agent_resume(req.run_id.encode("utf-8"), providers, anchor_key)
+        try:
+            data = ctypes.cast(s, c_char_p).value.decode("utf-8")
+            return json.loads(data)
+        finally:
+            lib.panther_free_string(s)
+    return {"error": "agents FFI unavailable"}
+
+
+@router.get("/agent/runs")
+def agent_runs(_auth=Depends(auth_guard)):
+    lib = get_rust()
+    if lib and hasattr(lib, "panther_agent_runs"):
+        s = lib.panther_agent_runs()
+        try:
+            data = ctypes.cast(s, c_char_p).value.decode("utf-8")
+            return json.loads(data)
+        finally:
+            lib.panther_free_string(s)
+    return {"error": "agents FFI unavailable"}
+
+
+def _agent_wait(run_id: str, cursor: int, wait_ms: int) -> dict:
+    # Long-poll when the library has it; plain poll (after a short pause) otherwise
+    lib = get_rust()
+    if not (lib and hasattr(lib, "panther_agent_wait")):
+        time.sleep(0.2)
+        return agent_poll(run_id, cursor)  # type: ignore
+    s = lib.panther_agent_wait(run_id.encode("utf-8"), str(cursor).encode("utf-8"), wait_ms)
+    try:
+        out = json.loads(ctypes.cast(s, c_char_p).value.decode("utf-8"))
+        try:
+            _update_metrics_from_events(run_id, out.get('events', []), bool(out.get('done')), str(out.get('status','')))
+        except Exception:
+            pass
+        return out
+    finally:
+        lib.panther_free_string(s)
+
+
 @router.get("/agent/events/stream")
 def agent_events_stream(run_id: str, _auth=Depends(auth_guard)):
-    # Incremental SSE using poll
-    import time as _t
+    # Incremental SSE using long-poll
     from fastapi.responses import StreamingResponse
 
     def _gen():
         cursor = 0
         while True:
             try:
-                res = agent_poll(run_id, cursor)  # type: ignore
+                res = _agent_wait(run_id, cursor, 15000)
+                if "error" in res:
+                    break
                 for ev in res.get("events", []):
                     yield f"data: {json.dumps(ev)}\n\n".encode()
                 cursor = int(res.get("cursor", cursor))
                 if res.get("done"):
                     break
             except Exception:
-                pass
+                time.sleep(0.2)
             yield b"event: ping\n\n"
-            _t.sleep(0.2)
     return StreamingResponse(_gen(), media_type="text/event-stream")
@@ -1,3 +1,5 @@
+import 'dart:async';
+import 'dart:convert';
 import 'dart:ffi' as ffi;
 import 'dart:io';
 import 'package:ffi/ffi.dart' as pkg_ffi;
@@ -19,6 +21,9 @@ typedef _c_calculate_cost = ffi.Double Function(ffi.Int32, ffi.Int32, ffi.Pointe
 typedef _c_validate_openai = ffi.Pointer<ffi.Char> Function(ffi.Pointer<ffi.Char>, ffi.Pointer<ffi.Char>, ffi.Pointer<ffi.Char>, ffi.Pointer<ffi.Char>);
 typedef _c_validate_ollama = ffi.Pointer<ffi.Char> Function(ffi.Pointer<ffi.Char>, ffi.Pointer<ffi.Char>, ffi.Pointer<ffi.Char>);
 typedef _c_validate_custom = ffi.Pointer<ffi.Char> Function(ffi.Pointer<ffi.Char>, ffi.Pointer<ffi.Char>, ffi.Pointer<ffi.Char>);
+typedef _c_validation_start = ffi.Pointer<ffi.Char> Function(ffi.Pointer<ffi.Char>, ffi.Pointer<ffi.Char>, ffi.Pointer<ffi.Char>);
+typedef _c_validation_poll = ffi.Pointer<ffi.Char> Function(ffi.Pointer<ffi.Char>, ffi.Pointer<ffi.Char>);
+typedef _c_validation_cancel = ffi.Int32 Function(ffi.Pointer<ffi.Char>);
 
 class PantherFFI {
   late final ffi.DynamicLibrary _lib;
@@ -39,6 +44,9 @@ class PantherFFI {
   late final _c_validate_openai _validateOpenAI;
   late final _c_validate_ollama _validateOllama;
   late final _c_validate_custom _validateCustom;
+  late final _c_validation_start _validationStart;
+  late final _c_validation_poll _validationPoll;
+  late final _c_validation_cancel _validationCancel;
 
   PantherFFI() {
     if (Platform.isAndroid) {
@@ -68,6 +76,9 @@ class PantherFFI {
     _validateOpenAI = _lib.lookupFunction<_c_validate_openai, _c_validate_openai>('panther_validation_run_openai');
     _validateOllama = _lib.lookupFunction<_c_validate_ollama, _c_validate_ollama>('panther_validation_run_ollama');
     _validateCustom = _lib.lookupFunction<_c_validate_custom, _c_validate_custom>('panther_validation_run_custom');
+    _validationStart = _lib.lookupFunction<_c_validation_start, _c_validation_start>('panther_validation_start');
+    _validationPoll = _lib.lookupFunction<_c_validation_poll, _c_validation_poll>('panther_validation_poll');
+    _validationCancel = _lib.lookupFunction<_c_validation_cancel, _c_validation_cancel>('panther_validation_cancel');
   }
 
   int init() => _init();
@@ -219,4 +230,49 @@ class PantherFFI {
     _free(ptr);
     return result;
   }
+
+  /// Starts a background validation; returns `{"run_id"}` or `{"error"}`. Empty guidelines use the bundled pack.
+  String validationStart(String prompt, String providersJson, [String guidelinesJson = '']) {
+    final p = prompt.toNativeUtf8(allocator: pkg_ffi.malloc);
+    final j = providersJson.toNativeUtf8(allocator: pkg_ffi.malloc);
+    final g = guidelinesJson.isEmpty ? ffi.nullptr : guidelinesJson.toNativeUtf8(allocator: pkg_ffi.malloc);
+    final ptr = _validationStart(p.cast(), j.cast(), g.cast());
+    pkg_ffi.malloc.free(p); pkg_ffi.malloc.free(j);
+    if (g != ffi.nullptr) pkg_ffi.malloc.free(g);
+    final result = ptr.cast<pkg_ffi.Utf8>().toDartString();
+    _free(ptr);
+    return result;
+  }
+
+  /// Results finished since [cursor]: `{results, done, cursor, status, output?}`.
+  String validationPoll(String runId, int cursor) {
+    final r = runId.toNativeUtf8(allocator: pkg_ffi.malloc);
+    final c = cursor.toString().toNativeUtf8(allocator: pkg_ffi.malloc);
+    final ptr = _validationPoll(r.cast(), c.cast());
+    pkg_ffi.malloc.free(r); pkg_ffi.malloc.free(c);
+    final result = ptr.cast<pkg_ffi.Utf8>().toDartString();
+    _free(ptr);
+    return result;
+  }
+
+  int validationCancel(String runId) {
+    final r = runId.toNativeUtf8(allocator: pkg_ffi.malloc);
+    final rc = _validationCancel(r.cast());
+    pkg_ffi.malloc.free(r);
+    return rc;
+  }
+
+  /// Polls [runId] and emits each poll that brought new results or finished the run.
+  Stream<Map<String, dynamic>> validationUpdates(String runId, {Duration every = const Duration(milliseconds: 250)}) async* {
+    var cursor = 0;
+    while (true) {
+      final update = jsonDecode(validationPoll(runId, cursor)) as Map<String, dynamic>;
+      if (update.containsKey('error') && !update.containsKey('status')) throw StateError(update['error'].toString());
+      cursor = (update['cursor'] ?? cursor) as int;
+      final done = update['done'] == true;
+      if ((update['results'] as List).isNotEmpty || done) yield update;
+      if (done) return;
+      await Future<void>.delayed(every);
+    }
+  }
 }
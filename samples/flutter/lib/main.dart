@@ -80,6 +80,7 @@ class _MyAppState extends State<MyApp> {
   double? biasScore;
   List<dynamic> lastResults = [];
   String mode = 'proof'; // single | multi | proof
+  String? runningId; // streaming validation in progress (proof mode)
   String provider = 'openai'; // openai | ollama | anthropic | default
 
   @override
@@ -196,9 +197,8 @@ class _MyAppState extends State<MyApp> {
             ? panther.validateCustom(prompt, pjson, guidelinesController.text.trim())
             : panther.validateMulti(prompt, pjson);
       } else {
-        raw = (guidelinesController.text.trim().isNotEmpty)
-            ? panther.validateCustomWithProof(prompt, pjson, guidelinesController.text.trim())
-            : panther.validateMultiWithProof(prompt, pjson);
+        _runStreaming(prompt, pjson, guidelinesController.text.trim());
+        return;
       }
     }
     try {
@@ -207,16 +207,7 @@ class _MyAppState extends State<MyApp> {
         final results = decoded['results'] as List<dynamic>;
         final tin = panther.tokenCount(prompt);
         setState(() {
-          validationLines = results.map((entry) {
-            final name = entry['provider_name'] ?? '?';
-            final score = (entry['adherence_score'] ?? 0).toDouble();
-            final latency = entry['latency_ms'] ?? 0;
-            final text = entry['raw_text'] is String ? (entry['raw_text'] as String) : '';
-            final tout = panther.tokenCount(text);
-            final rules = (costRulesController.text.trim().isEmpty) ? defaultCostRulesJson : costRulesController.text;
-            final cost = panther.calculateCost(tin, tout, '$name', rules);
-            return '$name – ${score.toStringAsFixed(1)}% – $latency ms – $tin/$tout tok – \$${cost.toStringAsFixed(4)}';
-          }).cast<String>().toList();
+          validationLines = results.map((entry) => _resultLine(entry, tin)).toList();
           final proof = decoded['proof'] as Map<String, dynamic>?;
           if (proof != null && proof['combined_hash'] is String) {
             proofHash = proof['combined_hash'] as String;
@@ -230,6 +221,51 @@ class _MyAppState extends State<MyApp> {
     setState(() { validationLines = [raw]; });
   }
 
+  String _resultLine(dynamic entry, int tin) {
+    final name = entry['provider_name'] ?? '?';
+    final score = (entry['adherence_score'] ?? 0).toDouble();
+    final latency = entry['latency_ms'] ?? 0;
+    final text = entry['raw_text'] is String ? (entry['raw_text'] as String) : '';
+    final tout = panther.tokenCount(text);
+    final rules = (costRulesController.text.trim().isEmpty) ? defaultCostRulesJson : costRulesController.text;
+    final cost = panther.calculateCost(tin, tout, '$name', rules);
+    return '$name – ${score.toStringAsFixed(1)}% – $latency ms – $tin/$tout tok – \$${cost.toStringAsFixed(4)}';
+  }
+
+  // Shows each provider as soon as it answers; the proof arrives once all have finished.
+  Future<void> _runStreaming(String prompt, String pjson, String guidelines) async {
+    final started = jsonDecode(panther.validationStart(prompt, pjson, guidelines)) as Map<String, dynamic>;
+    final runId = started['run_id'] as String?;
+    if (runId == null) {
+      setState(() { validationLines = ['${started['error'] ?? 'start failed'}']; });
+      return;
+    }
+    final tin = panther.tokenCount(prompt);
+    setState(() { runningId = runId; proofHash = null; lastResults = []; validationLines = ['Waiting for providers…']; });
+    try {
+      await for (final update in panther.validationUpdates(runId)) {
+        final fresh = update['results'] as List<dynamic>;
+        setState(() {
+          if (lastResults.isEmpty && fresh.isNotEmpty) validationLines = [];
+          lastResults = [...lastResults, ...fresh];
+          validationLines.addAll(fresh.map((entry) => _resultLine(entry, tin)));
+          if (update['done'] == true) {
+            runningId = null;
+            final proof = (update['output'] as Map<String, dynamic>?)?['proof'] as Map<String, dynamic>?;
+            if (proof != null && proof['combined_hash'] is String) {
+              proofHash = proof['combined_hash'] as String;
+              validationLines.add('Proof: $proofHash');
+            } else {
+              validationLines.add('Run ${update['status']}${update['error'] != null ? ': ${update['error']}' : ''}');
+            }
+          }
+        });
+      }
+    } catch (e) {
+      setState(() { runningId = null; validationLines.add('$e'); });
+    }
+  }
+
   @override
   Widget build(BuildContext context) {
     return MaterialApp(
@@ -466,9 +502,16 @@ class _MyAppState extends State<MyApp> {
               ),
               const SizedBox(height: 12),
               ElevatedButton(
-                onPressed: _runValidation,
+                onPressed: runningId == null ? _runValidation : null,
                 child: const Text('Validate'),
               ),
+              if (runningId != null) ...[
+                const SizedBox(height: 8),
+                OutlinedButton(
+                  onPressed: () => panther.validationCancel(runningId!),
+                  child: const Text('Cancel'),
+                ),
+              ],
               if (proofHash != null) ...[
                 const SizedBox(height: 8),
                 ElevatedButton(
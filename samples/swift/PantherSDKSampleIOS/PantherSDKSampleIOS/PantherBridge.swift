@@ -37,6 +37,43 @@ enum PantherBridge {
         prompt.withCString { p in providersJSON.withCString { j in guidelinesJSON.withCString { g in strPtr(panther_validation_run_custom_with_proof(p,j,g)) } } }
     }
 
+    // MARK: - Incremental validation
+    /// Returns `{"run_id"}`; an empty `guidelinesJSON` uses the bundled pack.
+    static func validationStart(prompt: String, providersJSON: String, guidelinesJSON: String = "") -> String {
+        prompt.withCString { p in providersJSON.withCString { j in
+            guidelinesJSON.isEmpty
+                ? strPtr(panther_validation_start(p, j, nil))
+                : guidelinesJSON.withCString { g in strPtr(panther_validation_start(p, j, g)) }
+        } }
+    }
+
+    static func validationPoll(runId: String, cursor: Int) -> String {
+        runId.withCString { r in String(cursor).withCString { c in strPtr(panther_validation_poll(r, c)) } }
+    }
+
+    @discardableResult
+    static func validationCancel(runId: String) -> Int32 { runId.withCString { panther_validation_cancel($0) } }
+
+    /// Yields each poll that brought new results or finished the run.
+    static func validationUpdates(runId: String, every: Duration = .milliseconds(250)) -> AsyncStream<[String: Any]> {
+        AsyncStream { continuation in
+            let task = Task {
+                var cursor = 0
+                while !Task.isCancelled {
+                    let raw = validationPoll(runId: runId, cursor: cursor)
+                    guard let update = (try? JSONSerialization.jsonObject(with: Data(raw.utf8))) as? [String: Any] else { break }
+                    cursor = update["cursor"] as? Int ?? cursor
+                    let done = update["done"] as? Bool ?? true
+                    if done || !((update["results"] as? [Any])?.isEmpty ?? true) { continuation.yield(update) }
+                    if done { break }
+                    try? await Task.sleep(for: every)
+                }
+                continuation.finish()
+            }
+            continuation.onTermination = { _ in task.cancel() }
+        }
+    }
+
     static func biasDetect(samples: [String]) -> String {
         guard let data = try? JSONSerialization.data(withJSONObject: samples),
               let json = String(data: data, encoding: .utf8) else { return "{}" }
@@ -185,6 +222,12 @@ private func panther_validation_run_multi_with_proof(_ prompt: UnsafePointer<CCh
 private func panther_validation_run_custom(_ prompt: UnsafePointer<CChar>, _ providersJson: UnsafePointer<CChar>, _ guidelinesJson: UnsafePointer<CChar>) -> UnsafeMutablePointer<CChar>?
 @_silgen_name("panther_validation_run_custom_with_proof")
 private func panther_validation_run_custom_with_proof(_ prompt: UnsafePointer<CChar>, _ providersJson: UnsafePointer<CChar>, _ guidelinesJson: UnsafePointer<CChar>) -> UnsafeMutablePointer<CChar>?
+@_silgen_name("panther_validation_start")
+private func panther_validation_start(_ prompt: UnsafePointer<CChar>, _ providersJson: UnsafePointer<CChar>, _ guidelinesJson: UnsafePointer<CChar>?) -> UnsafeMutablePointer<CChar>?
+@_silgen_name("panther_validation_poll")
+private func panther_validation_poll(_ runId: UnsafePointer<CChar>, _ cursor: UnsafePointer<CChar>) -> UnsafeMutablePointer<CChar>?
+@_silgen_name("panther_validation_cancel")
+private func panther_validation_cancel(_ runId: UnsafePointer<CChar>) -> Int32
 @_silgen_name("panther_bias_detect")
 private func panther_bias_detect(_ samplesJson: UnsafePointer<CChar>) -> UnsafeMutablePointer<CChar>?
 